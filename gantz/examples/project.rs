@@ -19,6 +19,7 @@ impl gantz::Node for One {
             n_inputs,
             n_outputs,
             gen_expr,
+            optional_outputs: vec![],
         }
     }
 
@@ -41,6 +42,7 @@ impl gantz::Node for Add {
             n_inputs,
             n_outputs,
             gen_expr,
+            optional_outputs: vec![],
         }
     }
 }
@@ -58,6 +60,7 @@ impl gantz::Node for Debug {
             n_inputs,
             n_outputs,
             gen_expr,
+            optional_outputs: vec![],
         }
     }
 }
@@ -96,9 +99,9 @@ fn main() {
     let debug = Box::new(Debug) as Box<_>;
 
     // Add nodes to the project.
-    let one = project.add_core_node(one);
-    let add = project.add_core_node(add);
-    let debug = project.add_core_node(debug);
+    let one = project.add_core_node(one).unwrap();
+    let add = project.add_core_node(add).unwrap();
+    let debug = project.add_core_node(debug).unwrap();
 
     // Update the root graph.
     let root = project.root_node_id();
@@ -113,6 +116,8 @@ fn main() {
                 gantz::Edge {
                     output: gantz::node::Output(0),
                     input: gantz::node::Input(0),
+                    cold: false,
+                    delay: false,
                 },
             );
             g.add_edge(
@@ -121,6 +126,8 @@ fn main() {
                 gantz::Edge {
                     output: gantz::node::Output(0),
                     input: gantz::node::Input(1),
+                    cold: false,
+                    delay: false,
                 },
             );
             g.add_edge(
@@ -129,22 +136,18 @@ fn main() {
                 gantz::Edge {
                     output: gantz::node::Output(0),
                     input: gantz::node::Input(0),
+                    cold: false,
+                    delay: false,
                 },
             );
         })
         .unwrap();
 
-    // Retrieve the path to the compiled library.
-    let dylib_path = project
-        .graph_node_dylib(&root)
-        .unwrap()
-        .expect("no dylib or node");
-    let lib = libloading::Library::new(&dylib_path).expect("failed to load library");
-    let symbol_name = "one_push_eval".as_bytes();
-    unsafe {
-        let foo_one_push_eval_fn: libloading::Symbol<fn(&mut [&mut dyn std::any::Any])> =
-            lib.get(symbol_name).expect("failed to load symbol");
-        // Execute the gantz graph (prints `2` to stdout).
-        foo_one_push_eval_fn(&mut []);
-    }
+    // Load the compiled library.
+    let graph = project.load_graph(&root).expect("failed to load graph");
+    let foo_one_push_eval_fn = graph
+        .push_fn("one_push_eval")
+        .expect("failed to load symbol");
+    // Execute the gantz graph (prints `2` to stdout).
+    foo_one_push_eval_fn.call(&mut []).unwrap();
 }
@@ -22,6 +22,16 @@
 //! ## Current Questions
 
 pub mod project;
+pub mod runtime;
+pub mod wrap;
 
 pub use gantz_core::{self as core, graph, node, Edge, Node};
+pub use gantz_derive::{node, GantzNode};
 pub use project::{Project, TempProject};
+pub use runtime::{GraphState, LoadedGraph};
+
+// Items used by the code generated via `gantz_derive`.
+#[doc(hidden)]
+pub mod __private {
+    pub use syn;
+}
@@ -0,0 +1,424 @@
+//! Items related to compiling graph nodes on a background thread.
+//!
+//! Background builds are requested via `Project::update_graph_in_background` or
+//! `Project::build_in_background`. Each request returns a **BuildJob** handle that may be used to
+//! check the build's progress, to cancel it or to wait for its result. Meanwhile, any previously
+//! loaded dynamic library remains valid and may continue to be evaluated.
+//!
+//! Builds are performed one at a time in the order in which they were requested. Successive
+//! requests for the same graph node are coalesced: a request for a graph whose build is still
+//! queued returns the queued job, while a request for a graph that is currently being compiled
+//! supersedes the running build.
+//!
+//! A **BuildEvent** is emitted for each build that completes, allowing hosts to reload the graph
+//! via `LoadedGraph::reload` as soon as a new dynamic library is ready. See
+//! `Project::build_events`.
+
+use super::config::BuildProfile;
+use super::{
+    graph_node_compile_package, package_dylib, workspace_cargo_config, GraphNodeCompileError,
+    InstanceId, NodeId, NodeIndex,
+};
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use thiserror::Error;
+
+/// A handle to a graph node build that has been queued for compilation on the background thread.
+///
+/// Handles are cheap to clone. Dropping a handle does not cancel the build.
+#[derive(Clone)]
+pub struct BuildJob {
+    state: Arc<JobState>,
+}
+
+/// A snapshot of the progress of a **BuildJob**.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct BuildProgress {
+    /// The stage that the build has reached.
+    pub stage: BuildStage,
+    /// The number of crates that have finished compiling.
+    ///
+    /// Only crates that require recompilation are counted.
+    pub crates_compiled: usize,
+    /// The names of the crates that are currently being compiled.
+    pub compiling: Vec<String>,
+}
+
+/// The stages through which a **BuildJob** progresses.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum BuildStage {
+    /// The build is waiting for the background thread.
+    Queued,
+    /// The graph node and its dependencies are being compiled.
+    Compiling,
+    /// The build has completed, failed or been cancelled.
+    Finished,
+}
+
+/// Emitted by the background thread each time a build completes or fails.
+#[derive(Clone, Debug)]
+pub enum BuildEvent {
+    /// A new dynamic library is ready for the graph node.
+    Ready { graph: NodeId, dylib: PathBuf },
+    /// Compilation of the graph node failed.
+    Failed {
+        graph: NodeId,
+        err: Arc<GraphNodeCompileError>,
+    },
+}
+
+/// The reasons a **BuildJob** may fail to produce a dynamic library.
+#[derive(Clone, Debug, Error)]
+pub enum BuildError {
+    #[error("the build was cancelled")]
+    Cancelled,
+    #[error("the build was superseded by a more recent build of the same graph node")]
+    Superseded,
+    #[error("failed to compile graph node: {err}")]
+    Compile { err: Arc<GraphNodeCompileError> },
+}
+
+// Owns the background build thread along with the queue of builds awaiting compilation.
+pub(crate) struct Builder {
+    workspace_dir: PathBuf,
+    queue: Arc<Queue>,
+    events_tx: mpsc::Sender<BuildEvent>,
+    events_rx: mpsc::Receiver<BuildEvent>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+// The queue of builds shared between the `Builder` and its thread.
+#[derive(Default)]
+struct Queue {
+    jobs: Mutex<Jobs>,
+    condvar: Condvar,
+}
+
+#[derive(Default)]
+struct Jobs {
+    pending: VecDeque<Arc<JobState>>,
+    running: Option<Arc<JobState>>,
+    closed: bool,
+}
+
+// The state shared between a `BuildJob` handle and the background thread.
+pub(crate) struct JobState {
+    graph: NodeId,
+    inner: Mutex<JobInner>,
+    condvar: Condvar,
+}
+
+struct JobInner {
+    package_id: cargo::core::PackageId,
+    instance_ids: BTreeMap<NodeIndex, InstanceId>,
+    profile: BuildProfile,
+    progress: BuildProgress,
+    cancelled: Option<BuildError>,
+    result: Option<Result<PathBuf, BuildError>>,
+}
+
+impl BuildJob {
+    /// The **NodeId** of the graph node being built.
+    pub fn graph(&self) -> NodeId {
+        self.state.graph
+    }
+
+    /// A snapshot of the build's current progress.
+    pub fn progress(&self) -> BuildProgress {
+        self.state.lock().progress.clone()
+    }
+
+    /// Whether or not the build has completed, failed or been cancelled.
+    pub fn is_finished(&self) -> bool {
+        self.state.lock().result.is_some()
+    }
+
+    /// Cancel the build.
+    ///
+    /// A queued build is finished immediately. A build that is currently compiling is aborted
+    /// before the next crate is compiled, however a crate that is already being compiled is
+    /// compiled to completion as the running rustc invocation cannot be interrupted. The result
+    /// of such a compilation is discarded. Has no effect if the build has already finished.
+    pub fn cancel(&self) {
+        self.state.cancel(BuildError::Cancelled);
+    }
+
+    /// The result of the build if it has finished.
+    pub fn try_result(&self) -> Option<Result<PathBuf, BuildError>> {
+        self.state.lock().result.clone()
+    }
+
+    /// Block the current thread until the build has finished.
+    ///
+    /// Returns the path to the newly compiled dynamic library on success.
+    pub fn wait(&self) -> Result<PathBuf, BuildError> {
+        let mut inner = self.state.lock();
+        loop {
+            if let Some(ref result) = inner.result {
+                return result.clone();
+            }
+            inner = self
+                .state
+                .condvar
+                .wait(inner)
+                .expect("failed to wait on build job");
+        }
+    }
+}
+
+impl Builder {
+    // Create a builder for graph nodes within the given workspace.
+    //
+    // The background thread is spawned upon the first request.
+    pub(crate) fn new(workspace_dir: PathBuf) -> Self {
+        let (events_tx, events_rx) = mpsc::channel();
+        Builder {
+            workspace_dir,
+            queue: Default::default(),
+            events_tx,
+            events_rx,
+            thread: None,
+        }
+    }
+
+    // Request a build of the graph node's package.
+    //
+    // If a build of the same graph is queued, its job is returned and will compile the latest
+    // source. If the graph is currently being compiled, the running build is superseded.
+    pub(crate) fn request(
+        &mut self,
+        graph: NodeId,
+        package_id: cargo::core::PackageId,
+        instance_ids: BTreeMap<NodeIndex, InstanceId>,
+        profile: BuildProfile,
+    ) -> BuildJob {
+        self.spawn_thread();
+        let mut jobs = self.queue.lock();
+
+        // Coalesce with a queued build of the same graph.
+        let queued = jobs
+            .pending
+            .iter()
+            .find(|job| job.graph == graph && job.lock().result.is_none())
+            .cloned();
+        if let Some(state) = queued {
+            let mut inner = state.lock();
+            inner.package_id = package_id;
+            inner.instance_ids = instance_ids;
+            inner.profile = profile;
+            std::mem::drop(inner);
+            return BuildJob { state };
+        }
+
+        // Supersede a running build of the same graph.
+        if let Some(ref running) = jobs.running {
+            if running.graph == graph {
+                running.cancel(BuildError::Superseded);
+            }
+        }
+
+        let progress = BuildProgress {
+            stage: BuildStage::Queued,
+            crates_compiled: 0,
+            compiling: vec![],
+        };
+        let inner = JobInner {
+            package_id,
+            instance_ids,
+            profile,
+            progress,
+            cancelled: None,
+            result: None,
+        };
+        let state = Arc::new(JobState {
+            graph,
+            inner: Mutex::new(inner),
+            condvar: Condvar::new(),
+        });
+        jobs.pending.push_back(state.clone());
+        self.queue.condvar.notify_all();
+        BuildJob { state }
+    }
+
+    // Events emitted by the background thread that have not yet been received.
+    pub(crate) fn events(&self) -> mpsc::TryIter<BuildEvent> {
+        self.events_rx.try_iter()
+    }
+
+    // Spawn the background thread if it is not already running.
+    fn spawn_thread(&mut self) {
+        if self.thread.is_some() {
+            return;
+        }
+        let ws_dir = self.workspace_dir.clone();
+        let queue = self.queue.clone();
+        let events_tx = self.events_tx.clone();
+        let thread = thread::Builder::new()
+            .name("gantz-build".into())
+            .spawn(move || run(ws_dir, queue, events_tx))
+            .expect("failed to spawn the gantz build thread");
+        self.thread = Some(thread);
+    }
+}
+
+// Cancels all queued and running builds.
+//
+// The background thread is detached rather than joined, as a crate that is already being compiled
+// cannot be interrupted and joining would block until it completes. The thread exits as soon as
+// the running build returns.
+impl Drop for Builder {
+    fn drop(&mut self) {
+        if self.thread.take().is_none() {
+            return;
+        }
+        let mut jobs = self.queue.lock();
+        jobs.closed = true;
+        let running = jobs.running.take();
+        for job in jobs.pending.drain(..).chain(running) {
+            job.cancel(BuildError::Cancelled);
+        }
+        self.queue.condvar.notify_all();
+    }
+}
+
+impl Queue {
+    fn lock(&self) -> MutexGuard<Jobs> {
+        self.jobs.lock().expect("failed to lock build queue")
+    }
+}
+
+impl JobState {
+    fn lock(&self) -> MutexGuard<JobInner> {
+        self.inner.lock().expect("failed to lock build job")
+    }
+
+    // Request that the build stop for the given reason.
+    //
+    // A build that has not yet started compiling is finished immediately.
+    fn cancel(&self, reason: BuildError) {
+        let mut inner = self.lock();
+        if inner.result.is_some() || inner.cancelled.is_some() {
+            return;
+        }
+        if inner.progress.stage == BuildStage::Queued {
+            inner.progress.stage = BuildStage::Finished;
+            inner.result = Some(Err(reason));
+            self.condvar.notify_all();
+        } else {
+            inner.cancelled = Some(reason);
+        }
+    }
+
+    // Called by the executor before compiling the given crate.
+    //
+    // Returns an error if the build has been cancelled.
+    pub(crate) fn crate_started(&self, name: &str) -> cargo::CargoResult<()> {
+        let mut inner = self.lock();
+        if let Some(ref reason) = inner.cancelled {
+            return Err(anyhow::anyhow!("{}", reason));
+        }
+        inner.progress.compiling.push(name.to_string());
+        Ok(())
+    }
+
+    // Called by the executor after compiling the given crate.
+    pub(crate) fn crate_finished(&self, name: &str) {
+        let mut inner = self.lock();
+        if let Some(ix) = inner.progress.compiling.iter().position(|n| n == name) {
+            inner.progress.compiling.remove(ix);
+        }
+        inner.progress.crates_compiled += 1;
+    }
+
+    // Mark the build as finished with the given result and wake all waiting handles.
+    fn finish(&self, result: Result<PathBuf, BuildError>) {
+        let mut inner = self.lock();
+        inner.progress.stage = BuildStage::Finished;
+        inner.progress.compiling.clear();
+        inner.result = Some(result);
+        self.condvar.notify_all();
+    }
+}
+
+// The background build thread.
+//
+// The thread uses its own cargo configuration, as `cargo::Config` may not be shared between
+// threads. Cargo's output is discarded; rustc diagnostics are instead reported via the
+// **GraphNodeCompileError** of failed builds.
+fn run(ws_dir: PathBuf, queue: Arc<Queue>, events_tx: mpsc::Sender<BuildEvent>) {
+    loop {
+        // Wait for the next build that has not been cancelled.
+        let job = {
+            let mut jobs = queue.lock();
+            loop {
+                if jobs.closed {
+                    return;
+                }
+                match jobs.pending.pop_front() {
+                    None => {
+                        jobs = queue.condvar.wait(jobs).expect("failed to wait on queue");
+                    }
+                    Some(job) => {
+                        let mut inner = job.lock();
+                        if inner.result.is_some() {
+                            continue;
+                        }
+                        inner.progress.stage = BuildStage::Compiling;
+                        std::mem::drop(inner);
+                        jobs.running = Some(job.clone());
+                        break job;
+                    }
+                }
+            }
+        };
+
+        let result = build(&ws_dir, &job);
+        queue.lock().running = None;
+
+        // Prefer reporting the cancellation over any error caused by it.
+        let cancelled = job.lock().cancelled.clone();
+        let result = match (cancelled, result) {
+            (Some(reason), _) => Err(reason),
+            (None, Ok(dylib)) => {
+                let graph = job.graph;
+                let event = BuildEvent::Ready {
+                    graph,
+                    dylib: dylib.clone(),
+                };
+                events_tx.send(event).ok();
+                Ok(dylib)
+            }
+            (None, Err(err)) => {
+                let err = Arc::new(err);
+                let graph = job.graph;
+                let event = BuildEvent::Failed {
+                    graph,
+                    err: err.clone(),
+                };
+                events_tx.send(event).ok();
+                Err(BuildError::Compile { err })
+            }
+        };
+        job.finish(result);
+    }
+}
+
+// Compile the job's package and return the path to the resulting dynamic library.
+//
+// A new cargo configuration is created for each build so that changes to the project's
+// **ProjectConfig** are respected.
+fn build(ws_dir: &Path, job: &Arc<JobState>) -> Result<PathBuf, GraphNodeCompileError> {
+    let cargo_config = workspace_cargo_config(ws_dir)?;
+    *cargo_config.shell() = cargo::core::Shell::from_write(Box::new(std::io::sink()));
+    let (package_id, instance_ids, profile) = {
+        let inner = job.lock();
+        let instance_ids = inner.instance_ids.clone();
+        (inner.package_id, instance_ids, inner.profile.clone())
+    };
+    let (cfg, exec_job) = (&cargo_config, Some(job.clone()));
+    graph_node_compile_package(ws_dir, cfg, &profile, package_id, &instance_ids, exec_job)?;
+    package_dylib(ws_dir, cfg, &profile, package_id)?
+        .ok_or_else(|| anyhow::anyhow!("no dynamic library was produced").into())
+}
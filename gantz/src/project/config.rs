@@ -0,0 +1,197 @@
+//! Items related to configuring how the crates generated for a project's graph nodes are built.
+//!
+//! A project's **ProjectConfig** is stored at `<proj_dir>/project_config.json`. The profile
+//! settings, target directory and rustflags are applied via a cargo configuration file generated
+//! at `<ws_dir>/.cargo/config.toml`. See `Project::set_config`.
+//!
+//! A `.cargo/config.toml` that was not generated by gantz is never overwritten or removed. As the
+//! file would apply to all crates within the workspace, it is never generated for projects that
+//! share an external workspace.
+
+use super::{write_atomic, JsonFileError};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+// The first line of each cargo configuration file generated by gantz.
+const CARGO_CONFIG_TOML_HEADER: &str = "# Generated by gantz. Changes will be overwritten.\n";
+
+/// Configuration for building the crates generated for a project's graph nodes.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct ProjectConfig {
+    /// The cargo profile with which graph nodes are compiled.
+    #[serde(default)]
+    pub profile: BuildProfile,
+    /// Overrides the `opt-level` of the profile, e.g. `"0"`, `"3"`, `"s"` or `"z"`.
+    #[serde(default)]
+    pub opt_level: Option<String>,
+    /// Overrides the `codegen-units` of the profile.
+    #[serde(default)]
+    pub codegen_units: Option<u32>,
+    /// Overrides the `lto` setting of the profile.
+    #[serde(default)]
+    pub lto: Option<Lto>,
+    /// The directory in which build artifacts are placed.
+    ///
+    /// Relative paths are relative to the project's workspace directory. By default, this is
+    /// `<ws_dir>/target`.
+    #[serde(default)]
+    pub target_dir: Option<PathBuf>,
+    /// Extra flags passed to rustc when compiling each crate, equivalent to `RUSTFLAGS`.
+    #[serde(default)]
+    pub rustflags: Vec<String>,
+}
+
+/// The cargo profile used to compile the project's graph nodes.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum BuildProfile {
+    /// The `dev` profile. Compiles quickly, but produces slower code.
+    Debug,
+    /// The `release` profile. This is the default.
+    Release,
+    /// A custom named profile.
+    ///
+    /// The profile must be declared within the workspace `Cargo.toml` and must be supported by
+    /// the version of cargo in use.
+    Custom(String),
+}
+
+/// The link-time optimisation performed when compiling graph nodes.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum Lto {
+    /// No link-time optimisation.
+    Off,
+    /// "Thin" LTO.
+    Thin,
+    /// "Fat" LTO across all crates.
+    Fat,
+}
+
+impl ProjectConfig {
+    // Load the config from the given path.
+    pub(crate) fn load<P>(path: P) -> Result<Self, JsonFileError>
+    where
+        P: AsRef<Path>,
+    {
+        let file = fs::File::open(path)?;
+        let t = serde_json::from_reader(file)?;
+        Ok(t)
+    }
+
+    // Save the config to the given path.
+    pub(crate) fn save<P>(&self, path: P) -> Result<(), JsonFileError>
+    where
+        P: AsRef<Path>,
+    {
+        let bytes = serde_json::to_vec_pretty(self)?;
+        write_atomic(path, &bytes)?;
+        Ok(())
+    }
+
+    /// The cargo configuration that applies this config to a workspace.
+    ///
+    /// Only the settings that are specified are included.
+    pub fn cargo_config_toml(&self) -> toml::Value {
+        let mut build = toml::value::Table::new();
+        if let Some(ref dir) = self.target_dir {
+            let dir = dir.to_string_lossy().into_owned();
+            build.insert("target-dir".into(), dir.into());
+        }
+        if !self.rustflags.is_empty() {
+            build.insert("rustflags".into(), self.rustflags.clone().into());
+        }
+
+        let mut profile = toml::value::Table::new();
+        if let Some(ref opt_level) = self.opt_level {
+            let value = match opt_level.parse::<i64>() {
+                Ok(n) => n.into(),
+                Err(_) => opt_level.clone().into(),
+            };
+            profile.insert("opt-level".into(), value);
+        }
+        if let Some(codegen_units) = self.codegen_units {
+            profile.insert("codegen-units".into(), i64::from(codegen_units).into());
+        }
+        if let Some(lto) = self.lto {
+            let value = match lto {
+                Lto::Off => false.into(),
+                Lto::Thin => "thin".into(),
+                Lto::Fat => "fat".into(),
+            };
+            profile.insert("lto".into(), value);
+        }
+
+        let mut table = toml::value::Table::new();
+        if !build.is_empty() {
+            table.insert("build".into(), build.into());
+        }
+        if !profile.is_empty() {
+            let mut profiles = toml::value::Table::new();
+            profiles.insert(self.profile.name().into(), profile.into());
+            table.insert("profile".into(), profiles.into());
+        }
+        table.into()
+    }
+
+    // Write the cargo configuration for this config to the given path, or remove the file if
+    // there is nothing to configure.
+    //
+    // Returns an error of kind `AlreadyExists` if a file not generated by gantz exists at the path.
+    pub(crate) fn write_cargo_config_toml<P>(&self, path: P) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        match fs::read_to_string(path) {
+            Ok(ref existing) if !existing.starts_with(CARGO_CONFIG_TOML_HEADER) => {
+                let msg = format!("{:?} was not generated by gantz", path);
+                return Err(io::Error::new(io::ErrorKind::AlreadyExists, msg));
+            }
+            Err(ref err) if err.kind() != io::ErrorKind::NotFound => {
+                return Err(io::Error::new(err.kind(), err.to_string()));
+            }
+            _ => (),
+        }
+        let toml = self.cargo_config_toml();
+        let is_empty = toml.as_table().map(|t| t.is_empty()).unwrap_or(true);
+        if is_empty {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+            return Ok(());
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let toml_string = toml::to_string_pretty(&toml)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let contents = format!("{}{}", CARGO_CONFIG_TOML_HEADER, toml_string);
+        write_atomic(path, contents.as_bytes())
+    }
+}
+
+impl BuildProfile {
+    /// The name of the profile as understood by cargo.
+    pub fn name(&self) -> &str {
+        match *self {
+            BuildProfile::Debug => "dev",
+            BuildProfile::Release => "release",
+            BuildProfile::Custom(ref name) => name,
+        }
+    }
+
+    /// The name of the directory within the target directory in which the profile's artifacts are
+    /// placed.
+    pub fn dir_name(&self) -> &str {
+        match *self {
+            BuildProfile::Debug => "debug",
+            _ => self.name(),
+        }
+    }
+}
+
+impl Default for BuildProfile {
+    fn default() -> Self {
+        BuildProfile::Release
+    }
+}
@@ -0,0 +1,3133 @@
+use crate::graph::{self, Edge, GraphNode};
+use crate::node::{self, Node, SerdeNode};
+use crate::runtime::{self, LoadGraphError, LoadedGraph};
+use petgraph::visit::GraphBase;
+use quote::ToTokens;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::{fs, io, ops};
+use thiserror::Error;
+
+pub mod build;
+pub mod config;
+pub mod verify;
+
+/// A gantz **Project** represents the context in which the user composes their gantz graph
+/// together at runtime.
+///
+/// The **Project** is responsible for managing the project directory - the directory in which the
+/// project can be saved and loaded and in which the workspace is situated and maintained.
+///
+/// Each project either shares an existing cargo workspace or has their own associated cargo
+/// workspace, which stores all locally created **node** crates. See `Project::open` and
+/// `Project::open_in_workspace`.
+pub struct Project {
+    /// Configuration information for cargo.
+    ///
+    /// Cargo is used to manage the project's workspace and its crates.
+    cargo_config: cargo::Config,
+    /// The path to the project directory.
+    ///
+    /// E.g. `~/gantz/projects/foo/`.
+    directory: PathBuf,
+    /// The path to the cargo workspace in which the project's node crates are stored.
+    ///
+    /// This is `<proj_dir>/workspace/` unless the project shares an existing workspace.
+    workspace_dir: PathBuf,
+    /// All nodes that have been imported into the project ready for use.
+    nodes: NodeCollection,
+    /// Whether or not the **NodeCollection** should be saved after each mutation.
+    ///
+    /// This is `false` by default.
+    autosave: bool,
+    /// Configuration for building the project's graph nodes.
+    config: config::ProjectConfig,
+    /// Compiles graph nodes on a background thread.
+    builder: build::Builder,
+}
+
+/// A wrapper around a `Project` that behaves exactly like a `Project` but removes the project
+/// directory and all its contents on `Drop`.
+///
+/// It also provides an alternate constructor that allows for specifying only the project name
+/// rather than the entire path. In this case, the temporary project will be created within the
+/// directory returned by `std::env::temp_dir()`.
+pub struct TempProject {
+    // An `Option` is used so that we can ensure the `Project` is dropped before cleaning up the
+    // directory. This will always be `Some` for the lifetime of the `TempProject` until drop.
+    project: Option<Project>,
+}
+
+/// A unique identifier representing an imported node.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct NodeId(u64);
+
+/// The BTreeMap used for storing imported nodes.
+pub type NodeTree = BTreeMap<NodeId, NodeKind>;
+
+/// Stores all nodes that have been created within or imported into the project.
+#[derive(Default, Deserialize, Serialize)]
+pub struct NodeCollection {
+    map: NodeTree,
+    // The lower bound for the next `NodeId`. Ensures IDs of removed nodes are never reused.
+    #[serde(default)]
+    next_id: u64,
+}
+
+/// The type used to represent node and edge indices.
+pub type Index = usize;
+pub type EdgeIndex = petgraph::graph::EdgeIndex<Index>;
+pub type NodeIndex = petgraph::graph::NodeIndex<Index>;
+
+/// The petgraph type used to represent a stable gantz graph.
+pub type StableGraph<N> = petgraph::stable_graph::StableGraph<N, Edge, petgraph::Directed, Index>;
+
+/// A graph composed of IDs into the `NodeCollection`.
+pub type NodeIdGraph = StableGraph<NodeId>;
+
+/// A **NodeIdGraph** along with its inlets and outlets.
+pub type NodeIdGraphNode = GraphNode<NodeIdGraph>;
+
+/// A graph to references to the actual **Node** implementations.
+///
+/// This graph is constructed at the time of code generation using the project's **NodeIdGraph**
+/// and the **NodeCollection**.
+type NodeRefGraph<'a> = StableGraph<NodeRef<'a>>;
+
+/// A **NodeRefGraph** along with the **NodeId** and cargo **PackageId** for the graph within this
+/// project.
+///
+/// This type implements the **EvaluatorFnBlock** implementation, enabling
+/// **ProjectNodeRefGraphNode** to implement the **Node** type.
+// TODO: Implement `GraphBase` and `EvaluatorFnBlock`.
+pub struct ProjectNodeRefGraph<'a> {
+    pub graph: NodeRefGraph<'a>,
+    pub id: NodeId,
+    pub package_id: cargo::core::PackageId,
+    pub instance_ids: &'a BTreeMap<NodeIndex, InstanceId>,
+}
+
+/// Shorthand for a **GraphNode** wrapped around a **ProjectNodeRefGraph**.
+pub type ProjectNodeRefGraphNode<'a> = GraphNode<ProjectNodeRefGraph<'a>>;
+
+/// Whether the node is a **Core** node (has no other internal **Node** dependencies) or is a
+/// **Graph** node, composed entirely of other gantz **Node**s.
+#[derive(Deserialize, Serialize)]
+pub enum NodeKind {
+    Core(Box<dyn SerdeNode>),
+    Graph(ProjectGraph),
+}
+
+/// A gantz node graph useful within gantz `Project`s.
+///
+/// This can be thought of as a node that is a graph composed of other nodes.
+#[derive(Deserialize, Serialize)]
+pub struct ProjectGraph {
+    pub graph: NodeIdGraphNode,
+    pub package_id: cargo::core::PackageId,
+    /// The persistent instance ID of each node within the graph.
+    #[serde(default)]
+    instance_ids: BTreeMap<NodeIndex, InstanceId>,
+    /// The instance ID to assign to the next node added to the graph.
+    #[serde(default)]
+    next_instance_id: u64,
+}
+
+/// The graph passed to the `update` function of `Project::update_graph`.
+///
+/// Dereferences to the **NodeIdGraphNode** being updated. The nodes removed via `remove_node`,
+/// `retain_nodes` or `clear` are recorded. As a **StableGraph** reuses the indices of removed
+/// nodes, this allows for a node added at the index of a removed node to be assigned a new
+/// **InstanceId** rather than inheriting the instance ID (and in turn the state) of the removed
+/// node. Nodes must not be removed via the inner graph directly.
+pub struct GraphUpdate<'a> {
+    graph: &'a mut NodeIdGraphNode,
+    removed: HashSet<NodeIndex>,
+}
+
+/// A persistent identifier for a single instance of a node within a **ProjectGraph**.
+///
+/// Unlike a **NodeIndex**, an instance ID is never reused after its node is removed from the
+/// graph. Instance IDs are used to identify nodes within generated code and to key node state.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct InstanceId(u64);
+
+/// A **Node** type constructed as a reference to a type implementing **Node**.
+///
+/// A graph of **NodeRef**s are created at the time of codegen in order to.
+pub enum NodeRef<'a> {
+    Core(&'a dyn Node),
+    Graph(ProjectNodeRefGraphNode<'a>),
+}
+
+/// Errors that may occur while creating a node crate.
+#[derive(Debug, Error)]
+pub enum OpenNodePackageError {
+    #[error("an IO error occurred: {err}")]
+    Io {
+        #[from]
+        err: io::Error,
+    },
+    #[error("a cargo error occurred: {err}")]
+    Cargo {
+        #[from]
+        err: anyhow::Error,
+    },
+    #[error("failed to update the manifest toml: {err}")]
+    UpdateTomlFile {
+        #[from]
+        err: UpdateTomlFileError,
+    },
+}
+
+/// Errors that may occur while checking an existing workspace or creating a new one.
+#[derive(Debug, Error)]
+pub enum CreateOrCheckWorkspaceError {
+    #[error("an IO error occurred: {err}")]
+    Io {
+        #[from]
+        err: io::Error,
+    },
+    #[error("cargo failed to open the workspace: {err}")]
+    Cargo {
+        #[from]
+        err: anyhow::Error,
+    },
+    #[error("failed to read the workspace manifest: {err}")]
+    TomlDeserialize {
+        #[from]
+        err: toml::de::Error,
+    },
+    #[error("the manifest at {path:?} has no `[workspace]` table")]
+    NotAWorkspace { path: PathBuf },
+}
+
+/// Errors that may occur while checking an existing project directory or creating a new one.
+#[derive(Debug, Error)]
+pub enum CreateOrCheckProjectDirectoryError {
+    #[error("an IO error occurred: {err}")]
+    Io {
+        #[from]
+        err: io::Error,
+    },
+    #[error("cargo failed to open the workspace: {err}")]
+    Workspace {
+        #[from]
+        err: CreateOrCheckWorkspaceError,
+    },
+}
+
+/// Errors that may occur while creating a new project.
+#[derive(Debug, Error)]
+pub enum ProjectOpenError {
+    #[error("an IO error occurred: {err}")]
+    Io {
+        #[from]
+        err: io::Error,
+    },
+    #[error("failed to initialise cargo config: {err}")]
+    CargoConfig {
+        #[from]
+        err: anyhow::Error,
+    },
+    #[error("failed to create or check existing project directory: {err}")]
+    Project {
+        #[from]
+        err: CreateOrCheckProjectDirectoryError,
+    },
+    #[error("failed to add the graph node to the collection: {err}")]
+    AddGraphNodeToCollection {
+        #[from]
+        err: AddGraphNodeToCollectionError,
+    },
+    #[error("failed to compile the root graph node: {err}")]
+    GraphNodeCompile {
+        #[from]
+        err: GraphNodeCompileError,
+    },
+    #[error("failed to save the new node collection: {err}")]
+    Save {
+        #[from]
+        err: JsonFileError,
+    },
+    #[error("the external workspace record at {path:?} could not be read or written: {err}")]
+    ExternalWorkspace {
+        path: PathBuf,
+        #[source]
+        err: JsonFileError,
+    },
+    #[error("the project uses the workspace at {recorded:?}, not {requested:?}")]
+    WorkspaceMismatch {
+        recorded: PathBuf,
+        requested: PathBuf,
+    },
+    #[error("the project config at {path:?} is corrupt or unreadable: {err}")]
+    Config {
+        path: PathBuf,
+        #[source]
+        err: JsonFileError,
+    },
+    #[error("the node collection at {path:?} is corrupt or unreadable: {err}")]
+    NodeCollection {
+        path: PathBuf,
+        #[source]
+        err: JsonFileError,
+    },
+    #[error("failed to verify the node collection: {err}")]
+    Verify {
+        #[from]
+        err: verify::VerifyError,
+    },
+    #[error("the node collection is inconsistent with the project:\n{report}")]
+    Inconsistent { report: verify::VerifyReport },
+    #[error("failed to repair the node collection: {err}")]
+    Repair {
+        #[from]
+        err: verify::RepairError,
+    },
+}
+
+/// Errors that may occur while adding a core node to the project.
+#[derive(Debug, Error)]
+pub enum AddCoreNodeError {
+    #[error("failed to save the node collection: {err}")]
+    Save {
+        #[from]
+        err: JsonFileError,
+    },
+}
+
+/// Errors that may occur while adding a graph node to the project.
+#[derive(Debug, Error)]
+pub enum AddGraphNodeError {
+    #[error("failed to add the graph node to the collection: {err}")]
+    AddGraphNodeToCollection {
+        #[from]
+        err: AddGraphNodeToCollectionError,
+    },
+    #[error("failed to save the node collection: {err}")]
+    Save {
+        #[from]
+        err: JsonFileError,
+    },
+}
+
+/// Errors that might occur when saving or loading JSON from a file.
+#[derive(Debug, Error)]
+pub enum JsonFileError {
+    #[error("an IO error occurred: {err}")]
+    Io {
+        #[from]
+        err: io::Error,
+    },
+    #[error("a JSON error occurred: {err}")]
+    Json {
+        #[from]
+        err: serde_json::Error,
+    },
+}
+
+/// Errors that may occur while retrieving the root directory of a package within the workspace.
+#[derive(Debug, Error)]
+pub enum PackageRootError {
+    #[error("a cargo error occurred: {err}")]
+    Cargo {
+        #[from]
+        err: anyhow::Error,
+    },
+    #[error("no matching `package_id` in workspace that matches graph node `package_id`")]
+    NoMatchingPackageId,
+}
+
+/// Errors that may occur when updating the dependencies of a graph node.
+#[derive(Debug, Error)]
+pub enum GraphNodeInsertDepsError {
+    #[error("failed to update the Cargo.toml file: {err}")]
+    UpdateTomlFile {
+        #[from]
+        err: UpdateTomlFileError,
+    },
+    #[error("the `CrateDep` could not be parsed as a valid dependency table")]
+    InvalidCrateDep { dep: node::CrateDep },
+    #[error("the graph requires two separate versions of the `{name}` crate")]
+    ConflictingCrateDeps { name: String },
+    #[error("failed to parse `CrateDep`'s `source` field as valid toml value: {err}")]
+    InvalidCrateDepSource {
+        #[from]
+        err: toml::de::Error,
+    },
+    #[error("failed to retrieve graph node package root directory: {err}")]
+    PackageRoot {
+        #[from]
+        err: PackageRootError,
+    },
+}
+
+/// Errors that may occur while replacing the source within a graph node crate.
+#[derive(Debug, Error)]
+pub enum GraphNodeReplaceSrcError {
+    #[error("an IO error occurred: {err}")]
+    Io {
+        #[from]
+        err: io::Error,
+    },
+    #[error("failed to retrieve graph node package root directory: {err}")]
+    PackageRoot {
+        #[from]
+        err: PackageRootError,
+    },
+}
+
+/// Errors that may occur while adding a graph node to a project's **NodeCollection**.
+#[derive(Debug, Error)]
+pub enum AddGraphNodeToCollectionError {
+    #[error("failed to open node cargo package: {err}")]
+    OpenNodePackage {
+        #[from]
+        err: OpenNodePackageError,
+    },
+    #[error("failed to insert deps to the Cargo.toml of the graph node: {err}")]
+    GraphNodeInsertDeps {
+        #[from]
+        err: GraphNodeInsertDepsError,
+    },
+    #[error("failed to update the src/lib.rs of the graph node: {err}")]
+    GraphNodeReplaceSrc {
+        #[from]
+        err: GraphNodeReplaceSrcError,
+    },
+}
+
+/// Errors that might occur while updating the contents of a toml file.
+#[derive(Debug, Error)]
+pub enum UpdateTomlFileError {
+    #[error("an IO error occurred: {err}")]
+    Io {
+        #[from]
+        err: io::Error,
+    },
+    #[error("failed to deserialize the toml: {err}")]
+    TomlDeserialize {
+        #[from]
+        err: toml::de::Error,
+    },
+    #[error("failed to serialize the toml: {err}")]
+    TomlSerialize {
+        #[from]
+        err: toml::ser::Error,
+    },
+    #[error("failed to parse the toml document: {err}")]
+    TomlEdit {
+        #[from]
+        err: toml_edit::TomlError,
+    },
+}
+
+/// Errors that might occur while compiling the project workspace.
+#[derive(Debug, Error)]
+pub enum WorkspaceCompileError {
+    #[error("a cargo error occurred: {err}")]
+    Cargo {
+        #[from]
+        err: anyhow::Error,
+    },
+}
+
+/// Errors that might occur while compiling a graph node.
+#[derive(Debug, Error)]
+pub enum GraphNodeCompileError {
+    #[error("a cargo error occurred: {err}")]
+    Cargo {
+        #[from]
+        err: anyhow::Error,
+    },
+    #[error("no matching `package_id` in workspace that matches graph node `package_id`")]
+    NoMatchingPackageId,
+    #[error("failed to compile the generated graph code: {err}")]
+    Rustc {
+        /// The diagnostics emitted by rustc, attributed to the nodes within the graph.
+        diagnostics: Vec<NodeDiagnostic>,
+        #[source]
+        err: anyhow::Error,
+    },
+}
+
+/// A diagnostic emitted by rustc while compiling the code generated for a graph node.
+///
+/// Where possible, the diagnostic is attributed to the node within the graph whose generated code
+/// caused the diagnostic.
+#[derive(Clone, Debug)]
+pub struct NodeDiagnostic {
+    /// The node whose generated code the diagnostic refers to, if known.
+    pub node: Option<NodeIndex>,
+    /// The input of the node, if the diagnostic refers to the value passed to a specific input.
+    pub input: Option<node::Input>,
+    /// The severity of the diagnostic, e.g. "error" or "warning".
+    pub level: String,
+    /// The primary message of the diagnostic.
+    pub message: String,
+    /// The diagnostic as it would be rendered by rustc, if available.
+    pub rendered: Option<String>,
+}
+
+/// Errors that might occur while replacing the project's **ProjectConfig**.
+#[derive(Debug, Error)]
+pub enum SetConfigError {
+    #[error("the build settings cannot be applied to the shared workspace at {workspace_dir:?}")]
+    ExternalWorkspace { workspace_dir: PathBuf },
+    #[error("failed to write the cargo config: {err}")]
+    CargoConfigToml {
+        #[from]
+        err: io::Error,
+    },
+    #[error("failed to save the project config: {err}")]
+    Save {
+        #[from]
+        err: JsonFileError,
+    },
+    #[error("failed to initialise cargo config: {err}")]
+    CargoConfig {
+        #[from]
+        err: anyhow::Error,
+    },
+    #[error("failed to compile graph node: {err}")]
+    GraphNodeCompile {
+        #[from]
+        err: GraphNodeCompileError,
+    },
+}
+
+/// Errors that might occur while updating a `ProjectGraph`'s graph.
+#[derive(Debug, Error)]
+pub enum UpdateGraphError {
+    #[error("the graph is invalid: {} problem(s) found", .diagnostics.len())]
+    InvalidGraph { diagnostics: Vec<Diagnostic> },
+    #[error("failed to update grap node dependencies: {err}")]
+    GraphNodeInsertDeps {
+        #[from]
+        err: GraphNodeInsertDepsError,
+    },
+    #[error("failed to replace graph node src: {err}")]
+    GraphNodeReplaceSrc {
+        #[from]
+        err: GraphNodeReplaceSrcError,
+    },
+    #[error("failed to compile graph node: {err}")]
+    GraphNodeCompile {
+        #[from]
+        err: GraphNodeCompileError,
+    },
+    #[error("failed to regenerate dependent graph node {id:?}: {err}")]
+    RegenerateDependent {
+        id: NodeId,
+        #[source]
+        err: RegenerateGraphNodeError,
+    },
+    #[error("failed to save the node collection: {err}")]
+    Save {
+        #[from]
+        err: JsonFileError,
+    },
+}
+
+/// Describes how to handle graphs that reference a node that is being removed, renamed or replaced.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ReferencePolicy {
+    /// Refuse to modify the node if one or more graphs reference it.
+    Refuse,
+    /// Modify the node and update all graphs that reference it.
+    ///
+    /// In the case of removal, all instances of the node are removed from each referencing graph.
+    /// In all cases, referencing graphs are regenerated and recompiled.
+    Cascade,
+}
+
+/// All graphs that reference some node, along with the indices of the node's instances within
+/// each graph.
+pub type NodeReferences = BTreeMap<NodeId, Vec<NodeIndex>>;
+
+/// An edge that was removed from a graph as it referred to an output or input that no longer
+/// exists.
+pub type PrunedEdge = graph::InvalidEdge<NodeIndex, EdgeIndex>;
+
+/// A problem found within a graph that prevents it from being compiled.
+pub type Diagnostic = graph::validate::Diagnostic<NodeIndex, EdgeIndex>;
+
+/// Errors that may occur while regenerating the source of a graph node and recompiling it.
+#[derive(Debug, Error)]
+pub enum RegenerateGraphNodeError {
+    #[error("the graph is invalid: {} problem(s) found", .diagnostics.len())]
+    InvalidGraph { diagnostics: Vec<Diagnostic> },
+    #[error("failed to update graph node dependencies: {err}")]
+    GraphNodeInsertDeps {
+        #[from]
+        err: GraphNodeInsertDepsError,
+    },
+    #[error("failed to replace graph node src: {err}")]
+    GraphNodeReplaceSrc {
+        #[from]
+        err: GraphNodeReplaceSrcError,
+    },
+    #[error("failed to compile graph node: {err}")]
+    GraphNodeCompile {
+        #[from]
+        err: GraphNodeCompileError,
+    },
+}
+
+/// Errors that may occur while removing a node from a project.
+#[derive(Debug, Error)]
+pub enum RemoveNodeError {
+    #[error("no node exists for the given `NodeId`")]
+    NoSuchNode,
+    #[error("the root graph node cannot be removed")]
+    RootNode,
+    #[error("the node is referenced by {} graph(s)", .references.len())]
+    Referenced { references: NodeReferences },
+    #[error("an IO error occurred: {err}")]
+    Io {
+        #[from]
+        err: io::Error,
+    },
+    #[error("failed to remove the crate from the workspace members: {err}")]
+    UpdateTomlFile {
+        #[from]
+        err: UpdateTomlFileError,
+    },
+    #[error("failed to regenerate a referencing graph: {err}")]
+    RegenerateGraphNode {
+        #[from]
+        err: RegenerateGraphNodeError,
+    },
+    #[error("failed to save the node collection: {err}")]
+    Save {
+        #[from]
+        err: JsonFileError,
+    },
+}
+
+/// Errors that may occur while renaming a graph node.
+#[derive(Debug, Error)]
+pub enum RenameGraphNodeError {
+    #[error("no graph node exists for the given `NodeId`")]
+    NoSuchGraphNode,
+    #[error("the root graph node must be named after the project and cannot be renamed")]
+    RootNode,
+    #[error("the node is referenced by {} graph(s)", .references.len())]
+    Referenced { references: NodeReferences },
+    #[error("a crate named `{name}` already exists within the workspace")]
+    CrateExists { name: String },
+    #[error("an IO error occurred: {err}")]
+    Io {
+        #[from]
+        err: io::Error,
+    },
+    #[error("a cargo error occurred: {err}")]
+    Cargo {
+        #[from]
+        err: anyhow::Error,
+    },
+    #[error("failed to retrieve graph node package root directory: {err}")]
+    PackageRoot {
+        #[from]
+        err: PackageRootError,
+    },
+    #[error("failed to update the manifest toml: {err}")]
+    UpdateTomlFile {
+        #[from]
+        err: UpdateTomlFileError,
+    },
+    #[error("failed to regenerate a graph: {err}")]
+    RegenerateGraphNode {
+        #[from]
+        err: RegenerateGraphNodeError,
+    },
+    #[error("failed to save the node collection: {err}")]
+    Save {
+        #[from]
+        err: JsonFileError,
+    },
+}
+
+/// Errors that may occur while replacing a core node.
+#[derive(Debug, Error)]
+pub enum ReplaceCoreNodeError {
+    #[error("no core node exists for the given `NodeId`")]
+    NoSuchCoreNode,
+    #[error("the node is referenced by {} graph(s)", .references.len())]
+    Referenced { references: NodeReferences },
+    #[error("failed to regenerate a referencing graph: {err}")]
+    RegenerateGraphNode {
+        #[from]
+        err: RegenerateGraphNodeError,
+    },
+    #[error("failed to save the node collection: {err}")]
+    Save {
+        #[from]
+        err: JsonFileError,
+    },
+}
+
+/// Errors that may occur while reconfiguring the inputs and outputs of a core node.
+#[derive(Debug, Error)]
+pub enum SetCoreNodeIoError {
+    #[error("no core node exists for the given `NodeId`")]
+    NoSuchCoreNode,
+    #[error("the core node has a fixed number of inputs and outputs")]
+    FixedIo,
+    #[error("{err}")]
+    UnsupportedIo {
+        #[from]
+        err: node::UnsupportedIoError,
+    },
+    #[error("the node is referenced by {} graph(s)", .references.len())]
+    Referenced { references: NodeReferences },
+    #[error("failed to regenerate a referencing graph: {err}")]
+    RegenerateGraphNode {
+        #[from]
+        err: RegenerateGraphNodeError,
+    },
+    #[error("failed to save the node collection: {err}")]
+    Save {
+        #[from]
+        err: JsonFileError,
+    },
+}
+
+/// A node from a corrupt node collection that failed to deserialize during recovery.
+#[derive(Debug)]
+pub struct FailedNode {
+    /// The key under which the node was stored within the collection.
+    ///
+    /// This is normally the **NodeId**, but is kept as a string in case the key itself is invalid.
+    pub key: String,
+    /// The error that occurred while deserializing the node.
+    pub err: serde_json::Error,
+}
+
+/// A report describing the recovery of a corrupt node collection.
+///
+/// Produced by `Project::open_with_recovery`.
+#[derive(Debug)]
+pub struct NodeCollectionRecovery {
+    /// The path to which the original node collection was copied before recovery.
+    pub backup_path: PathBuf,
+    /// All nodes that failed to deserialize and that were dropped from the collection.
+    ///
+    /// This is empty if the collection itself deserialized successfully or if it could not be
+    /// parsed as JSON at all. In the latter case the recovered collection only contains a new
+    /// root graph.
+    pub failed_nodes: Vec<FailedNode>,
+    /// The repairs applied to the collection after loading it. See `Project::repair`.
+    pub repair: verify::RepairReport,
+}
+
+/// Node crates within the project workspace are prefixed with this.
+pub const NODE_CRATE_PREFIX: &'static str = "gantz_node_";
+
+/// The **NodeId** of the project's root graph node.
+pub const ROOT_NODE_ID: NodeId = NodeId(0);
+
+impl Project {
+    /// Open a project at the given directory path.
+    ///
+    /// If the project does not yet exist, it will be created.
+    ///
+    /// First, the project directory is prepared:
+    ///
+    /// - Creates the given project directory if it does not yet exist.
+    /// - Creates the cargo workspace at `<proj_dir>/workspace` if it does not yet exist.
+    /// - Initialises `<proj_dir>/workspace/Cargo.toml` with an empty members list if it does not
+    ///   yet exist.
+    ///
+    /// The "name" of the project will match the final segment in the given directory path.
+    ///
+    /// Next, a crate for the project's root node is created:
+    ///
+    /// - Initialises a root node lib crate with the same name as the project. E.g.
+    ///   `<proj_dir>/workspace/<proj_name>/`
+    /// - Initialises `<proj_dir>/workspace/<proj_name>/Cargo.toml`.
+    /// - Initialises an empty `<proj_dir>/workspace/<proj_name>/src/lib.rs` file.
+    ///
+    /// Finally, the new node collection is written to `<proj_dir>/node_collection.json`.
+    ///
+    /// If a node collection already exists, it is loaded and checked for consistency with the
+    /// workspace via `Project::verify`. If the collection cannot be read or deserialized or if it
+    /// is inconsistent, an error is returned and the existing file is left untouched. See
+    /// `open_with_recovery` for a way of opening such a project.
+    ///
+    /// If the project was created via `open_in_workspace`, the recorded external workspace is
+    /// used rather than `<proj_dir>/workspace`.
+    pub fn open(directory: PathBuf) -> Result<Self, ProjectOpenError> {
+        let (project, _recovery) = Self::open_inner(directory, None, false)?;
+        Ok(project)
+    }
+
+    /// The same as `open`, but the project's node crates are stored within the existing cargo
+    /// workspace at the given directory rather than at `<proj_dir>/workspace`.
+    ///
+    /// Node crates are added as members of the workspace, sharing its `target` directory and
+    /// `Cargo.lock` with all other members. Only the members of the workspace `Cargo.toml` are
+    /// edited, preserving its formatting and comments. Node crate names must be unique within the
+    /// workspace, including the root graph node crate that is named after the project.
+    ///
+    /// The location of the workspace is recorded at `external_workspace_json_path` so that the
+    /// project may later be re-opened via `open`. An error is returned if the project was
+    /// previously opened within a different workspace.
+    pub fn open_in_workspace(
+        directory: PathBuf,
+        workspace_dir: PathBuf,
+    ) -> Result<Self, ProjectOpenError> {
+        let (project, _recovery) = Self::open_inner(directory, Some(workspace_dir), false)?;
+        Ok(project)
+    }
+
+    /// The same as `open`, but attempts to recover a project whose node collection is corrupt or
+    /// inconsistent.
+    ///
+    /// Before any changes are made, the original collection is copied to a backup path (see
+    /// `node_collection_backup_path`).
+    ///
+    /// If the node collection fails to deserialize, each node is deserialized individually. Nodes
+    /// that fail to deserialize are dropped from the collection and listed in the returned
+    /// **NodeCollectionRecovery**. The collection is then repaired via `Project::repair`, e.g.
+    /// references to dropped nodes are removed and a new root graph is created if necessary.
+    /// Finally, the recovered collection is saved.
+    ///
+    /// The returned recovery report is `None` if the collection loaded without error and was
+    /// found to be consistent.
+    pub fn open_with_recovery(
+        directory: PathBuf,
+    ) -> Result<(Self, Option<NodeCollectionRecovery>), ProjectOpenError> {
+        Self::open_inner(directory, None, true)
+    }
+
+    // Shared implementation of `open`, `open_in_workspace` and `open_with_recovery`.
+    fn open_inner(
+        directory: PathBuf,
+        external_workspace: Option<PathBuf>,
+        recover: bool,
+    ) -> Result<(Self, Option<NodeCollectionRecovery>), ProjectOpenError> {
+        // Determine the workspace, checking it against any previously recorded external workspace.
+        let ext_ws_path = external_workspace_json_path(&directory);
+        let recorded = match load_external_workspace(&ext_ws_path) {
+            Ok(recorded) => recorded,
+            Err(err) => {
+                let path = ext_ws_path;
+                return Err(ProjectOpenError::ExternalWorkspace { path, err });
+            }
+        };
+        let external_workspace = match external_workspace {
+            None => recorded.clone(),
+            Some(dir) => Some(fs::canonicalize(dir)?),
+        };
+        if let (Some(recorded), Some(requested)) = (&recorded, &external_workspace) {
+            if recorded != requested {
+                let recorded = recorded.clone();
+                let requested = requested.clone();
+                return Err(ProjectOpenError::WorkspaceMismatch {
+                    recorded,
+                    requested,
+                });
+            }
+        }
+        let ws_dir = match external_workspace {
+            Some(ref dir) => dir.clone(),
+            None => workspace_dir(&directory),
+        };
+        let cargo_config = workspace_cargo_config(&ws_dir)?;
+
+        // Prepare the project directory.
+        let ext_ws_dir = external_workspace.as_ref().map(|dir| dir.as_path());
+        create_or_check_project_dir(&directory, ext_ws_dir, &cargo_config)?;
+        if let (None, Some(dir)) = (&recorded, &external_workspace) {
+            if let Err(err) = save_external_workspace(&ext_ws_path, dir) {
+                let path = ext_ws_path;
+                return Err(ProjectOpenError::ExternalWorkspace { path, err });
+            }
+        }
+
+        // Load the build configuration.
+        let config_path = project_config_json_path(&directory);
+        let config = match config::ProjectConfig::load(&config_path) {
+            Ok(config) => config,
+            Err(JsonFileError::Io { ref err }) if err.kind() == io::ErrorKind::NotFound => {
+                Default::default()
+            }
+            Err(err) => {
+                let path = config_path;
+                return Err(ProjectOpenError::Config { path, err });
+            }
+        };
+
+        // Load the collection of nodes.
+        let json_path = node_collection_json_path(&directory);
+        let mut backup_path = None;
+        let mut failed_nodes = vec![];
+        let mut nodes = match NodeCollection::load(&json_path) {
+            Ok(nodes) => nodes,
+            // If no existing collection exists, create the default one.
+            Err(JsonFileError::Io { ref err }) if err.kind() == io::ErrorKind::NotFound => {
+                let mut nodes = NodeCollection::default();
+                let (dir, cfg, profile) = (&directory, &cargo_config, &config.profile);
+                create_root_graph_node::<ProjectOpenError>(dir, &ws_dir, cfg, profile, &mut nodes)?;
+                nodes.save(&json_path)?;
+                nodes
+            }
+            // The collection exists but is corrupt or unreadable.
+            Err(err) => {
+                if !recover {
+                    let path = json_path;
+                    return Err(ProjectOpenError::NodeCollection { path, err });
+                }
+                let backup = node_collection_backup_path(&directory);
+                fs::copy(&json_path, &backup)?;
+                backup_path = Some(backup);
+                let (nodes, failed) = NodeCollection::recover(&json_path);
+                failed_nodes = failed;
+                nodes
+            }
+        };
+
+        // Collections saved prior to the addition of instance IDs have none.
+        for kind in nodes.map.values_mut() {
+            if let NodeKind::Graph(ref mut g) = *kind {
+                g.sync_instance_ids(&HashSet::new());
+            }
+        }
+
+        let autosave = false;
+        let builder = build::Builder::new(ws_dir.clone());
+        let mut project = Project {
+            cargo_config,
+            directory,
+            workspace_dir: ws_dir,
+            nodes,
+            autosave,
+            config,
+            builder,
+        };
+
+        // Verify the collection, repairing it if necessary and requested.
+        let report = project.verify()?;
+        if backup_path.is_none() && report.is_consistent() {
+            return Ok((project, None));
+        }
+        if !recover {
+            return Err(ProjectOpenError::Inconsistent { report });
+        }
+        let backup_path = match backup_path {
+            Some(path) => path,
+            None => {
+                let backup = node_collection_backup_path(project.dir());
+                fs::copy(&json_path, &backup)?;
+                backup
+            }
+        };
+        let repair = project.repair()?;
+        project.save()?;
+        let recovery = NodeCollectionRecovery {
+            backup_path,
+            failed_nodes,
+            repair,
+        };
+        Ok((project, Some(recovery)))
+    }
+
+    /// Write the project's **NodeCollection** to `<proj_dir>/node_collection.json`.
+    ///
+    /// The collection is first written to a temporary file which is then renamed over the
+    /// existing file, ensuring a crash mid-write cannot corrupt the saved project.
+    pub fn save(&self) -> Result<(), JsonFileError> {
+        self.nodes.save(node_collection_json_path(self.dir()))
+    }
+
+    /// Whether or not the project is saved after each mutation.
+    pub fn autosave(&self) -> bool {
+        self.autosave
+    }
+
+    /// Specify whether or not the project should be saved after each mutation.
+    ///
+    /// When enabled, `add_core_node`, `add_graph_node` and `update_graph` will each call `save`
+    /// upon success.
+    pub fn set_autosave(&mut self, autosave: bool) {
+        self.autosave = autosave;
+    }
+
+    /// Add the given core node to the collection and return its unique identifier.
+    pub fn add_core_node(&mut self, node: Box<dyn SerdeNode>) -> Result<NodeId, AddCoreNodeError> {
+        let kind = NodeKind::Core(node);
+        let node_id = self.nodes.insert(kind);
+        self.save_if_autosave()?;
+        Ok(node_id)
+    }
+
+    /// Add the given node to the collection and return its unique identifier.
+    pub fn add_graph_node(
+        &mut self,
+        graph: NodeIdGraphNode,
+        node_name: &str,
+    ) -> Result<NodeId, AddGraphNodeError> {
+        let ws_dir = self.workspace_dir();
+        let Project {
+            ref cargo_config,
+            ref mut nodes,
+            ..
+        } = *self;
+        let n_id = add_graph_node_to_collection(ws_dir, node_name, cargo_config, graph, nodes)?;
+        self.save_if_autosave()?;
+        Ok(n_id)
+    }
+
+    /// Read-only access to the project's **NodeCollection**.
+    pub fn nodes(&self) -> &NodeCollection {
+        &self.nodes
+    }
+
+    /// The **NodeId** for the root graph node.
+    pub fn root_node_id(&self) -> NodeId {
+        ROOT_NODE_ID
+    }
+
+    /// The core node at the given **NodeId**.
+    ///
+    /// Returns `None` if there are no nodes for the given **NodeId** or if a node exists but it is
+    /// not a **Core** node.
+    pub fn core_node(&self, id: &NodeId) -> Option<&Box<dyn SerdeNode>> {
+        self.nodes.get(id).and_then(|kind| match kind {
+            NodeKind::Core(ref node) => Some(node),
+            _ => None,
+        })
+    }
+
+    /// The graph node at the given **NodeId**.
+    ///
+    /// Returns `None` if there are no nodes for the given **NodeId** or if a node exists but it is
+    /// not a **Graph** node.
+    pub fn graph_node<'a>(&'a self, id: &NodeId) -> Option<&'a ProjectGraph> {
+        self.nodes.get(id).and_then(|kind| match kind {
+            NodeKind::Graph(ref graph) => Some(graph),
+            _ => None,
+        })
+    }
+
+    /// Similar to `graph_node`, but returns a graph containing node references rather than IDs.
+    ///
+    /// The returned graph will also contain information about its inlets and outlets.
+    ///
+    /// Returns `None` if there are no nodes for the given **NodeId** or if a node exists but is
+    /// not a **Graph** node.
+    pub fn ref_graph_node<'a>(&'a self, id: &NodeId) -> Option<ProjectNodeRefGraphNode<'a>> {
+        let g = self.graph_node(id)?;
+        Some(id_graph_to_node_graph(*id, g, &self.nodes))
+    }
+
+    /// Update the graph associated with the graph node at the given **NodeId**.
+    ///
+    /// The graph is updated via a **GraphUpdate**, ensuring that nodes added during the update
+    /// never inherit the **InstanceId** of a removed node, even if they reuse its index.
+    ///
+    /// Following the update, any edges that refer to an output or input that does not exist on
+    /// the nodes they connect are removed from the graph and returned.
+    ///
+    /// The graph is then validated before any code is generated. If any problems are found, e.g.
+    /// a cycle that is not broken by a **delay** edge or an unconnected **Required** input, they
+    /// are returned within an **UpdateGraphError::InvalidGraph** error. The graph retains the
+    /// update in this case so that it may be corrected. Warnings, e.g. a possible type mismatch
+    /// between types that are not fully resolved, do not prevent compilation and may be retrieved
+    /// via `graph::validate::diagnostics`.
+    ///
+    /// Once the graph is compiled, all graphs that directly or indirectly contain it are
+    /// regenerated and recompiled in dependency order. See `dependent_graphs`. Edges within these
+    /// graphs that refer to an inlet or outlet that no longer exists are removed beforehand.
+    ///
+    /// Returns the edges removed from the updated graph and from each of the graphs that contain
+    /// it.
+    pub fn update_graph<F>(
+        &mut self,
+        id: &NodeId,
+        update: F,
+    ) -> Result<BTreeMap<NodeId, Vec<PrunedEdge>>, UpdateGraphError>
+    where
+        F: FnOnce(&mut GraphUpdate),
+    {
+        let edges = match self.update_graph_src(id, update)? {
+            None => return Ok(BTreeMap::new()),
+            Some(edges) => edges,
+        };
+        let mut pruned = BTreeMap::new();
+        pruned.insert(*id, edges);
+        let ws_dir = self.workspace_dir();
+        let node = self.graph_node(id).expect("no graph node for NodeId");
+        let profile = &self.config.profile;
+        let _compilation = graph_node_compile(&ws_dir, &self.cargo_config, profile, &node)?;
+
+        // Regenerate all graphs that contain the updated graph.
+        for g_id in self.nodes.dependent_graphs(id) {
+            pruned.insert(g_id, self.nodes.prune_invalid_edges(&g_id));
+            regenerate_graph_node(&ws_dir, &self.cargo_config, profile, &self.nodes, &g_id)
+                .map_err(|err| UpdateGraphError::RegenerateDependent { id: g_id, err })?;
+        }
+
+        self.save_if_autosave()?;
+        Ok(pruned)
+    }
+
+    /// The same as `update_graph`, but compiles the graph on a background thread rather than
+    /// blocking the caller.
+    ///
+    /// The graph is updated, validated and its source is generated before returning. The returned
+    /// **BuildJob** may be used to track the progress of compilation. Any previously loaded
+    /// library for the graph may continue to be used until the new library is ready. See the
+    /// `build` module for details.
+    ///
+    /// Builds are also queued for all graphs that contain the updated graph. Their progress may be
+    /// tracked via `build_events`.
+    ///
+    /// The returned job is `None` if there is no graph node for the given **NodeId**.
+    pub fn update_graph_in_background<F>(
+        &mut self,
+        id: &NodeId,
+        update: F,
+    ) -> Result<(BTreeMap<NodeId, Vec<PrunedEdge>>, Option<build::BuildJob>), UpdateGraphError>
+    where
+        F: FnOnce(&mut GraphUpdate),
+    {
+        let edges = match self.update_graph_src(id, update)? {
+            None => return Ok((BTreeMap::new(), None)),
+            Some(edges) => edges,
+        };
+        let mut pruned = BTreeMap::new();
+        pruned.insert(*id, edges);
+        self.save_if_autosave()?;
+        let job = self.build_in_background(id);
+
+        // Regenerate and queue builds for all graphs that contain the updated graph.
+        let ws_dir = self.workspace_dir();
+        for g_id in self.nodes.dependent_graphs(id) {
+            pruned.insert(g_id, self.nodes.prune_invalid_edges(&g_id));
+            regenerate_graph_node_src(&ws_dir, &self.cargo_config, &self.nodes, &g_id)
+                .map_err(|err| UpdateGraphError::RegenerateDependent { id: g_id, err })?;
+            self.build_in_background(&g_id);
+        }
+
+        Ok((pruned, job))
+    }
+
+    /// Compile the graph node at the given **NodeId** from its current source on a background
+    /// thread.
+    ///
+    /// Returns `None` if there is no graph node for the given **NodeId**.
+    pub fn build_in_background(&mut self, id: &NodeId) -> Option<build::BuildJob> {
+        let node = self.nodes.id_graph(id)?;
+        let instance_ids = node.instance_ids.clone();
+        let profile = self.config.profile.clone();
+        let job = self
+            .builder
+            .request(*id, node.package_id, instance_ids, profile);
+        Some(job)
+    }
+
+    /// Events emitted by the background build thread that have not yet been received.
+    ///
+    /// This does not block. A **BuildEvent::Ready** event indicates that a graph may be reloaded
+    /// via `LoadedGraph::reload`.
+    pub fn build_events(&self) -> impl Iterator<Item = build::BuildEvent> + '_ {
+        self.builder.events()
+    }
+
+    /// The path to the generated dynamic library for the graph node at the given `id`.
+    ///
+    /// Returns `None` if there is no dynamic library or no graph node for the given `id`.
+    pub fn graph_node_dylib(&self, id: &NodeId) -> cargo::CargoResult<Option<PathBuf>> {
+        let node = match self.graph_node(id) {
+            None => return Ok(None),
+            Some(n) => n,
+        };
+        let ws_dir = self.workspace_dir();
+        package_dylib(
+            ws_dir,
+            &self.cargo_config,
+            &self.config.profile,
+            node.package_id,
+        )
+    }
+
+    /// Load the generated dynamic library for the graph node at the given `id`.
+    ///
+    /// The returned **LoadedGraph** provides safe access to each of the graph's push, pull and full
+    /// evaluation functions.
+    pub fn load_graph(&self, id: &NodeId) -> Result<LoadedGraph, LoadGraphError> {
+        runtime::load_graph(self, id, false)
+    }
+
+    /// All graphs within the project that reference the node with the given **NodeId**, along with
+    /// the indices of the node's instances within each graph.
+    pub fn node_references(&self, id: &NodeId) -> NodeReferences {
+        self.nodes.references(id)
+    }
+
+    /// All graphs within the project that directly or indirectly contain the node with the given
+    /// **NodeId**.
+    ///
+    /// The graphs are ordered such that each graph follows all of the returned graphs that it
+    /// contains, i.e. the order in which the graphs must be regenerated after the node changes.
+    pub fn dependent_graphs(&self, id: &NodeId) -> Vec<NodeId> {
+        self.nodes.dependent_graphs(id)
+    }
+
+    /// Remove the node with the given **NodeId** from the project.
+    ///
+    /// If the node is referenced by other graphs, the given `policy` determines whether the
+    /// removal is refused or whether all instances of the node are removed from each referencing
+    /// graph. Referencing graphs and the graphs that contain them are regenerated and recompiled.
+    ///
+    /// If the node is a graph node, its crate is removed from the workspace members and its crate
+    /// directory is deleted. The root graph node cannot be removed.
+    ///
+    /// Returns the graphs that referenced the node.
+    pub fn remove_node(
+        &mut self,
+        id: &NodeId,
+        policy: ReferencePolicy,
+    ) -> Result<NodeReferences, RemoveNodeError> {
+        if *id == ROOT_NODE_ID {
+            return Err(RemoveNodeError::RootNode);
+        }
+        if !self.nodes.contains_key(id) {
+            return Err(RemoveNodeError::NoSuchNode);
+        }
+        let references = self.nodes.references(id);
+        if policy == ReferencePolicy::Refuse && !references.is_empty() {
+            return Err(RemoveNodeError::Referenced { references });
+        }
+        let dependents = self.nodes.dependent_graphs(id);
+
+        // Remove all instances of the node from referencing graphs.
+        for (g_id, indices) in &references {
+            let g = self.nodes.id_graph_mut(g_id).expect("no graph node");
+            for &ix in indices {
+                g.graph.inlets.retain(|&n| n != ix);
+                g.graph.outlets.retain(|&n| n != ix);
+                g.graph.remove_node(ix);
+            }
+            g.sync_instance_ids(&HashSet::new());
+        }
+
+        // Remove the node and, if it is a graph, its crate.
+        let ws_dir = self.workspace_dir();
+        if let Some(NodeKind::Graph(g)) = self.nodes.remove(id) {
+            let crate_name = format!("{}", g.package_id.name());
+            remove_workspace_member(&ws_dir, &crate_name)?;
+            let crate_dir = ws_dir.join(&crate_name);
+            if crate_dir.exists() {
+                fs::remove_dir_all(crate_dir)?;
+            }
+        }
+
+        // Regenerate the referencing graphs along with the graphs that contain them.
+        let profile = &self.config.profile;
+        for g_id in &dependents {
+            regenerate_graph_node(&ws_dir, &self.cargo_config, profile, &self.nodes, g_id)?;
+        }
+
+        self.save_if_autosave()?;
+        Ok(references)
+    }
+
+    /// Rename the graph node with the given **NodeId**.
+    ///
+    /// The node's crate is renamed to `node_crate_name(new_name)`: its directory is moved, its
+    /// `Cargo.toml` and the workspace members are updated and the crate is recompiled under its
+    /// new name. The root graph node cannot be renamed.
+    ///
+    /// While graphs refer to nodes by **NodeId** and remain valid, the given `policy` determines
+    /// whether renaming a referenced node is refused or whether the referencing graphs, along with
+    /// the graphs that contain them, are regenerated and recompiled.
+    ///
+    /// Returns the graphs that reference the node.
+    pub fn rename_graph_node(
+        &mut self,
+        id: &NodeId,
+        new_name: &str,
+        policy: ReferencePolicy,
+    ) -> Result<NodeReferences, RenameGraphNodeError> {
+        if *id == ROOT_NODE_ID {
+            return Err(RenameGraphNodeError::RootNode);
+        }
+        let old_package_id = match self.nodes.id_graph(id) {
+            None => return Err(RenameGraphNodeError::NoSuchGraphNode),
+            Some(g) => g.package_id,
+        };
+        let references = self.nodes.references(id);
+        if policy == ReferencePolicy::Refuse && !references.is_empty() {
+            return Err(RenameGraphNodeError::Referenced { references });
+        }
+
+        // Check the new crate name is available.
+        let ws_dir = self.workspace_dir();
+        let old_name = format!("{}", old_package_id.name());
+        let new_name = node_crate_name(new_name);
+        let new_dir = ws_dir.join(&new_name);
+        if new_dir.exists() {
+            return Err(RenameGraphNodeError::CrateExists { name: new_name });
+        }
+
+        // Move the crate and update its manifest along with the workspace members.
+        let old_dir = package_root(&ws_dir, &self.cargo_config, old_package_id)?;
+        fs::rename(&old_dir, &new_dir)?;
+        update_toml_file(manifest_path(&new_dir), |toml| {
+            for key in &["package", "lib"] {
+                if let Some(toml::Value::Table(ref mut table)) = toml.get_mut(key) {
+                    table.insert("name".to_string(), new_name.clone().into());
+                }
+            }
+        })?;
+        edit_toml_file(manifest_path(&ws_dir), |doc| {
+            if let Some(members) = workspace_members_mut(doc) {
+                for member in members.iter_mut() {
+                    if member.as_str() == Some(&old_name[..]) {
+                        let decor = member.decor().clone();
+                        *member = new_name.as_str().into();
+                        *member.decor_mut() = decor;
+                    }
+                }
+            }
+        })?;
+
+        // Update the stored package ID.
+        let ws_manifest_path = manifest_path(&ws_dir);
+        let package_id = {
+            let ws = cargo::core::Workspace::new(&ws_manifest_path, &self.cargo_config)?;
+            ws.members()
+                .find(|pkg| format!("{}", pkg.name()) == new_name)
+                .map(|pkg| pkg.package_id())
+                .expect("failed to find workspace package with matching name")
+        };
+        self.nodes
+            .id_graph_mut(id)
+            .expect("no graph node")
+            .package_id = package_id;
+
+        // Regenerate the renamed graph along with all graphs that contain it.
+        let profile = &self.config.profile;
+        regenerate_graph_node(&ws_dir, &self.cargo_config, profile, &self.nodes, id)?;
+        for g_id in &self.nodes.dependent_graphs(id) {
+            regenerate_graph_node(&ws_dir, &self.cargo_config, profile, &self.nodes, g_id)?;
+        }
+
+        self.save_if_autosave()?;
+        Ok(references)
+    }
+
+    /// Replace the implementation of the core node at the given **NodeId** in place.
+    ///
+    /// If the node is referenced by other graphs, the given `policy` determines whether the
+    /// replacement is refused or whether the referencing graphs, along with the graphs that
+    /// contain them, are regenerated and recompiled with the new implementation.
+    ///
+    /// Returns the graphs that reference the node.
+    pub fn replace_core_node(
+        &mut self,
+        id: &NodeId,
+        node: Box<dyn SerdeNode>,
+        policy: ReferencePolicy,
+    ) -> Result<NodeReferences, ReplaceCoreNodeError> {
+        if self.core_node(id).is_none() {
+            return Err(ReplaceCoreNodeError::NoSuchCoreNode);
+        }
+        let references = self.nodes.references(id);
+        if policy == ReferencePolicy::Refuse && !references.is_empty() {
+            return Err(ReplaceCoreNodeError::Referenced { references });
+        }
+        self.nodes.map.insert(*id, NodeKind::Core(node));
+        let ws_dir = self.workspace_dir();
+        let profile = &self.config.profile;
+        for g_id in &self.nodes.dependent_graphs(id) {
+            regenerate_graph_node(&ws_dir, &self.cargo_config, profile, &self.nodes, g_id)?;
+        }
+        self.save_if_autosave()?;
+        Ok(references)
+    }
+
+    /// Reconfigure the number of inputs and outputs of the core node at the given **NodeId**.
+    ///
+    /// The node must provide a **DynamicIo** implementation via `SerdeNode::dynamic_io_mut`.
+    ///
+    /// If the node is referenced by other graphs, the given `policy` determines whether the
+    /// reconfiguration is refused or whether the referencing graphs, along with the graphs that
+    /// contain them, are regenerated and recompiled. Edges within the referencing graphs that
+    /// refer to an output or input that no longer exists are removed.
+    ///
+    /// Returns the edges removed from each referencing graph.
+    pub fn set_core_node_io(
+        &mut self,
+        id: &NodeId,
+        io: node::Io,
+        policy: ReferencePolicy,
+    ) -> Result<BTreeMap<NodeId, Vec<PrunedEdge>>, SetCoreNodeIoError> {
+        let references = self.nodes.references(id);
+        let node = self
+            .nodes
+            .map
+            .get_mut(id)
+            .and_then(|n| n.core_mut())
+            .ok_or(SetCoreNodeIoError::NoSuchCoreNode)?;
+        let dynamic_io = node.dynamic_io_mut().ok_or(SetCoreNodeIoError::FixedIo)?;
+        if policy == ReferencePolicy::Refuse && !references.is_empty() {
+            return Err(SetCoreNodeIoError::Referenced { references });
+        }
+        dynamic_io.set_io(io)?;
+        let mut pruned = BTreeMap::new();
+        let ws_dir = self.workspace_dir();
+        let profile = &self.config.profile;
+        for g_id in references.keys() {
+            pruned.insert(*g_id, self.nodes.prune_invalid_edges(g_id));
+        }
+        for g_id in &self.nodes.dependent_graphs(id) {
+            regenerate_graph_node(&ws_dir, &self.cargo_config, profile, &self.nodes, g_id)?;
+        }
+        self.save_if_autosave()?;
+        Ok(pruned)
+    }
+
+    /// The configuration used to build the project's graph nodes.
+    pub fn config(&self) -> &config::ProjectConfig {
+        &self.config
+    }
+
+    /// Replace the configuration used to build the project's graph nodes.
+    ///
+    /// The config is saved to `project_config_json_path` and applied to the workspace via the
+    /// cargo configuration file at `cargo_config_toml_path`. All graph nodes are then recompiled
+    /// so that their dynamic libraries are available for the new profile.
+    ///
+    /// If the workspace already contains a `.cargo/config.toml` that was not generated by gantz,
+    /// it is left untouched and an error is returned.
+    ///
+    /// The cargo configuration file would apply to all crates within a shared workspace, so for
+    /// projects opened via `open_in_workspace` only the `profile` may be changed. A config that
+    /// specifies any other settings is refused with a **SetConfigError::ExternalWorkspace** error.
+    pub fn set_config(&mut self, config: config::ProjectConfig) -> Result<(), SetConfigError> {
+        let ws_dir = self.workspace_dir();
+        if ws_dir != workspace_dir(self.dir()) {
+            let has_settings = config
+                .cargo_config_toml()
+                .as_table()
+                .map(|t| !t.is_empty())
+                .unwrap_or(false);
+            if has_settings {
+                let workspace_dir = ws_dir;
+                return Err(SetConfigError::ExternalWorkspace { workspace_dir });
+            }
+        } else {
+            config.write_cargo_config_toml(cargo_config_toml_path(&ws_dir))?;
+        }
+        config.save(project_config_json_path(self.dir()))?;
+        self.cargo_config = workspace_cargo_config(&ws_dir)?;
+        self.config = config;
+        let profile = &self.config.profile;
+        for kind in self.nodes.values() {
+            if let NodeKind::Graph(ref g) = *kind {
+                graph_node_compile(&ws_dir, &self.cargo_config, profile, g)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The project directory.
+    pub fn dir(&self) -> &Path {
+        &self.directory
+    }
+
+    /// The path to the project's cargo workspace.
+    pub fn workspace_dir(&self) -> PathBuf {
+        self.workspace_dir.clone()
+    }
+
+    /// The project name.
+    pub fn name(&self) -> &str {
+        project_name(self.dir())
+    }
+
+    /// Check the project's **NodeCollection** for consistency with itself and the workspace.
+    ///
+    /// This is run automatically by `Project::open`, but may also be run on demand, e.g. after
+    /// the workspace has been modified externally.
+    ///
+    /// The following are checked:
+    ///
+    /// - The root node is a graph whose crate is named after the project.
+    /// - Every **NodeId** referenced by every graph exists within the collection.
+    /// - Every graph node's crate is a member of the workspace.
+    /// - Every graph node's stored **PackageId** matches that of its workspace member.
+    /// - Every graph's inlets and outlets refer to existing nodes within the graph.
+    pub fn verify(&self) -> Result<verify::VerifyReport, verify::VerifyError> {
+        verify::verify(self)
+    }
+
+    /// Verify the project and repair all inconsistencies that can be repaired.
+    ///
+    /// - Dangling **NodeId** references and invalid inlets and outlets are removed from graphs.
+    /// - Missing workspace members are re-created and stale **PackageId**s are updated.
+    /// - A new, empty root graph is created if the root node is missing or is not a graph.
+    ///
+    /// All graphs that are modified are regenerated and recompiled. Note that the collection is
+    /// not saved, unless `autosave` is enabled.
+    pub fn repair(&mut self) -> Result<verify::RepairReport, verify::RepairError> {
+        let report = verify::repair(self)?;
+        self.save_if_autosave()?;
+        Ok(report)
+    }
+
+    // Apply the update to the graph at the given `id`, prune invalid edges, validate the graph and
+    // write its dependencies and source to its crate.
+    //
+    // Returns `None` if there is no graph node for the given `id`.
+    fn update_graph_src<F>(
+        &mut self,
+        id: &NodeId,
+        update: F,
+    ) -> Result<Option<Vec<PrunedEdge>>, UpdateGraphError>
+    where
+        F: FnOnce(&mut GraphUpdate),
+    {
+        match self.nodes.id_graph_mut(id) {
+            Some(ref mut g) => {
+                let mut graph = GraphUpdate {
+                    graph: &mut g.graph,
+                    removed: HashSet::new(),
+                };
+                update(&mut graph);
+                let removed = graph.removed;
+                g.sync_instance_ids(&removed);
+            }
+            _ => return Ok(None),
+        }
+        let pruned = self.nodes.prune_invalid_edges(id);
+        let graph = self.nodes.ref_graph(id).expect("no graph node for NodeId");
+        let diagnostics = graph_node_diagnostics(&graph);
+        if !diagnostics.is_empty() {
+            return Err(UpdateGraphError::InvalidGraph { diagnostics });
+        }
+        let deps = graph_node_deps(&graph);
+        let file = graph_node_src(&graph);
+        let ws_dir = self.workspace_dir();
+        graph_node_insert_deps(&ws_dir, &self.cargo_config, graph.package_id, deps)?;
+        graph_node_replace_src(&ws_dir, &self.cargo_config, graph.package_id, file)?;
+        Ok(Some(pruned))
+    }
+
+    // Save the project if `autosave` is enabled.
+    fn save_if_autosave(&self) -> Result<(), JsonFileError> {
+        if self.autosave {
+            self.save()?;
+        }
+        Ok(())
+    }
+}
+
+impl TempProject {
+    /// Create a new temporary project with the given project name.
+    ///
+    /// The project will be created within the directory returned by `std::env::temp_dir()` and
+    /// will be removed when the `TempProject` is `Drop`ped.
+    pub fn open_with_name(name: &str) -> Result<Self, ProjectOpenError> {
+        let proj_dir = std::env::temp_dir().join(name);
+        Self::open(proj_dir)
+    }
+
+    /// The same as `Project::open` but creates a temporary project.
+    pub fn open(directory: PathBuf) -> Result<Self, ProjectOpenError> {
+        let project = Some(Project::open(directory)?);
+        Ok(TempProject { project })
+    }
+}
+
+impl NodeCollection {
+    // Load a node collection from the given path.
+    fn load<P>(path: P) -> Result<Self, JsonFileError>
+    where
+        P: AsRef<Path>,
+    {
+        let file = fs::File::open(path)?;
+        let t = serde_json::from_reader(file)?;
+        Ok(t)
+    }
+
+    // Load as much of the node collection at the given path as possible.
+    //
+    // Each node is deserialized individually. Those that fail are returned alongside the
+    // collection of nodes that succeeded. If the file cannot be read or parsed as a JSON object at
+    // all, an empty collection is returned.
+    fn recover<P>(path: P) -> (Self, Vec<FailedNode>)
+    where
+        P: AsRef<Path>,
+    {
+        let mut nodes = NodeCollection::default();
+        let mut failed = vec![];
+        let value = match fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).ok())
+        {
+            Some(v) => v,
+            None => return (nodes, failed),
+        };
+        let map = match value {
+            serde_json::Value::Object(mut obj) => match obj.remove("map") {
+                Some(serde_json::Value::Object(map)) => map,
+                _ => return (nodes, failed),
+            },
+            _ => return (nodes, failed),
+        };
+        for (key, value) in map {
+            let id = match key.parse::<u64>() {
+                Ok(u) => NodeId(u),
+                Err(_) => {
+                    let err = serde::de::Error::custom("node key is not a valid `NodeId`");
+                    failed.push(FailedNode { key, err });
+                    continue;
+                }
+            };
+            match serde_json::from_value::<NodeKind>(value) {
+                Ok(kind) => {
+                    nodes.map.insert(id, kind);
+                }
+                Err(err) => failed.push(FailedNode { key, err }),
+            }
+        }
+        (nodes, failed)
+    }
+
+    // Save the node collection to the given path.
+    //
+    // The write is atomic - see `write_atomic`.
+    fn save<P>(&self, path: P) -> Result<(), JsonFileError>
+    where
+        P: AsRef<Path>,
+    {
+        let bytes = serde_json::to_vec_pretty(self)?;
+        write_atomic(path, &bytes)?;
+        Ok(())
+    }
+
+    // The next unique identifier that will be produced for the next node to be inserted into the
+    // collection.
+    fn next_node_id(&self) -> NodeId {
+        self.keys()
+            .last()
+            .map(|&NodeId(u)| NodeId(u.checked_add(1).expect("no unique `NodeId`s remaining")))
+            .map(|NodeId(u)| NodeId(std::cmp::max(u, self.next_id)))
+            .unwrap_or(NodeId(self.next_id))
+    }
+
+    // Insert the given node and return the unique `NodeId` key associated with it.
+    fn insert(&mut self, node: NodeKind) -> NodeId {
+        let id = self.next_node_id();
+        self.map.insert(id, node);
+        self.next_id = id.0 + 1;
+        id
+    }
+
+    // Remove the node with the given ID from the collection.
+    //
+    // The ID will not be reused by subsequently inserted nodes.
+    fn remove(&mut self, id: &NodeId) -> Option<NodeKind> {
+        let kind = self.map.remove(id)?;
+        self.next_id = std::cmp::max(self.next_id, id.0 + 1);
+        Some(kind)
+    }
+
+    // All graphs that contain one or more instances of the node with the given ID, along with the
+    // indices of each instance within the graph.
+    fn references(&self, id: &NodeId) -> NodeReferences {
+        self.iter()
+            .filter_map(|(&g_id, kind)| {
+                let g = kind.graph()?;
+                let indices: Vec<_> = g
+                    .graph
+                    .node_indices()
+                    .filter(|&ix| g.graph[ix] == *id)
+                    .collect();
+                if indices.is_empty() {
+                    None
+                } else {
+                    Some((g_id, indices))
+                }
+            })
+            .collect()
+    }
+
+    // A reverse dependency index, mapping the ID of each node to the IDs of the graphs that
+    // directly contain one or more instances of it.
+    fn dependents_index(&self) -> BTreeMap<NodeId, BTreeSet<NodeId>> {
+        let mut index: BTreeMap<_, BTreeSet<_>> = BTreeMap::new();
+        for (&g_id, kind) in self.iter() {
+            if let Some(g) = kind.graph() {
+                for ix in g.graph.node_indices() {
+                    index.entry(g.graph[ix]).or_default().insert(g_id);
+                }
+            }
+        }
+        index
+    }
+
+    // All graphs that directly or indirectly contain the node with the given ID.
+    //
+    // Graphs are ordered such that each graph follows all of the affected graphs that it contains.
+    // Graphs that contain one another in a cycle are ordered by ID following all others.
+    fn dependent_graphs(&self, id: &NodeId) -> Vec<NodeId> {
+        let index = self.dependents_index();
+        let no_dependents = BTreeSet::new();
+        let dependents = |id: &NodeId| index.get(id).unwrap_or(&no_dependents);
+
+        // Collect all transitively affected graphs.
+        let mut affected = BTreeSet::new();
+        let mut stack = vec![*id];
+        while let Some(n) = stack.pop() {
+            for &g_id in dependents(&n) {
+                if g_id != *id && affected.insert(g_id) {
+                    stack.push(g_id);
+                }
+            }
+        }
+
+        // Count the affected graphs contained within each affected graph.
+        let mut n_children: BTreeMap<NodeId, usize> = affected.iter().map(|&g| (g, 0)).collect();
+        for child in &affected {
+            for parent in dependents(child) {
+                if let Some(count) = n_children.get_mut(parent) {
+                    *count += 1;
+                }
+            }
+        }
+
+        // Order graphs such that children precede their parents.
+        let mut order = vec![];
+        let mut ready: Vec<_> = n_children
+            .iter()
+            .filter(|&(_, &count)| count == 0)
+            .map(|(&g, _)| g)
+            .collect();
+        while let Some(g) = ready.pop() {
+            n_children.remove(&g);
+            order.push(g);
+            for parent in dependents(&g) {
+                if let Some(count) = n_children.get_mut(parent) {
+                    *count -= 1;
+                    if *count == 0 {
+                        ready.push(*parent);
+                    }
+                }
+            }
+        }
+        order.extend(n_children.keys().cloned());
+        order
+    }
+
+    // Retrieve the ID graph node at the given node ID.
+    //
+    // Returns `None` if there is no node for the given ID or if there is a node but it is not a
+    // graph.
+    fn id_graph(&self, id: &NodeId) -> Option<&ProjectGraph> {
+        self.get(id).and_then(|n| n.graph())
+    }
+
+    // The same as `id_graph` but provides mutable access to the graph.
+    fn id_graph_mut(&mut self, id: &NodeId) -> Option<&mut ProjectGraph> {
+        self.map.get_mut(id).and_then(|n| n.graph_mut())
+    }
+
+    // The same as `id_graph`, but returns the fully referenced graph without the `NodeId`
+    // indirection.
+    fn ref_graph(&self, id: &NodeId) -> Option<ProjectNodeRefGraphNode> {
+        self.id_graph(id)
+            .map(|g| id_graph_to_node_graph(*id, g, self))
+    }
+
+    // Remove all edges within the graph at the given ID that refer to an output or input that
+    // does not exist on the nodes they connect.
+    fn prune_invalid_edges(&mut self, id: &NodeId) -> Vec<PrunedEdge> {
+        let invalid = match self.ref_graph(id) {
+            None => return vec![],
+            Some(g) => graph::invalid_edges(&g.graph.graph),
+        };
+        let g = self.id_graph_mut(id).expect("no graph node for NodeId");
+        for edge in &invalid {
+            g.graph.remove_edge(edge.id);
+        }
+        invalid
+    }
+}
+
+impl ProjectGraph {
+    /// Create a graph node from the given graph and the **PackageId** of its crate.
+    ///
+    /// Each node within the graph is assigned a new instance ID.
+    pub fn new(graph: NodeIdGraphNode, package_id: cargo::core::PackageId) -> Self {
+        let mut g = ProjectGraph {
+            graph,
+            package_id,
+            instance_ids: Default::default(),
+            next_instance_id: 0,
+        };
+        g.sync_instance_ids(&HashSet::new());
+        g
+    }
+
+    /// The persistent instance ID of the node at the given index.
+    pub fn instance_id(&self, n: NodeIndex) -> Option<InstanceId> {
+        self.instance_ids.get(&n).cloned()
+    }
+
+    /// The persistent instance ID of every node within the graph.
+    pub fn instance_ids(&self) -> &BTreeMap<NodeIndex, InstanceId> {
+        &self.instance_ids
+    }
+
+    // Assign an instance ID to each node without one and forget the IDs of removed nodes.
+    //
+    // `removed` describes the indices of nodes removed during an edit, allowing to detect indices
+    // that have since been reused by a new node.
+    fn sync_instance_ids(&mut self, removed: &HashSet<NodeIndex>) {
+        let graph = &self.graph.graph;
+        self.instance_ids
+            .retain(|n, _| graph.contains_node(*n) && !removed.contains(n));
+        for n in graph.node_indices() {
+            if !self.instance_ids.contains_key(&n) {
+                let instance_id = InstanceId(self.next_instance_id);
+                self.next_instance_id += 1;
+                self.instance_ids.insert(n, instance_id);
+            }
+        }
+    }
+}
+
+impl<'a> GraphUpdate<'a> {
+    /// Remove the node at the given index along with its edges, returning its **NodeId**.
+    pub fn remove_node(&mut self, n: NodeIndex) -> Option<NodeId> {
+        let id = self.graph.remove_node(n);
+        if id.is_some() {
+            self.removed.insert(n);
+        }
+        id
+    }
+
+    /// Retain only the nodes for which `visit` returns `true`.
+    ///
+    /// See `StableGraph::retain_nodes`.
+    pub fn retain_nodes<F>(&mut self, visit: F)
+    where
+        F: FnMut(petgraph::graph::Frozen<NodeIdGraph>, NodeIndex) -> bool,
+    {
+        let prev: Vec<_> = self.graph.node_indices().collect();
+        self.graph.retain_nodes(visit);
+        let graph = &self.graph.graph;
+        let removed = prev.into_iter().filter(|&n| !graph.contains_node(n));
+        self.removed.extend(removed);
+    }
+
+    /// Remove all nodes and edges from the graph.
+    pub fn clear(&mut self) {
+        self.removed.extend(self.graph.node_indices());
+        self.graph.clear();
+    }
+}
+
+impl GraphNodeCompileError {
+    /// The rustc diagnostics associated with the error.
+    ///
+    /// This is empty unless the error is a **Rustc** error.
+    pub fn diagnostics(&self) -> &[NodeDiagnostic] {
+        match *self {
+            GraphNodeCompileError::Rustc {
+                ref diagnostics, ..
+            } => diagnostics,
+            _ => &[],
+        }
+    }
+}
+
+impl InstanceId {
+    /// The underlying integer value.
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+impl NodeKind {
+    /// Returns `Some` if the node is a graph node, `None` otherwise.
+    pub fn graph(&self) -> Option<&ProjectGraph> {
+        match *self {
+            NodeKind::Graph(ref g) => Some(g),
+            _ => None,
+        }
+    }
+
+    /// Returns `Some` if the node is a core node, `None` otherwise.
+    pub fn core(&self) -> Option<&dyn SerdeNode> {
+        match *self {
+            NodeKind::Core(ref n) => Some(&**n),
+            _ => None,
+        }
+    }
+
+    /// Returns `Some` if the node is a graph node, `None` otherwise.
+    pub fn graph_mut(&mut self) -> Option<&mut ProjectGraph> {
+        match *self {
+            NodeKind::Graph(ref mut g) => Some(g),
+            _ => None,
+        }
+    }
+
+    /// Returns `Some` if the node is a core node, `None` otherwise.
+    pub fn core_mut(&mut self) -> Option<&mut Box<dyn SerdeNode>> {
+        match *self {
+            NodeKind::Core(ref mut n) => Some(n),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> ProjectNodeRefGraph<'a> {
+    /// The instance ID of each node in the form expected by the `graph::codegen` functions.
+    pub fn node_instance_ids(&self) -> graph::codegen::NodeInstanceIdMap<NodeIndex> {
+        self.instance_ids
+            .iter()
+            .map(|(&n, instance_id)| (n, instance_id.0))
+            .collect()
+    }
+}
+
+impl<'a> GraphBase for ProjectNodeRefGraph<'a> {
+    type EdgeId = <NodeRefGraph<'a> as GraphBase>::EdgeId;
+    type NodeId = <NodeRefGraph<'a> as GraphBase>::NodeId;
+}
+
+impl<'a> graph::EvaluatorFnBlock for ProjectNodeRefGraph<'a> {
+    fn evaluator_fn_block(
+        &self,
+        inlets: &[Self::NodeId],
+        outlets: &[Self::NodeId],
+        signature: &syn::Signature,
+    ) -> syn::Block {
+        let push = inlets.iter().cloned();
+        let pull = outlets.iter().cloned();
+        let eval_order = graph::codegen::eval_order(&self.graph, push, pull);
+        let instance_ids = self.node_instance_ids();
+        let state_order: HashMap<_, _> =
+            graph::codegen::state_order_with_instance_ids(&self.graph, eval_order, &instance_ids)
+                .into_iter()
+                .enumerate()
+                .map(|(ix, n_id)| (n_id, ix))
+                .collect();
+
+        // The order within the state slice for each inlet node.
+        let inlet_state_indices = inlets.iter().map(|&inlet| state_order[&inlet]);
+        let mut outlet_state_indices = outlets.iter().map(|&outlet| state_order[&outlet]);
+
+        // Retrieve the inlet values from the signature args.
+        let mut inlet_values = Vec::with_capacity(inlets.len());
+        let mut inlet_types = Vec::with_capacity(inlets.len());
+        for arg in signature.inputs.iter() {
+            match *arg {
+                syn::FnArg::Typed(ref arg) => {
+                    match *arg.pat {
+                        syn::Pat::Ident(ref pat) => inlet_values.push(pat.ident.clone()),
+                        _ => unreachable!("graph eval signature contained non-`Ident` arg pattern"),
+                    }
+                    inlet_types.push(arg.ty.clone());
+                }
+                _ => unreachable!("graph eval signature should only use captured `FnArg`s"),
+            }
+        }
+
+        // Retrieve the outlet types from the signature return type.
+        let mut outlet_types = vec![];
+        if let syn::ReturnType::Type(_, ref ty) = signature.output {
+            match **ty {
+                syn::Type::Tuple(ref tuple) => outlet_types.extend(tuple.elems.iter().cloned()),
+                _ => outlet_types.push(*ty.clone()),
+            }
+        }
+
+        // - `()` for no outlets.
+        // - `#outlet` for single outlet.
+        // - `(#(#outlet),*)` for multiple outlets.
+        let return_outlets: syn::Expr = match outlets.len() {
+            0 => {
+                syn::parse_quote! { () }
+            }
+            1 => {
+                let outlet_ix = outlet_state_indices
+                    .next()
+                    .expect("expected 1 index, found none");
+                let outlet_ty = outlet_types.pop().expect("expected 1 index, found none");
+                syn::parse_quote! {
+                    node_states[#outlet_ix]
+                        .downcast_ref::<#outlet_ty>()
+                        .expect("unexpected outlet type")
+                        .clone()
+                }
+            }
+            _ => {
+                syn::parse_quote! {
+                    (#(
+                        node_states[#outlet_state_indices]
+                            .downcast_ref::<#outlet_types()>
+                            .expect("unexpected outlet type")
+                            .clone()
+                    ),*)
+                }
+            }
+        };
+
+        let block = syn::parse_quote! {{
+            let (node_states, full_graph_eval_fn_symbol) = state;
+
+            // Assign inlet values.
+            #(
+                *node_states[#inlet_state_indices]
+                    .downcast_mut::<#inlet_types>()
+                    .expect("unexpected inlet type") = #inlet_values;
+            )*
+
+            // Evaluate the full graph.
+            unsafe {
+                let unit_ptr: *mut () = *full_graph_eval_fn_symbol;
+                let eval_ptr: *mut libloading::Symbol<'static, fn(&mut [&mut dyn std::any::Any])> =
+                    unit_ptr as *mut _;
+                (*eval_ptr)(&mut node_states[..]);
+            };
+
+            // Retrieve the outlet values.
+            #return_outlets
+        }};
+
+        block
+    }
+}
+
+impl<'a> graph::Graph for ProjectNodeRefGraph<'a> {
+    type Node = NodeRef<'a>;
+
+    fn node(&self, id: Self::NodeId) -> Option<&Self::Node> {
+        self.graph.node_weight(id)
+    }
+
+    fn state_type(&self) -> syn::Type {
+        // State type must include:
+        // - Node states.
+        // - Dynamic library function symbols.
+        //
+        // TODO: Use a nicer type to produce nicer codegen. This will require implementing the type
+        // in a separate crate that is accessible both to this crate as well as to the generated
+        // code. For now, this gross type gets around that.
+        let ty = syn::parse_quote! {
+            (&'static mut [&'static mut dyn std::any::Any], *mut ())
+            //(&'static mut [&'static mut dyn std::any::Any], &'static mut dyn std::any::Any)
+        };
+        ty
+    }
+}
+
+// /// The `State` type expected by the `Project` graph type.
+// pub struct GraphState<'a> {
+//     /// The list of states necessary for the graph's child stateful nodes.
+//     pub node_states: &'a mut [&'a mut dyn std::any::Any],
+//     /// A reference to the function symbol for running the full graph.
+//     ///
+//     /// E.g. `&'a libloading::Symbol<'static, fn(X, Y) -> Z>`
+//     pub full_graph_eval_fn_symbol: &'a dyn std::any::Any,
+// }
+
+impl<'a> Node for NodeRef<'a> {
+    fn evaluator(&self) -> node::Evaluator {
+        match self {
+            NodeRef::Core(node) => node.evaluator(),
+            NodeRef::Graph(graph) => graph.evaluator(),
+        }
+    }
+
+    fn push_eval(&self) -> Option<node::EvalFn> {
+        match self {
+            NodeRef::Core(node) => node.push_eval(),
+            NodeRef::Graph(graph) => graph.push_eval(),
+        }
+    }
+
+    fn pull_eval(&self) -> Option<node::EvalFn> {
+        match self {
+            NodeRef::Core(node) => node.pull_eval(),
+            NodeRef::Graph(graph) => graph.pull_eval(),
+        }
+    }
+
+    fn state_type(&self) -> Option<syn::Type> {
+        match self {
+            NodeRef::Core(node) => node.state_type(),
+            NodeRef::Graph(graph) => graph.state_type(),
+        }
+    }
+
+    fn default_state(&self) -> Option<syn::Expr> {
+        match self {
+            NodeRef::Core(node) => node.default_state(),
+            // Nested graph state is constructed by the host.
+            NodeRef::Graph(_graph) => None,
+        }
+    }
+
+    fn crate_deps(&self) -> Vec<node::CrateDep> {
+        match self {
+            NodeRef::Core(node) => node.crate_deps(),
+            NodeRef::Graph(_graph) => {
+                vec![node::CrateDep::from_str(r#"libloading = "0.5""#).unwrap()]
+            }
+        }
+    }
+
+    fn io_types(&self) -> node::IoTypes {
+        match self {
+            NodeRef::Core(node) => node.io_types(),
+            NodeRef::Graph(graph) => graph.io_types(),
+        }
+    }
+
+    fn input_default(&self, input: node::Input) -> node::InputDefault {
+        match self {
+            NodeRef::Core(node) => node.input_default(input),
+            NodeRef::Graph(graph) => graph.input_default(input),
+        }
+    }
+
+    fn native(&self) -> Option<node::NativeFn> {
+        match self {
+            NodeRef::Core(node) => node.native(),
+            NodeRef::Graph(graph) => graph.native(),
+        }
+    }
+}
+
+impl ops::Deref for TempProject {
+    type Target = Project;
+    fn deref(&self) -> &Self::Target {
+        self.project.as_ref().expect("inner `project` was `None`")
+    }
+}
+
+impl ops::DerefMut for TempProject {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.project.as_mut().expect("inner `project` was `None`")
+    }
+}
+
+impl ops::Deref for NodeCollection {
+    type Target = NodeTree;
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+impl<'a> ops::Deref for ProjectNodeRefGraph<'a> {
+    type Target = NodeRefGraph<'a>;
+    fn deref(&self) -> &Self::Target {
+        &self.graph
+    }
+}
+
+impl<'a> ops::Deref for GraphUpdate<'a> {
+    type Target = NodeIdGraphNode;
+    fn deref(&self) -> &Self::Target {
+        self.graph
+    }
+}
+
+impl<'a> ops::DerefMut for GraphUpdate<'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.graph
+    }
+}
+
+impl Drop for TempProject {
+    fn drop(&mut self) {
+        let dir = self.dir().to_path_buf();
+        std::mem::drop(self.project.take());
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
+
+// The path to the generated dynamic library for the graph node package with the given ID.
+//
+// Returns `None` if the package is not a member of the workspace or has not yet been compiled.
+fn package_dylib<P>(
+    workspace_dir: P,
+    cargo_config: &cargo::Config,
+    profile: &config::BuildProfile,
+    package_id: cargo::core::PackageId,
+) -> cargo::CargoResult<Option<PathBuf>>
+where
+    P: AsRef<Path>,
+{
+    let ws_manifest_path = manifest_path(workspace_dir);
+    let ws = cargo::core::Workspace::new(&ws_manifest_path, cargo_config)?;
+    let target_dir = ws.target_dir().into_path_unlocked();
+    let pkg = match ws.members().find(|pkg| pkg.package_id() == package_id) {
+        Some(pkg) => pkg,
+        None => return Ok(None),
+    };
+    let target = match pkg.targets().iter().find(|target| target.is_dylib()) {
+        Some(t) => t,
+        None => return Ok(None),
+    };
+    let target_filestem = format!("lib{}", target.name());
+    let target_path = target_dir
+        .join(profile.dir_name())
+        .join(target_filestem)
+        .with_extension(dylib_ext());
+    if target_path.exists() {
+        Ok(Some(target_path))
+    } else {
+        Ok(None)
+    }
+}
+
+// Get the dylib extension for this platform.
+//
+// TODO: This should be exposed from cargo.
+fn dylib_ext() -> &'static str {
+    #[cfg(target_os = "linux")]
+    {
+        return "so";
+    }
+    #[cfg(target_os = "macos")]
+    {
+        return "dylib";
+    }
+    #[cfg(target_os = "windows")]
+    {
+        return "dll";
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    {
+        panic!("unknown dynamic library for this platform")
+    }
+}
+
+/// Given the project directory, retrieve the project name from the file stem.
+pub fn project_name(project_dir: &Path) -> &str {
+    project_dir
+        .file_stem()
+        .expect("failed to retrieve `file_stem` from project directory path")
+        .to_str()
+        .expect("failed to parse project `file_stem` as valid UTF-8")
+}
+
+/// Given the project directory, return the path to the project's workspace directory.
+pub fn workspace_dir<P>(project_dir: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    project_dir.as_ref().join("workspace")
+}
+
+/// Given a crate directory, return a path to its `Cargo.toml` file.
+pub fn manifest_path<P>(crate_dir: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    crate_dir.as_ref().join("Cargo.toml")
+}
+
+/// The path at which the project's node collection JSON is stored.
+pub fn node_collection_json_path<P>(project_dir: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    project_dir.as_ref().join("node_collection.json")
+}
+
+/// The path at which the location of the external workspace is recorded for projects opened via
+/// `Project::open_in_workspace`.
+pub fn external_workspace_json_path<P>(project_dir: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    project_dir.as_ref().join("external_workspace.json")
+}
+
+/// The path at which the project's **ProjectConfig** is stored.
+pub fn project_config_json_path<P>(project_dir: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    project_dir.as_ref().join("project_config.json")
+}
+
+/// The path at which the cargo configuration generated from the project's **ProjectConfig** is
+/// stored.
+pub fn cargo_config_toml_path<P>(workspace_dir: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    workspace_dir.as_ref().join(".cargo").join("config.toml")
+}
+
+/// The path to which a corrupt node collection is copied during recovery.
+///
+/// This is `<proj_dir>/node_collection.json.bak`, or `<proj_dir>/node_collection.json.bak.<n>`
+/// for the smallest `n` that does not yet exist in the case that previous backups exist.
+pub fn node_collection_backup_path<P>(project_dir: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    let base = node_collection_json_path(project_dir).with_extension("json.bak");
+    let mut path = base.clone();
+    let mut n = 1;
+    while path.exists() {
+        path = base.with_extension(format!("bak.{}", n));
+        n += 1;
+    }
+    path
+}
+
+/// Given some UTF-8 node name, return the name of the crate.
+pub fn node_crate_name(node_name: &str) -> String {
+    format!(
+        "{}{}",
+        NODE_CRATE_PREFIX,
+        slug::slugify(node_name).replace("-", "_")
+    )
+}
+
+/// Given the workspace directory and some UTF-8 node name, return the path to the crate directory.
+pub fn node_crate_dir<P>(workspace_dir: P, node_name: &str) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    workspace_dir.as_ref().join(node_crate_name(node_name))
+}
+
+/// Given a node crate directory, return the path to the src directory.
+pub fn node_crate_src<P>(node_crate_dir: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    node_crate_dir.as_ref().join("src")
+}
+
+/// Given a node crate directory, return the path to the lib.rs file.
+pub fn node_crate_lib_rs<P>(node_crate_src: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    node_crate_src.as_ref().join("lib.rs")
+}
+
+// Check the project at the given directory or create it if it does not exist.
+//
+// This does the following steps:
+//
+// - Creates the given project directory if it does not yet exist.
+// - Creates the cargo workspace at `<path>/workspace` if it does not yet exist.
+// - Initialises `<path>/workspace/Cargo.toml` with an empty members list if it does not yet exist.
+fn create_or_check_project_dir<P>(
+    project_dir: P,
+    external_workspace: Option<&Path>,
+    cargo_config: &cargo::Config,
+) -> Result<(), CreateOrCheckProjectDirectoryError>
+where
+    P: AsRef<Path>,
+{
+    // Create the project directory.
+    let project_dir = project_dir.as_ref();
+    if !project_dir.exists() {
+        fs::create_dir_all(project_dir)?;
+    }
+
+    match external_workspace {
+        // Open the existing workspace or create it if it does not exist.
+        None => {
+            let workspace_dir = workspace_dir(project_dir);
+            create_or_check_workspace(workspace_dir, cargo_config)?;
+        }
+        // An external workspace must already exist.
+        Some(workspace_dir) => check_external_workspace(workspace_dir, cargo_config)?,
+    }
+
+    Ok(())
+}
+
+// Check that the existing workspace at the given directory is a valid workspace root.
+fn check_external_workspace(
+    workspace_dir: &Path,
+    cargo_config: &cargo::Config,
+) -> Result<(), CreateOrCheckWorkspaceError> {
+    let workspace_manifest_path = manifest_path(workspace_dir);
+    let bytes = fs::read(&workspace_manifest_path)?;
+    let toml: toml::Value = toml::from_slice(&bytes)?;
+    if toml.get("workspace").is_none() {
+        let path = workspace_manifest_path;
+        return Err(CreateOrCheckWorkspaceError::NotAWorkspace { path });
+    }
+    cargo::core::Workspace::new(&workspace_manifest_path, cargo_config)?;
+    Ok(())
+}
+
+// Load the location of the external workspace recorded at the given path, if any.
+fn load_external_workspace(path: &Path) -> Result<Option<PathBuf>, JsonFileError> {
+    match fs::File::open(path) {
+        Ok(file) => Ok(Some(serde_json::from_reader(file)?)),
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+// Record the location of the external workspace at the given path.
+fn save_external_workspace(path: &Path, workspace_dir: &Path) -> Result<(), JsonFileError> {
+    let bytes = serde_json::to_vec_pretty(workspace_dir)?;
+    write_atomic(path, &bytes)?;
+    Ok(())
+}
+
+// Create a cargo configuration that behaves as though cargo were invoked from within the given
+// workspace directory.
+//
+// This ensures that the cargo configuration generated from the project's **ProjectConfig** is
+// respected.
+pub(crate) fn workspace_cargo_config<P>(workspace_dir: P) -> cargo::CargoResult<cargo::Config>
+where
+    P: AsRef<Path>,
+{
+    let cwd = std::env::current_dir()?.join(workspace_dir);
+    let homedir = cargo::util::homedir(&cwd)
+        .ok_or_else(|| anyhow::anyhow!("failed to locate the cargo home directory"))?;
+    let shell = cargo::core::Shell::new();
+    Ok(cargo::Config::new(shell, cwd, homedir))
+}
+
+// If a workspace does not exist at the given directory, create one.
+//
+// Returns an error if some IO error occurs of if cargo does not consider the existing/created
+// workspace to be a valid one.
+fn create_or_check_workspace<P>(
+    workspace_dir: P,
+    cargo_config: &cargo::Config,
+) -> Result<(), CreateOrCheckWorkspaceError>
+where
+    P: AsRef<Path>,
+{
+    // Create the workspace directory.
+    let workspace_dir = workspace_dir.as_ref();
+    if !workspace_dir.exists() {
+        fs::create_dir(&workspace_dir)?;
+    }
+
+    // Create the workspace cargo toml.
+    let workspace_manifest_path = manifest_path(&workspace_dir);
+    create_workspace_cargo_toml(&workspace_manifest_path)?;
+
+    // Verify the workspace.
+    cargo::core::Workspace::new(&workspace_manifest_path, cargo_config)?;
+
+    Ok(())
+}
+
+// Create a workspace `Cargo.toml` file at the given path if it does not yet exist.
+fn create_workspace_cargo_toml<P>(toml_path: P) -> io::Result<()>
+where
+    P: AsRef<Path>,
+{
+    // If the file already exists, don't do anything.
+    let toml_path = toml_path.as_ref();
+    if toml_path.exists() {
+        return Ok(());
+    }
+
+    // Create the toml to write to the file.
+    let toml_str = "[workspace]\nmembers = [\n]";
+
+    // Write the string to a file at the given path.
+    fs::write(toml_path, toml_str)
+}
+
+// Write the given bytes to the file at the given path by first writing to a temporary file within
+// the same directory and then renaming it over the destination.
+//
+// This ensures that the file at `path` is either left untouched or entirely replaced, even if the
+// process is interrupted mid-write.
+fn write_atomic<P>(path: P, bytes: &[u8]) -> io::Result<()>
+where
+    P: AsRef<Path>,
+{
+    use std::io::Write;
+    let path = path.as_ref();
+    let mut tmp_name = path
+        .file_name()
+        .expect("cannot atomically write to a path without a file name")
+        .to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+    {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp_path, path)
+}
+
+// Update the toml at the given file.
+fn update_toml_file<P, F>(toml_path: P, update: F) -> Result<(), UpdateTomlFileError>
+where
+    P: AsRef<Path>,
+    F: FnOnce(&mut toml::Value),
+{
+    let bytes = fs::read(&toml_path)?;
+    let mut toml: toml::Value = toml::from_slice(&bytes)?;
+    update(&mut toml);
+    let toml_string = toml::to_string_pretty(&toml)?;
+    write_atomic(&toml_path, toml_string.as_bytes())?;
+    Ok(())
+}
+
+// The same as `update_toml_file`, but preserves the formatting and comments of the document.
+//
+// Used for the workspace `Cargo.toml`, which may be maintained by hand in the case that the
+// project was opened via `Project::open_in_workspace`.
+fn edit_toml_file<P, F>(toml_path: P, edit: F) -> Result<(), UpdateTomlFileError>
+where
+    P: AsRef<Path>,
+    F: FnOnce(&mut toml_edit::Document),
+{
+    let string = fs::read_to_string(&toml_path)?;
+    let mut doc: toml_edit::Document = string.parse()?;
+    edit(&mut doc);
+    write_atomic(&toml_path, doc.to_string().as_bytes())?;
+    Ok(())
+}
+
+// The `workspace.members` array of the given workspace manifest, if any.
+fn workspace_members_mut(doc: &mut toml_edit::Document) -> Option<&mut toml_edit::Array> {
+    doc.get_mut("workspace")
+        .and_then(|ws| ws.get_mut("members"))
+        .and_then(|members| members.as_array_mut())
+}
+
+fn node_crate_new_options(dir_path: PathBuf) -> cargo::CargoResult<cargo::ops::NewOptions> {
+    let version_ctrl = None;
+    let bin = false;
+    let lib = true;
+    let name = None;
+    let edition = None;
+    let registry = None;
+    cargo::ops::NewOptions::new(version_ctrl, bin, lib, dir_path, name, edition, registry)
+}
+
+// Create a node crate within the given workspace directory with the given name.
+//
+// The crate name will be slugified before being used within the path.
+fn open_node_package<P>(
+    workspace_dir: P,
+    node_name: &str,
+    cargo_config: &cargo::Config,
+) -> Result<cargo::core::PackageId, OpenNodePackageError>
+where
+    P: AsRef<Path>,
+{
+    open_node_crate_package(workspace_dir, &node_crate_name(node_name), cargo_config)
+}
+
+// The same as `open_node_package`, but for the crate with the given name.
+//
+// This is useful for re-opening the package of an existing graph node whose crate name is already
+// known, e.g. via its `PackageId`.
+fn open_node_crate_package<P>(
+    workspace_dir: P,
+    node_crate_name: &str,
+    cargo_config: &cargo::Config,
+) -> Result<cargo::core::PackageId, OpenNodePackageError>
+where
+    P: AsRef<Path>,
+{
+    // If the directory doesn't exist yet, create it. This is done first so that cargo can open
+    // the workspace in the case that the crate is already listed as a member.
+    let workspace_dir = workspace_dir.as_ref();
+    let node_crate_dir_path = workspace_dir.join(node_crate_name);
+    if !node_crate_dir_path.exists() {
+        let new_options = node_crate_new_options(node_crate_dir_path.clone())?;
+        cargo::ops::new(&new_options, &cargo_config)?;
+
+        // Add the lib targets.
+        let node_crate_manifest_path = manifest_path(&node_crate_dir_path);
+        update_toml_file(&node_crate_manifest_path, |toml| {
+            let table = match toml {
+                toml::Value::Table(ref mut table) => table,
+                _ => return,
+            };
+            let mut lib_table = HashMap::default();
+            lib_table.insert("name".to_string(), node_crate_name.into());
+            let array = toml::Value::Array(vec!["lib".into(), "dylib".into()]);
+            lib_table.insert("crate-type".to_string(), array);
+            table.insert("lib".to_string(), lib_table.into());
+        })?;
+    }
+
+    // Check to see if the node exists within `workspace.members` yet. If not, add it.
+    let workspace_manifest_path = manifest_path(workspace_dir);
+    let exists = {
+        let workspace = cargo::core::Workspace::new(&workspace_manifest_path, &cargo_config)?;
+        workspace
+            .members()
+            .any(|pkg| format!("{}", pkg.name()) == node_crate_name)
+    };
+    if !exists {
+        edit_toml_file(&workspace_manifest_path, |doc| {
+            let ws = match doc
+                .get_mut("workspace")
+                .and_then(|ws| ws.as_table_like_mut())
+            {
+                Some(ws) => ws,
+                None => return,
+            };
+            let members = ws
+                .entry("members")
+                .or_insert_with(|| toml_edit::value(toml_edit::Array::new()));
+            if let Some(members) = members.as_array_mut() {
+                members.push(node_crate_name);
+            }
+        })?;
+    }
+
+    // Verify the package after creation (or if it already exists) by reading it.
+    let workspace = cargo::core::Workspace::new(&workspace_manifest_path, &cargo_config)?;
+    let pkg = workspace
+        .members()
+        .find(|pkg| format!("{}", pkg.name()) == node_crate_name)
+        .expect("failed to find workspace package with matching name");
+
+    Ok(pkg.package_id())
+}
+
+// Add the given node to the node collection and return the unique `NodeId` and generated
+// cargo workspace package associated with it.
+fn add_graph_node_to_collection<P>(
+    workspace_dir: P,
+    node_name: &str,
+    cargo_config: &cargo::Config,
+    graph: NodeIdGraphNode,
+    nodes: &mut NodeCollection,
+) -> Result<NodeId, AddGraphNodeToCollectionError>
+where
+    P: AsRef<Path>,
+{
+    let node_id = nodes.next_node_id();
+    insert_graph_node_to_collection(
+        workspace_dir,
+        node_name,
+        cargo_config,
+        graph,
+        nodes,
+        node_id,
+    )?;
+    Ok(node_id)
+}
+
+// Create an empty root graph node named after the project at `ROOT_NODE_ID` and compile it.
+//
+// Any existing node at `ROOT_NODE_ID` is replaced.
+fn create_root_graph_node<E>(
+    project_dir: &Path,
+    ws_dir: &Path,
+    cargo_config: &cargo::Config,
+    profile: &config::BuildProfile,
+    nodes: &mut NodeCollection,
+) -> Result<(), E>
+where
+    E: From<AddGraphNodeToCollectionError> + From<GraphNodeCompileError>,
+{
+    let proj_name = project_name(project_dir);
+    let graph_node = NodeIdGraphNode::default();
+    insert_graph_node_to_collection(
+        ws_dir,
+        proj_name,
+        cargo_config,
+        graph_node,
+        nodes,
+        ROOT_NODE_ID,
+    )?;
+    if let Some(node) = nodes.id_graph(&ROOT_NODE_ID) {
+        graph_node_compile(ws_dir, cargo_config, profile, node)?;
+    }
+    Ok(())
+}
+
+// Insert the given graph node into the node collection at the given `NodeId`, opening its cargo
+// workspace package and generating its source.
+fn insert_graph_node_to_collection<P>(
+    workspace_dir: P,
+    node_name: &str,
+    cargo_config: &cargo::Config,
+    graph: NodeIdGraphNode,
+    nodes: &mut NodeCollection,
+    node_id: NodeId,
+) -> Result<(), AddGraphNodeToCollectionError>
+where
+    P: AsRef<Path>,
+{
+    let package_id = open_node_package(&workspace_dir, node_name, cargo_config)?;
+    let kind = NodeKind::Graph(ProjectGraph::new(graph, package_id));
+    nodes.map.insert(node_id, kind);
+    let graph = nodes
+        .ref_graph(&node_id)
+        .expect("no graph node for the given ID");
+    let deps = graph_node_deps(&graph);
+    let file = graph_node_src(&graph);
+    graph_node_insert_deps(&workspace_dir, cargo_config, graph.package_id, deps)?;
+    graph_node_replace_src(&workspace_dir, cargo_config, graph.package_id, file)?;
+    Ok(())
+}
+
+// Compile all crates within the workspace.
+fn _workspace_compile<P>(
+    workspace_dir: P,
+    cargo_config: &cargo::Config,
+    profile: &config::BuildProfile,
+) -> Result<
+    HashMap<cargo::core::PackageId, cargo::core::compiler::Compilation>,
+    WorkspaceCompileError,
+>
+where
+    P: AsRef<Path>,
+{
+    let ws_manifest_path = manifest_path(workspace_dir);
+    let ws = cargo::core::Workspace::new(&ws_manifest_path, &cargo_config)?;
+    let mut compilations = HashMap::default();
+    for pkg in ws.members() {
+        let pkg_manifest_path = pkg.manifest_path();
+        let pkg_ws = cargo::core::Workspace::new(&pkg_manifest_path, &cargo_config)?;
+        let mode = cargo::core::compiler::CompileMode::Build;
+        let mut options = cargo::ops::CompileOptions::new(&cargo_config, mode)?;
+        options.build_config.message_format = cargo::core::compiler::MessageFormat::Human;
+        options.build_config.requested_profile = profile.name().into();
+        let compilation = cargo::ops::compile(&pkg_ws, &options)?;
+        compilations.insert(pkg.package_id(), compilation);
+    }
+    Ok(compilations)
+}
+
+// Compile the graph node associated with the given `NodeId`.
+fn graph_node_compile<'conf, P>(
+    workspace_dir: P,
+    cargo_config: &'conf cargo::Config,
+    profile: &config::BuildProfile,
+    node: &ProjectGraph,
+) -> Result<cargo::core::compiler::Compilation<'conf>, GraphNodeCompileError>
+where
+    P: AsRef<Path>,
+{
+    let (package_id, instance_ids) = (node.package_id, &node.instance_ids);
+    let (ws_dir, cfg) = (workspace_dir, cargo_config);
+    graph_node_compile_package(ws_dir, cfg, profile, package_id, instance_ids, None)
+}
+
+// Compile the graph node package with the given ID.
+//
+// The `instance_ids` of the graph's nodes are used to attribute rustc diagnostics to nodes. If a
+// background build `job` is given, it is updated as each crate is compiled and compilation is
+// aborted before the next crate if the job is cancelled.
+fn graph_node_compile_package<'conf, P>(
+    workspace_dir: P,
+    cargo_config: &'conf cargo::Config,
+    profile: &config::BuildProfile,
+    package_id: cargo::core::PackageId,
+    instance_ids: &BTreeMap<NodeIndex, InstanceId>,
+    job: Option<Arc<build::JobState>>,
+) -> Result<cargo::core::compiler::Compilation<'conf>, GraphNodeCompileError>
+where
+    P: AsRef<Path>,
+{
+    let ws_manifest_path = manifest_path(workspace_dir);
+    let ws = cargo::core::Workspace::new(&ws_manifest_path, &cargo_config)?;
+    let pkg = ws
+        .members()
+        .find(|pkg| pkg.package_id() == package_id)
+        .ok_or(GraphNodeCompileError::NoMatchingPackageId)?;
+    let pkg_manifest_path = pkg.manifest_path();
+    let pkg_ws = cargo::core::Workspace::new(&pkg_manifest_path, &cargo_config)?;
+    let mode = cargo::core::compiler::CompileMode::Build;
+    let mut options = cargo::ops::CompileOptions::new(&cargo_config, mode)?;
+    options.build_config.message_format = cargo::core::compiler::MessageFormat::Json {
+        render_diagnostics: false,
+        short: false,
+        ansi: false,
+    };
+    options.build_config.requested_profile = profile.name().into();
+    let collector = Arc::new(GraphNodeExecutor::new(package_id, job));
+    let exec: Arc<dyn cargo::core::compiler::Executor> = collector.clone();
+    match cargo::ops::compile_with_exec(&pkg_ws, &options, &exec) {
+        Ok(compilation) => Ok(compilation),
+        Err(err) => {
+            let diagnostics = collector.take();
+            if diagnostics.is_empty() {
+                return Err(err.into());
+            }
+            let lib_rs = node_crate_lib_rs(node_crate_src(pkg.root()));
+            let src = fs::read_to_string(&lib_rs).unwrap_or_default();
+            let nodes: HashMap<_, _> = instance_ids.iter().map(|(&n, id)| (id.0, n)).collect();
+            let diagnostics = diagnostics
+                .into_iter()
+                .map(|diagnostic| node_diagnostic(diagnostic, &src, &nodes))
+                .collect();
+            Err(GraphNodeCompileError::Rustc { diagnostics, err })
+        }
+    }
+}
+
+// A diagnostic emitted by rustc in its JSON format.
+#[derive(Deserialize)]
+struct RustcDiagnostic {
+    message: String,
+    level: String,
+    spans: Vec<RustcSpan>,
+    rendered: Option<String>,
+}
+
+// A span of source code referred to by a rustc JSON diagnostic.
+#[derive(Deserialize)]
+struct RustcSpan {
+    file_name: String,
+    byte_start: usize,
+    is_primary: bool,
+}
+
+// A cargo executor that collects the JSON diagnostics emitted by rustc while compiling a graph
+// node's package.
+//
+// If the compilation was requested by a background build job, the job's progress is updated and
+// compilation is aborted if the job is cancelled.
+struct GraphNodeExecutor {
+    package_id: cargo::core::PackageId,
+    job: Option<Arc<build::JobState>>,
+    diagnostics: Mutex<Vec<RustcDiagnostic>>,
+}
+
+impl GraphNodeExecutor {
+    fn new(package_id: cargo::core::PackageId, job: Option<Arc<build::JobState>>) -> Self {
+        let diagnostics = Default::default();
+        GraphNodeExecutor {
+            package_id,
+            job,
+            diagnostics,
+        }
+    }
+
+    // Take all diagnostics collected so far.
+    fn take(&self) -> Vec<RustcDiagnostic> {
+        let mut diagnostics = self
+            .diagnostics
+            .lock()
+            .expect("failed to lock rustc diagnostics");
+        std::mem::replace(&mut *diagnostics, vec![])
+    }
+}
+
+impl cargo::core::compiler::Executor for GraphNodeExecutor {
+    fn exec(
+        &self,
+        cmd: cargo::util::ProcessBuilder,
+        id: cargo::core::PackageId,
+        _target: &cargo::core::Target,
+        _mode: cargo::core::compiler::CompileMode,
+        on_stdout_line: &mut dyn FnMut(&str) -> cargo::CargoResult<()>,
+        on_stderr_line: &mut dyn FnMut(&str) -> cargo::CargoResult<()>,
+    ) -> cargo::CargoResult<()> {
+        let name = id.name();
+        if let Some(ref job) = self.job {
+            job.crate_started(&name)?;
+        }
+        let mut on_stderr = |line: &str| {
+            if id == self.package_id {
+                if let Ok(diagnostic) = serde_json::from_str::<RustcDiagnostic>(line) {
+                    self.diagnostics
+                        .lock()
+                        .expect("failed to lock rustc diagnostics")
+                        .push(diagnostic);
+                }
+            }
+            on_stderr_line(line)
+        };
+        let result = cmd.exec_with_streaming(on_stdout_line, &mut on_stderr, false);
+        if let Some(ref job) = self.job {
+            job.crate_finished(&name);
+        }
+        result.map(drop)
+    }
+}
+
+// Attribute the given rustc diagnostic to the node whose generated code caused it.
+//
+// The `src` is the generated code and `nodes` maps node instance IDs to their index.
+fn node_diagnostic(
+    diagnostic: RustcDiagnostic,
+    src: &str,
+    nodes: &HashMap<u64, NodeIndex>,
+) -> NodeDiagnostic {
+    let location = diagnostic
+        .spans
+        .iter()
+        .filter(|span| span.is_primary && span.file_name.ends_with("lib.rs"))
+        .find_map(|span| graph::codegen::src_location(src, span.byte_start));
+    let node = location.and_then(|loc| nodes.get(&loc.instance_id).cloned());
+    let input = location.and_then(|loc| loc.input);
+    NodeDiagnostic {
+        node,
+        input,
+        level: diagnostic.level,
+        message: diagnostic.message,
+        rendered: diagnostic.rendered,
+    }
+}
+
+// Regenerate the dependencies and source of the graph node with the given `NodeId` and recompile
+// it.
+//
+// Does nothing if there is no graph node for the given ID.
+fn regenerate_graph_node<P>(
+    workspace_dir: P,
+    cargo_config: &cargo::Config,
+    profile: &config::BuildProfile,
+    nodes: &NodeCollection,
+    id: &NodeId,
+) -> Result<(), RegenerateGraphNodeError>
+where
+    P: AsRef<Path>,
+{
+    regenerate_graph_node_src(&workspace_dir, cargo_config, nodes, id)?;
+    if let Some(node) = nodes.id_graph(id) {
+        graph_node_compile(&workspace_dir, cargo_config, profile, node)?;
+    }
+    Ok(())
+}
+
+// Regenerate the dependencies and source of the graph node with the given `NodeId` without
+// recompiling it.
+//
+// Does nothing if there is no graph node for the given ID.
+fn regenerate_graph_node_src<P>(
+    workspace_dir: P,
+    cargo_config: &cargo::Config,
+    nodes: &NodeCollection,
+    id: &NodeId,
+) -> Result<(), RegenerateGraphNodeError>
+where
+    P: AsRef<Path>,
+{
+    let graph = match nodes.ref_graph(id) {
+        Some(g) => g,
+        None => return Ok(()),
+    };
+    let diagnostics = graph_node_diagnostics(&graph);
+    if !diagnostics.is_empty() {
+        return Err(RegenerateGraphNodeError::InvalidGraph { diagnostics });
+    }
+    let deps = graph_node_deps(&graph);
+    let file = graph_node_src(&graph);
+    graph_node_insert_deps(&workspace_dir, cargo_config, graph.package_id, deps)?;
+    graph_node_replace_src(&workspace_dir, cargo_config, graph.package_id, file)?;
+    Ok(())
+}
+
+// Remove the member with the given crate name from the workspace `Cargo.toml`.
+fn remove_workspace_member<P>(
+    workspace_dir: P,
+    node_crate_name: &str,
+) -> Result<(), UpdateTomlFileError>
+where
+    P: AsRef<Path>,
+{
+    edit_toml_file(manifest_path(workspace_dir), |doc| {
+        if let Some(members) = workspace_members_mut(doc) {
+            members.retain(|member| member.as_str() != Some(node_crate_name));
+        }
+    })
+}
+
+// Given the `ProjectGraph` of the graph node with the given `id` and the `NodeCollection`, return a
+// graph capable of evaluation.
+fn id_graph_to_node_graph<'a>(
+    id: NodeId,
+    g: &'a ProjectGraph,
+    ns: &'a NodeCollection,
+) -> ProjectNodeRefGraphNode<'a> {
+    let inlets = g.graph.inlets.clone();
+    let outlets = g.graph.outlets.clone();
+    let graph = g.graph.map(
+        |_, n_id| match ns[n_id] {
+            NodeKind::Core(ref node) => NodeRef::Core(node.node()),
+            NodeKind::Graph(ref node) => NodeRef::Graph(id_graph_to_node_graph(*n_id, node, ns)),
+        },
+        |_, edge| edge.clone(),
+    );
+    let package_id = g.package_id;
+    let instance_ids = &g.instance_ids;
+    let graph = ProjectNodeRefGraph {
+        graph,
+        id,
+        package_id,
+        instance_ids,
+    };
+    GraphNode {
+        graph,
+        inlets,
+        outlets,
+    }
+}
+
+// Check the given graph node for problems that would prevent it from compiling.
+//
+// Warnings are omitted, as they do not prevent code generation.
+fn graph_node_diagnostics(g: &ProjectNodeRefGraphNode) -> Vec<Diagnostic> {
+    graph::validate::diagnostics(&g.graph.graph, &g.inlets, &g.outlets)
+        .into_iter()
+        .filter(|d| d.is_error())
+        .collect()
+}
+
+// Given a graph node, generate the src for the graph.
+fn graph_node_src(g: &ProjectNodeRefGraphNode) -> syn::File {
+    let instance_ids = g.graph.node_instance_ids();
+    let mut file = graph::codegen::file_with_instance_ids(
+        &g.graph.graph,
+        &g.inlets,
+        &g.outlets,
+        &instance_ids,
+    );
+
+    // Nested graph state is constructed by the host, so only core node state is handled here.
+    let node_state_types = graph::codegen::node_state_types(&g.graph.graph);
+    let core_nodes: Vec<_> = g
+        .graph
+        .graph
+        .node_indices()
+        .filter(|&n| match g.graph.graph[n] {
+            NodeRef::Core(_) => true,
+            NodeRef::Graph(_) => false,
+        })
+        .collect();
+    let node_state_default_fn = graph::codegen::node_state_default_fn(
+        &g.graph.graph,
+        &node_state_types,
+        core_nodes.iter().cloned(),
+    );
+    let node_state_migrate_fn =
+        graph::codegen::node_state_migrate_fn(&g.graph.graph, &node_state_types, core_nodes);
+    // State types are checked by the host for all nodes, including nested graphs.
+    let node_state_type_id_fn = graph::codegen::node_state_type_id_fn(
+        &g.graph.graph,
+        &node_state_types,
+        g.graph.graph.node_indices(),
+    );
+    file.items.push(syn::Item::Fn(node_state_default_fn));
+    file.items.push(syn::Item::Fn(node_state_migrate_fn));
+    file.items.push(syn::Item::Fn(node_state_type_id_fn));
+
+    // Record the layout within the library so that it may be loaded while the graph is edited.
+    let layout = runtime::GraphLayout::new(g);
+    file.items
+        .push(syn::Item::Fn(runtime::graph_layout_fn(&layout)));
+    file
+}
+
+// Find the set of crate dependencies required for a the graph node with the given `NodeId`.
+fn graph_node_deps(g: &ProjectNodeRefGraphNode) -> HashSet<node::CrateDep> {
+    graph::codegen::crate_deps(&g.graph.graph)
+}
+
+// Determine the root directory of the package with the given `PackageId`.
+fn package_root<P>(
+    workspace_dir: P,
+    cargo_config: &cargo::Config,
+    package_id: cargo::core::PackageId,
+) -> Result<PathBuf, PackageRootError>
+where
+    P: AsRef<Path>,
+{
+    let ws_manifest_path = manifest_path(workspace_dir);
+    let workspace = cargo::core::Workspace::new(&ws_manifest_path, &cargo_config)?;
+    let pkg = workspace
+        .members()
+        .find(|pkg| pkg.package_id() == package_id)
+        .ok_or(PackageRootError::NoMatchingPackageId)?;
+    Ok(pkg.root().to_path_buf())
+}
+
+// Replace the `[dependencies]` of the given graph node's `Cargo.toml` with the given set. For use
+// in conjunction with `graph_node_deps`.
+fn graph_node_insert_deps<P>(
+    workspace_dir: P,
+    cargo_config: &cargo::Config,
+    graph_node_pkg_id: cargo::core::PackageId,
+    deps: HashSet<node::CrateDep>,
+) -> Result<(), GraphNodeInsertDepsError>
+where
+    P: AsRef<Path>,
+{
+    let node_crate_dir = package_root(workspace_dir, cargo_config, graph_node_pkg_id)?;
+    let node_crate_manifest_path = manifest_path(node_crate_dir);
+    let mut dep_map = toml::value::Table::new();
+    for dep in deps {
+        let entry_str = format!("{} = {}", dep.name, dep.source);
+        let src: toml::Value = match toml::from_str(&entry_str)? {
+            toml::Value::Table(map) => match map.into_iter().next() {
+                Some((_name, src)) => src,
+                _ => unreachable!("cannot create dependency map with no entries"),
+            },
+            _ => return Err(GraphNodeInsertDepsError::InvalidCrateDep { dep }),
+        };
+        // Two separate sources for the same crate cannot be included within one graph.
+        match dep_map.get(&dep.name) {
+            Some(existing_src) if *existing_src != src => {
+                let name = dep.name;
+                return Err(GraphNodeInsertDepsError::ConflictingCrateDeps { name });
+            }
+            _ => (),
+        }
+        dep_map.insert(dep.name, src);
+    }
+    update_toml_file(&node_crate_manifest_path, |toml| {
+        // Retrieve the table.
+        let table = match toml {
+            toml::Value::Table(ref mut table) => table,
+            _ => return,
+        };
+
+        // The dependencies are entirely determined by the graph, so rebuild the table. This
+        // ensures the versions of replaced nodes are updated and those of removed nodes dropped.
+        table.insert("dependencies".to_string(), toml::Value::Table(dep_map));
+    })?;
+
+    Ok(())
+}
+
+// Replace the `src/lib.rs` file for the given graph node with the given file. For use in
+// conjunction with `graph_node_src`.
+fn graph_node_replace_src<P>(
+    workspace_dir: P,
+    cargo_config: &cargo::Config,
+    graph_node_pkg_id: cargo::core::PackageId,
+    file: syn::File,
+) -> Result<(), GraphNodeReplaceSrcError>
+where
+    P: AsRef<Path>,
+{
+    let node_crate_dir = package_root(workspace_dir, cargo_config, graph_node_pkg_id)?;
+    let node_crate_lib_rs = node_crate_lib_rs(node_crate_src(node_crate_dir));
+    let src_string = format!("{}", file.into_token_stream());
+    let src_bytes = src_string.as_bytes();
+    std::fs::write(&node_crate_lib_rs, src_bytes)?;
+    Ok(())
+}
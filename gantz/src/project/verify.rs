@@ -0,0 +1,419 @@
+//! Items related to checking a project's **NodeCollection** for consistency with itself and the
+//! project workspace, along with repairing any inconsistencies found.
+
+use super::{
+    create_root_graph_node, manifest_path, node_crate_name, open_node_crate_package,
+    regenerate_graph_node, AddGraphNodeToCollectionError, GraphNodeCompileError, JsonFileError,
+    NodeId, NodeIndex, NodeKind, OpenNodePackageError, Project, RegenerateGraphNodeError,
+    ROOT_NODE_ID,
+};
+use std::collections::BTreeSet;
+use std::path::{Component, Path, PathBuf};
+use std::{fmt, fs, io};
+use thiserror::Error;
+
+/// A single inconsistency detected within a project's **NodeCollection**.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Inconsistency {
+    /// There is no node at `ROOT_NODE_ID`, or the node is not a graph.
+    MissingRootGraph,
+    /// The crate of the root graph node is not named after the project.
+    RootGraphName { expected: String, found: String },
+    /// A node within a graph refers to a **NodeId** that does not exist within the collection.
+    DanglingNodeId {
+        /// The graph containing the dangling reference.
+        graph: NodeId,
+        /// The index of the node within the graph.
+        node: NodeIndex,
+        /// The **NodeId** that does not exist.
+        id: NodeId,
+    },
+    /// The crate of a graph node is not a member of the project workspace, or the crate directory
+    /// is missing.
+    MissingWorkspaceMember {
+        /// The graph node whose crate is missing.
+        graph: NodeId,
+        /// The stored **PackageId** of the missing crate.
+        package_id: cargo::core::PackageId,
+    },
+    /// The stored **PackageId** of a graph node differs from that of the workspace member with
+    /// the same name, e.g. due to a version change or because the project was moved.
+    StalePackageId {
+        /// The graph node with the stale **PackageId**.
+        graph: NodeId,
+        /// The **PackageId** stored within the collection.
+        stored: cargo::core::PackageId,
+        /// The **PackageId** of the workspace member.
+        found: cargo::core::PackageId,
+    },
+    /// One of a graph's inlets refers to a node index that does not exist within the graph.
+    InvalidInlet {
+        /// The graph whose inlet is invalid.
+        graph: NodeId,
+        /// The position of the inlet within the graph's inlets.
+        index: usize,
+        /// The non-existent node index.
+        node: NodeIndex,
+    },
+    /// One of a graph's outlets refers to a node index that does not exist within the graph.
+    InvalidOutlet {
+        /// The graph whose outlet is invalid.
+        graph: NodeId,
+        /// The position of the outlet within the graph's outlets.
+        index: usize,
+        /// The non-existent node index.
+        node: NodeIndex,
+    },
+}
+
+/// The result of verifying a project's **NodeCollection**.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct VerifyReport {
+    /// All inconsistencies found, in the order in which they were detected.
+    pub inconsistencies: Vec<Inconsistency>,
+}
+
+/// The result of repairing a project's **NodeCollection**.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RepairReport {
+    /// Inconsistencies that were successfully repaired.
+    pub repaired: Vec<Inconsistency>,
+    /// Inconsistencies that could not be repaired automatically.
+    pub unrepaired: Vec<Inconsistency>,
+}
+
+/// Errors that may occur while verifying a project.
+#[derive(Debug, Error)]
+pub enum VerifyError {
+    #[error("an IO error occurred: {err}")]
+    Io {
+        #[from]
+        err: io::Error,
+    },
+    #[error("failed to deserialize the workspace toml: {err}")]
+    TomlDeserialize {
+        #[from]
+        err: toml::de::Error,
+    },
+    #[error("cargo failed to open the workspace: {err}")]
+    Cargo {
+        #[from]
+        err: anyhow::Error,
+    },
+    #[error("invalid workspace member pattern: {err}")]
+    MemberPattern {
+        #[from]
+        err: glob::PatternError,
+    },
+}
+
+/// Errors that may occur while repairing a project.
+#[derive(Debug, Error)]
+pub enum RepairError {
+    #[error("failed to verify the project: {err}")]
+    Verify {
+        #[from]
+        err: VerifyError,
+    },
+    #[error("failed to re-open node cargo package: {err}")]
+    OpenNodePackage {
+        #[from]
+        err: OpenNodePackageError,
+    },
+    #[error("failed to create a new root graph node: {err}")]
+    AddGraphNodeToCollection {
+        #[from]
+        err: AddGraphNodeToCollectionError,
+    },
+    #[error("failed to regenerate a repaired graph: {err}")]
+    RegenerateGraphNode {
+        #[from]
+        err: RegenerateGraphNodeError,
+    },
+    #[error("failed to compile graph node: {err}")]
+    GraphNodeCompile {
+        #[from]
+        err: GraphNodeCompileError,
+    },
+    #[error("failed to save the node collection: {err}")]
+    Save {
+        #[from]
+        err: JsonFileError,
+    },
+}
+
+impl VerifyReport {
+    /// Whether or not the collection was found to be free of inconsistencies.
+    pub fn is_consistent(&self) -> bool {
+        self.inconsistencies.is_empty()
+    }
+}
+
+impl fmt::Display for Inconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Inconsistency::MissingRootGraph => write!(f, "the root node is missing or not a graph"),
+            Inconsistency::RootGraphName {
+                ref expected,
+                ref found,
+            } => write!(
+                f,
+                "the root graph crate is named `{}`, expected `{}`",
+                found, expected
+            ),
+            Inconsistency::DanglingNodeId { graph, node, id } => write!(
+                f,
+                "node {} in graph {:?} refers to non-existent {:?}",
+                node.index(),
+                graph,
+                id
+            ),
+            Inconsistency::MissingWorkspaceMember { graph, package_id } => write!(
+                f,
+                "the crate `{}` of graph {:?} is missing from the workspace",
+                package_id.name(),
+                graph
+            ),
+            Inconsistency::StalePackageId {
+                graph,
+                stored,
+                found,
+            } => write!(
+                f,
+                "graph {:?} refers to package `{}`, but the workspace contains `{}`",
+                graph, stored, found
+            ),
+            Inconsistency::InvalidInlet { graph, index, node } => write!(
+                f,
+                "inlet {} of graph {:?} refers to non-existent node {}",
+                index,
+                graph,
+                node.index()
+            ),
+            Inconsistency::InvalidOutlet { graph, index, node } => write!(
+                f,
+                "outlet {} of graph {:?} refers to non-existent node {}",
+                index,
+                graph,
+                node.index()
+            ),
+        }
+    }
+}
+
+impl fmt::Display for VerifyReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for inconsistency in &self.inconsistencies {
+            writeln!(f, "- {}", inconsistency)?;
+        }
+        Ok(())
+    }
+}
+
+/// Check the given project for inconsistencies. See `Project::verify`.
+pub fn verify(project: &Project) -> Result<VerifyReport, VerifyError> {
+    let mut inconsistencies = vec![];
+    let nodes = &project.nodes;
+    let ws_dir = project.workspace_dir();
+
+    // Check the root graph.
+    match nodes.get(&ROOT_NODE_ID) {
+        Some(NodeKind::Graph(ref g)) => {
+            let expected = node_crate_name(project.name());
+            let found = format!("{}", g.package_id.name());
+            if expected != found {
+                inconsistencies.push(Inconsistency::RootGraphName { expected, found });
+            }
+        }
+        _ => inconsistencies.push(Inconsistency::MissingRootGraph),
+    }
+
+    // Check the node references, inlets and outlets of each graph.
+    for (&graph, kind) in nodes.iter() {
+        let g = match kind.graph() {
+            Some(g) => g,
+            None => continue,
+        };
+        for node in g.graph.node_indices() {
+            let id = g.graph[node];
+            if !nodes.contains_key(&id) {
+                inconsistencies.push(Inconsistency::DanglingNodeId { graph, node, id });
+            }
+        }
+        for (index, &node) in g.graph.inlets.iter().enumerate() {
+            if !g.graph.contains_node(node) {
+                inconsistencies.push(Inconsistency::InvalidInlet { graph, index, node });
+            }
+        }
+        for (index, &node) in g.graph.outlets.iter().enumerate() {
+            if !g.graph.contains_node(node) {
+                inconsistencies.push(Inconsistency::InvalidOutlet { graph, index, node });
+            }
+        }
+    }
+
+    // Check each graph's crate is a listed workspace member with an existing directory.
+    let members = workspace_members(&ws_dir)?;
+    let mut present = BTreeSet::new();
+    for (&graph, kind) in nodes.iter() {
+        let g = match kind.graph() {
+            Some(g) => g,
+            None => continue,
+        };
+        let name = format!("{}", g.package_id.name());
+        let crate_dir = normalize(&ws_dir.join(&name));
+        let member_exists = members.contains(&crate_dir) && manifest_path(&crate_dir).exists();
+        if member_exists {
+            present.insert(graph);
+        } else {
+            let package_id = g.package_id;
+            inconsistencies.push(Inconsistency::MissingWorkspaceMember { graph, package_id });
+        }
+    }
+
+    // Compare the stored `PackageId`s with those of the workspace. Cargo refuses to open a
+    // workspace with missing members, so only do so if all listed members exist.
+    let all_members_exist = members.iter().all(|member| manifest_path(member).exists());
+    if all_members_exist {
+        let ws_manifest_path = manifest_path(&ws_dir);
+        let ws = cargo::core::Workspace::new(&ws_manifest_path, &project.cargo_config)?;
+        for &graph in &present {
+            let stored = nodes[&graph]
+                .graph()
+                .expect("expected graph node")
+                .package_id;
+            let pkg = ws.members().find(|pkg| pkg.name() == stored.name());
+            if let Some(pkg) = pkg {
+                let found = pkg.package_id();
+                if found != stored {
+                    let inconsistency = Inconsistency::StalePackageId {
+                        graph,
+                        stored,
+                        found,
+                    };
+                    inconsistencies.push(inconsistency);
+                }
+            }
+        }
+    }
+
+    Ok(VerifyReport { inconsistencies })
+}
+
+/// Verify the given project and repair all inconsistencies that can be repaired. See
+/// `Project::repair`.
+pub fn repair(project: &mut Project) -> Result<RepairReport, RepairError> {
+    let report = verify(project)?;
+    let mut repaired = vec![];
+    let mut unrepaired = vec![];
+
+    // Graphs whose source must be regenerated and recompiled once all repairs are complete.
+    let mut regenerate = BTreeSet::new();
+
+    let ws_dir = project.workspace_dir();
+    for inconsistency in report.inconsistencies {
+        match inconsistency {
+            Inconsistency::MissingRootGraph => {
+                let Project {
+                    ref directory,
+                    ref workspace_dir,
+                    ref cargo_config,
+                    ref config,
+                    ref mut nodes,
+                    ..
+                } = *project;
+                let (cfg, profile) = (cargo_config, &config.profile);
+                create_root_graph_node::<RepairError>(
+                    directory,
+                    workspace_dir,
+                    cfg,
+                    profile,
+                    nodes,
+                )?;
+            }
+            Inconsistency::RootGraphName { .. } => {
+                unrepaired.push(inconsistency);
+                continue;
+            }
+            Inconsistency::DanglingNodeId { graph, node, .. } => {
+                let g = project.nodes.id_graph_mut(&graph).expect("no graph node");
+                g.graph.inlets.retain(|&n| n != node);
+                g.graph.outlets.retain(|&n| n != node);
+                g.graph.remove_node(node);
+                g.sync_instance_ids(&Default::default());
+                regenerate.insert(graph);
+            }
+            Inconsistency::InvalidInlet { graph, node, .. } => {
+                let g = project.nodes.id_graph_mut(&graph).expect("no graph node");
+                g.graph.inlets.retain(|&n| n != node);
+                regenerate.insert(graph);
+            }
+            Inconsistency::InvalidOutlet { graph, node, .. } => {
+                let g = project.nodes.id_graph_mut(&graph).expect("no graph node");
+                g.graph.outlets.retain(|&n| n != node);
+                regenerate.insert(graph);
+            }
+            Inconsistency::MissingWorkspaceMember { graph, package_id } => {
+                let name = format!("{}", package_id.name());
+                let cargo_config = &project.cargo_config;
+                let package_id = open_node_crate_package(&ws_dir, &name, cargo_config)?;
+                let g = project.nodes.id_graph_mut(&graph).expect("no graph node");
+                g.package_id = package_id;
+                regenerate.insert(graph);
+            }
+            Inconsistency::StalePackageId { graph, found, .. } => {
+                let g = project.nodes.id_graph_mut(&graph).expect("no graph node");
+                g.package_id = found;
+                regenerate.insert(graph);
+            }
+        }
+        repaired.push(inconsistency);
+    }
+
+    // Regenerate and recompile all modified graphs.
+    let profile = &project.config.profile;
+    for id in regenerate {
+        regenerate_graph_node(&ws_dir, &project.cargo_config, profile, &project.nodes, &id)?;
+    }
+
+    Ok(RepairReport {
+        repaired,
+        unrepaired,
+    })
+}
+
+// Read the directories of the members from the workspace `Cargo.toml` directly.
+//
+// This is used rather than `cargo::core::Workspace` as cargo refuses to open a workspace with
+// missing members. As with cargo, members may be specified via glob patterns, which are expanded
+// to all matching directories.
+fn workspace_members(workspace_dir: &Path) -> Result<Vec<PathBuf>, VerifyError> {
+    let bytes = fs::read(manifest_path(workspace_dir))?;
+    let toml: toml::Value = toml::from_slice(&bytes)?;
+    let patterns = toml
+        .get("workspace")
+        .and_then(|ws| ws.get("members"))
+        .and_then(|members| members.as_array())
+        .map(|members| members.iter().filter_map(|m| m.as_str()).collect())
+        .unwrap_or_else(Vec::new);
+    let mut members = vec![];
+    let ws_dir_pattern = glob::Pattern::escape(&workspace_dir.to_string_lossy());
+    for pattern in patterns {
+        if !pattern.contains(&['*', '?', '['][..]) {
+            members.push(normalize(&workspace_dir.join(pattern)));
+            continue;
+        }
+        let pattern = Path::new(&ws_dir_pattern).join(pattern);
+        let paths = glob::glob(&pattern.to_string_lossy())?.filter_map(Result::ok);
+        let dirs = paths.filter(|path| path.is_dir());
+        members.extend(dirs.map(|path| normalize(&path)));
+    }
+    Ok(members)
+}
+
+// Remove all `.` components from the given path so that member paths may be compared.
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| *c != Component::CurDir)
+        .collect()
+}
@@ -0,0 +1,1143 @@
+//! Items for loading compiled graph dynamic libraries and calling into them safely.
+//!
+//! A **LoadedGraph** is obtained via `Project::load_graph`. It provides access to each of the
+//! graph's evaluation entry points - one for each push and pull evaluation node, along with the
+//! full evaluation function for graphs that have inlets or outlets.
+//!
+//! A **GraphState** allocates the state for each stateful node within a loaded graph, including
+//! the inner states of nested graph nodes, ready to be passed to the graph's entry points.
+//!
+//! After a graph has been recompiled, `LoadedGraph::reload` swaps in the new library while
+//! carrying over the state of each node instance that kept its state type.
+//!
+//! The entry points and node states are described by the **GraphLayout** recorded within each
+//! library at the time of code generation, rather than by the project's current graph. This
+//! allows a library to be loaded while the graph is being edited or rebuilt.
+
+use crate::project::{InstanceId, NodeId, NodeIndex, NodeRef, Project, ProjectNodeRefGraphNode};
+use crate::{graph, node};
+use serde::{Deserialize, Serialize};
+use std::any::{Any, TypeId};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{self, AtomicUsize};
+use std::sync::Arc;
+use std::{fmt, fs, io, mem};
+use thiserror::Error;
+
+/// The name of the function generated for describing the **GraphLayout** of a graph's library.
+pub const GRAPH_LAYOUT_FN_NAME: &str = "graph_layout";
+
+/// The signature of all generated evaluation functions.
+///
+/// The slice contains the state of each stateful node in the order determined by
+/// `graph::codegen::state_order_with_instance_ids` for the function's evaluation order, followed
+/// by the stored value of each cold inlet in the order determined by
+/// `graph::codegen::cold_inlet_order_with_instance_ids`. See **EntryPoint**.
+pub type EvalFnPtr = fn(&mut [&mut dyn Any]);
+
+// The signature of the generated function for constructing the default state of a node.
+type NodeStateDefaultFnPtr = fn(usize) -> Option<Box<dyn Any>>;
+
+// The signature of the generated function for migrating the state of a node between builds.
+type NodeStateMigrateFnPtr = fn(usize, Box<dyn Any>) -> Result<Box<dyn Any>, Box<dyn Any>>;
+
+// The signature of the generated function for describing the state type of a node.
+type NodeStateTypeIdFnPtr = fn(usize) -> Option<TypeId>;
+
+// The signature of the generated function for describing the layout of the library as JSON.
+type GraphLayoutFnPtr = fn() -> &'static str;
+
+/// A dynamic library generated for a graph node, loaded and ready for evaluation.
+pub struct LoadedGraph {
+    id: NodeId,
+    lib: Arc<Library>,
+    path: PathBuf,
+    layout: GraphLayout,
+    // The state type of each stateful node within the entry points, as described by the library.
+    state_types: HashMap<NodeIndex, TypeId>,
+}
+
+/// Describes the entry points and node states of a graph's dynamic library.
+///
+/// The layout is determined from the graph at the time of code generation and is recorded within
+/// the generated library, ensuring that it matches the library even if the graph has since been
+/// edited.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct GraphLayout {
+    /// The evaluation functions exported by the library, by name.
+    pub entry_points: BTreeMap<String, EntryPoint>,
+    /// The push and pull evaluation functions whose signatures cannot be called via
+    /// **EvalFnPtr**.
+    pub unsupported: Vec<String>,
+    /// Each stateful node within the graph, including nested graph nodes.
+    pub nodes: BTreeMap<NodeIndex, StatefulNode>,
+    /// The inputs connected via **cold** or **delay** edges, whose last value is stored.
+    pub cold_inlets: Vec<(NodeIndex, node::Input)>,
+}
+
+/// Describes a stateful node within a **GraphLayout**.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct StatefulNode {
+    /// The instance ID of the node at the time of code generation.
+    pub instance_id: InstanceId,
+    /// The **NodeId** of the nested graph in the case that the node is a graph node.
+    pub graph: Option<NodeId>,
+}
+
+/// Describes one of the evaluation functions exported by a graph's dynamic library.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct EntryPoint {
+    /// The kind of evaluation performed by the function.
+    pub kind: EntryKind,
+    /// The stateful nodes whose states must be passed to the function, in order.
+    pub state_order: Vec<NodeIndex>,
+    /// The cold inlets whose stored values must be passed to the function following the node
+    /// states, in order.
+    pub cold_inlets: Vec<(NodeIndex, node::Input)>,
+}
+
+/// The kind of evaluation performed by an entry point.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum EntryKind {
+    /// Push evaluation from the node at the given index.
+    Push(NodeIndex),
+    /// Pull evaluation from the node at the given index.
+    Pull(NodeIndex),
+    /// Full evaluation of the graph from its inlets to its outlets.
+    Full,
+}
+
+/// A handle to an evaluation function within a loaded graph's dynamic library.
+///
+/// The handle keeps the library loaded for as long as it exists, so it may safely outlive the
+/// **LoadedGraph** from which it was obtained.
+#[derive(Clone)]
+pub struct EvalFnHandle {
+    eval_fn: EvalFnPtr,
+    name: String,
+    entry_point: EntryPoint,
+    // The expected type of each of the node states passed to the function.
+    state_types: Vec<TypeId>,
+    // Whether or not the states include those of nested graph nodes. These may only be constructed
+    // by a **GraphState**.
+    nested: bool,
+    lib: Arc<Library>,
+}
+
+/// The state of every stateful node within a graph.
+///
+/// Nested graph nodes are given the `(node_states, full_eval_fn_symbol)` state expected by the
+/// generated code, backed by their own **GraphState** and a handle to their full evaluation
+/// function. This state refers to the inner node states and so is only built for the duration of
+/// each call.
+///
+/// The state also stores the last value received by each of the graph's cold inlets, that is, the
+/// inputs connected via **cold** or **delay** edges.
+pub struct GraphState {
+    graph: NodeId,
+    instance_ids: BTreeMap<NodeIndex, InstanceId>,
+    states: BTreeMap<NodeIndex, NodeState>,
+    cold_inlets: BTreeMap<(NodeIndex, node::Input), Option<Box<dyn Any>>>,
+    // Declared last so that the states are dropped before the library that allocated them.
+    lib: Arc<Library>,
+}
+
+// The state of a single stateful node.
+enum NodeState {
+    Core(Box<dyn Any>),
+    Graph(Box<NestedGraphState>),
+}
+
+// The state of a nested graph node.
+//
+// The state expected by the generated code is built from `full_eval` and the inner node states
+// for the duration of each evaluation. See `with_nested_values`.
+struct NestedGraphState {
+    full_eval: libloading::Symbol<'static, EvalFnPtr>,
+    // A handle to the same function, describing the layout of the inner node states.
+    full_eval_fn: EvalFnHandle,
+    // Declared last as `full_eval` points into the library owned by the state.
+    state: GraphState,
+}
+
+// The state expected by the generated code for a nested graph node: the inner node states along
+// with a pointer to the symbol of the nested graph's full evaluation function.
+//
+// The `'static` lifetimes are required by the generated code. The references are only valid for
+// the duration of a single evaluation. See `with_nested_values`.
+type NestedGraphValue = (&'static mut [&'static mut dyn Any], *mut ());
+
+// Called with the node states of a graph in the order expected by an entry point.
+type NodeStatesFn<'a> = dyn 'a + FnMut(&mut [&mut dyn Any]) -> Result<(), CallError>;
+
+// Called with the state expected by the generated code for each nested graph node.
+type NestedValuesFn<'a> =
+    dyn 'a + FnMut(&mut [(NodeIndex, NestedGraphValue)]) -> Result<(), CallError>;
+
+// A loaded dynamic library.
+//
+// If the library was loaded from a unique copy (see `load_library`), the copy is removed once the
+// library has been unloaded.
+struct Library {
+    lib: mem::ManuallyDrop<libloading::Library>,
+    copy_path: Option<PathBuf>,
+}
+
+/// Describes how node states were carried across a reload of a graph's dynamic library.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReloadReport {
+    /// Nodes whose state was carried over.
+    pub kept: Vec<NodeIndex>,
+    /// Nodes whose state type changed, in turn causing their state to be re-initialised.
+    pub reset: Vec<NodeIndex>,
+    /// Stateful nodes that were added, along with nodes that were replaced by a different node.
+    pub added: Vec<NodeIndex>,
+    /// Stateful nodes that were removed or replaced. Their state has been dropped.
+    pub removed: Vec<NodeIndex>,
+    /// Reports for each nested graph node whose state was carried over.
+    pub nested: BTreeMap<NodeIndex, ReloadReport>,
+}
+
+/// Errors that may occur while loading a graph's dynamic library.
+#[derive(Debug, Error)]
+pub enum LoadGraphError {
+    #[error("no graph node exists for the given `NodeId`")]
+    NoSuchGraphNode,
+    #[error("no compiled dynamic library exists for the graph node")]
+    NoDylib,
+    #[error("a cargo error occurred while locating the dynamic library: {err}")]
+    Cargo {
+        #[from]
+        err: anyhow::Error,
+    },
+    #[error("an IO error occurred while loading the dynamic library: {err}")]
+    Io {
+        #[from]
+        err: io::Error,
+    },
+    #[error("the evaluation function `{name}` has unsupported inputs or outputs")]
+    UnsupportedSignature { name: String },
+    #[error("the dynamic library does not describe the state type of node {node:?}")]
+    NoStateType { node: NodeIndex },
+    #[error("the graph layout described by the dynamic library is invalid: {err}")]
+    InvalidLayout {
+        #[from]
+        err: serde_json::Error,
+    },
+}
+
+/// Errors that may occur when requesting an entry point from a **LoadedGraph**.
+#[derive(Debug, Error)]
+pub enum EntryPointError {
+    #[error("no entry point named `{name}`")]
+    NoSuchEntryPoint { name: String },
+    #[error("the entry point `{name}` is a {found:?} entry point, not {expected}")]
+    UnexpectedKind {
+        name: String,
+        expected: &'static str,
+        found: EntryKind,
+    },
+}
+
+/// Errors that may occur while allocating a **GraphState**.
+#[derive(Debug, Error)]
+pub enum GraphStateError {
+    #[error("no graph node exists for the given `NodeId`")]
+    NoSuchGraphNode,
+    #[error("failed to load a nested graph: {err}")]
+    LoadGraph {
+        #[from]
+        err: LoadGraphError,
+    },
+    #[error("failed to load the node state default function: {err}")]
+    Io {
+        #[from]
+        err: io::Error,
+    },
+    #[error("node {node:?} does not provide its initial state via `Node::default_state`")]
+    NoDefaultState { node: NodeIndex },
+    #[error("the nested graph node has no inlets or outlets and in turn no full eval function")]
+    NoFullEvalFn,
+}
+
+/// Errors that may occur while reloading a graph's dynamic library.
+#[derive(Debug, Error)]
+pub enum ReloadError {
+    #[error("the given state does not belong to the reloaded graph")]
+    GraphMismatch,
+    #[error("failed to load the graph: {err}")]
+    LoadGraph {
+        #[from]
+        err: LoadGraphError,
+    },
+    #[error("failed to migrate the graph state: {err}")]
+    GraphState {
+        #[from]
+        err: GraphStateError,
+    },
+}
+
+/// Errors that may occur while calling an evaluation function.
+#[derive(Debug, Error)]
+pub enum CallError {
+    #[error("`{name}` expects {expected} node states, but {found} were given")]
+    StateCount {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    #[error("the node state at index {index} is not of the type expected by `{name}`")]
+    StateType { name: String, index: usize },
+    #[error("no state exists for node {node:?}")]
+    MissingNodeState { node: NodeIndex },
+    #[error("no stored value exists for input {input:?} of node {node:?}")]
+    MissingColdInlet { node: NodeIndex, input: node::Input },
+    #[error("`{name}` expects nested graph node states and must be called via `GraphState`")]
+    NestedGraphState { name: String },
+    #[error("the evaluation function does not belong to the library of the graph state")]
+    GraphMismatch,
+}
+
+impl LoadedGraph {
+    // Load the dynamic library at the given path, checking that each of the entry points described
+    // by its layout exists within it.
+    fn load(id: NodeId, path: PathBuf, fresh: bool) -> Result<Self, LoadGraphError> {
+        let lib = Arc::new(load_library(&path, fresh)?);
+
+        // Retrieve the layout recorded within the library.
+        let name = GRAPH_LAYOUT_FN_NAME.as_bytes();
+        // Safe as the symbol's signature is determined by `graph_layout_fn`.
+        let graph_layout: GraphLayoutFnPtr = unsafe { *lib.get::<GraphLayoutFnPtr>(name)? };
+        let layout: GraphLayout = serde_json::from_str(graph_layout())?;
+        if let Some(name) = layout.unsupported.first() {
+            let name = name.clone();
+            return Err(LoadGraphError::UnsupportedSignature { name });
+        }
+
+        for name in layout.entry_points.keys() {
+            // Check that the symbol exists. Safe as the symbol is not used.
+            unsafe {
+                lib.get::<EvalFnPtr>(name.as_bytes())?;
+            }
+        }
+
+        // Retrieve the state type of each stateful node from the library.
+        let name = graph::NODE_STATE_TYPE_ID_FN_NAME.as_bytes();
+        // Safe as the symbol's signature is determined by `codegen::node_state_type_id_fn`.
+        let node_state_type_id: NodeStateTypeIdFnPtr =
+            unsafe { *lib.get::<NodeStateTypeIdFnPtr>(name)? };
+        let mut state_types = HashMap::new();
+        for entry_point in layout.entry_points.values() {
+            for &node in &entry_point.state_order {
+                let ty =
+                    node_state_type_id(node.index()).ok_or(LoadGraphError::NoStateType { node })?;
+                state_types.insert(node, ty);
+            }
+        }
+
+        Ok(LoadedGraph {
+            id,
+            lib,
+            path,
+            layout,
+            state_types,
+        })
+    }
+
+    /// The **NodeId** of the graph node from which the library was generated.
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    /// Swap in the graph's most recently compiled dynamic library.
+    ///
+    /// The given `state` is migrated to the new library. The state of each node instance that
+    /// retains its state type is carried over, new stateful nodes are initialised and the state of
+    /// removed nodes is dropped. The same applies recursively to nested graph nodes. Values
+    /// stored for cold inlets are cleared.
+    ///
+    /// The previous library is unloaded once all **EvalFnHandle**s obtained from it are dropped.
+    /// Handles should be re-acquired after reloading, as the previous handles continue to call
+    /// into the previous library.
+    ///
+    /// On error `self` is left untouched, however the states of some nodes may have been lost
+    /// if the error occurred part way through migration. Evaluating with such a `state` returns
+    /// `CallError::MissingNodeState`.
+    pub fn reload(
+        &mut self,
+        project: &Project,
+        state: &mut GraphState,
+    ) -> Result<ReloadReport, ReloadError> {
+        if state.graph != self.id {
+            return Err(ReloadError::GraphMismatch);
+        }
+        let graph = load_graph(project, &self.id, true)?;
+        let mut report = ReloadReport::default();
+        let new_state = GraphState::build(project, &graph, Some(state), &mut report)?;
+        *state = new_state;
+        *self = graph;
+        Ok(report)
+    }
+
+    /// The path to the loaded dynamic library.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The layout of the graph as described by the loaded library.
+    pub fn layout(&self) -> &GraphLayout {
+        &self.layout
+    }
+
+    /// All entry points exported by the graph, ordered by name.
+    pub fn entry_points(&self) -> impl Iterator<Item = (&str, &EntryPoint)> {
+        self.layout
+            .entry_points
+            .iter()
+            .map(|(name, ep)| (&name[..], ep))
+    }
+
+    /// The entry point with the given name, if there is one.
+    pub fn entry_point(&self, name: &str) -> Option<&EntryPoint> {
+        self.layout.entry_points.get(name)
+    }
+
+    /// A handle to the entry point with the given name, regardless of its kind.
+    pub fn eval_fn(&self, name: &str) -> Result<EvalFnHandle, EntryPointError> {
+        let entry_point = self
+            .layout
+            .entry_points
+            .get(name)
+            .ok_or_else(|| EntryPointError::NoSuchEntryPoint {
+                name: name.to_string(),
+            })?
+            .clone();
+        // Safe as the symbol's type was checked against the graph when the entry point was
+        // discovered and as the handle keeps the library alive.
+        let eval_fn: EvalFnPtr = unsafe {
+            let symbol = self
+                .lib
+                .get::<EvalFnPtr>(name.as_bytes())
+                .expect("symbol checked during load");
+            *symbol
+        };
+        // Node states are followed by the stored values of cold inlets.
+        let node_state_types = entry_point.state_order.iter().map(|n| self.state_types[n]);
+        let cold_inlet_types = entry_point
+            .cold_inlets
+            .iter()
+            .map(|_| TypeId::of::<Option<Box<dyn Any>>>());
+        let state_types = node_state_types.chain(cold_inlet_types).collect();
+        let nested = entry_point.state_order.iter().any(|n| {
+            self.layout
+                .nodes
+                .get(n)
+                .map_or(false, |n| n.graph.is_some())
+        });
+        Ok(EvalFnHandle {
+            eval_fn,
+            name: name.to_string(),
+            entry_point,
+            state_types,
+            nested,
+            lib: self.lib.clone(),
+        })
+    }
+
+    /// A handle to the push evaluation function with the given name.
+    pub fn push_fn(&self, name: &str) -> Result<EvalFnHandle, EntryPointError> {
+        self.eval_fn_of_kind(name, "push", |kind| match kind {
+            EntryKind::Push(_) => true,
+            _ => false,
+        })
+    }
+
+    /// A handle to the pull evaluation function with the given name.
+    pub fn pull_fn(&self, name: &str) -> Result<EvalFnHandle, EntryPointError> {
+        self.eval_fn_of_kind(name, "pull", |kind| match kind {
+            EntryKind::Pull(_) => true,
+            _ => false,
+        })
+    }
+
+    /// A handle to the graph's full evaluation function.
+    ///
+    /// Only graphs with at least one inlet or outlet have a full evaluation function.
+    pub fn full_eval_fn(&self) -> Result<EvalFnHandle, EntryPointError> {
+        self.eval_fn_of_kind(graph::FULL_EVAL_FN_NAME, "full", |kind| {
+            kind == EntryKind::Full
+        })
+    }
+
+    // Retrieve the eval fn with the given name, checking that it is of the expected kind.
+    fn eval_fn_of_kind<F>(
+        &self,
+        name: &str,
+        expected: &'static str,
+        is_kind: F,
+    ) -> Result<EvalFnHandle, EntryPointError>
+    where
+        F: Fn(EntryKind) -> bool,
+    {
+        let handle = self.eval_fn(name)?;
+        let found = handle.entry_point.kind;
+        if !is_kind(found) {
+            let name = name.to_string();
+            return Err(EntryPointError::UnexpectedKind {
+                name,
+                expected,
+                found,
+            });
+        }
+        Ok(handle)
+    }
+}
+
+impl EvalFnHandle {
+    /// The name of the function's symbol.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Describes the kind of evaluation and the expected state layout.
+    pub fn entry_point(&self) -> &EntryPoint {
+        &self.entry_point
+    }
+
+    /// The raw function pointer.
+    ///
+    /// # Safety
+    ///
+    /// The pointer is only valid for as long as the library is loaded, i.e. for as long as this
+    /// handle or another handle to the same library exists. Calling the function bypasses the
+    /// checks performed by `call`, so the caller must ensure the node states match the function's
+    /// state layout.
+    pub unsafe fn as_ptr(&self) -> EvalFnPtr {
+        self.eval_fn
+    }
+
+    /// Call the evaluation function with the given node states.
+    ///
+    /// Returns an error if the number or the types of the node states do not match the function's
+    /// state layout.
+    ///
+    /// The state of a nested graph node refers to the nested graph's library and can only be
+    /// constructed by a **GraphState**. Functions whose state includes that of a nested graph node
+    /// return `CallError::NestedGraphState` and must be called via `GraphState::call` instead.
+    pub fn call(&self, node_states: &mut [&mut dyn Any]) -> Result<(), CallError> {
+        if self.nested {
+            let name = self.name.clone();
+            return Err(CallError::NestedGraphState { name });
+        }
+        self.call_checked(node_states)
+    }
+
+    // Call the evaluation function after checking the node states against its state layout.
+    //
+    // Nested graph node states are not checked beyond their type, so this must only be called
+    // with states constructed by a **GraphState**.
+    fn call_checked(&self, node_states: &mut [&mut dyn Any]) -> Result<(), CallError> {
+        self.check_node_states(node_states)?;
+        (self.eval_fn)(node_states);
+        Ok(())
+    }
+
+    // Check that the given node states match the function's state layout.
+    fn check_node_states(&self, node_states: &[&mut dyn Any]) -> Result<(), CallError> {
+        let expected = self.state_types.len();
+        if node_states.len() != expected {
+            return Err(CallError::StateCount {
+                name: self.name.clone(),
+                expected,
+                found: node_states.len(),
+            });
+        }
+        let types = node_states.iter().map(|state| Any::type_id(&**state));
+        match types
+            .zip(&self.state_types)
+            .position(|(ty, &expected)| ty != expected)
+        {
+            None => Ok(()),
+            Some(index) => Err(CallError::StateType {
+                name: self.name.clone(),
+                index,
+            }),
+        }
+    }
+}
+
+impl GraphState {
+    /// Allocate default-initialised state for every stateful node within the given graph.
+    ///
+    /// The state of each core node is constructed within the graph's dynamic library, so each
+    /// stateful core node must provide its initial state via `Node::default_state`. The dynamic
+    /// library of each nested graph node is loaded from the project. Cold inlets start without a
+    /// stored value.
+    pub fn new(project: &Project, graph: &LoadedGraph) -> Result<Self, GraphStateError> {
+        GraphState::build(project, graph, None, &mut ReloadReport::default())
+    }
+
+    // Allocate the state for the given graph.
+    //
+    // If `prev` is given, state is migrated from it where possible and the `report` is updated
+    // to describe the outcome for each node. Nested libraries are loaded fresh in this case, as
+    // they may also have been recompiled.
+    fn build(
+        project: &Project,
+        graph: &LoadedGraph,
+        mut prev: Option<&mut GraphState>,
+        report: &mut ReloadReport,
+    ) -> Result<Self, GraphStateError> {
+        let name = graph::NODE_STATE_DEFAULT_FN_NAME.as_bytes();
+        // Safe as the symbol's signature is determined by `codegen::node_state_default_fn`.
+        let node_state_default: NodeStateDefaultFnPtr =
+            unsafe { *graph.lib.get::<NodeStateDefaultFnPtr>(name)? };
+        let node_state_migrate: Option<NodeStateMigrateFnPtr> = match prev {
+            None => None,
+            Some(_) => {
+                let name = graph::NODE_STATE_MIGRATE_FN_NAME.as_bytes();
+                // Safe as the symbol's signature is determined by `codegen::node_state_migrate_fn`.
+                unsafe { Some(*graph.lib.get::<NodeStateMigrateFnPtr>(name)?) }
+            }
+        };
+        let fresh = prev.is_some();
+
+        let mut instance_ids = BTreeMap::new();
+        let mut states = BTreeMap::new();
+        for (&n, node) in &graph.layout.nodes {
+            let instance_id = node.instance_id;
+            let prev_state = prev
+                .as_mut()
+                .and_then(|prev| prev.take_state(n, instance_id));
+            let had_prev_state = prev_state.is_some();
+            let state = match node.graph {
+                None => {
+                    let migrated = match (prev_state, node_state_migrate) {
+                        (Some(NodeState::Core(state)), Some(migrate)) => {
+                            // The previous library is still loaded, so the state may be dropped
+                            // here if its type has changed.
+                            migrate(n.index(), state).ok()
+                        }
+                        _ => None,
+                    };
+                    let state = match migrated {
+                        Some(state) => {
+                            report.kept.push(n);
+                            state
+                        }
+                        None => {
+                            if had_prev_state {
+                                report.reset.push(n);
+                            } else {
+                                report.added.push(n);
+                            }
+                            node_state_default(n.index())
+                                .ok_or(GraphStateError::NoDefaultState { node: n })?
+                        }
+                    };
+                    NodeState::Core(state)
+                }
+                Some(id) => {
+                    let nested = load_graph(project, &id, fresh)?;
+                    let state = match prev_state {
+                        Some(NodeState::Graph(mut prev_nested)) => {
+                            let mut nested_report = ReloadReport::default();
+                            let nested_prev = Some(&mut prev_nested.state);
+                            let state = NestedGraphState::build(
+                                project,
+                                &nested,
+                                nested_prev,
+                                &mut nested_report,
+                            )?;
+                            report.kept.push(n);
+                            report.nested.insert(n, nested_report);
+                            state
+                        }
+                        _ => {
+                            if had_prev_state {
+                                report.reset.push(n);
+                            } else {
+                                report.added.push(n);
+                            }
+                            let mut nested_report = ReloadReport::default();
+                            NestedGraphState::build(project, &nested, None, &mut nested_report)?
+                        }
+                    };
+                    NodeState::Graph(Box::new(state))
+                }
+            };
+            instance_ids.insert(n, instance_id);
+            states.insert(n, state);
+        }
+
+        // Any remaining previous states belong to nodes that no longer exist.
+        if let Some(prev) = prev {
+            report.removed.extend(prev.states.keys().cloned());
+        }
+
+        // Values stored for cold inlets are of unknown type and so cannot be migrated.
+        let cold_inlets = graph
+            .layout
+            .cold_inlets
+            .iter()
+            .map(|&inlet| (inlet, None))
+            .collect();
+
+        Ok(GraphState {
+            graph: graph.id,
+            instance_ids,
+            states,
+            cold_inlets,
+            lib: graph.lib.clone(),
+        })
+    }
+
+    // Take the state of node `n` if it is still the same node instance.
+    fn take_state(&mut self, n: NodeIndex, instance_id: InstanceId) -> Option<NodeState> {
+        if self.instance_ids.get(&n) != Some(&instance_id) {
+            return None;
+        }
+        self.instance_ids.remove(&n);
+        self.states.remove(&n)
+    }
+
+    /// The **NodeId** of the graph node to which the state belongs.
+    pub fn graph(&self) -> NodeId {
+        self.graph
+    }
+
+    /// The indices of all stateful nodes within the graph.
+    pub fn nodes(&self) -> impl Iterator<Item = NodeIndex> + '_ {
+        self.states.keys().cloned()
+    }
+
+    /// The state of the core node at the given index.
+    ///
+    /// Returns `None` if there is no core node state for the index or if it is not of type `T`.
+    pub fn state<T: Any>(&self, n: NodeIndex) -> Option<&T> {
+        match self.states.get(&n)? {
+            NodeState::Core(state) => state.downcast_ref(),
+            NodeState::Graph(_) => None,
+        }
+    }
+
+    /// Mutable access to the state of the core node at the given index.
+    ///
+    /// Returns `None` if there is no core node state for the index or if it is not of type `T`.
+    pub fn state_mut<T: Any>(&mut self, n: NodeIndex) -> Option<&mut T> {
+        match self.states.get_mut(&n)? {
+            NodeState::Core(state) => state.downcast_mut(),
+            NodeState::Graph(_) => None,
+        }
+    }
+
+    /// The inner state of the nested graph node at the given index.
+    pub fn graph_state(&self, n: NodeIndex) -> Option<&GraphState> {
+        match self.states.get(&n)? {
+            NodeState::Graph(nested) => Some(&nested.state),
+            NodeState::Core(_) => None,
+        }
+    }
+
+    /// Mutable access to the inner state of the nested graph node at the given index.
+    pub fn graph_state_mut(&mut self, n: NodeIndex) -> Option<&mut GraphState> {
+        match self.states.get_mut(&n)? {
+            NodeState::Graph(nested) => Some(&mut nested.state),
+            NodeState::Core(_) => None,
+        }
+    }
+
+    /// Whether or not a value has been stored for the given cold inlet.
+    pub fn has_cold_inlet_value(&self, n: NodeIndex, input: node::Input) -> bool {
+        matches!(self.cold_inlets.get(&(n, input)), Some(Some(_)))
+    }
+
+    /// Call `f` with the node states in the order expected by the given entry point.
+    ///
+    /// The states of the nodes are followed by the stored values of the entry point's cold inlets.
+    /// The state of each nested graph node refers to its inner node states, so the node states are
+    /// only available for the duration of the call.
+    pub fn with_node_states<F, R>(&mut self, entry_point: &EntryPoint, f: F) -> Result<R, CallError>
+    where
+        F: FnOnce(&mut [&mut dyn Any]) -> R,
+    {
+        let mut f = Some(f);
+        let mut output = None;
+        self.scoped_node_states(entry_point, &mut |node_states| {
+            let f = f.take().expect("node states were provided more than once");
+            output = Some(f(node_states));
+            Ok(())
+        })?;
+        Ok(output.expect("node states were not provided"))
+    }
+
+    /// Call the given evaluation function with the node states in the order that it expects.
+    ///
+    /// Returns an error if the function does not belong to the library for which the state was
+    /// allocated, e.g. a handle obtained prior to `LoadedGraph::reload`.
+    pub fn call(&mut self, eval_fn: &EvalFnHandle) -> Result<(), CallError> {
+        // Safe as the symbol is only used to compare its address with that of the handle.
+        let same_lib = unsafe {
+            self.lib
+                .get::<EvalFnPtr>(eval_fn.name.as_bytes())
+                .map(|symbol| *symbol as usize == eval_fn.eval_fn as usize)
+                .unwrap_or(false)
+        };
+        if !same_lib {
+            return Err(CallError::GraphMismatch);
+        }
+        self.with_node_states(eval_fn.entry_point(), |node_states| {
+            eval_fn.call_checked(node_states)
+        })?
+    }
+
+    // Call `f` with the node states in the order expected by `entry_point`.
+    fn scoped_node_states(
+        &mut self,
+        entry_point: &EntryPoint,
+        f: &mut NodeStatesFn,
+    ) -> Result<(), CallError> {
+        let GraphState {
+            ref mut states,
+            ref mut cold_inlets,
+            ..
+        } = *self;
+        let mut core_states: HashMap<NodeIndex, &mut dyn Any> = HashMap::new();
+        let mut nested_states = vec![];
+        for (&n, state) in states.iter_mut() {
+            if !entry_point.state_order.contains(&n) {
+                continue;
+            }
+            match *state {
+                NodeState::Core(ref mut state) => {
+                    core_states.insert(n, &mut **state);
+                }
+                NodeState::Graph(ref mut nested) => nested_states.push((n, &mut **nested)),
+            }
+        }
+        let mut cold_inlets: HashMap<_, _> = cold_inlets
+            .iter_mut()
+            .map(|(&inlet, value)| (inlet, value))
+            .collect();
+        with_nested_values(&mut nested_states, &mut vec![], &mut |nested_values| {
+            let mut nested_values: HashMap<_, _> = nested_values
+                .iter_mut()
+                .map(|(n, value)| (*n, value))
+                .collect();
+            let mut node_states: Vec<&mut dyn Any> = vec![];
+            for &node in &entry_point.state_order {
+                let state: &mut dyn Any = match core_states.remove(&node) {
+                    Some(state) => state,
+                    None => match nested_values.remove(&node) {
+                        Some(value) => value as &mut dyn Any,
+                        None => return Err(CallError::MissingNodeState { node }),
+                    },
+                };
+                node_states.push(state);
+            }
+            for &(node, input) in &entry_point.cold_inlets {
+                let value = cold_inlets
+                    .remove(&(node, input))
+                    .ok_or(CallError::MissingColdInlet { node, input })?;
+                node_states.push(value);
+            }
+            f(&mut node_states)
+        })
+    }
+}
+
+impl NestedGraphState {
+    // Allocate the state for a nested graph node, migrating from `prev` if given.
+    fn build(
+        project: &Project,
+        graph: &LoadedGraph,
+        prev: Option<&mut GraphState>,
+        report: &mut ReloadReport,
+    ) -> Result<Self, GraphStateError> {
+        let full_eval_fn = graph
+            .full_eval_fn()
+            .map_err(|_| GraphStateError::NoFullEvalFn)?;
+        let state = GraphState::build(project, graph, prev, report)?;
+        // Safe as the symbol is dropped before the library, which is owned by `state`.
+        let full_eval = unsafe {
+            let name = graph::FULL_EVAL_FN_NAME.as_bytes();
+            let symbol = state.lib.get::<EvalFnPtr>(name)?;
+            mem::transmute::<libloading::Symbol<EvalFnPtr>, libloading::Symbol<'static, EvalFnPtr>>(
+                symbol,
+            )
+        };
+        Ok(NestedGraphState {
+            full_eval,
+            full_eval_fn,
+            state,
+        })
+    }
+}
+
+impl fmt::Debug for LoadedGraph {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LoadedGraph")
+            .field("id", &self.id)
+            .field("path", &self.path)
+            .field("entry_points", &self.entry_points)
+            .finish()
+    }
+}
+
+impl fmt::Debug for EvalFnHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EvalFnHandle")
+            .field("name", &self.name)
+            .field("entry_point", &self.entry_point)
+            .finish()
+    }
+}
+
+impl fmt::Debug for GraphState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GraphState")
+            .field("graph", &self.graph)
+            .field("nodes", &self.states.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Library {
+    fn new(lib: libloading::Library, copy_path: Option<PathBuf>) -> Self {
+        let lib = mem::ManuallyDrop::new(lib);
+        Library { lib, copy_path }
+    }
+}
+
+impl Deref for Library {
+    type Target = libloading::Library;
+    fn deref(&self) -> &Self::Target {
+        &self.lib
+    }
+}
+
+impl Drop for Library {
+    fn drop(&mut self) {
+        // Unload the library before removing the copy, as not all platforms allow removing a
+        // loaded library. Safe as `lib` is not accessed again.
+        unsafe {
+            mem::ManuallyDrop::drop(&mut self.lib);
+        }
+        if let Some(ref copy_path) = self.copy_path {
+            fs::remove_file(copy_path).ok();
+        }
+    }
+}
+
+// Build the state expected by the generated code for each of the given nested graph nodes, then
+// call `f` with the states.
+//
+// Each state refers to the inner node states of its nested graph, which are only borrowed within
+// the scope of this call. The states are removed from `values` before the borrows end.
+fn with_nested_values(
+    nested: &mut [(NodeIndex, &mut NestedGraphState)],
+    values: &mut Vec<(NodeIndex, NestedGraphValue)>,
+    f: &mut NestedValuesFn,
+) -> Result<(), CallError> {
+    let ((n, nested), rest) = match nested.split_first_mut() {
+        None => return f(values),
+        Some(split) => split,
+    };
+    let n = *n;
+    let NestedGraphState {
+        ref mut full_eval,
+        ref full_eval_fn,
+        ref mut state,
+    } = **nested;
+    let full_eval = full_eval as *mut libloading::Symbol<'static, EvalFnPtr> as *mut ();
+    state.scoped_node_states(full_eval_fn.entry_point(), &mut |node_states| {
+        full_eval_fn.check_node_states(node_states)?;
+        // Safe as the value is removed from `values` before the inner node states are released,
+        // and is otherwise only accessed by the generated code within `f`.
+        let node_states = unsafe {
+            mem::transmute::<&mut [&mut dyn Any], &'static mut [&'static mut dyn Any]>(node_states)
+        };
+        values.push((n, (node_states, full_eval)));
+        let result = with_nested_values(rest, values, f);
+        values.pop();
+        result
+    })
+}
+
+// Load the dynamic library for the graph node with the given `id`.
+//
+// If `fresh` is `true`, the library is loaded from a unique copy (see `load_library`).
+pub(crate) fn load_graph(
+    project: &Project,
+    id: &NodeId,
+    fresh: bool,
+) -> Result<LoadedGraph, LoadGraphError> {
+    if project.graph_node(id).is_none() {
+        return Err(LoadGraphError::NoSuchGraphNode);
+    }
+    let dylib_path = project
+        .graph_node_dylib(id)?
+        .ok_or(LoadGraphError::NoDylib)?;
+    LoadedGraph::load(*id, dylib_path, fresh)
+}
+
+// Load the dynamic library at the given path.
+//
+// Most platforms return the already loaded library when asked to load a path that is still
+// loaded, even if the file has since been rebuilt. When `fresh` is `true` the library is first
+// copied to a unique path to ensure that the latest build is loaded. The copy is removed once the
+// returned library is dropped.
+fn load_library(path: &Path, fresh: bool) -> io::Result<Library> {
+    if !fresh {
+        let lib = libloading::Library::new(path)?;
+        return Ok(Library::new(lib, None));
+    }
+    static RELOAD_COUNT: AtomicUsize = AtomicUsize::new(0);
+    let count = RELOAD_COUNT.fetch_add(1, atomic::Ordering::SeqCst);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("lib");
+    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+    let file_name = format!("{}-reload-{}-{}.{}", stem, std::process::id(), count, ext);
+    let copy_path = path.with_file_name(file_name);
+    fs::copy(path, &copy_path)?;
+    match libloading::Library::new(&copy_path) {
+        Ok(lib) => Ok(Library::new(lib, Some(copy_path))),
+        Err(err) => {
+            fs::remove_file(&copy_path).ok();
+            Err(err)
+        }
+    }
+}
+
+impl GraphLayout {
+    /// Describe the layout of the dynamic library generated for the given graph.
+    pub fn new(g: &ProjectNodeRefGraphNode) -> Self {
+        let graph = &g.graph.graph;
+        let mut entry_points = BTreeMap::new();
+        let mut unsupported = vec![];
+        for (name, entry_point) in entry_points_of(g) {
+            match entry_point {
+                Some(entry_point) => {
+                    entry_points.insert(name, entry_point);
+                }
+                None => unsupported.push(name),
+            }
+        }
+        let nodes = graph
+            .node_indices()
+            .filter_map(|n| {
+                let graph = match graph[n] {
+                    NodeRef::Core(node) => {
+                        node.state_type()?;
+                        None
+                    }
+                    NodeRef::Graph(ref nested) => Some(nested.graph.id),
+                };
+                let instance_id = g.graph.instance_ids[&n];
+                Some((n, StatefulNode { instance_id, graph }))
+            })
+            .collect();
+        let cold_inlets = graph
+            .edge_indices()
+            .filter(|&e| !graph[e].is_hot())
+            .filter_map(|e| {
+                let (_, n) = graph.edge_endpoints(e)?;
+                Some((n, graph[e].input))
+            })
+            .collect();
+        GraphLayout {
+            entry_points,
+            unsupported,
+            nodes,
+            cold_inlets,
+        }
+    }
+}
+
+/// Determine the entry points that will be exported by the dynamic library generated for the
+/// given graph.
+///
+/// Returns an error if one of the push or pull evaluation functions has a signature other than
+/// `fn()`, as these cannot be called via `EvalFnPtr`.
+pub fn entry_points(
+    g: &ProjectNodeRefGraphNode,
+) -> Result<BTreeMap<String, EntryPoint>, LoadGraphError> {
+    entry_points_of(g)
+        .into_iter()
+        .map(|(name, entry_point)| match entry_point {
+            Some(entry_point) => Ok((name, entry_point)),
+            None => Err(LoadGraphError::UnsupportedSignature { name }),
+        })
+        .collect()
+}
+
+// Generate the function that describes the given layout to the host.
+//
+// The layout is returned as JSON and read by `Project::load_graph` and `LoadedGraph::reload`.
+pub(crate) fn graph_layout_fn(layout: &GraphLayout) -> syn::ItemFn {
+    let json = serde_json::to_string(layout).expect("failed to serialize graph layout");
+    let ident = syn::Ident::new(GRAPH_LAYOUT_FN_NAME, proc_macro2::Span::call_site());
+    syn::parse_quote! {
+        #[no_mangle]
+        pub fn #ident() -> &'static str {
+            #json
+        }
+    }
+}
+
+// The entry points of the given graph by name, or `None` for those with unsupported signatures.
+fn entry_points_of(g: &ProjectNodeRefGraphNode) -> Vec<(String, Option<EntryPoint>)> {
+    let graph = &g.graph.graph;
+    let instance_ids = g.graph.node_instance_ids();
+    let mut entry_points = vec![];
+
+    // Describe the state layout for the given evaluation order.
+    let entry_point = |kind, order: Vec<NodeIndex>| {
+        let state_order = graph::codegen::state_order_with_instance_ids(
+            graph,
+            order.iter().cloned(),
+            &instance_ids,
+        );
+        let cold_inlets =
+            graph::codegen::cold_inlet_order_with_instance_ids(graph, order, &instance_ids);
+        EntryPoint {
+            kind,
+            state_order,
+            cold_inlets,
+        }
+    };
+
+    // Full evaluation.
+    if !g.inlets.is_empty() || !g.outlets.is_empty() {
+        let push = g.inlets.iter().cloned();
+        let pull = g.outlets.iter().cloned();
+        let order = graph::codegen::eval_order(graph, push, pull).collect();
+        let entry_point = entry_point(EntryKind::Full, order);
+        entry_points.push((graph::FULL_EVAL_FN_NAME.to_string(), Some(entry_point)));
+    }
+
+    // Push and pull evaluation.
+    let push_nodes = graph::codegen::push_nodes(graph)
+        .into_iter()
+        .map(|(n, eval)| (EntryKind::Push(n), eval));
+    let pull_nodes = graph::codegen::pull_nodes(graph)
+        .into_iter()
+        .map(|(n, eval)| (EntryKind::Pull(n), eval));
+    for (kind, eval) in push_nodes.chain(pull_nodes) {
+        let name = format!("{}", eval.signature.ident);
+        let sig = &eval.signature;
+        if !sig.inputs.is_empty() || sig.output != syn::ReturnType::Default {
+            entry_points.push((name, None));
+            continue;
+        }
+        let order = match kind {
+            EntryKind::Push(n) => graph::codegen::push_eval_order(graph, n).collect(),
+            EntryKind::Pull(n) => graph::codegen::pull_eval_order(graph, n).collect(),
+            EntryKind::Full => unreachable!(),
+        };
+        entry_points.push((name, Some(entry_point(kind, order))));
+    }
+
+    entry_points
+}
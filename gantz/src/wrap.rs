@@ -0,0 +1,762 @@
+//! Items for generating **Node** types for the public free functions of an external crate.
+//!
+//! This is intended for use within a build script, allowing for wrapping an entire module of a
+//! crate as gantz nodes and keeping the wrappers in sync as the crate changes:
+//!
+//! ```ignore
+//! // build.rs
+//! let dep = r#"num-integer = "0.1""#.parse().unwrap();
+//! let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR").unwrap());
+//! let work_dir = out_dir.join("wrap");
+//! let out_path = out_dir.join("num_integer_nodes.rs");
+//! gantz::wrap::generate_to_file(&dep, "", &work_dir, &out_path).unwrap();
+//!
+//! // lib.rs
+//! pub mod num_integer_nodes {
+//!     include!(concat!(env!("OUT_DIR"), "/num_integer_nodes.rs"));
+//! }
+//! ```
+//!
+//! The crate that includes the generated module must depend on `gantz`, `serde` and `typetag`.
+//!
+//! Functions are skipped if they are `unsafe`, `async`, `extern` or conditionally compiled via
+//! `#[cfg(...)]`, or if their signature refers to a type that cannot be named from the crate
+//! generated for a graph. Types are nameable if they
+//! are primitives, within the prelude, within `std`, `core` or `alloc`, or are declared within or
+//! imported into the module via a path within the wrapped crate. Re-exports and glob imports are
+//! not followed.
+
+use crate::node::CrateDep;
+use crate::project::{manifest_path, workspace_cargo_config};
+use quote::ToTokens;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, io};
+use syn::visit_mut::{self, VisitMut};
+use thiserror::Error;
+
+/// The parsed source of a module within an external crate.
+#[derive(Clone, Debug)]
+pub struct Module {
+    /// The name by which the crate is referred to within generated code, e.g. `num_integer`.
+    pub crate_name: String,
+    /// The path to the module from the crate root. Empty for the crate root itself.
+    pub path: Vec<String>,
+    /// The items declared within the module.
+    pub items: Vec<syn::Item>,
+}
+
+/// A **Node** type generated for one of the module's functions.
+#[derive(Clone, Debug)]
+pub struct WrappedFn {
+    /// The name of the wrapped function.
+    pub fn_name: String,
+    /// The name of the generated node type.
+    pub type_name: String,
+    /// The function used as the node's `Evaluator::Fn`.
+    pub fn_item: syn::ItemFn,
+}
+
+/// A public function for which no **Node** type was generated.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SkippedFn {
+    /// The name of the function.
+    pub fn_name: String,
+    /// The reason the function was skipped.
+    pub reason: SkipReason,
+}
+
+/// The reason a public function was skipped.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum SkipReason {
+    /// The function is conditionally compiled via `#[cfg(...)]` and might not exist within the
+    /// crate's build.
+    Cfg,
+    /// The function is `unsafe`.
+    Unsafe,
+    /// The function is `async`.
+    Async,
+    /// The function uses a non-Rust ABI.
+    Extern,
+    /// The function is variadic.
+    Variadic,
+    /// The function takes a `self` argument.
+    Receiver,
+    /// An argument's type contains `impl Trait`, which cannot be passed as an explicit type
+    /// parameter by the generated evaluator function.
+    ImplTraitArg,
+    /// The signature refers to a path that cannot be named from the generated crate.
+    UnnameablePath(String),
+}
+
+/// The **Node** types generated for the public functions of a module.
+#[derive(Clone, Debug)]
+pub struct Generated {
+    /// The nodes generated for each wrapped function.
+    pub nodes: Vec<WrappedFn>,
+    /// The public functions that were skipped.
+    pub skipped: Vec<SkippedFn>,
+    /// The generated source, containing a node type for each wrapped function.
+    pub file: syn::File,
+}
+
+/// Errors that might occur while loading a module of an external crate.
+#[derive(Debug, Error)]
+pub enum LoadModuleError {
+    #[error("an IO error occurred: {err}")]
+    Io {
+        #[from]
+        err: io::Error,
+    },
+    #[error("cargo failed to resolve the crate: {err}")]
+    Cargo {
+        #[from]
+        err: anyhow::Error,
+    },
+    #[error("failed to parse {path:?}: {err}")]
+    Parse {
+        path: PathBuf,
+        #[source]
+        err: syn::Error,
+    },
+    #[error("no crate matching the dependency `{name}` was resolved")]
+    NoMatchingPackage { name: String },
+    #[error("the crate `{name}` has no library target")]
+    NoLibTarget { name: String },
+    #[error("no public, unconditionally compiled module `{module}` was found")]
+    ModuleNotFound { module: String },
+}
+
+/// Errors that might occur while generating the nodes for a module and writing them to a file.
+#[derive(Debug, Error)]
+pub enum GenerateToFileError {
+    #[error("failed to load the module: {err}")]
+    LoadModule {
+        #[from]
+        err: LoadModuleError,
+    },
+    #[error("failed to write the generated source: {err}")]
+    Io {
+        #[from]
+        err: io::Error,
+    },
+}
+
+// Primitive types along with the types and traits of the prelude.
+const PRELUDE: &[&str] = &[
+    "bool",
+    "char",
+    "str",
+    "i8",
+    "i16",
+    "i32",
+    "i64",
+    "i128",
+    "isize",
+    "u8",
+    "u16",
+    "u32",
+    "u64",
+    "u128",
+    "usize",
+    "f32",
+    "f64",
+    "Option",
+    "Result",
+    "Vec",
+    "String",
+    "Box",
+    "ToString",
+    "ToOwned",
+    "Clone",
+    "Copy",
+    "Send",
+    "Sync",
+    "Sized",
+    "Unpin",
+    "Fn",
+    "FnMut",
+    "FnOnce",
+    "Drop",
+    "Default",
+    "Eq",
+    "PartialEq",
+    "Ord",
+    "PartialOrd",
+    "Iterator",
+    "IntoIterator",
+    "Extend",
+    "DoubleEndedIterator",
+    "ExactSizeIterator",
+    "AsRef",
+    "AsMut",
+    "Into",
+    "From",
+];
+
+// Crates that are always available to the generated crate.
+const STD_CRATES: &[&str] = &["std", "core", "alloc"];
+
+impl Module {
+    /// The path to the module including the crate name, e.g. `["num_integer", "roots"]`.
+    pub fn abs_path(&self) -> Vec<String> {
+        std::iter::once(self.crate_name.clone())
+            .chain(self.path.iter().cloned())
+            .collect()
+    }
+}
+
+impl Generated {
+    /// The generated source as a string, ready to be written to a file and `include!`d.
+    pub fn to_source(&self) -> String {
+        self.file.to_token_stream().to_string()
+    }
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SkipReason::Cfg => write!(f, "the function is conditionally compiled"),
+            SkipReason::Unsafe => write!(f, "the function is `unsafe`"),
+            SkipReason::Async => write!(f, "the function is `async`"),
+            SkipReason::Extern => write!(f, "the function uses a non-Rust ABI"),
+            SkipReason::Variadic => write!(f, "the function is variadic"),
+            SkipReason::Receiver => write!(f, "the function takes `self`"),
+            SkipReason::ImplTraitArg => write!(f, "an argument's type contains `impl Trait`"),
+            SkipReason::UnnameablePath(ref path) => {
+                write!(f, "`{}` cannot be named from the generated crate", path)
+            }
+        }
+    }
+}
+
+/// Resolve the given crate dependency and parse the module at the given path within it.
+///
+/// The module path is relative to the crate root, e.g. `"roots"` or `"a::b"`. An empty string
+/// refers to the crate root.
+///
+/// The crate is resolved (and downloaded if necessary) by cargo via a placeholder package created
+/// within `work_dir`.
+pub fn load_module(
+    dep: &CrateDep,
+    module_path: &str,
+    work_dir: &Path,
+) -> Result<Module, LoadModuleError> {
+    // Create a package depending on the crate.
+    fs::create_dir_all(work_dir)?;
+    let manifest = format!(
+        "[package]\nname = \"gantz-wrap\"\nversion = \"0.0.0\"\nedition = \"2018\"\n\n\
+         [lib]\npath = \"lib.rs\"\n\n[dependencies]\n{} = {}\n\n[workspace]\n",
+        dep.name, dep.source,
+    );
+    fs::write(manifest_path(work_dir), manifest)?;
+    fs::write(work_dir.join("lib.rs"), "")?;
+
+    // Resolve the dependency to find the crate's source.
+    let cargo_config = workspace_cargo_config(work_dir)?;
+    let ws = cargo::core::Workspace::new(&manifest_path(work_dir), &cargo_config)?;
+    let root_id = ws.current()?.package_id();
+    let (packages, resolve) = cargo::ops::resolve_ws(&ws)?;
+    let (pkg_id, pkg_dep) = resolve
+        .deps(root_id)
+        .find_map(|(id, deps)| {
+            deps.iter()
+                .find(|d| d.name_in_toml().as_str() == dep.name)
+                .map(|d| (id, d.clone()))
+        })
+        .ok_or_else(|| LoadModuleError::NoMatchingPackage {
+            name: dep.name.clone(),
+        })?;
+    let pkg = packages.get_one(pkg_id)?;
+    let lib = pkg
+        .targets()
+        .iter()
+        .find(|target| target.is_lib())
+        .ok_or_else(|| LoadModuleError::NoLibTarget {
+            name: dep.name.clone(),
+        })?;
+    let lib_rs = lib
+        .src_path()
+        .path()
+        .ok_or_else(|| LoadModuleError::NoLibTarget {
+            name: dep.name.clone(),
+        })?
+        .to_path_buf();
+    let crate_name = match pkg_dep.explicit_name_in_toml() {
+        Some(name) => name.replace("-", "_"),
+        None => lib.crate_name(),
+    };
+
+    // Find the module.
+    let path: Vec<String> = module_path
+        .split("::")
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect();
+    let items = module_items(&lib_rs, &path)?;
+    Ok(Module {
+        crate_name,
+        path,
+        items,
+    })
+}
+
+/// Generate a **Node** type for each public free function of the given module.
+///
+/// Each node's evaluator calls into the function via its path and its `crate_deps` return `dep`.
+/// Each node type is named after the function in `UpperCamelCase` and is registered as a
+/// `SerdeNode` under the function's full path.
+pub fn generate(dep: &CrateDep, module: &Module) -> Generated {
+    let scope = module_scope(module);
+    let mut nodes = vec![];
+    let mut skipped = vec![];
+    let mut items = vec![];
+    for item in &module.items {
+        let item_fn = match *item {
+            syn::Item::Fn(ref item_fn) => item_fn,
+            _ => continue,
+        };
+        match item_fn.vis {
+            syn::Visibility::Public(_) => (),
+            _ => continue,
+        }
+        let fn_name = item_fn.sig.ident.to_string();
+        match wrap_fn(module, &scope, item_fn) {
+            Ok(wrapped) => {
+                items.extend(node_items(dep, module, &wrapped).items);
+                nodes.push(wrapped);
+            }
+            Err(reason) => skipped.push(SkippedFn { fn_name, reason }),
+        }
+    }
+    let file = syn::File {
+        shebang: None,
+        attrs: vec![],
+        items,
+    };
+    Generated {
+        nodes,
+        skipped,
+        file,
+    }
+}
+
+/// Load the module at the given path, generate its nodes and write the source to `out_path`.
+///
+/// The file is only written if its contents have changed, avoiding needless rebuilds.
+pub fn generate_to_file(
+    dep: &CrateDep,
+    module_path: &str,
+    work_dir: &Path,
+    out_path: &Path,
+) -> Result<Generated, GenerateToFileError> {
+    let module = load_module(dep, module_path, work_dir)?;
+    let generated = generate(dep, &module);
+    let src = generated.to_source();
+    let unchanged = fs::read_to_string(out_path)
+        .map(|existing| existing == src)
+        .unwrap_or(false);
+    if !unchanged {
+        fs::write(out_path, src)?;
+    }
+    Ok(generated)
+}
+
+// Parse the items of the module at the given path from the crate root at `lib_rs`.
+fn module_items(lib_rs: &Path, path: &[String]) -> Result<Vec<syn::Item>, LoadModuleError> {
+    let mut items = parse_file(lib_rs)?.items;
+    // The directory containing the files of child modules.
+    let mut dir = lib_rs.parent().map(Path::to_path_buf).unwrap_or_default();
+    // The directory against which `#[path = "…"]` attributes of child modules are resolved.
+    let mut path_attr_dir = dir.clone();
+    for (i, segment) in path.iter().enumerate() {
+        let item_mod = items
+            .into_iter()
+            .filter_map(|item| match item {
+                syn::Item::Mod(item_mod) => Some(item_mod),
+                _ => None,
+            })
+            .find(|item_mod| {
+                matches!(item_mod.vis, syn::Visibility::Public(_))
+                    && item_mod.ident == segment
+                    && !is_cfg_gated(&item_mod.attrs)
+            })
+            .ok_or_else(|| LoadModuleError::ModuleNotFound {
+                module: path[..i + 1].join("::"),
+            })?;
+        let attr_path = path_attr(&item_mod.attrs);
+        match (item_mod.content, attr_path) {
+            (Some((_, content)), attr_path) => {
+                items = content;
+                dir = match attr_path {
+                    Some(attr_path) => path_attr_dir.join(attr_path),
+                    None => dir.join(segment),
+                };
+                path_attr_dir = dir.clone();
+            }
+            // Files at a `#[path]` are treated like `mod.rs` files.
+            (None, Some(attr_path)) => {
+                let file = path_attr_dir.join(attr_path);
+                items = parse_file(&file)?.items;
+                dir = file.parent().map(Path::to_path_buf).unwrap_or_default();
+                path_attr_dir = dir.clone();
+            }
+            (None, None) => {
+                let file = dir.join(format!("{}.rs", segment));
+                let mod_dir = dir.join(segment);
+                items = match file.exists() {
+                    true => {
+                        path_attr_dir = dir.clone();
+                        parse_file(&file)?.items
+                    }
+                    false => {
+                        path_attr_dir = mod_dir.clone();
+                        parse_file(&mod_dir.join("mod.rs"))?.items
+                    }
+                };
+                dir = mod_dir;
+            }
+        }
+    }
+    Ok(items)
+}
+
+// Whether the item is conditionally compiled via a `#[cfg(...)]` attribute.
+//
+// The predicate is not evaluated, as the configuration of the crate's build is unknown.
+fn is_cfg_gated(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attr| attr.path.is_ident("cfg"))
+}
+
+// The file path given by the module's `#[path = "…"]` attribute, if any.
+fn path_attr(attrs: &[syn::Attribute]) -> Option<String> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("path"))
+        .find_map(|attr| match attr.parse_meta() {
+            Ok(syn::Meta::NameValue(syn::MetaNameValue {
+                lit: syn::Lit::Str(lit),
+                ..
+            })) => Some(lit.value()),
+            _ => None,
+        })
+}
+
+// Read and parse the Rust source file at the given path.
+fn parse_file(path: &Path) -> Result<syn::File, LoadModuleError> {
+    let src = fs::read_to_string(path)?;
+    syn::parse_file(&src).map_err(|err| LoadModuleError::Parse {
+        path: path.to_path_buf(),
+        err,
+    })
+}
+
+// Map the names declared within or imported into the module to their absolute paths.
+fn module_scope(module: &Module) -> HashMap<String, Vec<String>> {
+    let mod_path = module.abs_path();
+    let local = |ident: &syn::Ident| {
+        let mut path = mod_path.clone();
+        path.push(ident.to_string());
+        (ident.to_string(), path)
+    };
+    let mut scope = HashMap::new();
+    let mut imports = vec![];
+    for item in &module.items {
+        match *item {
+            syn::Item::Struct(ref item) => scope.extend(Some(local(&item.ident))),
+            syn::Item::Enum(ref item) => scope.extend(Some(local(&item.ident))),
+            syn::Item::Union(ref item) => scope.extend(Some(local(&item.ident))),
+            syn::Item::Type(ref item) => scope.extend(Some(local(&item.ident))),
+            syn::Item::Trait(ref item) => scope.extend(Some(local(&item.ident))),
+            syn::Item::Mod(ref item) => scope.extend(Some(local(&item.ident))),
+            syn::Item::Use(ref item) => use_tree_imports(vec![], &item.tree, &mut imports),
+            _ => (),
+        }
+    }
+    // Resolve imports against the local items first, as paths may refer to local modules.
+    let resolved: Vec<_> = imports
+        .into_iter()
+        .filter_map(|(name, path)| {
+            let (prefix, consumed) = abs_path_prefix(module, &scope, &path)?;
+            let path = prefix.into_iter().chain(path[consumed..].iter().cloned());
+            Some((name, path.collect()))
+        })
+        .collect();
+    scope.extend(resolved);
+    scope
+}
+
+// Collect the name and path of each import within the `use` tree.
+fn use_tree_imports(
+    prefix: Vec<String>,
+    tree: &syn::UseTree,
+    out: &mut Vec<(String, Vec<String>)>,
+) {
+    match *tree {
+        syn::UseTree::Path(ref p) => {
+            let mut prefix = prefix;
+            prefix.push(p.ident.to_string());
+            use_tree_imports(prefix, &p.tree, out);
+        }
+        syn::UseTree::Name(ref n) if n.ident == "self" => {
+            if let Some(name) = prefix.last() {
+                out.push((name.clone(), prefix.clone()));
+            }
+        }
+        syn::UseTree::Name(ref n) => {
+            let mut path = prefix;
+            path.push(n.ident.to_string());
+            out.push((n.ident.to_string(), path));
+        }
+        syn::UseTree::Rename(ref r) => {
+            let mut path = prefix;
+            path.push(r.ident.to_string());
+            out.push((r.rename.to_string(), path));
+        }
+        syn::UseTree::Group(ref g) => {
+            for tree in &g.items {
+                use_tree_imports(prefix.clone(), tree, out);
+            }
+        }
+        syn::UseTree::Glob(_) => (),
+    }
+}
+
+// Resolve the leading segments of the given path as it appears within the module to a prefix that
+// may be named from the generated crate.
+//
+// Returns the prefix along with the number of leading segments that it replaces, or `None` if the
+// path refers to an item that cannot be named, e.g. an item from a crate that is not a dependency
+// of the generated crate.
+fn abs_path_prefix(
+    module: &Module,
+    scope: &HashMap<String, Vec<String>>,
+    path: &[String],
+) -> Option<(Vec<String>, usize)> {
+    let first = path.first()?;
+    let prefix = match &first[..] {
+        "crate" => vec![module.crate_name.clone()],
+        "self" => module.abs_path(),
+        "super" => {
+            let supers = path.iter().take_while(|s| *s == "super").count();
+            let mut mod_path = module.abs_path();
+            for _ in 0..supers {
+                mod_path.pop();
+            }
+            if mod_path.is_empty() {
+                return None;
+            }
+            return Some((mod_path, supers));
+        }
+        s if STD_CRATES.contains(&s) => vec![s.to_string()],
+        s => scope.get(s)?.clone(),
+    };
+    Some((prefix, 1))
+}
+
+// Finds any `impl Trait` types within a function argument.
+struct ImplTraitFinder {
+    found: bool,
+}
+
+// Rewrites the paths within a signature so that they may be named from the generated crate.
+struct PathRewriter<'a> {
+    module: &'a Module,
+    scope: &'a HashMap<String, Vec<String>>,
+    // The names of the function's generic parameters.
+    generics: HashSet<String>,
+    // The first path that could not be named, if any.
+    unnameable: Option<String>,
+}
+
+impl VisitMut for ImplTraitFinder {
+    fn visit_type_impl_trait_mut(&mut self, _ty: &mut syn::TypeImplTrait) {
+        self.found = true;
+    }
+}
+
+impl<'a> VisitMut for PathRewriter<'a> {
+    fn visit_path_mut(&mut self, path: &mut syn::Path) {
+        if path.leading_colon.is_none() {
+            let segments: Vec<String> = path.segments.iter().map(|s| s.ident.to_string()).collect();
+            let first = &segments[0];
+            let is_known = PRELUDE.contains(&&first[..]) && !self.scope.contains_key(first);
+            if !is_known && !self.generics.contains(first) {
+                match abs_path_prefix(self.module, self.scope, &segments) {
+                    None => {
+                        if self.unnameable.is_none() {
+                            let path_str = path.to_token_stream().to_string().replace(' ', "");
+                            self.unnameable = Some(path_str);
+                        }
+                    }
+                    Some((prefix, consumed)) => {
+                        let replaced = path.segments[consumed - 1].clone();
+                        let mut new_segments: syn::punctuated::Punctuated<_, _> = prefix
+                            .iter()
+                            .map(|s| {
+                                let ident = syn::Ident::new(s, replaced.ident.span());
+                                syn::PathSegment::from(ident)
+                            })
+                            .collect();
+                        // Retain any generic arguments on the final replaced segment.
+                        if let Some(last) = new_segments.last_mut() {
+                            last.arguments = replaced.arguments;
+                        }
+                        new_segments.extend(path.segments.iter().skip(consumed).cloned());
+                        path.segments = new_segments;
+                    }
+                }
+            }
+        }
+        visit_mut::visit_path_mut(self, path);
+    }
+}
+
+// Whether the type of the given argument contains an `impl Trait`.
+fn contains_impl_trait(arg: &syn::FnArg) -> bool {
+    let mut finder = ImplTraitFinder { found: false };
+    finder.visit_fn_arg_mut(&mut arg.clone());
+    finder.found
+}
+
+// Produce the evaluator function for the given function.
+fn wrap_fn(
+    module: &Module,
+    scope: &HashMap<String, Vec<String>>,
+    item_fn: &syn::ItemFn,
+) -> Result<WrappedFn, SkipReason> {
+    if is_cfg_gated(&item_fn.attrs) {
+        return Err(SkipReason::Cfg);
+    }
+    let sig = &item_fn.sig;
+    if sig.unsafety.is_some() {
+        return Err(SkipReason::Unsafe);
+    }
+    if sig.asyncness.is_some() {
+        return Err(SkipReason::Async);
+    }
+    if sig.abi.is_some() {
+        return Err(SkipReason::Extern);
+    }
+    if sig.variadic.is_some() {
+        return Err(SkipReason::Variadic);
+    }
+    if sig.inputs.iter().any(contains_impl_trait) {
+        return Err(SkipReason::ImplTraitArg);
+    }
+
+    // Name each argument, replacing complex patterns.
+    let mut sig = sig.clone();
+    sig.constness = None;
+    let mut arg_idents = vec![];
+    for (i, arg) in sig.inputs.iter_mut().enumerate() {
+        let pat_type = match *arg {
+            syn::FnArg::Typed(ref mut pat_type) => pat_type,
+            syn::FnArg::Receiver(_) => return Err(SkipReason::Receiver),
+        };
+        let ident = match *pat_type.pat {
+            syn::Pat::Ident(ref pat) => pat.ident.clone(),
+            _ => syn::Ident::new(&format!("arg{}", i), proc_macro2::Span::call_site()),
+        };
+        *pat_type.pat = syn::parse_quote! { #ident };
+        arg_idents.push(ident);
+    }
+
+    // Rewrite the paths within the signature.
+    let generics = sig
+        .generics
+        .params
+        .iter()
+        .filter_map(|param| match *param {
+            syn::GenericParam::Type(ref ty) => Some(ty.ident.to_string()),
+            syn::GenericParam::Const(ref c) => Some(c.ident.to_string()),
+            syn::GenericParam::Lifetime(_) => None,
+        })
+        .collect();
+    let mut rewriter = PathRewriter {
+        module,
+        scope,
+        generics,
+        unnameable: None,
+    };
+    rewriter.visit_signature_mut(&mut sig);
+    if let Some(path) = rewriter.unnameable {
+        return Err(SkipReason::UnnameablePath(path));
+    }
+
+    // Call into the function via its path, passing type parameters explicitly.
+    let fn_name = sig.ident.to_string();
+    let fn_path: syn::Path =
+        syn::parse_str(&format!("{}::{}", module.abs_path().join("::"), fn_name))
+            .expect("failed to parse function path");
+    let param_idents: Vec<_> = sig
+        .generics
+        .params
+        .iter()
+        .filter_map(|param| match *param {
+            syn::GenericParam::Type(ref ty) => Some(ty.ident.clone()),
+            syn::GenericParam::Const(ref c) => Some(c.ident.clone()),
+            syn::GenericParam::Lifetime(_) => None,
+        })
+        .collect();
+    let turbofish = match param_idents.is_empty() {
+        true => quote::quote! {},
+        false => quote::quote! { ::<#(#param_idents),*> },
+    };
+
+    // Name the evaluator fn after the full path to avoid conflicts between wrapped crates.
+    let eval_fn_name = format!("{}_{}", module.abs_path().join("_"), fn_name);
+    sig.ident = syn::Ident::new(&eval_fn_name, proc_macro2::Span::call_site());
+    let fn_item = syn::parse_quote! {
+        #sig {
+            #fn_path #turbofish (#(#arg_idents),*)
+        }
+    };
+    let type_name = crate::node::fn_node_type_name(&fn_name);
+    Ok(WrappedFn {
+        fn_name,
+        type_name,
+        fn_item,
+    })
+}
+
+// The items for the node type of the given function.
+fn node_items(dep: &CrateDep, module: &Module, wrapped: &WrappedFn) -> syn::File {
+    let ident = syn::Ident::new(&wrapped.type_name, proc_macro2::Span::call_site());
+    let fn_path = format!("{}::{}", module.abs_path().join("::"), wrapped.fn_name);
+    let doc = format!("The **Node** generated for `{}`.", fn_path);
+    let fn_src = wrapped.fn_item.to_token_stream().to_string();
+    let (dep_name, dep_source) = (&dep.name, &dep.source);
+    syn::parse_quote! {
+        #[doc = #doc]
+        #[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+        #[derive(serde::Deserialize, serde::Serialize)]
+        pub struct #ident;
+
+        impl gantz::Node for #ident {
+            fn evaluator(&self) -> gantz::node::Evaluator {
+                let fn_item = gantz::__private::syn::parse_str(#fn_src)
+                    .expect("failed to parse generated evaluator fn");
+                gantz::node::Evaluator::Fn {
+                    fn_item,
+                    optional_outputs: vec![],
+                }
+            }
+
+            fn crate_deps(&self) -> Vec<gantz::node::CrateDep> {
+                vec![gantz::node::CrateDep {
+                    name: #dep_name.to_string(),
+                    source: #dep_source.to_string(),
+                }]
+            }
+        }
+
+        #[typetag::serde(name = #fn_path)]
+        impl gantz::node::SerdeNode for #ident {
+            fn node(&self) -> &dyn gantz::Node {
+                self
+            }
+        }
+    }
+}
@@ -0,0 +1,107 @@
+// Testing compilation of graphs on the background build thread.
+
+use gantz::node::{self, SerdeNode, WithPushEval, WithStateType};
+use gantz::project::build::{BuildError, BuildEvent, BuildStage};
+use gantz::Edge;
+
+fn node_push() -> node::Push<node::Expr> {
+    node::expr("()").unwrap().with_push_eval_name("push")
+}
+
+fn node_counter() -> node::State<node::Expr> {
+    node::expr(r#"{ #push; let count = *state; *state += 1; count }"#)
+        .unwrap()
+        .with_default_state_ty("u32")
+        .unwrap()
+}
+
+// The loaded graph keeps running while a new build is in progress and successive edits to the
+// same graph are coalesced.
+#[test]
+fn test_background_build() {
+    // Create a temp project.
+    let mut project = gantz::TempProject::open_with_name("test_background_build").unwrap();
+
+    // Add the nodes to the project.
+    let push = project
+        .add_core_node(Box::new(node_push()) as Box<dyn SerdeNode>)
+        .unwrap();
+    let counter = project
+        .add_core_node(Box::new(node_counter()) as Box<_>)
+        .unwrap();
+
+    // Compose the initial graph and wait for it to build.
+    let root = project.root_node_id();
+    let mut ids = None;
+    let (_pruned, job) = project
+        .update_graph_in_background(&root, |g| {
+            let p = g.add_node(push);
+            let c = g.add_node(counter);
+            g.add_edge(p, c, Edge::from((0, 0)));
+            ids = Some(c);
+        })
+        .unwrap();
+    let job = job.expect("no build job for the root graph");
+    let dylib = job.wait().unwrap();
+    assert_eq!(job.progress().stage, BuildStage::Finished);
+    match project.build_events().next() {
+        Some(BuildEvent::Ready { graph, dylib: path }) => {
+            assert_eq!(graph, root);
+            assert_eq!(path, dylib);
+        }
+        _ => panic!("expected a ready event"),
+    }
+    let counter_a = ids.unwrap();
+
+    // Load the graph.
+    let mut graph = project.load_graph(&root).unwrap();
+    let mut state = gantz::GraphState::new(&project, &graph).unwrap();
+    let push_fn = graph.push_fn("push").unwrap();
+
+    // Make two edits in quick succession, evaluating the loaded graph in the meantime.
+    let mut ids = None;
+    let (_, job_a) = project
+        .update_graph_in_background(&root, |g| {
+            let c = g.add_node(counter);
+            g.add_edge(counter_a, c, Edge::from((0, 0)));
+            ids = Some(c);
+        })
+        .unwrap();
+    let counter_b = ids.unwrap();
+    state.call(&push_fn).unwrap();
+    let (_, job_b) = project
+        .update_graph_in_background(&root, |g| {
+            let c = g.add_node(counter);
+            g.add_edge(counter_b, c, Edge::from((0, 0)));
+        })
+        .unwrap();
+    state.call(&push_fn).unwrap();
+    assert_eq!(state.state::<u32>(counter_a), Some(&2));
+
+    // The first edit is either coalesced with or superseded by the second.
+    let (job_a, job_b) = (job_a.unwrap(), job_b.unwrap());
+    let dylib = job_b.wait().unwrap();
+    match job_a.wait() {
+        Ok(path) => assert_eq!(path, dylib),
+        Err(BuildError::Superseded) => (),
+        Err(err) => panic!("unexpected build error: {}", err),
+    }
+    let ready = project
+        .build_events()
+        .filter(|event| match event {
+            BuildEvent::Ready { .. } => true,
+            _ => false,
+        })
+        .count();
+    assert!(ready >= 1);
+
+    // Reload the graph with the latest build.
+    drop(push_fn);
+    let report = graph.reload(&project, &mut state).unwrap();
+    assert_eq!(report.kept, vec![counter_a]);
+    assert_eq!(report.added.len(), 2);
+    let push_fn = graph.push_fn("push").unwrap();
+    state.call(&push_fn).unwrap();
+    assert_eq!(state.state::<u32>(counter_a), Some(&3));
+    assert_eq!(state.state::<u32>(counter_b), Some(&1));
+}
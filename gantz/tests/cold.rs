@@ -0,0 +1,182 @@
+// Testing evaluation boundaries via cold edges.
+
+use gantz::node::{self, SerdeNode, WithPushEval, WithStateType};
+use gantz::Edge;
+use serde::{Deserialize, Serialize};
+
+fn node_push(push_eval_name: &str) -> node::Push<node::Expr> {
+    node::expr("()")
+        .unwrap()
+        .with_push_eval_name(push_eval_name)
+}
+
+// A simple counter node.
+//
+// Increases its `u32` state by `1` each time it receives an input of any type.
+fn node_counter() -> node::State<node::Expr> {
+    node::expr(r#"{ #push; let count = *state; *state += 1; count }"#)
+        .unwrap()
+        .with_default_state_ty("u32")
+        .unwrap()
+}
+
+// Stores the value received on its second input each time its first input is triggered.
+fn node_store() -> node::State<node::Expr> {
+    node::expr(r#"{ #push; let value: u32 = #value; *state = value; }"#)
+        .unwrap()
+        .with_default_state_ty("u32")
+        .unwrap()
+}
+
+// Produces the `u64` received on its second input each time its first input is triggered.
+#[derive(Deserialize, Serialize)]
+struct Hold;
+
+impl gantz::Node for Hold {
+    fn evaluator(&self) -> node::Evaluator {
+        let fn_item = syn::parse_quote! {
+            fn hold(_trigger: (), value: u64) -> u64 {
+                value
+            }
+        };
+        node::Evaluator::Fn {
+            fn_item,
+            optional_outputs: vec![],
+        }
+    }
+}
+
+#[typetag::serde]
+impl SerdeNode for Hold {
+    fn node(&self) -> &dyn gantz::Node {
+        self
+    }
+}
+
+// A graph with a cold edge between the counter and the store.
+//
+//    ----------
+//    | push_a |  // push_eval
+//    -+--------
+//     |
+//    -+---------    ----------
+//    | counter |    | push_b |  // push_eval
+//    -+---------    -+--------
+//     :              |
+//     :  (cold)      |
+//     :              |
+//    -+--------------+-
+//    |      store     |
+//    ------------------
+//
+// Pushing from `push_a` updates the counter without evaluating the store, while pushing from
+// `push_b` evaluates the store with the last value produced by the counter.
+#[test]
+fn test_graph_with_cold_edge() {
+    // Create a temp project.
+    let mut project = gantz::TempProject::open_with_name("test_graph_with_cold_edge").unwrap();
+
+    // Add the nodes to the project.
+    let push_a = project
+        .add_core_node(Box::new(node_push("push_a")) as Box<dyn SerdeNode>)
+        .unwrap();
+    let push_b = project
+        .add_core_node(Box::new(node_push("push_b")) as Box<_>)
+        .unwrap();
+    let counter = project
+        .add_core_node(Box::new(node_counter()) as Box<_>)
+        .unwrap();
+    let store = project
+        .add_core_node(Box::new(node_store()) as Box<_>)
+        .unwrap();
+
+    // Compose the graph.
+    let root = project.root_node_id();
+    let mut ids = None;
+    project
+        .update_graph(&root, |g| {
+            let p_a = g.add_node(push_a);
+            let p_b = g.add_node(push_b);
+            let c = g.add_node(counter);
+            let s = g.add_node(store);
+            g.add_edge(p_a, c, Edge::from((0, 0)));
+            g.add_edge(p_b, s, Edge::from((0, 0)));
+            g.add_edge(c, s, Edge::cold(0.into(), 1.into()));
+            ids = Some((p_a, c, s));
+        })
+        .unwrap();
+    let (p_a, c, s) = ids.unwrap();
+
+    // Evaluation from `push_a` stops at the cold edge.
+    {
+        let g = project
+            .ref_graph_node(&root)
+            .expect("no graph for project root node");
+        let order = gantz::graph::codegen::push_eval_order(&**g, p_a).collect::<Vec<_>>();
+        assert_eq!(order, vec![p_a, c]);
+    }
+
+    // Load the compiled library.
+    let graph = project.load_graph(&root).expect("failed to load graph");
+    let push_a_fn = graph.push_fn("push_a").unwrap();
+    let push_b_fn = graph.push_fn("push_b").unwrap();
+    let mut state = gantz::GraphState::new(&project, &graph).unwrap();
+
+    // The store is not evaluated until the counter has produced a value.
+    state.call(&push_b_fn).unwrap();
+    assert!(!state.has_cold_inlet_value(s, 1.into()));
+    assert_eq!(state.state::<u32>(s), Some(&0));
+
+    // Pushing from the counter stores the value without evaluating the store.
+    for _ in 0..3 {
+        state.call(&push_a_fn).unwrap();
+    }
+    assert!(state.has_cold_inlet_value(s, 1.into()));
+    assert_eq!(state.state::<u32>(c), Some(&3));
+    assert_eq!(state.state::<u32>(s), Some(&0));
+
+    // Pushing from `push_b` reads the last value produced by the counter.
+    state.call(&push_b_fn).unwrap();
+    assert_eq!(state.state::<u32>(s), Some(&2));
+}
+
+// A value stored for a cold inlet is read as the declared type of the input. A value of another
+// type is reported rather than being treated as if no value had been stored.
+//
+// The same as `test_graph_with_cold_edge`, but the `u32` counter value is stored for the `u64`
+// input of a `hold` node.
+#[test]
+#[should_panic(expected = "is not of the expected type")]
+fn test_graph_with_cold_edge_type_mismatch() {
+    let mut project =
+        gantz::TempProject::open_with_name("test_graph_with_cold_edge_type_mismatch").unwrap();
+    let push_a = project
+        .add_core_node(Box::new(node_push("push_a")) as Box<dyn SerdeNode>)
+        .unwrap();
+    let push_b = project
+        .add_core_node(Box::new(node_push("push_b")) as Box<_>)
+        .unwrap();
+    let counter = project
+        .add_core_node(Box::new(node_counter()) as Box<_>)
+        .unwrap();
+    let hold = project.add_core_node(Box::new(Hold) as Box<_>).unwrap();
+    let root = project.root_node_id();
+    project
+        .update_graph(&root, |g| {
+            let p_a = g.add_node(push_a);
+            let p_b = g.add_node(push_b);
+            let c = g.add_node(counter);
+            let h = g.add_node(hold);
+            g.add_edge(p_a, c, Edge::from((0, 0)));
+            g.add_edge(p_b, h, Edge::from((0, 0)));
+            g.add_edge(c, h, Edge::cold(0.into(), 1.into()));
+        })
+        .unwrap();
+
+    let graph = project.load_graph(&root).expect("failed to load graph");
+    let push_a_fn = graph.push_fn("push_a").unwrap();
+    let push_b_fn = graph.push_fn("push_b").unwrap();
+    let mut state = gantz::GraphState::new(&project, &graph).unwrap();
+    state.call(&push_a_fn).unwrap();
+    state.call(&push_b_fn).unwrap();
+}
@@ -0,0 +1,98 @@
+// Testing conditional evaluation via nodes with optional outputs.
+
+use gantz::node::{self, SerdeNode, WithOptionalOutputs, WithPushEval, WithStateType};
+use gantz::Edge;
+
+fn node_push() -> node::Push<node::Expr> {
+    node::expr("()").unwrap().with_push_eval_name("push")
+}
+
+// A simple counter node.
+//
+// Increases its `u32` state by `1` each time it receives an input of any type.
+fn node_counter() -> node::State<node::Expr> {
+    node::expr(r#"{ #push; let count = *state; *state += 1; count }"#)
+        .unwrap()
+        .with_default_state_ty("u32")
+        .unwrap()
+}
+
+// A gate that only produces a value for even inputs.
+fn node_even() -> node::Optional<node::Expr> {
+    node::expr(r#"{ let n: u32 = #n; if n % 2 == 0 { Some(n) } else { None } }"#)
+        .unwrap()
+        .with_optional_output()
+}
+
+// A graph where half of the nodes are only evaluated on every second push.
+//
+//    --------
+//    | push | // push_eval
+//    -+------
+//     |
+//    -+---------
+//    | counter |
+//    -+---------
+//     |
+//    -+------
+//    | even |
+//    -+------
+//     |
+//    -+---------
+//    | counter |
+//    -+---------
+//     |
+//    -+---------
+//    | counter |
+//    -+---------
+//
+// The push evaluation enabled `push` node is called five times once loaded.
+#[test]
+fn test_graph_with_gate() {
+    // Create a temp project.
+    let mut project = gantz::TempProject::open_with_name("test_graph_with_gate").unwrap();
+
+    // Add the nodes to the project.
+    let push = project
+        .add_core_node(Box::new(node_push()) as Box<dyn SerdeNode>)
+        .unwrap();
+    let counter = project
+        .add_core_node(Box::new(node_counter()) as Box<_>)
+        .unwrap();
+    let even = project
+        .add_core_node(Box::new(node_even()) as Box<_>)
+        .unwrap();
+
+    // Compose the graph.
+    let root = project.root_node_id();
+    let mut counter_ids = vec![];
+    project
+        .update_graph(&root, |g| {
+            let p = g.add_node(push);
+            let c_a = g.add_node(counter);
+            let e = g.add_node(even);
+            let c_b = g.add_node(counter);
+            let c_c = g.add_node(counter);
+            g.add_edge(p, c_a, Edge::from((0, 0)));
+            g.add_edge(c_a, e, Edge::from((0, 0)));
+            g.add_edge(e, c_b, Edge::from((0, 0)));
+            g.add_edge(c_b, c_c, Edge::from((0, 0)));
+            counter_ids = vec![c_a, c_b, c_c];
+        })
+        .unwrap();
+
+    // Load the compiled library and push evaluation five times.
+    let graph = project.load_graph(&root).expect("failed to load graph");
+    let push_fn = graph.push_fn("push").expect("failed to load symbol");
+    let mut state = gantz::GraphState::new(&project, &graph).unwrap();
+    for _ in 0..5 {
+        state.call(&push_fn).unwrap();
+    }
+
+    // The first counter yields 0, 1, 2, 3 and 4, so only three values pass through the gate.
+    let counts: Vec<u32> = counter_ids
+        .iter()
+        .map(|&n| *state.state::<u32>(n).expect("no counter state"))
+        .collect();
+    assert_eq!(counts, vec![5, 3, 3]);
+}
@@ -0,0 +1,75 @@
+// Tests for configuring how a project's graph nodes are built.
+
+use gantz::node::{self, SerdeNode, WithPushEval, WithStateType};
+use gantz::project::config::{BuildProfile, ProjectConfig};
+use gantz::Edge;
+
+fn node_push() -> node::Push<node::Expr> {
+    node::expr("()").unwrap().with_push_eval_name("push")
+}
+
+fn node_counter() -> node::State<node::Expr> {
+    node::expr(r#"{ #push; let count = *state; *state += 1; count }"#)
+        .unwrap()
+        .with_default_state_ty("u32")
+        .unwrap()
+}
+
+// Check that graphs are compiled with and loaded from the configured profile, and that the config
+// persists when the project is re-opened.
+#[test]
+fn test_project_config_debug_profile() {
+    // Create a temp project.
+    let mut project =
+        gantz::TempProject::open_with_name("test_project_config_debug_profile").unwrap();
+    assert_eq!(project.config().profile, BuildProfile::Release);
+
+    // Switch to an unoptimised debug build.
+    let config = ProjectConfig {
+        profile: BuildProfile::Debug,
+        opt_level: Some("0".to_string()),
+        codegen_units: Some(16),
+        ..Default::default()
+    };
+    project.set_config(config.clone()).unwrap();
+    let ws_dir = project.workspace_dir();
+    assert!(gantz::project::cargo_config_toml_path(&ws_dir).exists());
+
+    // Add the nodes to the project.
+    let push = project
+        .add_core_node(Box::new(node_push()) as Box<dyn SerdeNode>)
+        .unwrap();
+    let counter = project
+        .add_core_node(Box::new(node_counter()) as Box<_>)
+        .unwrap();
+
+    // Compose the graph.
+    let root = project.root_node_id();
+    let mut ids = None;
+    project
+        .update_graph(&root, |g| {
+            let p = g.add_node(push);
+            let c = g.add_node(counter);
+            g.add_edge(p, c, Edge::from((0, 0)));
+            ids = Some(c);
+        })
+        .unwrap();
+    let c = ids.unwrap();
+
+    // The dylib is found within the debug profile's directory.
+    let dylib = project.graph_node_dylib(&root).unwrap().expect("no dylib");
+    let profile_dir = dylib.parent().and_then(|dir| dir.file_name()).unwrap();
+    assert_eq!(profile_dir, "debug");
+
+    // Load the graph and evaluate it.
+    let graph = project.load_graph(&root).unwrap();
+    let mut state = gantz::GraphState::new(&project, &graph).unwrap();
+    let push_fn = graph.push_fn("push").unwrap();
+    state.call(&push_fn).unwrap();
+    assert_eq!(state.state::<u32>(c), Some(&1));
+
+    // The config is restored when the project is re-opened.
+    project.save().unwrap();
+    let reopened = gantz::Project::open(project.dir().to_path_buf()).unwrap();
+    assert_eq!(reopened.config(), &config);
+}
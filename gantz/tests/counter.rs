@@ -41,8 +41,10 @@ fn test_graph_with_counter() {
     let counter = node_counter();
 
     // Add the nodes to the project.
-    let push = project.add_core_node(Box::new(push) as Box<dyn SerdeNode>);
-    let counter = project.add_core_node(Box::new(counter) as Box<_>);
+    let push = project
+        .add_core_node(Box::new(push) as Box<dyn SerdeNode>)
+        .unwrap();
+    let counter = project.add_core_node(Box::new(counter) as Box<_>).unwrap();
 
     // Compose the graph.
     let root = project.root_node_id();
@@ -118,10 +120,16 @@ fn test_graph_with_counters() {
     let counter = node_counter();
 
     // Add the nodes to the project.
-    let push_a = project.add_core_node(Box::new(push_a) as Box<dyn SerdeNode>);
-    let push_b = project.add_core_node(Box::new(push_b) as Box<dyn SerdeNode>);
-    let push_c = project.add_core_node(Box::new(push_c) as Box<dyn SerdeNode>);
-    let counter = project.add_core_node(Box::new(counter) as Box<_>);
+    let push_a = project
+        .add_core_node(Box::new(push_a) as Box<dyn SerdeNode>)
+        .unwrap();
+    let push_b = project
+        .add_core_node(Box::new(push_b) as Box<dyn SerdeNode>)
+        .unwrap();
+    let push_c = project
+        .add_core_node(Box::new(push_c) as Box<dyn SerdeNode>)
+        .unwrap();
+    let counter = project.add_core_node(Box::new(counter) as Box<_>).unwrap();
 
     // Compose the graph.
     let root = project.root_node_id();
@@ -0,0 +1,110 @@
+// Testing the values passed to unconnected inputs.
+
+use gantz::graph::validate::DiagnosticKind;
+use gantz::node::{self, InputDefault, SerdeNode, WithInputDefaults, WithPushEval};
+use gantz::project::UpdateGraphError;
+use gantz::Edge;
+
+fn node_push() -> node::Push<node::Expr> {
+    node::expr("()").unwrap().with_push_eval_name("push")
+}
+
+fn node_one() -> node::Expr {
+    node::expr("{ #push; 1 }").unwrap()
+}
+
+// An add node whose right-hand side defaults to `41`.
+fn node_add() -> node::InputDefaults<node::Expr> {
+    let defaults = vec![InputDefault::Unit, InputDefault::expr("41").unwrap()];
+    node::expr("#l + #r").unwrap().with_input_defaults(defaults)
+}
+
+// An assert_eq node whose inputs must both be connected.
+fn node_assert_eq() -> node::InputDefaults<node::Expr> {
+    let defaults = vec![InputDefault::Required, InputDefault::Required];
+    node::expr("assert_eq!(#l, #r)")
+        .unwrap()
+        .with_input_defaults(defaults)
+}
+
+// The right-hand side of `add` is left unconnected and falls back to its default.
+//
+//    --------
+//    | push | // push_eval
+//    -+------
+//     |
+//     |-----------
+//     |          |
+//    -+-----    -+-----------
+//    | one |    | forty_two |
+//    -+-----    -+-----------
+//     |          |
+//    -+-----     |
+//    | add |     |
+//    -+-----     |
+//     |          |
+//    -+----------+-
+//    | assert_eq  |
+//    --------------
+#[test]
+fn test_input_defaults() {
+    // Create a temp project.
+    let mut project = gantz::TempProject::open_with_name("test_input_defaults").unwrap();
+
+    // Add the nodes to the project.
+    let push = project
+        .add_core_node(Box::new(node_push()) as Box<dyn SerdeNode>)
+        .unwrap();
+    let one = project
+        .add_core_node(Box::new(node_one()) as Box<_>)
+        .unwrap();
+    let add = project
+        .add_core_node(Box::new(node_add()) as Box<_>)
+        .unwrap();
+    let forty_two = project
+        .add_core_node(Box::new(node::expr("{ #push; 42 }").unwrap()) as Box<_>)
+        .unwrap();
+    let assert_eq = project
+        .add_core_node(Box::new(node_assert_eq()) as Box<_>)
+        .unwrap();
+
+    // Leave the second input of `assert_eq` unconnected to check the required input error.
+    let root = project.root_node_id();
+    let mut ixs = None;
+    let result = project.update_graph(&root, |g| {
+        let push = g.add_node(push);
+        let one = g.add_node(one);
+        let add = g.add_node(add);
+        let assert_eq = g.add_node(assert_eq);
+        g.add_edge(push, one, Edge::from((0, 0)));
+        g.add_edge(one, add, Edge::from((0, 0)));
+        g.add_edge(add, assert_eq, Edge::from((0, 0)));
+        ixs = Some((push, assert_eq));
+    });
+    let (push_ix, assert_eq_ix) = ixs.unwrap();
+    match result {
+        Err(UpdateGraphError::InvalidGraph { diagnostics }) => {
+            assert_eq!(diagnostics.len(), 1);
+            let kind = DiagnosticKind::MissingRequiredInput {
+                input: node::Input(1),
+            };
+            assert_eq!(diagnostics[0].kind, kind);
+            assert_eq!(diagnostics[0].nodes, vec![assert_eq_ix]);
+        }
+        _ => panic!("expected a missing required input error"),
+    }
+
+    // Connect the missing input.
+    project
+        .update_graph(&root, |g| {
+            let forty_two = g.add_node(forty_two);
+            g.add_edge(push_ix, forty_two, Edge::from((0, 0)));
+            g.add_edge(forty_two, assert_eq_ix, Edge::from((0, 1)));
+        })
+        .unwrap();
+
+    // Load the compiled library and evaluate.
+    let graph = project.load_graph(&root).expect("failed to load graph");
+    let push_eval_fn = graph.push_fn("push").expect("failed to load symbol");
+    push_eval_fn.call(&mut []).unwrap();
+}
@@ -0,0 +1,152 @@
+// Testing the generation of stateful nodes via `#[derive(GantzNode)]`.
+
+use gantz::node::{self, Evaluator, SerdeNode, WithPushEval, WithStateType};
+use gantz::{Edge, GantzNode};
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Deserialize, Serialize, GantzNode)]
+#[inlets(push: (), step: u32)]
+#[outlets(count: u32)]
+pub struct Counter {
+    count: u32,
+}
+
+#[derive(Default, Deserialize, Serialize, GantzNode)]
+#[inlets(value: crate::Sample)]
+#[outlets(min: f32, max: f32)]
+#[process = "update"]
+pub struct Range {
+    min: f32,
+    max: f32,
+}
+
+pub struct Sample(pub f32);
+
+impl Counter {
+    pub fn process(&mut self, _push: (), step: u32) -> u32 {
+        self.count += step;
+        self.count
+    }
+}
+
+impl Range {
+    pub fn update(&mut self, value: Sample) -> (f32, f32) {
+        self.min = self.min.min(value.0);
+        self.max = self.max.max(value.0);
+        (self.min, self.max)
+    }
+}
+
+// Unwrap the function from the given evaluator.
+fn fn_item(evaluator: Evaluator) -> syn::ItemFn {
+    match evaluator {
+        Evaluator::Fn { fn_item, .. } => fn_item,
+        Evaluator::Expr { .. } => panic!("expected a fn evaluator"),
+    }
+}
+
+// The evaluator accepts the inlets, returns the outlets and calls into the process method with the
+// state that is added to the signature during codegen.
+#[test]
+fn test_derive_evaluator() {
+    let mut counter = Counter::default();
+    assert_eq!(counter.process((), 2), 2);
+    let mut range = Range::default();
+    assert_eq!(range.update(Sample(-1.0)), (-1.0, 0.0));
+
+    let expected: syn::ItemFn = syn::parse_quote! {
+        fn derive_counter(push: (), step: u32) -> u32 {
+            derive::Counter::process(state, push, step)
+        }
+    };
+    assert_eq!(fn_item(gantz::Node::evaluator(&counter)), expected);
+    let io_types = gantz::Node::io_types(&counter);
+    let u32_ty: syn::Type = syn::parse_quote! { u32 };
+    assert_eq!(
+        io_types.inputs,
+        vec![Some(syn::parse_quote! { () }), Some(u32_ty.clone())]
+    );
+    assert_eq!(io_types.outputs, vec![Some(u32_ty)]);
+
+    // Multiple outlets produce a tuple and paths relative to the defining crate are named via the
+    // crate.
+    let expected: syn::ItemFn = syn::parse_quote! {
+        fn derive_range(value: derive::Sample) -> (f32, f32) {
+            derive::Range::update(state, value)
+        }
+    };
+    let evaluator = gantz::Node::evaluator(&range);
+    assert_eq!(evaluator.n_outputs(), 2);
+    assert_eq!(fn_item(evaluator), expected);
+}
+
+// The struct itself is the node's state and the defining crate is its dependency.
+#[test]
+fn test_derive_state_type_and_crate_deps() {
+    let expected: syn::Type = syn::parse_quote! { derive::Counter };
+    assert_eq!(gantz::Node::state_type(&Counter::default()), Some(expected));
+    let deps = gantz::Node::crate_deps(&Counter::default());
+    assert_eq!(deps.len(), 1);
+    assert_eq!(deps[0].name, env!("CARGO_PKG_NAME"));
+    assert!(deps[0].source.contains(env!("CARGO_MANIFEST_DIR")));
+}
+
+// Derived nodes may be serialized and deserialized as `SerdeNode` trait objects.
+#[test]
+fn test_derive_serde() {
+    let node = Box::new(Counter { count: 3 }) as Box<dyn SerdeNode>;
+    let json = serde_json::to_string(&node).unwrap();
+    let node: Box<dyn SerdeNode> = serde_json::from_str(&json).unwrap();
+    assert_eq!(node.node().evaluator().n_inputs(), 2);
+}
+
+// Derived nodes may be compiled and evaluated within a graph, including multiple instances of the
+// same node, each with their own state.
+//
+// The node must be defined within a crate on which the graph's crate may depend, so the
+// `Accumulate` node is defined within the `derive_nodes` crate.
+#[test]
+fn test_derive_graph() {
+    let mut project = gantz::TempProject::open_with_name("test_derive_graph").unwrap();
+    let push = node::expr("()").unwrap().with_push_eval_name("push");
+    let push = project
+        .add_core_node(Box::new(push) as Box<dyn SerdeNode>)
+        .unwrap();
+    let two = node::expr("{ #push; 2 }").unwrap();
+    let two = project.add_core_node(Box::new(two) as Box<_>).unwrap();
+    let accumulate = derive_nodes::Accumulate::default();
+    let accumulate = project
+        .add_core_node(Box::new(accumulate) as Box<_>)
+        .unwrap();
+    let store = node::expr("{ let value: u32 = #value; *state = value; }")
+        .unwrap()
+        .with_default_state_ty("u32")
+        .unwrap();
+    let store = project.add_core_node(Box::new(store) as Box<_>).unwrap();
+
+    let root = project.root_node_id();
+    let mut store_ix = None;
+    project
+        .update_graph(&root, |g| {
+            let p = g.add_node(push);
+            let t = g.add_node(two);
+            let a_a = g.add_node(accumulate);
+            let a_b = g.add_node(accumulate);
+            let s = g.add_node(store);
+            g.add_edge(p, t, Edge::from((0, 0)));
+            g.add_edge(t, a_a, Edge::from((0, 0)));
+            g.add_edge(a_a, a_b, Edge::from((0, 0)));
+            g.add_edge(a_b, s, Edge::from((0, 0)));
+            store_ix = Some(s);
+        })
+        .unwrap();
+    let store_ix = store_ix.unwrap();
+
+    let graph = project.load_graph(&root).expect("failed to load graph");
+    let mut state = gantz::GraphState::new(&project, &graph).unwrap();
+    let push_fn = graph.push_fn("push").unwrap();
+    for _ in 0..3 {
+        state.call(&push_fn).unwrap();
+    }
+    assert_eq!(state.state::<u32>(store_ix), Some(&12));
+}
@@ -0,0 +1,22 @@
+//! Nodes derived via `GantzNode` for evaluation within the graphs of gantz's tests.
+//!
+//! Derived nodes are evaluated within the graph's crate, so they must be defined within a crate on
+//! which the graph's crate may depend, rather than within a test itself.
+
+use gantz::GantzNode;
+use serde::{Deserialize, Serialize};
+
+/// Accumulates each `step` received, producing the total.
+#[derive(Default, Deserialize, Serialize, GantzNode)]
+#[inlets(step: u32)]
+#[outlets(total: u32)]
+pub struct Accumulate {
+    total: u32,
+}
+
+impl Accumulate {
+    pub fn process(&mut self, step: u32) -> u32 {
+        self.total += step;
+        self.total
+    }
+}
@@ -0,0 +1,74 @@
+// Testing the attribution of compiler errors to the nodes that caused them.
+
+use gantz::node::{self, SerdeNode, WithPushEval};
+use gantz::project::UpdateGraphError;
+use gantz::Edge;
+
+fn node_push() -> node::Push<node::Expr> {
+    node::expr("()").unwrap().with_push_eval_name("push")
+}
+
+fn node_int() -> node::Expr {
+    node::expr("{ #push; 1i32 }").unwrap()
+}
+
+// Expects an `f32`, so an `i32` passed to its input fails to compile.
+fn node_float() -> node::Expr {
+    node::expr("{ let x: f32 = #x; x }").unwrap()
+}
+
+// A type error within the generated code is reported against the node and input that caused it.
+//
+//    --------
+//    | push | // push_eval
+//    -+------
+//     |
+//    -+-----
+//    | int |
+//    -+-----
+//     |
+//    -+-------
+//    | float |
+//    ---------
+#[test]
+fn test_rustc_diagnostic_node_attribution() {
+    // Create a temp project.
+    let mut project =
+        gantz::TempProject::open_with_name("test_rustc_diagnostic_node_attribution").unwrap();
+
+    // Add the nodes to the project.
+    let push = project
+        .add_core_node(Box::new(node_push()) as Box<dyn SerdeNode>)
+        .unwrap();
+    let int = project
+        .add_core_node(Box::new(node_int()) as Box<_>)
+        .unwrap();
+    let float = project
+        .add_core_node(Box::new(node_float()) as Box<_>)
+        .unwrap();
+
+    // Compose the graph.
+    let root = project.root_node_id();
+    let mut ids = None;
+    let result = project.update_graph(&root, |g| {
+        let p = g.add_node(push);
+        let i = g.add_node(int);
+        let f = g.add_node(float);
+        g.add_edge(p, i, Edge::from((0, 0)));
+        g.add_edge(i, f, Edge::from((0, 0)));
+        ids = Some(f);
+    });
+    let f = ids.unwrap();
+    match result {
+        Err(UpdateGraphError::GraphNodeCompile { err }) => {
+            let diagnostic = err
+                .diagnostics()
+                .iter()
+                .find(|d| d.level == "error")
+                .expect("expected a rustc error diagnostic");
+            assert_eq!(diagnostic.node, Some(f));
+            assert_eq!(diagnostic.input, Some(node::Input(0)));
+        }
+        _ => panic!("expected a compile error"),
+    }
+}
@@ -0,0 +1,162 @@
+// Tests for nodes with a dynamic number of inputs and outputs.
+
+use gantz::node::{self, DynamicIo, SerdeNode, WithPushEval, WithStateType};
+use gantz::project::{ReferencePolicy, SetCoreNodeIoError};
+use gantz::Edge;
+use serde::{Deserialize, Serialize};
+
+fn node_push() -> node::Push<node::Expr> {
+    node::expr("()").unwrap().with_push_eval_name("push")
+}
+
+fn node_int(i: u32) -> node::Expr {
+    node::expr(&format!("{{ #push; {}u32 }}", i)).unwrap()
+}
+
+// Stores the last value received.
+fn node_store() -> node::State<node::Expr> {
+    node::expr("{ *state = #value; }")
+        .unwrap()
+        .with_default_state_ty("u32")
+        .unwrap()
+}
+
+// A variadic sum with a configurable number of inputs.
+#[derive(Deserialize, Serialize)]
+struct Sum {
+    n_inputs: u32,
+}
+
+impl gantz::Node for Sum {
+    fn evaluator(&self) -> gantz::node::Evaluator {
+        let n_inputs = self.n_inputs;
+        let n_outputs = 1;
+        let gen_expr = Box::new(move |args: Vec<syn::Expr>| {
+            syn::parse_quote! { 0 #(+ #args)* }
+        });
+        gantz::node::Evaluator::Expr {
+            n_inputs,
+            n_outputs,
+            gen_expr,
+            optional_outputs: vec![],
+        }
+    }
+}
+
+impl DynamicIo for Sum {
+    fn set_io(&mut self, io: node::Io) -> Result<(), node::UnsupportedIoError> {
+        if io.n_outputs != 1 {
+            return Err(node::UnsupportedIoError { io });
+        }
+        self.n_inputs = io.n_inputs;
+        Ok(())
+    }
+}
+
+#[typetag::serde]
+impl gantz::node::SerdeNode for Sum {
+    fn node(&self) -> &dyn gantz::Node {
+        self
+    }
+
+    fn dynamic_io_mut(&mut self) -> Option<&mut dyn DynamicIo> {
+        Some(self)
+    }
+}
+
+// A graph summing three ints, after which the sum is reconfigured to only two inputs.
+//
+//    --------
+//    | push | // push_eval
+//    -+------
+//     |
+//     |--------------------
+//     |         |         |
+//    -+-----   -+-----   -+-------
+//    | one |   | two |   | three |
+//    -+-----   -+-----   -+-------
+//     |         |         |
+//     |         |    ------
+//     |         |    |
+//    -+---------+----+-
+//    |       sum      |
+//    -+----------------
+//     |
+//    -+-------
+//    | store |
+//    ---------
+#[test]
+fn test_dynamic_io() {
+    // Create a temp project.
+    let mut project = gantz::TempProject::open_with_name("test_dynamic_io").unwrap();
+
+    // Add the nodes to the project.
+    let push = project
+        .add_core_node(Box::new(node_push()) as Box<dyn SerdeNode>)
+        .unwrap();
+    let ints: Vec<_> = (1..=3)
+        .map(|i| {
+            project
+                .add_core_node(Box::new(node_int(i)) as Box<_>)
+                .unwrap()
+        })
+        .collect();
+    let sum = project
+        .add_core_node(Box::new(Sum { n_inputs: 3 }) as Box<_>)
+        .unwrap();
+    let store = project
+        .add_core_node(Box::new(node_store()) as Box<_>)
+        .unwrap();
+
+    // Compose the graph.
+    let root = project.root_node_id();
+    let mut store_ix = None;
+    let pruned = project
+        .update_graph(&root, |g| {
+            let p = g.add_node(push);
+            let s = g.add_node(sum);
+            for (i, &int) in ints.iter().enumerate() {
+                let n = g.add_node(int);
+                g.add_edge(p, n, Edge::from((0, 0)));
+                g.add_edge(n, s, Edge::from((0, i as u32)));
+            }
+            let st = g.add_node(store);
+            g.add_edge(s, st, Edge::from((0, 0)));
+            store_ix = Some(st);
+        })
+        .unwrap();
+    assert!(pruned[&root].is_empty());
+    let store_ix = store_ix.unwrap();
+
+    // Check the sum of all three inputs.
+    let mut graph = project.load_graph(&root).unwrap();
+    let mut state = gantz::GraphState::new(&project, &graph).unwrap();
+    state.call(&graph.push_fn("push").unwrap()).unwrap();
+    assert_eq!(state.state::<u32>(store_ix), Some(&6));
+
+    // Nodes with a fixed configuration cannot be reconfigured.
+    match project.set_core_node_io(&store, node::Io::new(2, 0), ReferencePolicy::Cascade) {
+        Err(SetCoreNodeIoError::FixedIo) => (),
+        _ => panic!("expected `SetCoreNodeIoError::FixedIo`"),
+    }
+
+    // Reduce the sum to two inputs. The edge from `three` must be pruned.
+    let pruned = project
+        .set_core_node_io(&sum, node::Io::new(2, 1), ReferencePolicy::Cascade)
+        .unwrap();
+    assert_eq!(pruned[&root].len(), 1);
+    assert_eq!(pruned[&root][0].edge, Edge::from((0, 2)));
+    graph.reload(&project, &mut state).unwrap();
+    state.call(&graph.push_fn("push").unwrap()).unwrap();
+    assert_eq!(state.state::<u32>(store_ix), Some(&3));
+
+    // Edges to inputs that do not exist are pruned by `update_graph`.
+    let pruned = project
+        .update_graph(&root, |g| {
+            let n = g.node_indices().find(|&n| g[n] == ints[2]).unwrap();
+            let s = g.node_indices().find(|&n| g[n] == sum).unwrap();
+            g.add_edge(n, s, Edge::from((0, 2)));
+        })
+        .unwrap();
+    assert_eq!(pruned[&root].len(), 1);
+}
@@ -0,0 +1,134 @@
+// Testing feedback loops via delay edges.
+
+use gantz::graph::validate::DiagnosticKind;
+use gantz::node::{self, InputDefault, SerdeNode, WithInputDefaults, WithPushEval, WithStateType};
+use gantz::project::UpdateGraphError;
+use gantz::Edge;
+
+fn node_push() -> node::Push<node::Expr> {
+    node::expr("()").unwrap().with_push_eval_name("push")
+}
+
+fn node_one() -> node::Expr {
+    node::expr("{ #push; 1u32 }").unwrap()
+}
+
+// An add node whose right-hand side defaults to `0`.
+fn node_add() -> node::InputDefaults<node::Expr> {
+    let defaults = vec![InputDefault::Unit, InputDefault::expr("0").unwrap()];
+    node::expr("#l + #r").unwrap().with_input_defaults(defaults)
+}
+
+// Stores the value received on its input.
+fn node_store() -> node::State<node::Expr> {
+    node::expr(r#"{ let value: u32 = #value; *state = value; }"#)
+        .unwrap()
+        .with_default_state_ty("u32")
+        .unwrap()
+}
+
+// An accumulator, formed by feeding the output of `add` back into its second input.
+//
+//    --------
+//    | push | // push_eval
+//    -+------
+//     |
+//    -+-----
+//    | one |
+//    -+-----
+//     |
+//     |   ...........
+//     |   :         :  (delay)
+//    -+---+-        :
+//    | add |        :
+//    -+-----        :
+//     |.............:
+//     |
+//    -+-------
+//    | store |
+//    ---------
+#[test]
+fn test_graph_with_delay_edge() {
+    // Create a temp project.
+    let mut project = gantz::TempProject::open_with_name("test_graph_with_delay_edge").unwrap();
+
+    // Add the nodes to the project.
+    let push = project
+        .add_core_node(Box::new(node_push()) as Box<dyn SerdeNode>)
+        .unwrap();
+    let one = project
+        .add_core_node(Box::new(node_one()) as Box<_>)
+        .unwrap();
+    let add = project
+        .add_core_node(Box::new(node_add()) as Box<_>)
+        .unwrap();
+    let store = project
+        .add_core_node(Box::new(node_store()) as Box<_>)
+        .unwrap();
+
+    // Compose the graph.
+    let root = project.root_node_id();
+    let mut ids = None;
+    project
+        .update_graph(&root, |g| {
+            let p = g.add_node(push);
+            let o = g.add_node(one);
+            let a = g.add_node(add);
+            let s = g.add_node(store);
+            g.add_edge(p, o, Edge::from((0, 0)));
+            g.add_edge(o, a, Edge::from((0, 0)));
+            g.add_edge(a, a, Edge::delay(0.into(), 1.into()));
+            g.add_edge(a, s, Edge::from((0, 0)));
+            ids = Some((a, s));
+        })
+        .unwrap();
+    let (a, s) = ids.unwrap();
+
+    // Load the compiled library.
+    let graph = project.load_graph(&root).expect("failed to load graph");
+    let push_fn = graph.push_fn("push").unwrap();
+    let mut state = gantz::GraphState::new(&project, &graph).unwrap();
+
+    // Each push adds `1` to the value produced by the previous push.
+    assert!(!state.has_cold_inlet_value(a, 1.into()));
+    for _ in 0..3 {
+        state.call(&push_fn).unwrap();
+    }
+    assert!(state.has_cold_inlet_value(a, 1.into()));
+    assert_eq!(state.state::<u32>(s), Some(&3));
+}
+
+// The same accumulator without the delay edge is reported as an illegal cycle.
+#[test]
+fn test_graph_with_illegal_cycle() {
+    // Create a temp project.
+    let mut project = gantz::TempProject::open_with_name("test_graph_with_illegal_cycle").unwrap();
+
+    // Add the nodes to the project.
+    let one = project
+        .add_core_node(Box::new(node_one()) as Box<dyn SerdeNode>)
+        .unwrap();
+    let add = project
+        .add_core_node(Box::new(node_add()) as Box<_>)
+        .unwrap();
+
+    // Compose the graph.
+    let root = project.root_node_id();
+    let mut ids = None;
+    let result = project.update_graph(&root, |g| {
+        let o = g.add_node(one);
+        let a = g.add_node(add);
+        g.add_edge(o, a, Edge::from((0, 0)));
+        g.add_edge(a, a, Edge::from((0, 1)));
+        ids = Some(a);
+    });
+    let a = ids.unwrap();
+    match result {
+        Err(UpdateGraphError::InvalidGraph { diagnostics }) => {
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(diagnostics[0].kind, DiagnosticKind::Cycle);
+            assert_eq!(diagnostics[0].nodes, vec![a]);
+        }
+        _ => panic!("expected an illegal cycle error"),
+    }
+}
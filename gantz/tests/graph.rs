@@ -56,11 +56,15 @@ fn test_graph1() {
     let assert_eq = node_assert_eq();
 
     // Add the nodes to the project.
-    let push = project.add_core_node(Box::new(push) as Box<dyn SerdeNode>);
-    let one = project.add_core_node(Box::new(one) as Box<_>);
-    let add = project.add_core_node(Box::new(add) as Box<_>);
-    let two = project.add_core_node(Box::new(two) as Box<_>);
-    let assert_eq = project.add_core_node(Box::new(assert_eq) as Box<_>);
+    let push = project
+        .add_core_node(Box::new(push) as Box<dyn SerdeNode>)
+        .unwrap();
+    let one = project.add_core_node(Box::new(one) as Box<_>).unwrap();
+    let add = project.add_core_node(Box::new(add) as Box<_>).unwrap();
+    let two = project.add_core_node(Box::new(two) as Box<_>).unwrap();
+    let assert_eq = project
+        .add_core_node(Box::new(assert_eq) as Box<_>)
+        .unwrap();
 
     // Compose the graph.
     let root = project.root_node_id();
@@ -109,7 +113,10 @@ impl gantz::Node for Mul {
                 a * b
             }
         };
-        gantz::node::Evaluator::Fn { fn_item }
+        gantz::node::Evaluator::Fn {
+            fn_item,
+            optional_outputs: vec![],
+        }
     }
 }
 
@@ -161,11 +168,15 @@ fn test_graph2_evaluator_fn() {
     assert_eq!(mul_eval.n_outputs(), 1);
 
     // Add the nodes to the project.
-    let push = project.add_core_node(Box::new(push) as Box<dyn SerdeNode>);
-    let two = project.add_core_node(Box::new(two) as Box<_>);
-    let mul = project.add_core_node(Box::new(mul) as Box<_>);
-    let four = project.add_core_node(Box::new(four) as Box<_>);
-    let assert_eq = project.add_core_node(Box::new(assert_eq) as Box<_>);
+    let push = project
+        .add_core_node(Box::new(push) as Box<dyn SerdeNode>)
+        .unwrap();
+    let two = project.add_core_node(Box::new(two) as Box<_>).unwrap();
+    let mul = project.add_core_node(Box::new(mul) as Box<_>).unwrap();
+    let four = project.add_core_node(Box::new(four) as Box<_>).unwrap();
+    let assert_eq = project
+        .add_core_node(Box::new(assert_eq) as Box<_>)
+        .unwrap();
 
     // Compose the graph.
     let root = project.root_node_id();
@@ -229,10 +240,12 @@ fn test_graph3_pull_eval() {
     let assert_eq = node_assert_eq().with_pull_eval_name("assert_eq");
 
     // Add the nodes to the project.
-    let one = project.add_core_node(Box::new(one) as Box<_>);
-    let add = project.add_core_node(Box::new(add) as Box<_>);
-    let two = project.add_core_node(Box::new(two) as Box<_>);
-    let assert_eq = project.add_core_node(Box::new(assert_eq) as Box<_>);
+    let one = project.add_core_node(Box::new(one) as Box<_>).unwrap();
+    let add = project.add_core_node(Box::new(add) as Box<_>).unwrap();
+    let two = project.add_core_node(Box::new(two) as Box<_>).unwrap();
+    let assert_eq = project
+        .add_core_node(Box::new(assert_eq) as Box<_>)
+        .unwrap();
 
     // Compose the graph.
     let root = project.root_node_id();
@@ -293,9 +306,11 @@ fn test_graph4_should_panic() {
     let assert_eq = node_assert_eq().with_pull_eval_name("assert_eq");
 
     // Add the nodes to the project.
-    let one = project.add_core_node(Box::new(one) as Box<_>);
-    let add = project.add_core_node(Box::new(add) as Box<_>);
-    let assert_eq = project.add_core_node(Box::new(assert_eq) as Box<_>);
+    let one = project.add_core_node(Box::new(one) as Box<_>).unwrap();
+    let add = project.add_core_node(Box::new(add) as Box<_>).unwrap();
+    let assert_eq = project
+        .add_core_node(Box::new(assert_eq) as Box<_>)
+        .unwrap();
 
     // Compose the graph.
     let root = project.root_node_id();
@@ -0,0 +1,232 @@
+// Testing that the interpreter and the compiled graph produce the same results.
+
+use gantz::graph::interp::Program;
+use gantz::node::{self, native, NativeFn, SerdeNativeFn, SerdeNode, WithNative};
+use gantz::node::{WithOptionalOutputs, WithPushEval, WithStateType};
+use gantz::Edge;
+use serde::{Deserialize, Serialize};
+use std::rc::Rc;
+
+// The native implementation of a node that produces `()` when pushed.
+#[derive(Deserialize, Serialize)]
+struct PushNative;
+
+// The native implementation of `node_counter`.
+#[derive(Deserialize, Serialize)]
+struct CounterNative;
+
+// The native implementation of `node_even`.
+#[derive(Deserialize, Serialize)]
+struct EvenNative;
+
+// The native implementation of `node_store`.
+#[derive(Deserialize, Serialize)]
+struct StoreNative;
+
+#[typetag::serde]
+impl SerdeNativeFn for PushNative {
+    fn native_fn(&self) -> NativeFn {
+        Rc::new(|_args, _state| Ok(vec![native::output(())]))
+    }
+}
+
+#[typetag::serde]
+impl SerdeNativeFn for CounterNative {
+    fn native_fn(&self) -> NativeFn {
+        Rc::new(|_args, state| {
+            let state = native::state::<u32>(state)?;
+            let count = *state;
+            *state += 1;
+            Ok(vec![native::output(count)])
+        })
+    }
+}
+
+#[typetag::serde]
+impl SerdeNativeFn for EvenNative {
+    fn native_fn(&self) -> NativeFn {
+        Rc::new(|args, _state| {
+            let n = *native::input::<u32>(args, 0)?;
+            Ok(vec![if n % 2 == 0 { native::output(n) } else { None }])
+        })
+    }
+}
+
+#[typetag::serde]
+impl SerdeNativeFn for StoreNative {
+    fn native_fn(&self) -> NativeFn {
+        Rc::new(|args, state| {
+            let value = *native::input::<u32>(args, 1)?;
+            *native::state::<u32>(state)? = value;
+            Ok(vec![native::output(())])
+        })
+    }
+}
+
+// Enables push evaluation via the given name.
+fn node_push(push_eval_name: &str) -> Box<dyn SerdeNode> {
+    let node = node::expr("()")
+        .unwrap()
+        .with_serde_native(PushNative)
+        .with_push_eval_name(push_eval_name);
+    Box::new(node)
+}
+
+// A simple counter node.
+//
+// Increases its `u32` state by `1` each time it receives an input of any type.
+fn node_counter() -> Box<dyn SerdeNode> {
+    let node = node::expr(r#"{ #push; let count = *state; *state += 1; count }"#)
+        .unwrap()
+        .with_serde_native(CounterNative)
+        .with_default_state_ty("u32")
+        .unwrap();
+    Box::new(node)
+}
+
+// A gate that only produces a value for even inputs.
+fn node_even() -> Box<dyn SerdeNode> {
+    let node = node::expr(r#"{ let n: u32 = #n; if n % 2 == 0 { Some(n) } else { None } }"#)
+        .unwrap()
+        .with_serde_native(EvenNative)
+        .with_optional_output();
+    Box::new(node)
+}
+
+// Stores the value received on its second input each time its first input is triggered.
+fn node_store() -> Box<dyn SerdeNode> {
+    let node = node::expr(r#"{ #push; let value: u32 = #value; *state = value; }"#)
+        .unwrap()
+        .with_serde_native(StoreNative)
+        .with_default_state_ty("u32")
+        .unwrap();
+    Box::new(node)
+}
+
+// Push evaluation from each of the given push nodes in turn, both via the compiled graph and via
+// the interpreter, each with their own state. Returns the `u32` state of the given nodes for both.
+//
+// The interpreter is given node states in the layout of the compiled graph's entry points, which
+// is determined by the instance IDs of the project graph's nodes.
+fn eval_both(
+    project: &gantz::Project,
+    pushes: &[(&str, gantz::project::NodeIndex)],
+    nodes: &[gantz::project::NodeIndex],
+) -> (Vec<u32>, Vec<u32>) {
+    let root = project.root_node_id();
+    let graph = project.load_graph(&root).expect("failed to load graph");
+    let mut compiled = gantz::GraphState::new(project, &graph).unwrap();
+    let mut interpreted = gantz::GraphState::new(project, &graph).unwrap();
+
+    let g = project
+        .ref_graph_node(&root)
+        .expect("no graph for project root node");
+    for &(name, n) in pushes {
+        let push_fn = graph.push_fn(name).expect("failed to load symbol");
+        compiled.call(&push_fn).unwrap();
+
+        let order = gantz::graph::codegen::push_eval_order(&**g, n);
+        let program =
+            Program::with_instance_ids(&**g, order, &g.graph.node_instance_ids()).unwrap();
+        interpreted
+            .with_node_states(push_fn.entry_point(), |node_states| {
+                program.eval(node_states)
+            })
+            .unwrap()
+            .unwrap();
+    }
+
+    let states = |state: &gantz::GraphState| -> Vec<u32> {
+        nodes
+            .iter()
+            .map(|&n| *state.state::<u32>(n).expect("no node state"))
+            .collect()
+    };
+    (states(&compiled), states(&interpreted))
+}
+
+// A graph where half of the nodes are only evaluated on every second push.
+//
+// See `test_graph_with_gate`.
+#[test]
+fn test_interp_gate() {
+    let mut project = gantz::TempProject::open_with_name("test_interp_gate").unwrap();
+    let push = project.add_core_node(node_push("push")).unwrap();
+    let counter = project.add_core_node(node_counter()).unwrap();
+    let even = project.add_core_node(node_even()).unwrap();
+
+    let root = project.root_node_id();
+    let mut ids = None;
+    project
+        .update_graph(&root, |g| {
+            let p = g.add_node(push);
+            let c_a = g.add_node(counter);
+            let e = g.add_node(even);
+            let c_b = g.add_node(counter);
+            let c_c = g.add_node(counter);
+            g.add_edge(p, c_a, Edge::from((0, 0)));
+            g.add_edge(c_a, e, Edge::from((0, 0)));
+            g.add_edge(e, c_b, Edge::from((0, 0)));
+            g.add_edge(c_b, c_c, Edge::from((0, 0)));
+            ids = Some((p, vec![c_a, c_b, c_c]));
+        })
+        .unwrap();
+    let (p, counters) = ids.unwrap();
+
+    let pushes = vec![("push", p); 5];
+    let (compiled, interpreted) = eval_both(&project, &pushes, &counters);
+    assert_eq!(compiled, vec![5, 3, 3]);
+    assert_eq!(interpreted, compiled);
+}
+
+// A graph with a cold edge between the counter and the store.
+//
+// See `test_graph_with_cold_edge`.
+#[test]
+fn test_interp_cold_edge() {
+    let mut project = gantz::TempProject::open_with_name("test_interp_cold_edge").unwrap();
+    let push_a = project.add_core_node(node_push("push_a")).unwrap();
+    let push_b = project.add_core_node(node_push("push_b")).unwrap();
+    let counter = project.add_core_node(node_counter()).unwrap();
+    let store = project.add_core_node(node_store()).unwrap();
+
+    let root = project.root_node_id();
+    let mut ids = None;
+    project
+        .update_graph(&root, |g| {
+            let p_a = g.add_node(push_a);
+            let p_b = g.add_node(push_b);
+            let c = g.add_node(counter);
+            let s = g.add_node(store);
+            g.add_edge(p_a, c, Edge::from((0, 0)));
+            g.add_edge(p_b, s, Edge::from((0, 0)));
+            g.add_edge(c, s, Edge::cold(0.into(), 1.into()));
+            ids = Some((p_a, p_b, c, s));
+        })
+        .unwrap();
+    let (p_a, p_b, c, s) = ids.unwrap();
+
+    let pushes = [
+        ("push_b", p_b),
+        ("push_a", p_a),
+        ("push_a", p_a),
+        ("push_b", p_b),
+        ("push_a", p_a),
+    ];
+    let (compiled, interpreted) = eval_both(&project, &pushes, &[c, s]);
+    assert_eq!(compiled, vec![3, 1]);
+    assert_eq!(interpreted, compiled);
+}
+
+// Nodes with a serializable native implementation retain it once deserialized.
+#[test]
+fn test_interp_serde_native() {
+    let json = serde_json::to_string(&node_counter()).unwrap();
+    let node: Box<dyn SerdeNode> = serde_json::from_str(&json).unwrap();
+    let native = node.node().native().expect("no native implementation");
+    let mut count = 1u32;
+    let outputs = native(&[&()], Some(&mut count)).unwrap();
+    assert_eq!(count, 2);
+    let output = outputs[0].as_ref().expect("no output").as_any();
+    assert_eq!(output.downcast_ref::<u32>(), Some(&1));
+}
@@ -0,0 +1,287 @@
+// Tests for calling into a graph's dynamic library via `LoadedGraph`.
+
+use gantz::node::{self, SerdeNode, WithPushEval, WithStateType};
+use gantz::runtime::{CallError, GraphStateError};
+use gantz::Edge;
+
+fn node_push(push_eval_name: &str) -> node::Push<node::Expr> {
+    node::expr("()")
+        .unwrap()
+        .with_push_eval_name(push_eval_name)
+}
+
+// A simple counter node.
+//
+// Increases its `u32` state by `1` each time it receives an input of any type.
+fn node_counter() -> node::State<node::Expr> {
+    node::expr(r#"{ #push; let count = *state; *state += 1; count }"#)
+        .unwrap()
+        .with_default_state_ty("u32")
+        .unwrap()
+}
+
+// A counter whose generated code fails to compile.
+fn node_broken_counter() -> node::State<node::Expr> {
+    node::expr(r#"{ #push; let step: u32 = "one"; *state += step; }"#)
+        .unwrap()
+        .with_default_state_ty("u32")
+        .unwrap()
+}
+
+// Check that the node states passed to an evaluation function are checked against its layout.
+//
+// Uses the same graph as `test_graph_with_counter`.
+#[test]
+fn test_loaded_graph_call() {
+    let mut project = gantz::TempProject::open_with_name("test_loaded_graph_call").unwrap();
+    let push = project
+        .add_core_node(Box::new(node_push("push")) as Box<dyn SerdeNode>)
+        .unwrap();
+    let counter = project
+        .add_core_node(Box::new(node_counter()) as Box<_>)
+        .unwrap();
+    let root = project.root_node_id();
+    project
+        .update_graph(&root, |g| {
+            let p = g.add_node(push);
+            let c = g.add_node(counter);
+            g.add_edge(p, c, Edge::from((0, 0)));
+        })
+        .unwrap();
+
+    // Load the compiled library.
+    let graph = project.load_graph(&root).expect("failed to load graph");
+    let push_eval_fn = graph.push_fn("push").expect("failed to load symbol");
+    assert!(graph.pull_fn("push").is_err());
+
+    // Prepare the `node_states` and execute the graph.
+    let mut count = 0u32;
+    let mut node_states = [&mut count as &mut dyn std::any::Any];
+    push_eval_fn.call(&mut node_states[..]).unwrap();
+    push_eval_fn.call(&mut node_states[..]).unwrap();
+    push_eval_fn.call(&mut node_states[..]).unwrap();
+    assert_eq!(count, 3);
+
+    // Too few node states is an error rather than undefined behaviour.
+    match push_eval_fn.call(&mut []) {
+        Err(CallError::StateCount {
+            expected, found, ..
+        }) => assert_eq!((expected, found), (1, 0)),
+        _ => panic!("expected `StateCount` error"),
+    }
+
+    // As is a node state of the wrong type.
+    let mut count = 0u64;
+    match push_eval_fn.call(&mut [&mut count]) {
+        Err(CallError::StateType { index, .. }) => assert_eq!(index, 0),
+        _ => panic!("expected `StateType` error"),
+    }
+    assert_eq!(count, 0);
+}
+
+// Check that a `GraphState` lays out node states as expected by each evaluation function.
+//
+// Uses the same graph as `test_graph_with_counters`.
+#[test]
+fn test_loaded_graph_state() {
+    let mut project = gantz::TempProject::open_with_name("test_loaded_graph_state").unwrap();
+    let push_a = project
+        .add_core_node(Box::new(node_push("push_a")) as Box<dyn SerdeNode>)
+        .unwrap();
+    let push_b = project
+        .add_core_node(Box::new(node_push("push_b")) as Box<_>)
+        .unwrap();
+    let push_c = project
+        .add_core_node(Box::new(node_push("push_c")) as Box<_>)
+        .unwrap();
+    let counter = project
+        .add_core_node(Box::new(node_counter()) as Box<_>)
+        .unwrap();
+    let root = project.root_node_id();
+    let mut counter_ids = vec![];
+    project
+        .update_graph(&root, |g| {
+            let p_a = g.add_node(push_a);
+            let p_b = g.add_node(push_b);
+            let p_c = g.add_node(push_c);
+            let c_a = g.add_node(counter);
+            let c_b = g.add_node(counter);
+            let c_c = g.add_node(counter);
+            g.add_edge(p_a, c_a, Edge::from((0, 0)));
+            g.add_edge(c_a, c_b, Edge::from((0, 0)));
+            g.add_edge(p_b, c_b, Edge::from((0, 0)));
+            g.add_edge(c_b, c_c, Edge::from((0, 0)));
+            g.add_edge(p_c, c_c, Edge::from((0, 0)));
+            counter_ids = vec![c_a, c_b, c_c];
+        })
+        .unwrap();
+
+    let graph = project.load_graph(&root).expect("failed to load graph");
+    let push_a_fn = graph.push_fn("push_a").unwrap();
+    let push_b_fn = graph.push_fn("push_b").unwrap();
+    let push_c_fn = graph.push_fn("push_c").unwrap();
+    assert_eq!(push_b_fn.entry_point().state_order, &counter_ids[1..]);
+
+    let mut state = gantz::GraphState::new(&project, &graph).unwrap();
+    state.call(&push_a_fn).unwrap();
+    state.call(&push_b_fn).unwrap();
+    state.call(&push_c_fn).unwrap();
+    let counts: Vec<u32> = counter_ids
+        .iter()
+        .map(|&n| *state.state::<u32>(n).expect("no counter state"))
+        .collect();
+    assert_eq!(counts, vec![1, 2, 3]);
+}
+
+// Check that graphs containing state types without a default may still be compiled and called,
+// in which case the host must provide the state.
+#[test]
+fn test_loaded_graph_no_default_state() {
+    let mut project =
+        gantz::TempProject::open_with_name("test_loaded_graph_no_default_state").unwrap();
+    let push = project
+        .add_core_node(Box::new(node_push("push")) as Box<dyn SerdeNode>)
+        .unwrap();
+    let elapsed = node::expr(r#"{ #push; let _ = state.elapsed(); }"#)
+        .unwrap()
+        .with_state_ty("std::time::Instant")
+        .unwrap();
+    let elapsed = project.add_core_node(Box::new(elapsed) as Box<_>).unwrap();
+    let root = project.root_node_id();
+    let mut elapsed_ix = None;
+    project
+        .update_graph(&root, |g| {
+            let p = g.add_node(push);
+            let e = g.add_node(elapsed);
+            g.add_edge(p, e, Edge::from((0, 0)));
+            elapsed_ix = Some(e);
+        })
+        .unwrap();
+    let elapsed_ix = elapsed_ix.unwrap();
+
+    // The state cannot be allocated by the graph's library.
+    let graph = project.load_graph(&root).expect("failed to load graph");
+    match gantz::GraphState::new(&project, &graph) {
+        Err(GraphStateError::NoDefaultState { node }) => assert_eq!(node, elapsed_ix),
+        _ => panic!("expected `NoDefaultState` error"),
+    }
+
+    // The host may provide the state instead.
+    let push_eval_fn = graph.push_fn("push").unwrap();
+    let mut instant = std::time::Instant::now();
+    push_eval_fn.call(&mut [&mut instant]).unwrap();
+}
+
+// Check that the state layout is keyed by instance ID rather than by evaluation order, so that an
+// edit inserting a counter upstream of another leaves the index of the existing state unchanged.
+#[test]
+fn test_loaded_graph_state_layout() {
+    let mut project = gantz::TempProject::open_with_name("test_loaded_graph_state_layout").unwrap();
+    let push = project
+        .add_core_node(Box::new(node_push("push")) as Box<dyn SerdeNode>)
+        .unwrap();
+    let counter = project
+        .add_core_node(Box::new(node_counter()) as Box<_>)
+        .unwrap();
+    let root = project.root_node_id();
+    let mut ids = None;
+    project
+        .update_graph(&root, |g| {
+            let p = g.add_node(push);
+            let c_a = g.add_node(counter);
+            let e = g.add_edge(p, c_a, Edge::from((0, 0)));
+            ids = Some((p, c_a, e));
+        })
+        .unwrap();
+    let (p, c_a, e) = ids.unwrap();
+
+    let mut graph = project.load_graph(&root).unwrap();
+    let mut state = gantz::GraphState::new(&project, &graph).unwrap();
+    let push_fn = graph.push_fn("push").unwrap();
+    state.call(&push_fn).unwrap();
+    state.call(&push_fn).unwrap();
+
+    // Insert a second counter between the push node and the first counter.
+    let mut c_b = None;
+    project
+        .update_graph(&root, |g| {
+            let c = g.add_node(counter);
+            g.remove_edge(e);
+            g.add_edge(p, c, Edge::from((0, 0)));
+            g.add_edge(c, c_a, Edge::from((0, 0)));
+            c_b = Some(c);
+        })
+        .unwrap();
+    let c_b = c_b.unwrap();
+
+    // The second counter is evaluated first, but its state follows that of the first counter.
+    graph.reload(&project, &mut state).unwrap();
+    let push_fn = graph.push_fn("push").unwrap();
+    assert_eq!(push_fn.entry_point().state_order, vec![c_a, c_b]);
+    state.call(&push_fn).unwrap();
+    assert_eq!(state.state::<u32>(c_a), Some(&3));
+    assert_eq!(state.state::<u32>(c_b), Some(&1));
+}
+
+// Check that the layout is read from the loaded library rather than from the project's graph, which
+// may have been edited since the library was built.
+#[test]
+fn test_loaded_graph_layout_from_library() {
+    let mut project =
+        gantz::TempProject::open_with_name("test_loaded_graph_layout_from_library").unwrap();
+    let push = project
+        .add_core_node(Box::new(node_push("push")) as Box<dyn SerdeNode>)
+        .unwrap();
+    let counter = project
+        .add_core_node(Box::new(node_counter()) as Box<_>)
+        .unwrap();
+    let broken = project
+        .add_core_node(Box::new(node_broken_counter()) as Box<_>)
+        .unwrap();
+    let root = project.root_node_id();
+    let mut ids = None;
+    project
+        .update_graph(&root, |g| {
+            let p = g.add_node(push);
+            let c_a = g.add_node(counter);
+            g.add_edge(p, c_a, Edge::from((0, 0)));
+            ids = Some((p, c_a));
+        })
+        .unwrap();
+    let (p, c_a) = ids.unwrap();
+
+    // Add a stateful node that fails to compile, leaving the previous library in place.
+    let mut c_b = None;
+    let result = project.update_graph(&root, |g| {
+        let c = g.add_node(broken);
+        g.add_edge(p, c, Edge::from((0, 0)));
+        c_b = Some(c);
+    });
+    assert!(result.is_err());
+    let c_b = c_b.unwrap();
+
+    // The loaded library only knows of the first counter.
+    let mut graph = project.load_graph(&root).unwrap();
+    assert!(!graph.layout().nodes.contains_key(&c_b));
+    let mut state = gantz::GraphState::new(&project, &graph).unwrap();
+    assert_eq!(state.nodes().collect::<Vec<_>>(), vec![c_a]);
+    let push_fn = graph.push_fn("push").unwrap();
+    assert_eq!(push_fn.entry_point().state_order, vec![c_a]);
+    state.call(&push_fn).unwrap();
+    assert_eq!(state.state::<u32>(c_a), Some(&1));
+
+    // Once fixed and reloaded, handles to the previous library are rejected.
+    project
+        .update_graph(&root, |g| {
+            g.remove_node(c_b);
+        })
+        .unwrap();
+    graph.reload(&project, &mut state).unwrap();
+    match state.call(&push_fn) {
+        Err(CallError::GraphMismatch) => (),
+        _ => panic!("expected `GraphMismatch` error"),
+    }
+    let push_fn = graph.push_fn("push").unwrap();
+    state.call(&push_fn).unwrap();
+    assert_eq!(state.state::<u32>(c_a), Some(&2));
+}
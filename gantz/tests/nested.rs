@@ -1,4 +1,5 @@
-use gantz::node::{self, SerdeNode, WithPushEval};
+use gantz::node::{self, SerdeNode, WithPushEval, WithStateType};
+use gantz::runtime::CallError;
 use gantz::Edge;
 
 fn node_push() -> node::Push<node::Expr> {
@@ -17,6 +18,18 @@ fn node_assert_eq() -> node::Expr {
     node::expr("assert_eq!(#l, #r)").unwrap()
 }
 
+fn node_double() -> node::Expr {
+    node::expr("#x * 2").unwrap()
+}
+
+// Stores the `i32` received on its input.
+fn node_store() -> node::State<node::Expr> {
+    node::expr(r#"{ let value: i32 = #value; *state = value; }"#)
+        .unwrap()
+        .with_default_state_ty("i32")
+        .unwrap()
+}
+
 // A simple test for nested graph support.
 //
 // This is the core method of abstraction provided by gantz, so it better work!
@@ -78,20 +91,27 @@ fn test_graph_nested_stateless() {
     let outlet = gantz::graph::Outlet::parse("i32").unwrap();
 
     // Add the nodes to the project.
-    let push = project.add_core_node(Box::new(push) as Box<dyn SerdeNode>);
-    let six = project.add_core_node(Box::new(six) as Box<_>);
-    let seven = project.add_core_node(Box::new(seven) as Box<_>);
-    let forty_two = project.add_core_node(Box::new(forty_two) as Box<_>);
-    let mul = project.add_core_node(Box::new(mul) as Box<_>);
-    let assert_eq = project.add_core_node(Box::new(assert_eq) as Box<_>);
-    let inlet = project.add_core_node(Box::new(inlet) as _);
-    let outlet = project.add_core_node(Box::new(outlet) as _);
+    let push = project
+        .add_core_node(Box::new(push) as Box<dyn SerdeNode>)
+        .unwrap();
+    let six = project.add_core_node(Box::new(six) as Box<_>).unwrap();
+    let seven = project.add_core_node(Box::new(seven) as Box<_>).unwrap();
+    let forty_two = project
+        .add_core_node(Box::new(forty_two) as Box<_>)
+        .unwrap();
+    let mul = project.add_core_node(Box::new(mul) as Box<_>).unwrap();
+    let assert_eq = project
+        .add_core_node(Box::new(assert_eq) as Box<_>)
+        .unwrap();
+    let inlet = project.add_core_node(Box::new(inlet) as _).unwrap();
+    let outlet = project.add_core_node(Box::new(outlet) as _).unwrap();
     // We'll use the project root graph as GRAPH B, but we still need to add a node for GRAPH A.
     let graph_a = project
         .add_graph_node(Default::default(), "graph_a")
         .unwrap();
 
     // Compose the inner GRAPH A first.
+    let mut outlet_ix = None;
     project
         .update_graph(&graph_a, |g| {
             let inlet_a = g.add_inlet(inlet);
@@ -101,11 +121,13 @@ fn test_graph_nested_stateless() {
             g.add_edge(inlet_a, mul, Edge::from((0, 0)));
             g.add_edge(inlet_b, mul, Edge::from((0, 1)));
             g.add_edge(mul, outlet, Edge::from((0, 0)));
+            outlet_ix = Some(outlet);
         })
         .unwrap();
 
     // Now compose the project root graph.
     let root = project.root_node_id();
+    let mut graph_a_ix = None;
     project
         .update_graph(&root, |g| {
             let push = g.add_node(push);
@@ -121,52 +143,228 @@ fn test_graph_nested_stateless() {
             g.add_edge(seven, graph_a, Edge::from((0, 1)));
             g.add_edge(graph_a, assert_eq, Edge::from((0, 0)));
             g.add_edge(forty_two, assert_eq, Edge::from((0, 1)));
+            graph_a_ix = Some(graph_a);
         })
         .unwrap();
 
-    // Retrieve the path to the compiled library.
-    let root_dylib_path = project
-        .graph_node_dylib(&root)
-        .unwrap()
-        .expect("no dylib or node");
-    let graph_a_dylib_path = project
-        .graph_node_dylib(&graph_a)
-        .unwrap()
-        .expect("no dylib or node");
-    let root_lib = libloading::Library::new(&root_dylib_path).expect("failed to load root library");
-    let graph_a_lib =
-        libloading::Library::new(&graph_a_dylib_path).expect("failed to load graph a library");
-    let symbol_name = "push".as_bytes();
-    unsafe {
-        let push_eval_fn: libloading::Symbol<fn(&mut [&mut dyn std::any::Any])> =
-            root_lib.get(symbol_name).expect("failed to load symbol");
-
-        let graph_a_full_eval: libloading::Symbol<fn(&mut [&mut dyn std::any::Any])> = graph_a_lib
-            .get(gantz::graph::FULL_EVAL_FN_NAME.as_bytes())
-            .expect("failed to load symbol");
-        let mut graph_a_full_eval: libloading::Symbol<'static, fn(&mut [&mut dyn std::any::Any])> =
-            std::mem::transmute(graph_a_full_eval);
-
-        let mut inlet0 = 0i32;
-        let mut inlet1 = 0i32;
-        let mut outlet0 = 0i32;
-
-        let graph_a_node_state = &mut [
-            &mut inlet0 as &mut dyn std::any::Any,
-            &mut inlet1 as _,
-            &mut outlet0 as _,
-        ][..];
-        let mut graph_a: (&'static mut [&'static mut dyn std::any::Any], *mut ()) = (
-            std::mem::transmute(graph_a_node_state),
-            &mut graph_a_full_eval as *mut _ as *mut _,
-            //std::mem::transmute(&mut graph_a_full_eval as &mut dyn std::any::Any),
-        );
-
-        let node_states = &mut [&mut graph_a as &mut dyn std::any::Any];
-
-        // Execute the gantz graph.
-        {
-            push_eval_fn(node_states);
-        }
+    // Load the compiled library and allocate the node states, including those of GRAPH A.
+    let graph = project.load_graph(&root).expect("failed to load graph");
+    let push_eval_fn = graph.push_fn("push").expect("failed to load symbol");
+    let mut state = gantz::GraphState::new(&project, &graph).unwrap();
+
+    // Execute the gantz graph.
+    state.call(&push_eval_fn).unwrap();
+
+    // The state of GRAPH A can only be constructed by the `GraphState`.
+    match push_eval_fn.call(&mut []) {
+        Err(CallError::NestedGraphState { .. }) => (),
+        _ => panic!("expected `NestedGraphState` error"),
     }
+
+    // The outlet of GRAPH A should hold the result of the multiplication.
+    let graph_a_state = state
+        .graph_state(graph_a_ix.unwrap())
+        .expect("no state for GRAPH A");
+    assert_eq!(graph_a_state.state::<i32>(outlet_ix.unwrap()), Some(&42));
+}
+
+// Check that graphs containing a nested graph are regenerated and recompiled in dependency order
+// when the nested graph changes.
+//
+// GRAPH A doubles its input, GRAPH B wraps GRAPH A and the root graph pushes `21` through GRAPH B
+// into a `store` node. A second outlet is then added to GRAPH A, changing the layout of its state.
+//
+//    --------
+//    | push | // push_eval
+//    -+------
+//     |
+//    -+----
+//    | 21 |
+//    -+----
+//     |
+//    -+---------
+//    | GRAPH B |
+//    -+---------
+//     |
+//    -+-------
+//    | store |
+//    ---------
+#[test]
+fn test_graph_nested_dependents_recompiled() {
+    // Create a temp project.
+    let mut project =
+        gantz::TempProject::open_with_name("test_graph_nested_dependents_recompiled").unwrap();
+
+    // Add the nodes to the project.
+    let push = project
+        .add_core_node(Box::new(node_push()) as Box<dyn SerdeNode>)
+        .unwrap();
+    let twenty_one = project
+        .add_core_node(Box::new(node_int(21)) as Box<_>)
+        .unwrap();
+    let double = project
+        .add_core_node(Box::new(node_double()) as Box<_>)
+        .unwrap();
+    let store = project
+        .add_core_node(Box::new(node_store()) as Box<_>)
+        .unwrap();
+    let inlet = gantz::graph::Inlet::parse("i32").unwrap();
+    let outlet = gantz::graph::Outlet::parse("i32").unwrap();
+    let inlet = project.add_core_node(Box::new(inlet) as _).unwrap();
+    let outlet = project.add_core_node(Box::new(outlet) as _).unwrap();
+    let graph_a = project
+        .add_graph_node(Default::default(), "graph_a")
+        .unwrap();
+    let graph_b = project
+        .add_graph_node(Default::default(), "graph_b")
+        .unwrap();
+
+    // Compose GRAPH A.
+    let mut double_ix = None;
+    project
+        .update_graph(&graph_a, |g| {
+            let i = g.add_inlet(inlet);
+            let d = g.add_node(double);
+            let o = g.add_outlet(outlet);
+            g.add_edge(i, d, Edge::from((0, 0)));
+            g.add_edge(d, o, Edge::from((0, 0)));
+            double_ix = Some(d);
+        })
+        .unwrap();
+    let double_ix = double_ix.unwrap();
+
+    // Compose GRAPH B, wrapping GRAPH A.
+    let mut graph_a_ix = None;
+    project
+        .update_graph(&graph_b, |g| {
+            let i = g.add_inlet(inlet);
+            let a = g.add_node(graph_a);
+            let o = g.add_outlet(outlet);
+            g.add_edge(i, a, Edge::from((0, 0)));
+            g.add_edge(a, o, Edge::from((0, 0)));
+            graph_a_ix = Some(a);
+        })
+        .unwrap();
+    let graph_a_ix = graph_a_ix.unwrap();
+
+    // Compose the root graph.
+    let root = project.root_node_id();
+    let mut ids = None;
+    project
+        .update_graph(&root, |g| {
+            let p = g.add_node(push);
+            let n = g.add_node(twenty_one);
+            let b = g.add_node(graph_b);
+            let s = g.add_node(store);
+            g.add_edge(p, n, Edge::from((0, 0)));
+            g.add_edge(n, b, Edge::from((0, 0)));
+            g.add_edge(b, s, Edge::from((0, 0)));
+            ids = Some((b, s));
+        })
+        .unwrap();
+    let (graph_b_ix, store_ix) = ids.unwrap();
+    assert_eq!(project.dependent_graphs(&graph_a), vec![graph_b, root]);
+    assert_eq!(project.dependent_graphs(&graph_b), vec![root]);
+
+    // Load and evaluate the root graph.
+    let mut graph = project.load_graph(&root).expect("failed to load graph");
+    let mut state = gantz::GraphState::new(&project, &graph).unwrap();
+    let push_fn = graph.push_fn("push").unwrap();
+    state.call(&push_fn).unwrap();
+    assert_eq!(state.state::<i32>(store_ix), Some(&42));
+
+    // Add a second outlet to GRAPH A. Only GRAPH A is updated directly.
+    let mut outlet_b = None;
+    project
+        .update_graph(&graph_a, |g| {
+            let o = g.add_outlet(outlet);
+            g.add_edge(double_ix, o, Edge::from((0, 0)));
+            outlet_b = Some(o);
+        })
+        .unwrap();
+    let outlet_b = outlet_b.unwrap();
+
+    // The root graph was rebuilt against the new layout of GRAPH A.
+    drop(push_fn);
+    graph.reload(&project, &mut state).unwrap();
+    let push_fn = graph.push_fn("push").unwrap();
+    state.call(&push_fn).unwrap();
+    assert_eq!(state.state::<i32>(store_ix), Some(&42));
+    let graph_a_state = state
+        .graph_state(graph_b_ix)
+        .and_then(|b| b.graph_state(graph_a_ix))
+        .expect("no state for GRAPH A");
+    assert_eq!(graph_a_state.state::<i32>(outlet_b), Some(&42));
+}
+
+// Check that removing an outlet from a nested graph prunes the edges from that outlet within the
+// graphs that contain it.
+//
+// GRAPH A doubles its input and has two outlets, the second of which is connected to a `store` node
+// within the root graph. The second outlet is then removed from GRAPH A.
+#[test]
+fn test_graph_nested_dependents_pruned() {
+    let mut project =
+        gantz::TempProject::open_with_name("test_graph_nested_dependents_pruned").unwrap();
+
+    let push = project
+        .add_core_node(Box::new(node_push()) as Box<dyn SerdeNode>)
+        .unwrap();
+    let twenty_one = project
+        .add_core_node(Box::new(node_int(21)) as Box<_>)
+        .unwrap();
+    let double = project
+        .add_core_node(Box::new(node_double()) as Box<_>)
+        .unwrap();
+    let store = project
+        .add_core_node(Box::new(node_store()) as Box<_>)
+        .unwrap();
+    let inlet = gantz::graph::Inlet::parse("i32").unwrap();
+    let outlet = gantz::graph::Outlet::parse("i32").unwrap();
+    let inlet = project.add_core_node(Box::new(inlet) as _).unwrap();
+    let outlet = project.add_core_node(Box::new(outlet) as _).unwrap();
+    let graph_a = project
+        .add_graph_node(Default::default(), "graph_a")
+        .unwrap();
+
+    // Compose GRAPH A with two outlets.
+    project
+        .update_graph(&graph_a, |g| {
+            let i = g.add_inlet(inlet);
+            let d = g.add_node(double);
+            let o_a = g.add_outlet(outlet);
+            let o_b = g.add_outlet(outlet);
+            g.add_edge(i, d, Edge::from((0, 0)));
+            g.add_edge(d, o_a, Edge::from((0, 0)));
+            g.add_edge(d, o_b, Edge::from((0, 0)));
+        })
+        .unwrap();
+
+    // Compose the root graph, storing the value of GRAPH A's second outlet.
+    let root = project.root_node_id();
+    project
+        .update_graph(&root, |g| {
+            let p = g.add_node(push);
+            let n = g.add_node(twenty_one);
+            let a = g.add_node(graph_a);
+            let s = g.add_node(store);
+            g.add_edge(p, n, Edge::from((0, 0)));
+            g.add_edge(n, a, Edge::from((0, 0)));
+            g.add_edge(a, s, Edge::from((1, 0)));
+        })
+        .unwrap();
+
+    // Remove the second outlet. The edge to the store within the root graph no longer refers to
+    // an output of GRAPH A.
+    let pruned = project
+        .update_graph(&graph_a, |g| {
+            let o_b = g.outlets.pop().unwrap();
+            g.remove_node(o_b);
+        })
+        .unwrap();
+    assert!(pruned[&graph_a].is_empty());
+    assert_eq!(pruned[&root].len(), 1);
+    assert_eq!(pruned[&root][0].edge, Edge::from((1, 0)));
+    let root_graph = project.graph_node(&root).unwrap();
+    assert_eq!(root_graph.graph.edge_count(), 2);
 }
@@ -0,0 +1,104 @@
+// Testing the generation of nodes from existing functions via the `#[gantz::node]` attribute.
+
+use gantz::node::{CrateDep, Evaluator, SerdeNode};
+use gantz::{Edge, Node};
+
+pub struct Vec2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+#[gantz::node]
+pub fn mul_f32(a: f32, b: f32) -> f32 {
+    a * b
+}
+
+#[gantz::node(name = "SplitVec2", dep = r#"my_geom = "0.1""#)]
+pub fn split(v: crate::Vec2) -> (f32, f32) {
+    (v.x, v.y)
+}
+
+// Unwrap the function from the given evaluator.
+fn fn_item(evaluator: Evaluator) -> syn::ItemFn {
+    match evaluator {
+        Evaluator::Fn { fn_item, .. } => fn_item,
+        Evaluator::Expr { .. } => panic!("expected a fn evaluator"),
+    }
+}
+
+// The generated evaluator has the same signature as the function and calls into it via its path.
+#[test]
+fn test_node_fn_evaluator() {
+    assert_eq!(mul_f32(2.0, 3.0), 6.0);
+    assert_eq!(split(Vec2 { x: 1.0, y: 2.0 }), (1.0, 2.0));
+
+    let node = MulF32;
+    let expected: syn::ItemFn = syn::parse_quote! {
+        fn mul_f32(a: f32, b: f32) -> f32 {
+            node_fn::mul_f32(a, b)
+        }
+    };
+    assert_eq!(fn_item(gantz::Node::evaluator(&node)), expected);
+    let f32_ty: syn::Type = syn::parse_quote! { f32 };
+    let io_types = gantz::Node::io_types(&node);
+    assert_eq!(
+        io_types.inputs,
+        vec![Some(f32_ty.clone()), Some(f32_ty.clone())]
+    );
+    assert_eq!(io_types.outputs, vec![Some(f32_ty)]);
+
+    // Paths relative to the defining crate are named via the given dependency.
+    let node = SplitVec2;
+    let expected: syn::ItemFn = syn::parse_quote! {
+        fn split(v: my_geom::Vec2) -> (f32, f32) {
+            my_geom::split(v)
+        }
+    };
+    assert_eq!(fn_item(gantz::Node::evaluator(&node)), expected);
+    assert_eq!(gantz::Node::evaluator(&node).n_outputs(), 2);
+}
+
+// The node depends on the defining crate, unless a dependency is given.
+#[test]
+fn test_node_fn_crate_deps() {
+    let deps = gantz::Node::crate_deps(&MulF32);
+    assert_eq!(deps.len(), 1);
+    assert_eq!(deps[0].name, env!("CARGO_PKG_NAME"));
+    assert!(deps[0].source.contains(env!("CARGO_MANIFEST_DIR")));
+
+    let deps = gantz::Node::crate_deps(&SplitVec2);
+    let expected = CrateDep {
+        name: "my_geom".to_string(),
+        source: r#""0.1""#.to_string(),
+    };
+    assert_eq!(deps, vec![expected]);
+}
+
+// Generated nodes may be serialized and deserialized as `SerdeNode` trait objects.
+#[test]
+fn test_node_fn_serde() {
+    let node = Box::new(MulF32) as Box<dyn SerdeNode>;
+    let json = serde_json::to_string(&node).unwrap();
+    let node: Box<dyn SerdeNode> = serde_json::from_str(&json).unwrap();
+    assert_eq!(node.node().evaluator().n_inputs(), 2);
+}
+
+// Each instance of a generated node shares the same evaluator function, which is only included
+// within the generated code once.
+#[test]
+fn test_node_fn_multiple_instances() {
+    let mut g = petgraph::stable_graph::StableGraph::<Box<dyn Node>, Edge>::default();
+    let a = g.add_node(Box::new(MulF32));
+    let b = g.add_node(Box::new(MulF32));
+    g.add_edge(a, b, Edge::from((0, 0)));
+    let file = gantz::graph::codegen::file(&g, &[], &[]);
+    let mul_f32_fns = file
+        .items
+        .iter()
+        .filter(|item| match item {
+            syn::Item::Fn(item_fn) => item_fn.sig.ident == "mul_f32",
+            _ => false,
+        })
+        .count();
+    assert_eq!(mul_f32_fns, 1);
+}
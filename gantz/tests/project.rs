@@ -0,0 +1,461 @@
+// Tests for the project API.
+
+use gantz::node::{self, CrateDep, SerdeNode, WithPushEval, WithStateType};
+use gantz::project::verify::Inconsistency;
+use gantz::project::{ReferencePolicy, RemoveNodeError, ReplaceCoreNodeError};
+use gantz::Edge;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+// An expression node that depends on the given crate.
+#[derive(Deserialize, Serialize)]
+struct WithDep {
+    expr: node::Expr,
+    dep: CrateDep,
+}
+
+impl gantz::Node for WithDep {
+    fn evaluator(&self) -> node::Evaluator {
+        gantz::Node::evaluator(&self.expr)
+    }
+
+    fn crate_deps(&self) -> Vec<CrateDep> {
+        vec![self.dep.clone()]
+    }
+}
+
+#[typetag::serde]
+impl SerdeNode for WithDep {
+    fn node(&self) -> &dyn gantz::Node {
+        self
+    }
+}
+
+fn node_push() -> node::Push<node::Expr> {
+    node::expr("()").unwrap().with_push_eval_name("push")
+}
+
+fn node_int(i: i32) -> node::Expr {
+    node::expr(&format!("{{ #push; {} }}", i)).unwrap()
+}
+
+fn node_counter() -> node::State<node::Expr> {
+    node::expr(r#"{ #push; let count = *state; *state += 1; count }"#)
+        .unwrap()
+        .with_default_state_ty("u32")
+        .unwrap()
+}
+
+// Check that a saved project can be re-opened with all nodes and graph edits intact.
+#[test]
+fn test_project_save_and_reopen() {
+    // Create a temp project.
+    let mut project = gantz::TempProject::open_with_name("test_project_save_and_reopen").unwrap();
+
+    // Add some nodes and compose the graph.
+    let push = project
+        .add_core_node(Box::new(node_push()) as Box<dyn SerdeNode>)
+        .unwrap();
+    let one = project
+        .add_core_node(Box::new(node_int(1)) as Box<_>)
+        .unwrap();
+    let root = project.root_node_id();
+    project
+        .update_graph(&root, |g| {
+            let push = g.add_node(push);
+            let one = g.add_node(one);
+            g.add_edge(push, one, Edge::from((0, 0)));
+        })
+        .unwrap();
+    project.save().unwrap();
+
+    // Re-open the project from the same directory.
+    let reopened = gantz::Project::open(project.dir().to_path_buf()).unwrap();
+    assert_eq!(reopened.nodes().len(), project.nodes().len());
+    let g = reopened.graph_node(&root).expect("no root graph node");
+    assert_eq!(g.graph.node_count(), 2);
+    assert_eq!(g.graph.edge_count(), 1);
+}
+
+// Check that autosave writes the collection after each mutation without an explicit `save`.
+#[test]
+fn test_project_autosave() {
+    // Create a temp project.
+    let mut project = gantz::TempProject::open_with_name("test_project_autosave").unwrap();
+    assert!(!project.autosave());
+    project.set_autosave(true);
+
+    // Add a node. This should be persisted immediately.
+    let one = project
+        .add_core_node(Box::new(node_int(1)) as Box<dyn SerdeNode>)
+        .unwrap();
+
+    let reopened = gantz::Project::open(project.dir().to_path_buf()).unwrap();
+    assert!(reopened.core_node(&one).is_some());
+}
+
+// Check that a corrupt node collection is reported on open, and that opening with recovery keeps
+// a backup of the original and reports the nodes that failed to deserialize.
+#[test]
+fn test_project_open_corrupt_node_collection() {
+    // Create a temp project with a couple of core nodes.
+    let mut project =
+        gantz::TempProject::open_with_name("test_project_open_corrupt_node_collection").unwrap();
+    let one = project
+        .add_core_node(Box::new(node_int(1)) as Box<dyn SerdeNode>)
+        .unwrap();
+    let two = project
+        .add_core_node(Box::new(node_int(2)) as Box<_>)
+        .unwrap();
+    project.save().unwrap();
+
+    // Corrupt the entry for the `two` node by giving it an unknown node type.
+    let json_path = gantz::project::node_collection_json_path(project.dir());
+    let bytes = std::fs::read(&json_path).unwrap();
+    let mut json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    let key = format!("{}", serde_json::to_value(&two).unwrap());
+    json["map"][&key]["Core"]["type"] = "NoSuchNodeType".into();
+    std::fs::write(&json_path, serde_json::to_vec(&json).unwrap()).unwrap();
+
+    // A regular open should refuse to touch the corrupt collection.
+    match gantz::Project::open(project.dir().to_path_buf()) {
+        Err(gantz::project::ProjectOpenError::NodeCollection { .. }) => (),
+        Err(err) => panic!("unexpected error: {}", err),
+        Ok(_) => panic!("expected corrupt node collection error"),
+    }
+
+    // Opening with recovery should drop only the corrupt node.
+    let (recovered, report) =
+        gantz::Project::open_with_recovery(project.dir().to_path_buf()).unwrap();
+    let report = report.expect("expected a recovery report");
+    assert!(report.backup_path.exists());
+    assert!(report.repair.repaired.is_empty());
+    assert_eq!(report.failed_nodes.len(), 1);
+    assert_eq!(report.failed_nodes[0].key, key);
+    assert!(recovered.core_node(&one).is_some());
+    assert!(recovered.core_node(&two).is_none());
+
+    // The recovered collection should have been saved, so a regular open now succeeds.
+    gantz::Project::open(project.dir().to_path_buf()).unwrap();
+}
+
+// Check that a graph referencing a node that no longer exists is detected by verification and
+// removed by the repair pass.
+#[test]
+fn test_project_verify_dangling_node_id() {
+    // Create a temp project with a single node in the root graph.
+    let mut project =
+        gantz::TempProject::open_with_name("test_project_verify_dangling_node_id").unwrap();
+    assert!(project.verify().unwrap().is_consistent());
+    let one = project
+        .add_core_node(Box::new(node_int(1)) as Box<dyn SerdeNode>)
+        .unwrap();
+    let root = project.root_node_id();
+    project
+        .update_graph(&root, |g| {
+            g.add_node(one);
+        })
+        .unwrap();
+    project.save().unwrap();
+
+    // Remove the `one` node from the saved collection.
+    let json_path = gantz::project::node_collection_json_path(project.dir());
+    let bytes = std::fs::read(&json_path).unwrap();
+    let mut json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    let key = format!("{}", serde_json::to_value(&one).unwrap());
+    json["map"].as_object_mut().unwrap().remove(&key);
+    std::fs::write(&json_path, serde_json::to_vec(&json).unwrap()).unwrap();
+
+    // A regular open should report the dangling reference.
+    match gantz::Project::open(project.dir().to_path_buf()) {
+        Err(gantz::project::ProjectOpenError::Inconsistent { report }) => {
+            assert_eq!(report.inconsistencies.len(), 1);
+            match report.inconsistencies[0] {
+                Inconsistency::DanglingNodeId { graph, id, .. } => {
+                    assert_eq!(graph, root);
+                    assert_eq!(id, one);
+                }
+                ref other => panic!("unexpected inconsistency: {}", other),
+            }
+        }
+        Err(err) => panic!("unexpected error: {}", err),
+        Ok(_) => panic!("expected inconsistent node collection error"),
+    }
+
+    // Recovery should remove the dangling node from the root graph.
+    let (recovered, report) =
+        gantz::Project::open_with_recovery(project.dir().to_path_buf()).unwrap();
+    let report = report.expect("expected a recovery report");
+    assert!(report.failed_nodes.is_empty());
+    assert_eq!(report.repair.repaired.len(), 1);
+    assert!(report.repair.unrepaired.is_empty());
+    let g = recovered.graph_node(&root).expect("no root graph node");
+    assert_eq!(g.graph.node_count(), 0);
+    assert!(recovered.verify().unwrap().is_consistent());
+}
+
+// Check node removal with both reference policies.
+#[test]
+fn test_project_remove_node() {
+    // Create a temp project with a single node in the root graph.
+    let mut project = gantz::TempProject::open_with_name("test_project_remove_node").unwrap();
+    let one = project
+        .add_core_node(Box::new(node_int(1)) as Box<dyn SerdeNode>)
+        .unwrap();
+    let root = project.root_node_id();
+    project
+        .update_graph(&root, |g| {
+            g.add_node(one);
+        })
+        .unwrap();
+
+    // The root node can never be removed.
+    match project.remove_node(&root, ReferencePolicy::Cascade) {
+        Err(RemoveNodeError::RootNode) => (),
+        _ => panic!("expected root node removal to be refused"),
+    }
+
+    // The `one` node is referenced by the root graph.
+    match project.remove_node(&one, ReferencePolicy::Refuse) {
+        Err(RemoveNodeError::Referenced { references }) => {
+            assert_eq!(references.keys().collect::<Vec<_>>(), vec![&root]);
+        }
+        _ => panic!("expected referenced node removal to be refused"),
+    }
+
+    // Cascading removes the node from the root graph too.
+    let references = project.remove_node(&one, ReferencePolicy::Cascade).unwrap();
+    assert_eq!(references[&root].len(), 1);
+    assert!(project.core_node(&one).is_none());
+    let g = project.graph_node(&root).expect("no root graph node");
+    assert_eq!(g.graph.node_count(), 0);
+
+    // Removed IDs are never reused.
+    let two = project
+        .add_core_node(Box::new(node_int(2)) as Box<dyn SerdeNode>)
+        .unwrap();
+    assert_ne!(one, two);
+}
+
+// Check that renaming a graph node renames its crate within the workspace.
+#[test]
+fn test_project_rename_graph_node() {
+    let mut project = gantz::TempProject::open_with_name("test_project_rename_graph_node").unwrap();
+    let graph_a = project
+        .add_graph_node(Default::default(), "graph_a")
+        .unwrap();
+    let references = project
+        .rename_graph_node(&graph_a, "graph_b", ReferencePolicy::Refuse)
+        .unwrap();
+    assert!(references.is_empty());
+    let ws_dir = project.workspace_dir();
+    assert!(!ws_dir
+        .join(gantz::project::node_crate_name("graph_a"))
+        .exists());
+    assert!(ws_dir
+        .join(gantz::project::node_crate_name("graph_b"))
+        .exists());
+    let g = project.graph_node(&graph_a).expect("no graph node");
+    assert_eq!(
+        format!("{}", g.package_id.name()),
+        gantz::project::node_crate_name("graph_b"),
+    );
+    assert!(project.graph_node_dylib(&graph_a).unwrap().is_some());
+    assert!(project.verify().unwrap().is_consistent());
+
+    // Removing the graph node removes its crate.
+    project
+        .remove_node(&graph_a, ReferencePolicy::Refuse)
+        .unwrap();
+    assert!(!ws_dir
+        .join(gantz::project::node_crate_name("graph_b"))
+        .exists());
+    assert!(project.verify().unwrap().is_consistent());
+}
+
+// Check that a core node may be replaced in place.
+#[test]
+fn test_project_replace_core_node() {
+    let mut project = gantz::TempProject::open_with_name("test_project_replace_core_node").unwrap();
+    let int = project
+        .add_core_node(Box::new(node_int(1)) as Box<dyn SerdeNode>)
+        .unwrap();
+    let root = project.root_node_id();
+    project
+        .update_graph(&root, |g| {
+            g.add_node(int);
+        })
+        .unwrap();
+    let two = Box::new(node_int(2)) as Box<dyn SerdeNode>;
+    match project.replace_core_node(&int, two, ReferencePolicy::Refuse) {
+        Err(ReplaceCoreNodeError::Referenced { .. }) => (),
+        _ => panic!("expected referenced node replacement to be refused"),
+    }
+    let two = Box::new(node_int(2)) as Box<dyn SerdeNode>;
+    let references = project
+        .replace_core_node(&int, two, ReferencePolicy::Cascade)
+        .unwrap();
+    assert_eq!(references[&root].len(), 1);
+    let node = project.core_node(&int).expect("no core node");
+    let expected = Box::new(node_int(2)) as Box<dyn SerdeNode>;
+    assert_eq!(
+        serde_json::to_string(node).unwrap(),
+        serde_json::to_string(&expected).unwrap(),
+    );
+}
+
+// Create a library crate named `gantz_test_dep` with the given version within the given directory.
+fn dep_crate(dir: &Path, version: &str) -> CrateDep {
+    fs::create_dir_all(dir.join("src")).unwrap();
+    let manifest = format!(
+        "[package]\nname = \"gantz_test_dep\"\nversion = {:?}\nedition = \"2018\"\n",
+        version,
+    );
+    fs::write(dir.join("Cargo.toml"), manifest).unwrap();
+    fs::write(dir.join("src").join("lib.rs"), "").unwrap();
+    CrateDep {
+        name: "gantz_test_dep".to_string(),
+        source: format!("{{ path = {:?} }}", dir.display().to_string()),
+    }
+}
+
+// The number of workspace member manifests that contain the given string.
+fn manifests_containing(ws_dir: &Path, s: &str) -> usize {
+    fs::read_dir(ws_dir)
+        .unwrap()
+        .filter_map(|entry| fs::read_to_string(entry.unwrap().path().join("Cargo.toml")).ok())
+        .filter(|manifest| manifest.contains(s))
+        .count()
+}
+
+// Check that the dependencies of graphs follow the replacement and removal of their nodes.
+#[test]
+fn test_project_replace_core_node_deps() {
+    let mut project =
+        gantz::TempProject::open_with_name("test_project_replace_core_node_deps").unwrap();
+    let deps_dir = std::env::temp_dir().join("test_project_replace_core_node_deps_crates");
+    let _ = fs::remove_dir_all(&deps_dir);
+    let v1 = dep_crate(&deps_dir.join("v1"), "0.1.0");
+    let v2 = dep_crate(&deps_dir.join("v2"), "0.2.0");
+    let with_dep = |dep: &CrateDep| {
+        let expr = node::expr("()").unwrap();
+        let dep = dep.clone();
+        Box::new(WithDep { expr, dep }) as Box<dyn SerdeNode>
+    };
+
+    let node = project.add_core_node(with_dep(&v1)).unwrap();
+    let root = project.root_node_id();
+    project
+        .update_graph(&root, |g| {
+            g.add_node(node);
+        })
+        .unwrap();
+    let ws_dir = project.workspace_dir();
+    assert_eq!(manifests_containing(&ws_dir, "v1"), 1);
+
+    // Replacing the node with one requiring another version of the crate updates the dependency.
+    project
+        .replace_core_node(&node, with_dep(&v2), ReferencePolicy::Cascade)
+        .unwrap();
+    assert_eq!(manifests_containing(&ws_dir, "v1"), 0);
+    assert_eq!(manifests_containing(&ws_dir, "v2"), 1);
+
+    // Removing the node drops the dependency.
+    project
+        .remove_node(&node, ReferencePolicy::Cascade)
+        .unwrap();
+    assert_eq!(manifests_containing(&ws_dir, "gantz_test_dep"), 0);
+}
+
+// Check that node instance IDs persist across edits and are never reused.
+#[test]
+fn test_project_instance_ids() {
+    let mut project = gantz::TempProject::open_with_name("test_project_instance_ids").unwrap();
+    let one = project
+        .add_core_node(Box::new(node_int(1)) as Box<dyn SerdeNode>)
+        .unwrap();
+    let root = project.root_node_id();
+    let mut a = None;
+    let mut b = None;
+    project
+        .update_graph(&root, |g| {
+            a = Some(g.add_node(one));
+            b = Some(g.add_node(one));
+        })
+        .unwrap();
+    let (a, b) = (a.unwrap(), b.unwrap());
+    let g = project.graph_node(&root).expect("no root graph node");
+    let a_instance = g.instance_id(a).expect("no instance ID");
+    let b_instance = g.instance_id(b).expect("no instance ID");
+    assert_ne!(a_instance, b_instance);
+
+    // Replace `a` with a new instance. The index is reused but the instance ID is not.
+    let mut c = None;
+    project
+        .update_graph(&root, |g| {
+            g.remove_node(a);
+            c = Some(g.add_node(one));
+        })
+        .unwrap();
+    let c = c.unwrap();
+    let g = project.graph_node(&root).expect("no root graph node");
+    assert_eq!(a, c);
+    assert_eq!(g.instance_id(b), Some(b_instance));
+    let c_instance = g.instance_id(c).expect("no instance ID");
+    assert!(c_instance != a_instance && c_instance != b_instance);
+
+    // Instance IDs survive a save and reopen.
+    project.save().unwrap();
+    let reopened = gantz::Project::open(project.dir().to_path_buf()).unwrap();
+    let g = reopened.graph_node(&root).expect("no root graph node");
+    assert_eq!(g.instance_id(b), Some(b_instance));
+    assert_eq!(g.instance_id(c), Some(c_instance));
+}
+
+// Check that a graph still compiles and evaluates once a node has been removed from it.
+//
+// Removing a node leaves a gap in the graph's node indices, so nodes following the gap must be
+// looked up by their ID rather than by their position within the graph.
+#[test]
+fn test_project_compile_after_node_removal() {
+    let mut project =
+        gantz::TempProject::open_with_name("test_project_compile_after_node_removal").unwrap();
+    let one = project
+        .add_core_node(Box::new(node_int(1)) as Box<dyn SerdeNode>)
+        .unwrap();
+    let push = project
+        .add_core_node(Box::new(node_push()) as Box<_>)
+        .unwrap();
+    let counter = project
+        .add_core_node(Box::new(node_counter()) as Box<_>)
+        .unwrap();
+
+    // Add the counter after a node that is later removed.
+    let root = project.root_node_id();
+    let mut ids = None;
+    project
+        .update_graph(&root, |g| {
+            let a = g.add_node(one);
+            let p = g.add_node(push);
+            let c = g.add_node(counter);
+            g.add_edge(p, c, Edge::from((0, 0)));
+            ids = Some((a, c));
+        })
+        .unwrap();
+    let (a, c) = ids.unwrap();
+    project
+        .update_graph(&root, |g| {
+            g.remove_node(a);
+        })
+        .unwrap();
+
+    // The counter's state is still found and updated.
+    let graph = project.load_graph(&root).unwrap();
+    let mut state = gantz::GraphState::new(&project, &graph).unwrap();
+    let push_fn = graph.push_fn("push").unwrap();
+    for _ in 0..2 {
+        state.call(&push_fn).unwrap();
+    }
+    assert_eq!(state.state::<u32>(c), Some(&2));
+}
@@ -0,0 +1,151 @@
+// Tests for reloading graph dynamic libraries while preserving node state.
+
+use gantz::node::{self, SerdeNode, WithPushEval, WithStateType};
+use gantz::project::ReferencePolicy;
+use gantz::Edge;
+
+fn node_push() -> node::Push<node::Expr> {
+    node::expr("()").unwrap().with_push_eval_name("push")
+}
+
+// A counter node with state of the given integer type.
+fn node_counter(ty: &str) -> node::State<node::Expr> {
+    node::expr(r#"{ #push; let count = *state; *state += 1; count }"#)
+        .unwrap()
+        .with_default_state_ty(ty)
+        .unwrap()
+}
+
+// Check that counter state survives the addition of a new counter and is reset when the counter's
+// state type changes.
+//
+// Starts with a single counter pushed by a `push` node, then adds a second counter downstream of
+// the first and reloads.
+#[test]
+fn test_reload_preserves_state() {
+    // Create a temp project.
+    let mut project = gantz::TempProject::open_with_name("test_reload_preserves_state").unwrap();
+
+    // Add the nodes to the project.
+    let push = project
+        .add_core_node(Box::new(node_push()) as Box<dyn SerdeNode>)
+        .unwrap();
+    let counter = project
+        .add_core_node(Box::new(node_counter("u32")) as Box<_>)
+        .unwrap();
+
+    // Compose the initial graph.
+    let root = project.root_node_id();
+    let mut push_ix = None;
+    let mut counter_a = None;
+    project
+        .update_graph(&root, |g| {
+            let p = g.add_node(push);
+            let c = g.add_node(counter);
+            g.add_edge(p, c, Edge::from((0, 0)));
+            push_ix = Some(p);
+            counter_a = Some(c);
+        })
+        .unwrap();
+    let (push_ix, counter_a) = (push_ix.unwrap(), counter_a.unwrap());
+
+    // Load the graph and count to 3.
+    let mut graph = project.load_graph(&root).unwrap();
+    let mut state = gantz::GraphState::new(&project, &graph).unwrap();
+    let push_fn = graph.push_fn("push").unwrap();
+    for _ in 0..3 {
+        state.call(&push_fn).unwrap();
+    }
+    assert_eq!(state.state::<u32>(counter_a), Some(&3));
+
+    // Add a second counter downstream of the first.
+    let mut counter_b = None;
+    project
+        .update_graph(&root, |g| {
+            let c = g.add_node(counter);
+            g.add_edge(counter_a, c, Edge::from((0, 0)));
+            counter_b = Some(c);
+        })
+        .unwrap();
+    let counter_b = counter_b.unwrap();
+
+    // Reload, keeping the state of the first counter.
+    let report = graph.reload(&project, &mut state).unwrap();
+    assert_eq!(report.kept, vec![counter_a]);
+    assert_eq!(report.added, vec![counter_b]);
+    assert!(report.reset.is_empty());
+    assert!(report.removed.is_empty());
+    let push_fn = graph.push_fn("push").unwrap();
+    state.call(&push_fn).unwrap();
+    assert_eq!(state.state::<u32>(counter_a), Some(&4));
+    assert_eq!(state.state::<u32>(counter_b), Some(&1));
+
+    // Change the counter's state type. Both counters must be re-initialised.
+    let counter_u64 = Box::new(node_counter("u64")) as Box<dyn SerdeNode>;
+    project
+        .replace_core_node(&counter, counter_u64, ReferencePolicy::Cascade)
+        .unwrap();
+    let report = graph.reload(&project, &mut state).unwrap();
+    assert_eq!(report.reset, vec![counter_a, counter_b]);
+    assert!(report.kept.is_empty());
+    let push_fn = graph.push_fn("push").unwrap();
+    state.call(&push_fn).unwrap();
+    assert_eq!(state.state::<u64>(counter_a), Some(&1));
+    assert_eq!(state.state::<u64>(counter_b), Some(&1));
+
+    // Removing the push node leaves the counters' states in place.
+    project
+        .update_graph(&root, |g| {
+            g.remove_node(push_ix);
+        })
+        .unwrap();
+    let report = graph.reload(&project, &mut state).unwrap();
+    assert_eq!(report.kept, vec![counter_a, counter_b]);
+    assert!(graph.push_fn("push").is_err());
+}
+
+// Check that the copies of the library made for each reload are removed once unloaded.
+#[test]
+fn test_reload_removes_library_copies() {
+    let mut project =
+        gantz::TempProject::open_with_name("test_reload_removes_library_copies").unwrap();
+    let push = project
+        .add_core_node(Box::new(node_push()) as Box<dyn SerdeNode>)
+        .unwrap();
+    let counter = project
+        .add_core_node(Box::new(node_counter("u32")) as Box<_>)
+        .unwrap();
+    let root = project.root_node_id();
+    project
+        .update_graph(&root, |g| {
+            let p = g.add_node(push);
+            let c = g.add_node(counter);
+            g.add_edge(p, c, Edge::from((0, 0)));
+        })
+        .unwrap();
+
+    // The copies are made alongside the original library.
+    let dylib_path = project.graph_node_dylib(&root).unwrap().unwrap();
+    let copies = || {
+        std::fs::read_dir(dylib_path.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.contains("-reload-"))
+            .count()
+    };
+
+    let mut graph = project.load_graph(&root).unwrap();
+    let mut state = gantz::GraphState::new(&project, &graph).unwrap();
+    for _ in 0..3 {
+        graph.reload(&project, &mut state).unwrap();
+    }
+
+    // Only the copy that is currently loaded remains.
+    let push_fn = graph.push_fn("push").unwrap();
+    state.call(&push_fn).unwrap();
+    assert_eq!(copies(), 1);
+
+    // Once all users of the library are dropped, so is the copy.
+    drop((push_fn, state, graph));
+    assert_eq!(copies(), 0);
+}
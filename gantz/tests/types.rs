@@ -0,0 +1,184 @@
+// Testing static type checking of the edges between nodes.
+
+use gantz::graph::validate::{DiagnosticKind, Severity};
+use gantz::node::{self, SerdeNode};
+use gantz::project::UpdateGraphError;
+use gantz::Edge;
+use serde::{Deserialize, Serialize};
+
+// A node producing an `i32`.
+#[derive(Deserialize, Serialize)]
+struct Int;
+
+// A node expecting an `f32`.
+#[derive(Deserialize, Serialize)]
+struct Half;
+
+impl gantz::Node for Int {
+    fn evaluator(&self) -> node::Evaluator {
+        let fn_item = syn::parse_quote! {
+            fn int() -> i32 {
+                1
+            }
+        };
+        node::Evaluator::Fn {
+            fn_item,
+            optional_outputs: vec![],
+        }
+    }
+}
+
+impl gantz::Node for Half {
+    fn evaluator(&self) -> node::Evaluator {
+        let fn_item = syn::parse_quote! {
+            fn half(f: f32) -> f32 {
+                f / 2.0
+            }
+        };
+        node::Evaluator::Fn {
+            fn_item,
+            optional_outputs: vec![],
+        }
+    }
+}
+
+// A node producing an `f32` via the `c_float` alias.
+#[derive(Deserialize, Serialize)]
+struct CFloat;
+
+impl gantz::Node for CFloat {
+    fn evaluator(&self) -> node::Evaluator {
+        let fn_item = syn::parse_quote! {
+            fn c_float() -> std::os::raw::c_float {
+                1.0
+            }
+        };
+        node::Evaluator::Fn {
+            fn_item,
+            optional_outputs: vec![],
+        }
+    }
+}
+
+#[typetag::serde]
+impl SerdeNode for Int {
+    fn node(&self) -> &dyn gantz::Node {
+        self
+    }
+}
+
+#[typetag::serde]
+impl SerdeNode for CFloat {
+    fn node(&self) -> &dyn gantz::Node {
+        self
+    }
+}
+
+#[typetag::serde]
+impl SerdeNode for Half {
+    fn node(&self) -> &dyn gantz::Node {
+        self
+    }
+}
+
+// Connecting an `i32` output to an `f32` input is reported before compilation, while the type
+// flowing into an `Expr` node is inferred.
+//
+//    -+-----
+//    | int |
+//    -+-----
+//     |
+//     |---------
+//     |        |
+//    -+------ -+-----
+//    | half | | neg |
+//    -------- -------
+#[test]
+fn test_edge_type_mismatch() {
+    // Create a temp project.
+    let mut project = gantz::TempProject::open_with_name("test_edge_type_mismatch").unwrap();
+
+    // Add the nodes to the project.
+    let int = project
+        .add_core_node(Box::new(Int) as Box<dyn SerdeNode>)
+        .unwrap();
+    let half = project.add_core_node(Box::new(Half) as Box<_>).unwrap();
+    let neg = project
+        .add_core_node(Box::new(node::expr("-#x").unwrap()) as Box<_>)
+        .unwrap();
+
+    // Compose the graph.
+    let root = project.root_node_id();
+    let mut ids = None;
+    let result = project.update_graph(&root, |g| {
+        let i = g.add_node(int);
+        let h = g.add_node(half);
+        let n = g.add_node(neg);
+        g.add_edge(i, h, Edge::from((0, 0)));
+        g.add_edge(i, n, Edge::from((0, 0)));
+        ids = Some((i, h, n));
+    });
+    let (i, h, n) = ids.unwrap();
+    match result {
+        Err(UpdateGraphError::InvalidGraph { diagnostics }) => {
+            assert_eq!(diagnostics.len(), 1);
+            let kind = DiagnosticKind::TypeMismatch {
+                output: node::Output(0),
+                input: node::Input(0),
+            };
+            assert_eq!(diagnostics[0].kind, kind);
+            assert_eq!(diagnostics[0].nodes, vec![i, h]);
+        }
+        _ => panic!("expected a type mismatch error"),
+    }
+
+    // The type flowing into `neg` is inferred from `int`.
+    let g = project
+        .ref_graph_node(&root)
+        .expect("no graph for project root node");
+    let types = gantz::graph::types::infer(&**g);
+    let expected: syn::Type = syn::parse_quote! { i32 };
+    assert_eq!(types.inputs[&(n, node::Input(0))], expected);
+}
+
+// A mismatch between types that are not fully resolved is only a warning, as the types may refer
+// to the same type via an alias.
+//
+//    -+---------
+//    | c_float |
+//    -+---------
+//     |
+//    -+------
+//    | half |
+//    --------
+#[test]
+fn test_edge_type_mismatch_unresolved() {
+    let mut project =
+        gantz::TempProject::open_with_name("test_edge_type_mismatch_unresolved").unwrap();
+    let c_float = project
+        .add_core_node(Box::new(CFloat) as Box<dyn SerdeNode>)
+        .unwrap();
+    let half = project.add_core_node(Box::new(Half) as Box<_>).unwrap();
+
+    // The graph compiles, as `c_float` is an alias for `f32`.
+    let root = project.root_node_id();
+    let mut ids = None;
+    project
+        .update_graph(&root, |g| {
+            let c = g.add_node(c_float);
+            let h = g.add_node(half);
+            g.add_edge(c, h, Edge::from((0, 0)));
+            ids = Some((c, h));
+        })
+        .unwrap();
+    let (c, h) = ids.unwrap();
+
+    // The possible mismatch is still reported.
+    let g = project
+        .ref_graph_node(&root)
+        .expect("no graph for project root node");
+    let diagnostics = gantz::graph::validate::diagnostics(&g.graph.graph, &g.inlets, &g.outlets);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(diagnostics[0].nodes, vec![c, h]);
+}
@@ -0,0 +1,220 @@
+// Testing projects whose node crates are stored within a shared cargo workspace.
+
+use gantz::node::{self, SerdeNode, WithPushEval, WithStateType};
+use gantz::project::config::{BuildProfile, ProjectConfig};
+use gantz::project::verify::Inconsistency;
+use gantz::project::{ProjectOpenError, ReferencePolicy, SetConfigError};
+use gantz::Edge;
+use std::fs;
+
+fn node_push() -> node::Push<node::Expr> {
+    node::expr("()").unwrap().with_push_eval_name("push")
+}
+
+fn node_counter() -> node::State<node::Expr> {
+    node::expr(r#"{ #push; let count = *state; *state += 1; count }"#)
+        .unwrap()
+        .with_default_state_ty("u32")
+        .unwrap()
+}
+
+// Node crates are added as members of the external workspace and built within its target
+// directory. The workspace is remembered when the project is re-opened.
+#[test]
+fn test_project_in_external_workspace() {
+    // Create an existing workspace outside of the project directory.
+    let tmp = std::env::temp_dir().join("test_project_in_external_workspace");
+    let _ = fs::remove_dir_all(&tmp);
+    let ext_ws_dir = tmp.join("shared_workspace");
+    fs::create_dir_all(&ext_ws_dir).unwrap();
+    fs::write(ext_ws_dir.join("Cargo.toml"), "[workspace]\nmembers = []\n").unwrap();
+    let ext_ws_dir = fs::canonicalize(&ext_ws_dir).unwrap();
+
+    // Open the project within the workspace.
+    let proj_dir = tmp.join("project");
+    let mut project =
+        gantz::Project::open_in_workspace(proj_dir.clone(), ext_ws_dir.clone()).unwrap();
+    assert_eq!(project.workspace_dir(), ext_ws_dir);
+    assert!(!gantz::project::workspace_dir(&proj_dir).exists());
+
+    // The root graph node crate is a member of the external workspace.
+    let manifest = fs::read_to_string(ext_ws_dir.join("Cargo.toml")).unwrap();
+    let toml: toml::Value = toml::from_str(&manifest).unwrap();
+    let members = toml["workspace"]["members"].as_array().unwrap();
+    assert_eq!(members.len(), 1);
+
+    // Add the nodes to the project and compose the graph.
+    let push = project
+        .add_core_node(Box::new(node_push()) as Box<dyn SerdeNode>)
+        .unwrap();
+    let counter = project
+        .add_core_node(Box::new(node_counter()) as Box<_>)
+        .unwrap();
+    let root = project.root_node_id();
+    let mut ids = None;
+    project
+        .update_graph(&root, |g| {
+            let p = g.add_node(push);
+            let c = g.add_node(counter);
+            g.add_edge(p, c, Edge::from((0, 0)));
+            ids = Some(c);
+        })
+        .unwrap();
+    let c = ids.unwrap();
+
+    // The dylib is built within the external workspace's target directory.
+    let dylib = project.graph_node_dylib(&root).unwrap().expect("no dylib");
+    assert!(dylib.starts_with(ext_ws_dir.join("target")));
+
+    // Load the graph and evaluate it.
+    let graph = project.load_graph(&root).unwrap();
+    let mut state = gantz::GraphState::new(&project, &graph).unwrap();
+    let push_fn = graph.push_fn("push").unwrap();
+    state.call(&push_fn).unwrap();
+    assert_eq!(state.state::<u32>(c), Some(&1));
+    drop(push_fn);
+    drop(state);
+    drop(graph);
+
+    // Re-opening the project uses the recorded workspace.
+    project.save().unwrap();
+    drop(project);
+    let reopened = gantz::Project::open(proj_dir.clone()).unwrap();
+    assert_eq!(reopened.workspace_dir(), ext_ws_dir);
+    assert_eq!(reopened.root_node_id(), root);
+    drop(reopened);
+
+    // Opening the project within a different workspace is an error.
+    let other_ws_dir = tmp.join("other_workspace");
+    fs::create_dir_all(&other_ws_dir).unwrap();
+    fs::write(
+        other_ws_dir.join("Cargo.toml"),
+        "[workspace]\nmembers = []\n",
+    )
+    .unwrap();
+    match gantz::Project::open_in_workspace(proj_dir, other_ws_dir) {
+        Err(ProjectOpenError::WorkspaceMismatch { .. }) => (),
+        _ => panic!("expected a workspace mismatch error"),
+    }
+
+    fs::remove_dir_all(&tmp).unwrap();
+}
+
+// Editing the members of an external workspace preserves the formatting and comments of its
+// manifest.
+#[test]
+fn test_project_in_external_workspace_manifest_preserved() {
+    let tmp = std::env::temp_dir().join("test_project_in_external_workspace_manifest_preserved");
+    let _ = fs::remove_dir_all(&tmp);
+    let ext_ws_dir = tmp.join("shared_workspace");
+    fs::create_dir_all(&ext_ws_dir).unwrap();
+    let manifest = "\
+# A workspace shared with other crates.
+[workspace]
+members = [] # Managed in part by gantz.
+
+[profile.release]
+lto = true # Keep binaries small.
+";
+    fs::write(ext_ws_dir.join("Cargo.toml"), manifest).unwrap();
+    let ext_ws_dir = fs::canonicalize(&ext_ws_dir).unwrap();
+
+    // Add, rename and remove a graph node, each of which edits the workspace members.
+    let proj_dir = tmp.join("project");
+    let mut project = gantz::Project::open_in_workspace(proj_dir, ext_ws_dir.clone()).unwrap();
+    let graph = project.add_graph_node(Default::default(), "foo").unwrap();
+    project
+        .rename_graph_node(&graph, "bar", ReferencePolicy::Refuse)
+        .unwrap();
+    let edited = fs::read_to_string(ext_ws_dir.join("Cargo.toml")).unwrap();
+    assert!(edited.contains(&gantz::project::node_crate_name("bar")));
+    assert!(!edited.contains(&gantz::project::node_crate_name("foo")));
+    project
+        .remove_node(&graph, ReferencePolicy::Refuse)
+        .unwrap();
+
+    // Only the members have changed.
+    let edited = fs::read_to_string(ext_ws_dir.join("Cargo.toml")).unwrap();
+    let root_crate = gantz::project::node_crate_name("project");
+    let expected = manifest.replace("[]", &format!("[\"{}\"]", root_crate));
+    assert_eq!(edited, expected);
+
+    drop(project);
+    fs::remove_dir_all(&tmp).unwrap();
+}
+
+// Build settings that require a cargo configuration file are refused for a shared workspace, as
+// the file would apply to all of the workspace's crates. The profile may still be changed.
+#[test]
+fn test_project_in_external_workspace_config() {
+    let tmp = std::env::temp_dir().join("test_project_in_external_workspace_config");
+    let _ = fs::remove_dir_all(&tmp);
+    let ext_ws_dir = tmp.join("shared_workspace");
+    fs::create_dir_all(&ext_ws_dir).unwrap();
+    fs::write(ext_ws_dir.join("Cargo.toml"), "[workspace]\nmembers = []\n").unwrap();
+    let ext_ws_dir = fs::canonicalize(&ext_ws_dir).unwrap();
+
+    let proj_dir = tmp.join("project");
+    let mut project = gantz::Project::open_in_workspace(proj_dir, ext_ws_dir.clone()).unwrap();
+    let config = ProjectConfig {
+        opt_level: Some("0".to_string()),
+        ..Default::default()
+    };
+    match project.set_config(config) {
+        Err(SetConfigError::ExternalWorkspace { .. }) => (),
+        _ => panic!("expected the config to be refused for the external workspace"),
+    }
+    assert_eq!(project.config(), &ProjectConfig::default());
+
+    let config = ProjectConfig {
+        profile: BuildProfile::Debug,
+        ..Default::default()
+    };
+    project.set_config(config.clone()).unwrap();
+    assert_eq!(project.config(), &config);
+    assert!(!gantz::project::cargo_config_toml_path(&ext_ws_dir).exists());
+
+    drop(project);
+    fs::remove_dir_all(&tmp).unwrap();
+}
+
+// Workspace members specified via glob patterns are expanded when verifying the project, so that
+// stale package IDs are still detected.
+#[test]
+fn test_project_in_external_workspace_glob_members() {
+    let tmp = std::env::temp_dir().join("test_project_in_external_workspace_glob_members");
+    let _ = fs::remove_dir_all(&tmp);
+    let ext_ws_dir = tmp.join("shared_workspace");
+    let other_dir = ext_ws_dir.join("crates").join("other");
+    fs::create_dir_all(other_dir.join("src")).unwrap();
+    let other_manifest = "[package]\nname = \"other\"\nversion = \"0.1.0\"\nedition = \"2018\"\n";
+    fs::write(other_dir.join("Cargo.toml"), other_manifest).unwrap();
+    fs::write(other_dir.join("src").join("lib.rs"), "").unwrap();
+    let ws_manifest = "[workspace]\nmembers = [\"crates/*\"]\n";
+    fs::write(ext_ws_dir.join("Cargo.toml"), ws_manifest).unwrap();
+    let ext_ws_dir = fs::canonicalize(&ext_ws_dir).unwrap();
+
+    let proj_dir = tmp.join("project");
+    let mut project = gantz::Project::open_in_workspace(proj_dir, ext_ws_dir.clone()).unwrap();
+    assert!(project.verify().unwrap().is_consistent());
+
+    // Change the version of the root graph node's crate.
+    let root_crate_dir = ext_ws_dir.join(gantz::project::node_crate_name("project"));
+    let manifest_path = root_crate_dir.join("Cargo.toml");
+    let manifest = fs::read_to_string(&manifest_path).unwrap();
+    let manifest = manifest.replace("version = \"0.1.0\"", "version = \"0.2.0\"");
+    fs::write(&manifest_path, manifest).unwrap();
+    let report = project.verify().unwrap();
+    match report.inconsistencies[..] {
+        [Inconsistency::StalePackageId { graph, .. }] => assert_eq!(graph, project.root_node_id()),
+        _ => panic!(
+            "expected a stale package ID, found {:?}",
+            report.inconsistencies
+        ),
+    }
+    project.repair().unwrap();
+    assert!(project.verify().unwrap().is_consistent());
+
+    drop(project);
+    fs::remove_dir_all(&tmp).unwrap();
+}
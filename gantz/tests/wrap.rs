@@ -0,0 +1,210 @@
+// Testing the generation of nodes for the public functions of an external crate.
+
+use gantz::node::{self, CrateDep, SerdeNode, WithPushEval};
+use gantz::wrap::{SkipReason, SkippedFn};
+use gantz::Edge;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+const OPS_RS: &str = r#"
+use std::collections::*;
+
+pub struct Pair {
+    pub a: i32,
+    pub b: i32,
+}
+
+pub fn double(x: i32) -> i32 {
+    x * 2
+}
+
+pub fn sum(pair: Pair) -> i32 {
+    pair.a + pair.b
+}
+
+pub unsafe fn read(x: *const i32) -> i32 {
+    *x
+}
+
+pub fn count(map: HashMap<i32, i32>) -> usize {
+    map.len()
+}
+
+pub fn total(values: impl IntoIterator<Item = i32>) -> i32 {
+    values.into_iter().sum()
+}
+
+#[cfg(feature = "extra")]
+pub fn extra() -> i32 {
+    0
+}
+
+fn private() {}
+"#;
+
+// A node that evaluates one of the generated evaluator functions.
+//
+// Generated node types are normally `include!`d from a build script's output, so this stands in
+// for them here.
+#[derive(Deserialize, Serialize)]
+struct Wrapped {
+    fn_src: String,
+    dep: CrateDep,
+}
+
+impl gantz::Node for Wrapped {
+    fn evaluator(&self) -> node::Evaluator {
+        node::Evaluator::Fn {
+            fn_item: syn::parse_str(&self.fn_src).unwrap(),
+            optional_outputs: vec![],
+        }
+    }
+
+    fn crate_deps(&self) -> Vec<CrateDep> {
+        vec![self.dep.clone()]
+    }
+}
+
+#[typetag::serde]
+impl SerdeNode for Wrapped {
+    fn node(&self) -> &dyn gantz::Node {
+        self
+    }
+}
+
+fn node_push() -> node::Push<node::Expr> {
+    node::expr("()").unwrap().with_push_eval_name("push")
+}
+
+fn node_int(i: i32) -> node::Expr {
+    node::expr(&format!("{{ #push; {} }}", i)).unwrap()
+}
+
+fn node_assert_eq() -> node::Expr {
+    node::expr("assert_eq!(#l, #r)").unwrap()
+}
+
+// Public functions of a local crate's module are wrapped and those that cannot be are reported.
+// The module is located via its `#[path]` attribute, while `#[cfg]`-gated items are skipped.
+// The generated evaluator for `double` is then used within a graph.
+//
+//    --------
+//    | push | // push_eval
+//    -+------
+//     |
+//     |----------
+//     |         |
+//    -+----    -+----
+//    | 21 |    | 42 |
+//    -+----    -+----
+//     |         |
+//    -+-------  |
+//    | double | |
+//    -+-------  |
+//     |         |
+//     |    ------
+//     |    |
+//    -+----+----
+//    |assert_eq|
+//    -----------
+#[test]
+fn test_wrap_crate_module() {
+    // Create a crate to wrap.
+    let tmp = std::env::temp_dir().join("test_wrap_crate_module");
+    let _ = fs::remove_dir_all(&tmp);
+    let crate_dir = tmp.join("mathlib");
+    fs::create_dir_all(crate_dir.join("src")).unwrap();
+    let manifest = "[package]\nname = \"mathlib\"\nversion = \"0.1.0\"\nedition = \"2018\"\n\n\
+                    [features]\nextra = []\n";
+    fs::write(crate_dir.join("Cargo.toml"), manifest).unwrap();
+    let lib_rs =
+        "#[path = \"operations.rs\"]\npub mod ops;\n#[cfg(feature = \"extra\")]\npub mod extra;\n";
+    fs::write(crate_dir.join("src").join("lib.rs"), lib_rs).unwrap();
+    fs::write(crate_dir.join("src").join("operations.rs"), OPS_RS).unwrap();
+
+    // Load the module and generate the nodes.
+    let dep = CrateDep {
+        name: "mathlib".to_string(),
+        source: format!("{{ path = {:?} }}", crate_dir.display().to_string()),
+    };
+    let module = gantz::wrap::load_module(&dep, "ops", &tmp.join("work")).unwrap();
+    let extra = gantz::wrap::load_module(&dep, "extra", &tmp.join("work"));
+    assert!(matches!(
+        extra,
+        Err(gantz::wrap::LoadModuleError::ModuleNotFound { .. })
+    ));
+    assert_eq!(module.crate_name, "mathlib");
+    assert_eq!(module.path, vec!["ops".to_string()]);
+    let generated = gantz::wrap::generate(&dep, &module);
+    let type_names: Vec<_> = generated.nodes.iter().map(|n| &n.type_name[..]).collect();
+    assert_eq!(type_names, vec!["Double", "Sum"]);
+    let expected_sum: syn::ItemFn = syn::parse_quote! {
+        fn mathlib_ops_sum(pair: mathlib::ops::Pair) -> i32 {
+            mathlib::ops::sum(pair)
+        }
+    };
+    assert_eq!(generated.nodes[1].fn_item, expected_sum);
+    let expected_skipped = vec![
+        SkippedFn {
+            fn_name: "read".to_string(),
+            reason: SkipReason::Unsafe,
+        },
+        SkippedFn {
+            fn_name: "count".to_string(),
+            reason: SkipReason::UnnameablePath("HashMap<i32,i32>".to_string()),
+        },
+        SkippedFn {
+            fn_name: "total".to_string(),
+            reason: SkipReason::ImplTraitArg,
+        },
+        SkippedFn {
+            fn_name: "extra".to_string(),
+            reason: SkipReason::Cfg,
+        },
+    ];
+    assert_eq!(generated.skipped, expected_skipped);
+    assert!(generated.to_source().contains("mathlib::ops::double"));
+
+    // Evaluate the wrapped `double` function within a graph.
+    let mut project = gantz::TempProject::open_with_name("test_wrap_crate_module_project").unwrap();
+    let double = Wrapped {
+        fn_src: quote::ToTokens::to_token_stream(&generated.nodes[0].fn_item).to_string(),
+        dep,
+    };
+    let push = project
+        .add_core_node(Box::new(node_push()) as Box<dyn SerdeNode>)
+        .unwrap();
+    let twenty_one = project
+        .add_core_node(Box::new(node_int(21)) as Box<_>)
+        .unwrap();
+    let double = project.add_core_node(Box::new(double) as Box<_>).unwrap();
+    let forty_two = project
+        .add_core_node(Box::new(node_int(42)) as Box<_>)
+        .unwrap();
+    let assert_eq = project
+        .add_core_node(Box::new(node_assert_eq()) as Box<_>)
+        .unwrap();
+    let root = project.root_node_id();
+    project
+        .update_graph(&root, |g| {
+            let push = g.add_node(push);
+            let twenty_one = g.add_node(twenty_one);
+            let double = g.add_node(double);
+            let forty_two = g.add_node(forty_two);
+            let assert_eq = g.add_node(assert_eq);
+            g.add_edge(push, twenty_one, Edge::from((0, 0)));
+            g.add_edge(push, forty_two, Edge::from((0, 0)));
+            g.add_edge(twenty_one, double, Edge::from((0, 0)));
+            g.add_edge(double, assert_eq, Edge::from((0, 0)));
+            g.add_edge(forty_two, assert_eq, Edge::from((0, 1)));
+        })
+        .unwrap();
+    let graph = project.load_graph(&root).expect("failed to load graph");
+    let push_eval_fn = graph.push_fn("push").expect("failed to load symbol");
+    push_eval_fn.call(&mut []).unwrap();
+    drop(push_eval_fn);
+    drop(graph);
+    drop(project);
+
+    fs::remove_dir_all(&tmp).unwrap();
+}
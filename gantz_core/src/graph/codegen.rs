@@ -1,8 +1,8 @@
 use super::Edge;
 use crate::node::{self, Node};
 use petgraph::visit::{
-    Data, Dfs, EdgeRef, GraphRef, IntoEdgesDirected, IntoNodeReferences, NodeIndexable, NodeRef,
-    Topo, Visitable, Walker,
+    Data, EdgeFiltered, EdgeRef, GraphRef, IntoEdgesDirected, IntoNodeIdentifiers,
+    IntoNodeReferences, NodeIndexable, NodeRef, Topo, VisitMap, Visitable, Walker,
 };
 use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
@@ -28,6 +28,27 @@ pub struct ExprInput<NI> {
     pub output: node::Output,
     /// Whether or not using the value in this argument requires cloning.
     pub requires_clone: bool,
+    /// Whether or not the value is read from the value stored for a **cold** inlet.
+    ///
+    /// This is the case when the source node is not evaluated within the same evaluation, or when
+    /// the value is carried by a **delay** edge.
+    pub stored: bool,
+    /// Whether or not the value is carried by a **delay** edge.
+    ///
+    /// Delayed values are read from storage before any nodes are evaluated, so that the value
+    /// produced during the previous evaluation is used.
+    pub delayed: bool,
+}
+
+/// The location within a graph of some code generated by `file`.
+///
+/// See `src_location`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SrcLocation {
+    /// The instance ID of the node whose evaluation the code belongs to.
+    pub instance_id: u64,
+    /// The input of the node, if the code produces the value passed to a specific input.
+    pub input: Option<node::Input>,
 }
 
 /// Shorthand for the node evaluator map passed between codegen stages.
@@ -36,6 +57,12 @@ pub type NodeEvaluatorMap<Id> = HashMap<Id, node::Evaluator>;
 /// Shorthand for the node state type map passed between codegen stages.
 pub type NodeStateTypeMap<Id> = HashMap<Id, syn::Type>;
 
+/// A map from node IDs to the persistent instance IDs used to identify nodes within generated code.
+///
+/// Instance IDs are used for generated identifiers and diagnostics. Keeping them stable across
+/// graph edits keeps the generated code stable too.
+pub type NodeInstanceIdMap<Id> = HashMap<Id, u64>;
+
 /// Given a graph of gantz nodes, produce the `Evaluator` associated with each.
 pub fn node_evaluators<G>(g: G) -> NodeEvaluatorMap<G::NodeId>
 where
@@ -48,6 +75,19 @@ where
         .collect()
 }
 
+/// Instance IDs derived from the index of each node within the graph.
+///
+/// This is suitable for graphs whose node indices remain stable across edits and are never reused.
+pub fn node_instance_ids<G>(g: G) -> NodeInstanceIdMap<G::NodeId>
+where
+    G: IntoNodeReferences + NodeIndexable,
+    G::NodeId: Eq + Hash,
+{
+    g.node_references()
+        .map(|n| (n.id(), g.to_index(n.id()) as u64))
+        .collect()
+}
+
 /// Given a graph of gantz nodes, produce a map from stateful node IDs to their associated state
 /// types.
 pub fn node_state_types<G>(g: G) -> NodeStateTypeMap<G::NodeId>
@@ -73,7 +113,7 @@ where
     Id: 'a + Eq + Hash,
 {
     evaluators.iter().filter_map(move |(id, eval)| match eval {
-        node::Evaluator::Fn { ref fn_item } => {
+        node::Evaluator::Fn { ref fn_item, .. } => {
             let mut fn_item = fn_item.clone();
             if let Some(ty) = state_types.get(&id) {
                 fn_item
@@ -114,20 +154,27 @@ where
 }
 
 /// An iterator yielding all nodes reachable via pushing from the given node.
+///
+/// Evaluation does not propagate across **cold** edges, so nodes only reachable via a cold edge
+/// are not included.
 pub fn push_reachable<G>(g: G, n: G::NodeId) -> impl Iterator<Item = G::NodeId>
 where
     G: IntoEdgesDirected + Visitable,
+    G: Data<EdgeWeight = Edge>,
 {
-    Dfs::new(g, n).iter(g)
+    hot_reachable(g, n, petgraph::Outgoing).into_iter()
 }
 
 /// An iterator yielding all nodes reachable via pulling from the given node.
+///
+/// Evaluation does not propagate across **cold** edges, so nodes only reachable via a cold edge
+/// are not included.
 pub fn pull_reachable<G>(g: G, n: G::NodeId) -> impl Iterator<Item = G::NodeId>
 where
     G: IntoEdgesDirected + Visitable,
+    G: Data<EdgeWeight = Edge>,
 {
-    let rev_g = petgraph::visit::Reversed(g);
-    Dfs::new(rev_g, n).iter(rev_g)
+    hot_reachable(g, n, petgraph::Incoming).into_iter()
 }
 
 /// Push evaluation from the specified node.
@@ -140,10 +187,13 @@ where
 pub fn push_eval_order<G>(g: G, n: G::NodeId) -> impl Iterator<Item = G::NodeId>
 where
     G: IntoEdgesDirected + IntoNodeReferences + Visitable,
+    G: Data<EdgeWeight = Edge>,
     G::NodeId: Eq + Hash,
 {
     let dfs: HashSet<G::NodeId> = push_reachable(g, n).collect();
-    Topo::new(g).iter(g).filter(move |node| dfs.contains(&node))
+    topo_order(g)
+        .into_iter()
+        .filter(move |node| dfs.contains(&node))
 }
 
 /// Pull evaluation from the specified node.
@@ -156,10 +206,13 @@ where
 pub fn pull_eval_order<G>(g: G, n: G::NodeId) -> impl Iterator<Item = G::NodeId>
 where
     G: IntoEdgesDirected + IntoNodeReferences + Visitable,
+    G: Data<EdgeWeight = Edge>,
     G::NodeId: Eq + Hash,
 {
     let dfs: HashSet<G::NodeId> = pull_reachable(g, n).collect();
-    Topo::new(g).iter(g).filter(move |node| dfs.contains(&node))
+    topo_order(g)
+        .into_iter()
+        .filter(move |node| dfs.contains(&node))
 }
 
 /// The evaluation order for given any number of simultaneously pushing and pulling nodes.
@@ -172,6 +225,7 @@ where
 pub fn eval_order<G, A, B>(g: G, push: A, pull: B) -> impl Iterator<Item = G::NodeId>
 where
     G: IntoEdgesDirected + IntoNodeReferences + Visitable,
+    G: Data<EdgeWeight = Edge>,
     G::NodeId: Eq + Hash,
     A: IntoIterator<Item = G::NodeId>,
     B: IntoIterator<Item = G::NodeId>,
@@ -179,30 +233,112 @@ where
     let mut reachable = HashSet::new();
     reachable.extend(push.into_iter().flat_map(|n| push_reachable(g, n)));
     reachable.extend(pull.into_iter().flat_map(|n| pull_reachable(g, n)));
-    Topo::new(g).iter(g).filter(move |n| reachable.contains(&n))
+    topo_order(g)
+        .into_iter()
+        .filter(move |n| reachable.contains(&n))
 }
 
 /// Given a node evaluation order, this filters out all non-stateful nodes.
 ///
 /// This order of the yielded node IDs matches the expected order in which state should be laid out
-/// when passed to the generated evaluation function.
+/// when passed to the evaluation functions generated via `file`, in which nodes are identified by
+/// their index within the graph. See `state_order_with_instance_ids`.
 pub fn state_order<G, I>(g: G, eval_order: I) -> impl Iterator<Item = G::NodeId>
 where
     G: IntoNodeReferences + NodeIndexable,
+    G::NodeId: Eq + Hash,
+    G::NodeWeight: Node,
     I: IntoIterator<Item = G::NodeId>,
+{
+    state_order_with_instance_ids(g, eval_order, &node_instance_ids(g)).into_iter()
+}
+
+/// The same as `state_order`, but orders the stateful nodes by the given instance IDs.
+///
+/// This matches the layout of state expected by the evaluation functions generated via
+/// `file_with_instance_ids`. Keying the layout by instance ID rather than by evaluation order
+/// keeps the index of each node's state stable across edits that change the evaluation order.
+pub fn state_order_with_instance_ids<G, I>(
+    g: G,
+    eval_order: I,
+    instance_ids: &NodeInstanceIdMap<G::NodeId>,
+) -> Vec<G::NodeId>
+where
+    G: IntoNodeReferences,
+    G::NodeId: Eq + Hash,
     G::NodeWeight: Node,
+    I: IntoIterator<Item = G::NodeId>,
 {
-    eval_order.into_iter().filter(move |&n| {
-        g.node_references()
-            .nth(g.to_index(n))
-            .expect("node in `eval_order` does not exist within the given graph")
-            .weight()
-            .state_type()
-            .is_some()
-    })
+    let mut order: Vec<_> = eval_order
+        .into_iter()
+        .filter(|&n| {
+            g.node_references()
+                .find(|n_ref| n_ref.id() == n)
+                .expect("node in `eval_order` does not exist within the given graph")
+                .weight()
+                .state_type()
+                .is_some()
+        })
+        .collect();
+    order.sort_by_key(|n| instance_ids[n]);
+    order
+}
+
+/// Given a node evaluation order, produce the **cold** inlets whose stored values are accessed by
+/// the evaluation.
+///
+/// A cold inlet is the destination of a **cold** or **delay** edge. It is identified by its node
+/// and input and is accessed if either the source or the destination of its edge is evaluated.
+/// Inlets are ordered by node index and then by input.
+///
+/// The stored value of each inlet is passed to the generated evaluation function as an
+/// `Option<Box<dyn Any>>`, following the states of the nodes yielded by `state_order`.
+pub fn cold_inlet_order<G, I>(g: G, eval_order: I) -> Vec<(G::NodeId, node::Input)>
+where
+    G: IntoEdgesDirected + IntoNodeReferences + NodeIndexable,
+    G: Data<EdgeWeight = Edge>,
+    G::NodeId: Eq + Hash,
+    I: IntoIterator<Item = G::NodeId>,
+{
+    cold_inlet_order_with_instance_ids(g, eval_order, &node_instance_ids(g))
+}
+
+/// The same as `cold_inlet_order`, but orders the inlets by the given instance IDs of their nodes
+/// and then by input.
+///
+/// The stored value of each inlet follows the states of the nodes yielded by
+/// `state_order_with_instance_ids`.
+pub fn cold_inlet_order_with_instance_ids<G, I>(
+    g: G,
+    eval_order: I,
+    instance_ids: &NodeInstanceIdMap<G::NodeId>,
+) -> Vec<(G::NodeId, node::Input)>
+where
+    G: IntoEdgesDirected,
+    G: Data<EdgeWeight = Edge>,
+    G::NodeId: Eq + Hash,
+    I: IntoIterator<Item = G::NodeId>,
+{
+    let mut inlets = vec![];
+    for n in eval_order {
+        let incoming = g.edges_directed(n, petgraph::Incoming);
+        let outgoing = g.edges_directed(n, petgraph::Outgoing);
+        for e_ref in incoming.chain(outgoing) {
+            let w = e_ref.weight();
+            if !w.is_hot() {
+                inlets.push((e_ref.target(), w.input));
+            }
+        }
+    }
+    inlets.sort_by_key(|(n, input)| (instance_ids[n], *input));
+    inlets.dedup();
+    inlets
 }
 
 /// Given a node evaluation order, produce the series of evaluation steps required.
+///
+/// Edges referring to an output or input that does not exist on the nodes they connect are
+/// ignored. See `graph::invalid_edges`.
 pub fn eval_steps<G, I>(
     g: G,
     node_evaluators: &NodeEvaluatorMap<G::NodeId>,
@@ -228,12 +364,42 @@ where
 
         // Create an argument for each input to this child.
         for e_ref in g.edges_directed(node, petgraph::Incoming) {
-            // Only consider edges to nodes that we have already visited.
-            if !visited.contains(&e_ref.source()) {
+            let w = e_ref.weight();
+
+            // Ignore edges referring to an output or input that does not exist.
+            let n_outputs = node_evaluators[&e_ref.source()].n_outputs();
+            if w.input.0 >= child_evaluator.n_inputs() || w.output.0 >= n_outputs {
                 continue;
             }
 
-            let w = e_ref.weight();
+            // Values carried by delay edges are always read from storage.
+            if w.delay {
+                let arg = ExprInput {
+                    node: e_ref.source(),
+                    output: w.output,
+                    requires_clone: false,
+                    stored: true,
+                    delayed: true,
+                };
+                args[w.input.0 as usize] = Some(arg);
+                continue;
+            }
+
+            // Only consider edges to nodes that we have already visited, unless the value can be
+            // read from a cold inlet.
+            if !visited.contains(&e_ref.source()) {
+                if w.cold {
+                    let arg = ExprInput {
+                        node: e_ref.source(),
+                        output: w.output,
+                        requires_clone: false,
+                        stored: true,
+                        delayed: false,
+                    };
+                    args[w.input.0 as usize] = Some(arg);
+                }
+                continue;
+            }
 
             // Check how many connections their are from the parent's output and see if the
             // value will need to be cloned when passed to this input.
@@ -260,6 +426,8 @@ where
                 node: e_ref.source(),
                 output: w.output,
                 requires_clone,
+                stored: false,
+                delayed: false,
             };
             args[w.input.0 as usize] = Some(arg);
         }
@@ -280,22 +448,26 @@ pub fn ty_from_fn_arg(arg: &syn::FnArg) -> Option<syn::Type> {
 }
 
 /// Generate a sequence of evaluation statements, one for each given evaluation step.
+///
+/// The variables for each node's outputs are named after the node's instance ID.
 pub fn eval_stmts<G>(
     g: G,
     steps: &[EvalStep<G::NodeId>],
     node_state_types: &NodeStateTypeMap<G::NodeId>,
     node_evaluators: &NodeEvaluatorMap<G::NodeId>,
+    instance_ids: &NodeInstanceIdMap<G::NodeId>,
 ) -> Vec<syn::Stmt>
 where
-    G: GraphRef + IntoNodeReferences + NodeIndexable,
+    G: GraphRef + IntoEdgesDirected + IntoNodeReferences + NodeIndexable,
+    G: Data<EdgeWeight = Edge>,
     G::NodeId: Eq + Hash,
     G::NodeWeight: Node,
 {
     type LValues<NI> = HashMap<(NI, node::Output), syn::Ident>;
 
     // A function for constructing a variable name.
-    fn var_name(node_ix: usize, out_ix: u32) -> String {
-        format!("_node{}_output{}", node_ix, out_ix)
+    fn var_name(instance_id: u64, out_ix: u32) -> String {
+        format!("_node{}_output{}", instance_id, out_ix)
     }
 
     // Insert the lvalue for the node output with the given name into the given map.
@@ -321,31 +493,51 @@ where
         syn::Pat::Ident(pat_ident)
     }
 
+    // Bind the value passed to an input to a variable named after the input.
+    //
+    // The binding is marked, allowing for attributing compiler diagnostics to the input. See
+    // `src_location`.
+    fn bind_input(ident: &syn::Ident, marker: syn::Attribute, expr: syn::Expr) -> syn::Expr {
+        syn::parse_quote! {{
+            #marker
+            let #ident = #expr;
+            #ident
+        }}
+    }
+
+    // The expr used for an unconnected input.
+    fn default_expr(default: node::InputDefault) -> syn::Expr {
+        match default {
+            node::InputDefault::Expr(expr) => syn::parse_quote! { { #expr } },
+            node::InputDefault::Unit | node::InputDefault::Required => syn::parse_quote! { () },
+        }
+    }
+
     // Retrieve the expr for the input to the function.
-    fn input_expr<G>(
-        g: G,
-        arg: Option<&ExprInput<G::NodeId>>,
-        lvals: &LValues<G::NodeId>,
-    ) -> syn::Expr
+    fn input_expr<G>(g: G, arg: &ExprInput<G::NodeId>, lvals: &LValues<G::NodeId>) -> syn::Expr
     where
         G: NodeIndexable,
         G::NodeId: Eq + Hash,
     {
-        match arg {
-            None => syn::parse_quote! { () },
-            Some(arg) => {
-                let ident = lvals.get(&(arg.node, arg.output)).unwrap_or_else(|| {
-                    panic!(
-                        "no lvalue for expected arg (node {}, output {})",
-                        g.to_index(arg.node),
-                        arg.output.0,
-                    );
-                });
-                match arg.requires_clone {
-                    false => syn::parse_quote! { { #ident } },
-                    true => syn::parse_quote! { { #ident.clone() } },
-                }
-            }
+        let ident = lvals.get(&(arg.node, arg.output)).unwrap_or_else(|| {
+            panic!(
+                "no lvalue for expected arg (node {}, output {})",
+                g.to_index(arg.node),
+                arg.output.0,
+            );
+        });
+        match arg.requires_clone {
+            false => syn::parse_quote! { { #ident } },
+            true => syn::parse_quote! { { #ident.clone() } },
+        }
+    }
+
+    // An expression for the stored value of the cold inlet at the given index into the node states.
+    fn cold_inlet_expr(node_states_idx: usize) -> syn::Expr {
+        syn::parse_quote! {
+            _node_states[#node_states_idx]
+                .downcast_mut::<Option<Box<dyn std::any::Any>>>()
+                .expect("failed to downcast stored value of cold inlet")
         }
     }
 
@@ -355,7 +547,7 @@ where
     // Also keeps track of each the lvalue ident for each output of the node so that they may be
     // passed to following node exprs.
     fn lvalues_pat<Id>(
-        step_ix: usize,
+        instance_id: u64,
         step: &EvalStep<Id>,
         n_outputs: u32,
         lvalues: &mut LValues<Id>,
@@ -363,7 +555,7 @@ where
     where
         Id: Copy + Eq + Hash,
     {
-        let v_name = |vi| var_name(step_ix, vi);
+        let v_name = |vi| var_name(instance_id, vi);
         let mut insert_lval = |vi, name: &str| {
             insert_lvalue(step.node, vi, name, lvalues);
         };
@@ -392,57 +584,275 @@ where
     // evaluation step is evaluated and the outputs are destructured from a tuple.
     let mut stmts: Vec<syn::Stmt> = vec![];
 
+    // Reads of values carried by delay edges, evaluated ahead of all other statements.
+    let mut delayed_reads: Vec<syn::Stmt> = vec![];
+
     // Keep track of each of the lvalues for each of the statements.
     let mut lvalues: LValues<G::NodeId> = Default::default();
 
-    // The next index into the node_states slice.
-    //
-    // TODO: This should be calculated ahead of this function in an easy-to-access manner so that
-    // users can pre-prepare node states properly.
-    let mut node_state_idx: usize = 0;
+    // The index of each node's state within the node_states slice.
+    let step_nodes = || steps.iter().map(|step| step.node);
+    let node_state_indices: HashMap<_, _> =
+        state_order_with_instance_ids(g, step_nodes(), instance_ids)
+            .into_iter()
+            .enumerate()
+            .map(|(i, n)| (n, i))
+            .collect();
+
+    // The stored values of cold inlets follow the states of the nodes within the node_states slice.
+    let n_node_states = node_state_indices.len();
+    let cold_inlet_indices: HashMap<_, _> =
+        cold_inlet_order_with_instance_ids(g, step_nodes(), instance_ids)
+            .into_iter()
+            .enumerate()
+            .map(|(i, inlet)| (inlet, n_node_states + i))
+            .collect();
+
+    // Outputs whose lvalues are of type `Option<T>`, either because the output is optional or
+    // because the node was only conditionally evaluated.
+    let mut conditional_outputs: HashSet<(G::NodeId, node::Output)> = Default::default();
+
+    for step in steps {
+        let ne = &node_evaluators[&step.node];
+        let n_outputs = ne.n_outputs();
+        let instance_id = *instance_ids
+            .get(&step.node)
+            .expect("no instance ID for step's node");
+
+        let n_ref = g
+            .node_references()
+            .find(|n_ref| n_ref.id() == step.node)
+            .expect("no node for step's node ID");
+        let n_id = n_ref.id();
+        let node = n_ref.weight();
 
-    for (si, step) in steps.iter().enumerate() {
         // Retrieve an expression for each argument to the current node's expression.
         //
-        // E.g. `_n1_v0`, `_n3_v1.clone()` or `Default::default()`.
+        // E.g. `_n1_v0`, `_n3_v1.clone()` or the node's default for unconnected inputs.
+        //
+        // Arguments sourced from conditional outputs are instead bound to an `_input` variable
+        // that is only available if the output produced a value. The same applies to values read
+        // from cold inlets, which are only available once a value has been stored.
+        let mut cond_exprs: Vec<syn::Expr> = vec![];
+        let mut cond_pats: Vec<syn::Pat> = vec![];
         let args: Vec<syn::Expr> = step
             .args
             .iter()
-            .map(|arg| input_expr(g, arg.as_ref(), &lvalues))
+            .enumerate()
+            .map(|(i, arg)| {
+                let name = format!("_node{}_input{}", instance_id, i);
+                let ident = syn::Ident::new(&name, proc_macro2::Span::call_site());
+                let marker = src_marker(instance_id, Some(i as u32));
+                match arg {
+                    Some(arg) if arg.stored => {
+                        let input = node::Input(i as u32);
+                        let slot = cold_inlet_expr(cold_inlet_indices[&(step.node, input)]);
+                        let cold_name = format!("_node{}_cold{}", instance_id, i);
+                        let cold = syn::Ident::new(&cold_name, proc_macro2::Span::call_site());
+                        // Downcast to the declared type of the input where known. A stored value
+                        // of another type is reported rather than treated as no value.
+                        let ty: syn::Type = match node.io_types().inputs.get(i) {
+                            Some(Some(ty)) => ty.clone(),
+                            _ => syn::parse_quote! { _ },
+                        };
+                        let msg = format!(
+                            "stored value for input {} of node {} is not of the expected type",
+                            i, instance_id,
+                        );
+                        let read: syn::Stmt = syn::parse_quote! {
+                            #marker
+                            let #cold: Option<#ty> = #slot.as_ref().map(|value| {
+                                value.downcast_ref::<#ty>().cloned().expect(#msg)
+                            });
+                        };
+                        match arg.delayed {
+                            false => stmts.push(read),
+                            true => delayed_reads.push(read),
+                        }
+                        // Delayed values fall back to the input's default expression, if any.
+                        match (arg.delayed, node.input_default(input)) {
+                            (true, node::InputDefault::Expr(expr)) => {
+                                let expr = syn::parse_quote! { #cold.unwrap_or_else(|| #expr) };
+                                bind_input(&ident, marker, expr)
+                            }
+                            _ => {
+                                cond_exprs.push(syn::parse_quote! { #cold });
+                                cond_pats.push(var_pat(&name));
+                                syn::parse_quote! { #ident }
+                            }
+                        }
+                    }
+                    Some(arg) if conditional_outputs.contains(&(arg.node, arg.output)) => {
+                        cond_exprs.push(input_expr(g, arg, &lvalues));
+                        cond_pats.push(var_pat(&name));
+                        syn::parse_quote! { #ident }
+                    }
+                    Some(arg) => bind_input(&ident, marker, input_expr(g, arg, &lvalues)),
+                    None => {
+                        let expr = default_expr(node.input_default(node::Input(i as u32)));
+                        bind_input(&ident, marker, expr)
+                    }
+                }
+            })
             .collect();
-        let ne = &node_evaluators[&step.node];
-        let n_outputs = ne.n_outputs();
-        let lhs: syn::Pat = lvalues_pat(si, step, n_outputs, &mut lvalues);
-        let n_id = g
-            .node_references()
-            .nth(g.to_index(step.node))
-            .expect("no node for step's node index")
-            .id();
+        let is_conditional = !cond_exprs.is_empty();
+        let lhs: syn::Pat = lvalues_pat(instance_id, step, n_outputs, &mut lvalues);
         let maybe_state_ty = node_state_types.get(&n_id);
         let expr: syn::Expr = ne.expr(args, maybe_state_ty.is_some());
 
         let rhs: syn::Expr = match maybe_state_ty {
             None => expr,
             Some(node_state_ty) => {
-                let expr = syn::parse_quote! {{
+                let msg = format!(
+                    "failed to downcast state of node {} to expected node state type",
+                    instance_id,
+                );
+                let node_state_idx = node_state_indices[&n_id];
+                syn::parse_quote! {{
                     let state: &mut #node_state_ty = _node_states[#node_state_idx]
                         .downcast_mut::<#node_state_ty>()
-                        .expect("failed to downcast to expected node state type");
+                        .expect(#msg);
                     #expr
-                }};
-                node_state_idx += 1;
-                expr
+                }}
+            }
+        };
+
+        // Only evaluate the node if all conditional inputs produced a value.
+        let rhs: syn::Expr = match is_conditional {
+            false => rhs,
+            true => {
+                let is_optional = |vi| ne.is_optional_output(node::Output(vi));
+                let (some_expr, none_expr): (syn::Expr, syn::Expr) = match n_outputs {
+                    0 => (syn::parse_quote! {{ #rhs; }}, syn::parse_quote! { () }),
+                    1 if is_optional(0) => (rhs, syn::parse_quote! { None }),
+                    1 => (syn::parse_quote! { Some(#rhs) }, syn::parse_quote! { None }),
+                    vs => {
+                        let outs: Vec<syn::Ident> = (0..vs)
+                            .map(|vi| {
+                                let name = format!("_output{}", vi);
+                                syn::Ident::new(&name, proc_macro2::Span::call_site())
+                            })
+                            .collect();
+                        let wrapped = outs.iter().enumerate().map(|(vi, out)| {
+                            let expr: syn::Expr = match is_optional(vi as u32) {
+                                true => syn::parse_quote! { #out },
+                                false => syn::parse_quote! { Some(#out) },
+                            };
+                            expr
+                        });
+                        let nones = outs.iter().map(|_| quote::quote! { None });
+                        let some_expr = syn::parse_quote! {{
+                            let (#(#outs),*) = #rhs;
+                            (#(#wrapped),*)
+                        }};
+                        (some_expr, syn::parse_quote! { (#(#nones),*) })
+                    }
+                };
+                syn::parse_quote! {
+                    match (#(#cond_exprs,)*) {
+                        (#(Some(#cond_pats),)*) => #some_expr,
+                        _ => #none_expr,
+                    }
+                }
             }
         };
 
+        // Track the outputs that might not produce a value.
+        for vi in 0..n_outputs {
+            let output = node::Output(vi);
+            if is_conditional || ne.is_optional_output(output) {
+                conditional_outputs.insert((step.node, output));
+            }
+        }
+
+        // The statement is marked, allowing for attributing compiler diagnostics to the node. See
+        // `src_location`.
+        let marker = src_marker(instance_id, None);
         let stmt: syn::Stmt = syn::parse_quote! {
+            #marker
             let #lhs = #rhs;
         };
 
         stmts.push(stmt);
+
+        // Store the value of each output connected to a cold inlet.
+        for e_ref in g.edges_directed(step.node, petgraph::Outgoing) {
+            let w = e_ref.weight();
+            if w.is_hot() {
+                continue;
+            }
+            let ident = match lvalues.get(&(step.node, w.output)) {
+                None => continue,
+                Some(ident) => ident,
+            };
+            let slot = cold_inlet_expr(cold_inlet_indices[&(e_ref.target(), w.input)]);
+            let stmt: syn::Stmt = match conditional_outputs.contains(&(step.node, w.output)) {
+                false => syn::parse_quote! {
+                    *#slot = Some(Box::new(#ident.clone()) as Box<dyn std::any::Any>);
+                },
+                true => syn::parse_quote! {
+                    if let Some(ref value) = #ident {
+                        *#slot = Some(Box::new(value.clone()) as Box<dyn std::any::Any>);
+                    }
+                },
+            };
+            stmts.push(stmt);
+        }
     }
 
-    stmts
+    delayed_reads.extend(stmts);
+    delayed_reads
+}
+
+/// Attribute the code at the given byte offset within a source file generated by `file` to the
+/// node whose evaluation the code belongs to.
+///
+/// The statements generated for the evaluation of each node and for the value passed to each of
+/// its inputs are marked with a `#[doc = "gantz:node <id> input <input>"]` attribute. A marker
+/// applies until the end of the block in which it resides, or until the next marker within the
+/// same block. The innermost marker that applies to the given offset determines the location.
+///
+/// Returns `None` if the code does not belong to the evaluation of any node, e.g. code within a
+/// node state function.
+pub fn src_location(src: &str, offset: usize) -> Option<SrcLocation> {
+    let bytes = src.as_bytes();
+    let end = std::cmp::min(offset, bytes.len());
+    // The markers that apply at the current position, along with the depth of their block.
+    let mut markers: Vec<(usize, SrcLocation)> = vec![];
+    let mut depth = 0;
+    let mut i = 0;
+    while i < end {
+        match bytes[i] {
+            b'{' | b'(' | b'[' => depth += 1,
+            b'}' | b')' | b']' => {
+                while markers.last().map(|&(d, _)| d == depth).unwrap_or(false) {
+                    markers.pop();
+                }
+                depth = depth.saturating_sub(1);
+            }
+            b'"' => {
+                i = skip_str_lit(src, i);
+                continue;
+            }
+            b'\'' => {
+                i = skip_char_lit(src, i);
+                continue;
+            }
+            b'#' => {
+                if let Some((location, next)) = parse_src_marker(src, i) {
+                    if markers.last().map(|&(d, _)| d == depth).unwrap_or(false) {
+                        markers.pop();
+                    }
+                    markers.push((depth, location));
+                    i = next;
+                    continue;
+                }
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+    markers.last().map(|&(_, location)| location)
 }
 
 /// Generate a function for performing evaluation of the given statements.
@@ -496,9 +906,11 @@ pub fn eval_fns<'a, G, I>(
     eval_nodes: I,
     node_state_types: &NodeStateTypeMap<G::NodeId>,
     node_evaluators: &NodeEvaluatorMap<G::NodeId>,
+    instance_ids: &NodeInstanceIdMap<G::NodeId>,
 ) -> Vec<syn::ItemFn>
 where
-    G: GraphRef + IntoNodeReferences + NodeIndexable,
+    G: GraphRef + IntoEdgesDirected + IntoNodeReferences + NodeIndexable,
+    G: Data<EdgeWeight = Edge>,
     G::NodeId: 'a + Eq + Hash,
     G::NodeWeight: Node,
     I: IntoIterator<Item = (G::NodeId, node::EvalFn, &'a [EvalStep<G::NodeId>])>,
@@ -506,7 +918,7 @@ where
     eval_nodes
         .into_iter()
         .map(|(_n, eval, steps)| {
-            let stmts = eval_stmts(g, steps, node_state_types, node_evaluators);
+            let stmts = eval_stmts(g, steps, node_state_types, node_evaluators, instance_ids);
             eval_fn(eval, stmts)
         })
         .collect()
@@ -514,7 +926,31 @@ where
 
 /// Given a gantz graph, generate the rust code src file with all the necessary functions for
 /// executing it.
+///
+/// Nodes are identified within the generated code by their index within the graph. See
+/// `file_with_instance_ids` for graphs whose node indices may be reused.
+///
+/// The graph is expected to be valid. See `graph::validate::diagnostics`.
 pub fn file<G>(g: G, inlets: &[G::NodeId], outlets: &[G::NodeId]) -> syn::File
+where
+    G: GraphRef + IntoEdgesDirected + IntoNodeReferences + NodeIndexable + Visitable,
+    G: Data<EdgeWeight = Edge>,
+    G::NodeId: Eq + Hash,
+    G::NodeWeight: Node,
+{
+    file_with_instance_ids(g, inlets, outlets, &node_instance_ids(g))
+}
+
+/// The same as `file`, but identifies nodes within the generated code by the given instance IDs.
+///
+/// Items are generated in a deterministic order so that an edit to the graph only affects the
+/// generated code for the nodes involved.
+pub fn file_with_instance_ids<G>(
+    g: G,
+    inlets: &[G::NodeId],
+    outlets: &[G::NodeId],
+    instance_ids: &NodeInstanceIdMap<G::NodeId>,
+) -> syn::File
 where
     G: GraphRef + IntoEdgesDirected + IntoNodeReferences + NodeIndexable + Visitable,
     G: Data<EdgeWeight = Edge>,
@@ -523,7 +959,18 @@ where
 {
     let node_state_types = node_state_types(g);
     let node_evaluators = node_evaluators(g);
-    let node_evaluator_fn_items = node_evaluator_fns(&node_state_types, &node_evaluators);
+    let mut node_evaluator_fn_items: Vec<_> =
+        node_evaluator_fns(&node_state_types, &node_evaluators).collect();
+    node_evaluator_fn_items.sort_by_key(|(id, _)| instance_ids.get(id));
+
+    // Multiple instances of the same node produce identical function items, only one of which may
+    // be included within the file.
+    let mut unique_fn_items: Vec<syn::ItemFn> = vec![];
+    for (_, item_fn) in node_evaluator_fn_items {
+        if !unique_fn_items.contains(&item_fn) {
+            unique_fn_items.push(item_fn);
+        }
+    }
 
     let full_eval_steps = match (inlets.is_empty(), outlets.is_empty()) {
         (true, true) => None,
@@ -552,17 +999,18 @@ where
         .chain(pull_node_eval_steps)
         .chain(push_node_eval_steps);
     let all_eval_fn_items = all_eval_steps.map(|(steps, eval)| {
-        let stmts = eval_stmts(g, &steps, &node_state_types, &node_evaluators);
+        let stmts = eval_stmts(g, &steps, &node_state_types, &node_evaluators, instance_ids);
         let item_fn = eval_fn(eval, stmts);
         syn::Item::Fn(item_fn)
     });
 
-    let items = node_evaluator_fn_items
-        .map(|(_, item_fn)| syn::Item::Fn(item_fn.clone()))
+    let items = unique_fn_items
+        .into_iter()
+        .map(syn::Item::Fn)
         .chain(all_eval_fn_items)
         .collect();
 
-    let attrs = vec![allow_unused_braces_attr()];
+    let attrs = vec![allow_unused_braces_attr(), allow_unused_doc_comments_attr()];
 
     let file = syn::File {
         shebang: None,
@@ -572,6 +1020,145 @@ where
     file
 }
 
+/// Generate a function for constructing the default state of each of the given nodes.
+///
+/// The generated function takes the index of a node within the graph and returns the node's
+/// initial state, or `None` if the node is not one of the given `nodes`. Only nodes that provide
+/// their initial state via `Node::default_state` are included, so state types need not implement
+/// `Default`.
+///
+/// This allows hosts to allocate node state without knowing the concrete state types.
+pub fn node_state_default_fn<G, I>(
+    g: G,
+    node_state_types: &NodeStateTypeMap<G::NodeId>,
+    nodes: I,
+) -> syn::ItemFn
+where
+    G: IntoNodeReferences + NodeIndexable,
+    G::NodeId: Eq + Hash,
+    G::NodeWeight: Node,
+    I: IntoIterator<Item = G::NodeId>,
+{
+    let nodes: HashSet<_> = nodes.into_iter().collect();
+    let mut defaults: Vec<_> = g
+        .node_references()
+        .filter(|n_ref| nodes.contains(&n_ref.id()))
+        .filter_map(|n_ref| {
+            let ty = node_state_types.get(&n_ref.id())?;
+            let expr = n_ref.weight().default_state()?;
+            Some((g.to_index(n_ref.id()), ty, expr))
+        })
+        .collect();
+    defaults.sort_by_key(|(ix, _, _)| *ix);
+    let indices = defaults.iter().map(|(ix, _, _)| ix);
+    let types = defaults.iter().map(|(_, ty, _)| ty);
+    let exprs = defaults.iter().map(|(_, _, expr)| expr);
+    let ident = super::node_state_default_fn_ident();
+    syn::parse_quote! {
+        #[no_mangle]
+        pub fn #ident(node_index: usize) -> Option<Box<dyn std::any::Any>> {
+            match node_index {
+                #(
+                    #indices => {
+                        let state: #types = #exprs;
+                        Some(Box::new(state))
+                    }
+                )*
+                _ => None,
+            }
+        }
+    }
+}
+
+/// Generate a function for migrating node state allocated by a previous build of the graph.
+///
+/// The generated function takes the index of a node within the graph along with its previous
+/// state. If the node is one of the given `nodes` and the previous state is of the node's current
+/// state type, the state is moved into a new allocation and returned as `Ok`. Otherwise the
+/// previous state is returned as `Err`. As with `node_state_default_fn`, only nodes that provide
+/// their initial state via `Node::default_state` are included, as the state of all other nodes is
+/// owned by the host.
+///
+/// This allows hosts to carry node state across a reload of the graph's dynamic library, after
+/// which the library that allocated the previous state may be safely unloaded.
+pub fn node_state_migrate_fn<G, I>(
+    g: G,
+    node_state_types: &NodeStateTypeMap<G::NodeId>,
+    nodes: I,
+) -> syn::ItemFn
+where
+    G: IntoNodeReferences + NodeIndexable,
+    G::NodeId: Eq + Hash,
+    G::NodeWeight: Node,
+    I: IntoIterator<Item = G::NodeId>,
+{
+    let nodes: HashSet<_> = nodes.into_iter().collect();
+    let mut migrations: Vec<_> = g
+        .node_references()
+        .filter(|n_ref| nodes.contains(&n_ref.id()))
+        .filter(|n_ref| n_ref.weight().default_state().is_some())
+        .filter_map(|n_ref| {
+            let ty = node_state_types.get(&n_ref.id())?;
+            Some((g.to_index(n_ref.id()), ty))
+        })
+        .collect();
+    migrations.sort_by_key(|(ix, _)| *ix);
+    let (indices, types): (Vec<_>, Vec<_>) = migrations.into_iter().unzip();
+    let ident = super::node_state_migrate_fn_ident();
+    syn::parse_quote! {
+        #[no_mangle]
+        pub fn #ident(
+            node_index: usize,
+            state: Box<dyn std::any::Any>,
+        ) -> Result<Box<dyn std::any::Any>, Box<dyn std::any::Any>> {
+            match node_index {
+                #(
+                    #indices => match state.downcast::<#types>() {
+                        Ok(state) => Ok(Box::new(*state)),
+                        Err(state) => Err(state),
+                    },
+                )*
+                _ => Err(state),
+            }
+        }
+    }
+}
+
+/// Generate a function for describing the state type of each of the given nodes.
+///
+/// The generated function takes the index of a node within the graph and returns the `TypeId` of
+/// the node's state type, or `None` if the node is not one of the given stateful `nodes`.
+///
+/// This allows hosts to check that node states are of the expected type before passing them to
+/// the generated evaluation functions.
+pub fn node_state_type_id_fn<G, I>(
+    g: G,
+    node_state_types: &NodeStateTypeMap<G::NodeId>,
+    nodes: I,
+) -> syn::ItemFn
+where
+    G: NodeIndexable,
+    G::NodeId: Eq + Hash,
+    I: IntoIterator<Item = G::NodeId>,
+{
+    let (indices, types): (Vec<_>, Vec<_>) = nodes
+        .into_iter()
+        .filter_map(|n| node_state_types.get(&n).map(|ty| (g.to_index(n), ty)))
+        .unzip();
+    let ident = super::node_state_type_id_fn_ident();
+    syn::parse_quote! {
+        #[no_mangle]
+        pub fn #ident(node_index: usize) -> Option<std::any::TypeId> {
+            match node_index {
+                #(
+                    #indices => Some(std::any::TypeId::of::<#types>()),
+                )*
+                _ => None,
+            }
+        }
+    }
+}
+
 /// The total set of crate dependencies required for all nodes within the given graph.
 ///
 /// This is useful for filling the `[dependencies]` entry of a generated crate's `Cargo.toml`.
@@ -585,6 +1172,49 @@ where
         .collect()
 }
 
+// A topological ordering of the graph's nodes that ignores delay edges.
+//
+// Nodes that form a cycle via other edges are omitted. See `graph::cycles`.
+fn topo_order<G>(g: G) -> Vec<G::NodeId>
+where
+    G: IntoEdgesDirected + IntoNodeIdentifiers + Visitable,
+    G: Data<EdgeWeight = Edge>,
+{
+    let no_delay = EdgeFiltered::from_fn(g, |e: G::EdgeRef| !e.weight().delay);
+    Topo::new(&no_delay).iter(&no_delay).collect()
+}
+
+// Nodes reachable from `n` in the given direction via a depth-first search that only traverses
+// hot edges.
+fn hot_reachable<G>(g: G, n: G::NodeId, dir: petgraph::Direction) -> Vec<G::NodeId>
+where
+    G: IntoEdgesDirected + Visitable,
+    G: Data<EdgeWeight = Edge>,
+{
+    let mut visited = g.visit_map();
+    let mut stack = vec![n];
+    let mut reachable = vec![];
+    while let Some(n) = stack.pop() {
+        if !visited.visit(n) {
+            continue;
+        }
+        reachable.push(n);
+        for e_ref in g.edges_directed(n, dir) {
+            if !e_ref.weight().is_hot() {
+                continue;
+            }
+            let next = match dir {
+                petgraph::Outgoing => e_ref.target(),
+                petgraph::Incoming => e_ref.source(),
+            };
+            if !visited.is_visited(&next) {
+                stack.push(next);
+            }
+        }
+    }
+    reachable
+}
+
 fn no_mangle_attr() -> syn::Attribute {
     syn::parse_quote! { #[no_mangle] }
 }
@@ -593,6 +1223,89 @@ fn allow_unused_braces_attr() -> syn::Attribute {
     syn::parse_quote! { #![allow(unused_braces)] }
 }
 
+// Source markers are doc attributes on statements, which are otherwise ignored by rustdoc.
+fn allow_unused_doc_comments_attr() -> syn::Attribute {
+    syn::parse_quote! { #![allow(unused_doc_comments)] }
+}
+
+// The prefix of the doc attribute used to mark generated source. See `src_location`.
+const SRC_MARKER_PREFIX: &str = "gantz:node ";
+
+// The attribute marking the source generated for the given node or one of its inputs.
+fn src_marker(instance_id: u64, input: Option<u32>) -> syn::Attribute {
+    let doc = match input {
+        None => format!("{}{}", SRC_MARKER_PREFIX, instance_id),
+        Some(input) => format!("{}{} input {}", SRC_MARKER_PREFIX, instance_id, input),
+    };
+    syn::parse_quote! { #[doc = #doc] }
+}
+
+// Parse the source marker whose `#` is at the given byte index.
+//
+// Returns the location along with the index following the marker.
+fn parse_src_marker(src: &str, i: usize) -> Option<(SrcLocation, usize)> {
+    let rest = src[i + 1..].trim_start().strip_prefix('[')?;
+    let rest = rest.trim_start().strip_prefix("doc")?;
+    let rest = rest.trim_start().strip_prefix('=')?;
+    let rest = rest.trim_start().strip_prefix('"')?;
+    let rest = rest.strip_prefix(SRC_MARKER_PREFIX)?;
+    let doc_end = rest.find('"')?;
+    let after = rest[doc_end + 1..].trim_start().strip_prefix(']')?;
+    let mut words = rest[..doc_end].split(' ');
+    let instance_id = words.next()?.parse().ok()?;
+    let input = match (words.next(), words.next(), words.next()) {
+        (None, _, _) => None,
+        (Some("input"), Some(input), None) => Some(node::Input(input.parse().ok()?)),
+        _ => return None,
+    };
+    let location = SrcLocation { instance_id, input };
+    Some((location, src.len() - after.len()))
+}
+
+// The index following the string literal whose opening quote is at the given byte index.
+//
+// Handles raw string literals, whose `r` and `#`s precede the opening quote.
+fn skip_str_lit(src: &str, i: usize) -> usize {
+    let bytes = src.as_bytes();
+    let hashes = bytes[..i].iter().rev().take_while(|&&b| b == b'#').count();
+    let is_raw = i > hashes && bytes[i - hashes - 1] == b'r' && {
+        let before = &bytes[..i - hashes - 1];
+        let before = before.strip_suffix(b"b").unwrap_or(before);
+        !before
+            .last()
+            .map(|&b| b.is_ascii_alphanumeric() || b == b'_')
+            .unwrap_or(false)
+    };
+    let mut j = i + 1;
+    while j < bytes.len() {
+        match bytes[j] {
+            b'\\' if !is_raw => j += 1,
+            b'"' if !is_raw => return j + 1,
+            b'"' if bytes[j + 1..].iter().take_while(|&&b| b == b'#').count() >= hashes => {
+                return j + 1 + hashes;
+            }
+            _ => (),
+        }
+        j += 1;
+    }
+    bytes.len()
+}
+
+// The index following the character literal whose opening quote is at the given byte index, or
+// the index following the quote in the case that it begins a lifetime.
+fn skip_char_lit(src: &str, i: usize) -> usize {
+    let rest = &src[i + 1..];
+    let len = match rest.chars().next() {
+        Some('\\') => match rest.get(2..).and_then(|r| r.find('\'')) {
+            Some(end) => end + 3,
+            None => return src.len(),
+        },
+        Some(c) if rest[c.len_utf8()..].starts_with('\'') => c.len_utf8() + 1,
+        _ => 0,
+    };
+    i + 1 + len
+}
+
 // A function that produces the `node_states` argument that is to be appended to the evaluation
 // function `inputs`.
 //
@@ -0,0 +1,380 @@
+//! An interpreter for evaluating graphs at runtime without compiling their generated code.
+//!
+//! Each node is evaluated via its native implementation (see `Node::native`). Evaluation is
+//! scheduled via `codegen::eval_order` and `codegen::eval_steps` and follows the same semantics as
+//! the code generated via `codegen::eval_stmts`, including conditional evaluation downstream of
+//! optional outputs and the values stored for **cold** inlets. This allows for previewing changes
+//! to a graph immediately while its dynamic library builds in the background.
+//!
+//! A **Program** accepts the node states in the same layout as the generated evaluation functions:
+//! the state of each node yielded by `codegen::state_order`, followed by the stored value of each
+//! inlet yielded by `codegen::cold_inlet_order`. Programs created via `Program::with_instance_ids`
+//! instead use the layout of the equivalent `_with_instance_ids` functions. As a result, the same
+//! node states may be used with both the interpreter and the generated code.
+//!
+//! The interpreter does not support:
+//!
+//! - Nodes without a native implementation, including nested graph nodes.
+//! - Inputs that fall back to an `InputDefault::Expr`, as the expression cannot be evaluated.
+
+use super::{codegen, Edge};
+use crate::node::{self, Node};
+use petgraph::visit::{
+    Data, EdgeRef, IntoEdgesDirected, IntoNodeReferences, NodeIndexable, NodeRef, Visitable,
+};
+use std::any::Any;
+use std::collections::HashMap;
+use std::hash::Hash;
+use thiserror::Error;
+
+/// A series of evaluation steps prepared for interpretation.
+///
+/// The program holds the native implementation of each evaluated node, so the graph need not be
+/// borrowed during evaluation. The program should be re-created each time the graph changes.
+pub struct Program {
+    steps: Vec<Step>,
+    n_node_states: usize,
+    n_cold_inlets: usize,
+}
+
+// A single node evaluation.
+struct Step {
+    // The index of the node within the graph, used for error reporting.
+    node: usize,
+    native: node::NativeFn,
+    args: Vec<Arg>,
+    // The index of the node's state within the node states.
+    state: Option<usize>,
+    n_outputs: u32,
+    optional_outputs: Vec<node::Output>,
+    // Outputs whose values are stored for cold inlets, along with the index of each inlet within
+    // the node states.
+    cold_stores: Vec<(node::Output, usize)>,
+}
+
+// The source of the value passed to an input.
+enum Arg {
+    // The unit value `()`, passed to unconnected inputs.
+    Unit,
+    // The value produced by an output of the node evaluated at the given step.
+    Output { step: usize, output: node::Output },
+    // The value stored for the cold inlet at the given index within the node states.
+    Stored { index: usize },
+}
+
+/// Errors that may occur while preparing a **Program**.
+#[derive(Debug, Error)]
+pub enum ProgramError {
+    #[error("node {node} does not provide a native implementation")]
+    NoNative { node: usize },
+    #[error("the default expression for input {} of node {node} cannot be interpreted", .input.0)]
+    InputDefaultExpr { node: usize, input: node::Input },
+}
+
+/// Errors that may occur while interpreting a **Program**.
+#[derive(Debug, Error)]
+pub enum EvalError {
+    #[error("expected {expected} node states, found {found}")]
+    NodeStateCount { expected: usize, found: usize },
+    #[error("the node state at index {index} is not the stored value of a cold inlet")]
+    ColdInletType { index: usize },
+    #[error("node {node} produced {found} outputs, expected {expected}")]
+    OutputCount {
+        node: usize,
+        expected: u32,
+        found: usize,
+    },
+    #[error("node {node} produced no value for non-optional output {}", .output.0)]
+    MissingOutput { node: usize, output: node::Output },
+    #[error("failed to evaluate node {node}: {err}")]
+    Native {
+        node: usize,
+        #[source]
+        err: node::NativeError,
+    },
+}
+
+impl Program {
+    /// Prepare the given evaluation order for interpretation.
+    ///
+    /// Returns an error if any of the nodes within the evaluation order does not provide a native
+    /// implementation, or if one of their inputs falls back to an `InputDefault::Expr`.
+    pub fn new<G, I>(g: G, eval_order: I) -> Result<Self, ProgramError>
+    where
+        G: IntoEdgesDirected + IntoNodeReferences + NodeIndexable,
+        G: Data<EdgeWeight = Edge>,
+        G::NodeId: Eq + Hash,
+        G::NodeWeight: Node,
+        I: IntoIterator<Item = G::NodeId>,
+    {
+        Program::with_instance_ids(g, eval_order, &codegen::node_instance_ids(g))
+    }
+
+    /// The same as `new`, but expects node states in the layout determined by the given instance
+    /// IDs, as with the evaluation functions generated via `codegen::file_with_instance_ids`.
+    pub fn with_instance_ids<G, I>(
+        g: G,
+        eval_order: I,
+        instance_ids: &codegen::NodeInstanceIdMap<G::NodeId>,
+    ) -> Result<Self, ProgramError>
+    where
+        G: IntoEdgesDirected + IntoNodeReferences + NodeIndexable,
+        G: Data<EdgeWeight = Edge>,
+        G::NodeId: Eq + Hash,
+        G::NodeWeight: Node,
+        I: IntoIterator<Item = G::NodeId>,
+    {
+        let node_evaluators = codegen::node_evaluators(g);
+        let eval_order: Vec<_> = eval_order.into_iter().collect();
+        let eval_steps = codegen::eval_steps(g, &node_evaluators, eval_order.iter().cloned());
+
+        // The stored values of cold inlets follow the states of the nodes within the node states.
+        let node_state_indices: HashMap<_, _> =
+            codegen::state_order_with_instance_ids(g, eval_order.iter().cloned(), instance_ids)
+                .into_iter()
+                .enumerate()
+                .map(|(i, n)| (n, i))
+                .collect();
+        let n_node_states = node_state_indices.len();
+        let cold_inlet_indices: HashMap<_, _> =
+            codegen::cold_inlet_order_with_instance_ids(g, eval_order, instance_ids)
+                .into_iter()
+                .enumerate()
+                .map(|(i, inlet)| (inlet, n_node_states + i))
+                .collect();
+
+        let mut step_indices = HashMap::new();
+        let mut steps = vec![];
+        for eval_step in eval_steps {
+            let node_ix = g.to_index(eval_step.node);
+            let n_ref = g
+                .node_references()
+                .find(|n_ref| n_ref.id() == eval_step.node)
+                .expect("no node for step's node ID");
+            let node = n_ref.weight();
+            let native = node
+                .native()
+                .ok_or(ProgramError::NoNative { node: node_ix })?;
+            let evaluator = &node_evaluators[&eval_step.node];
+
+            // Determine the source of each argument.
+            let mut args = vec![];
+            for (i, arg) in eval_step.args.iter().enumerate() {
+                let input = node::Input(i as u32);
+                let arg = match arg {
+                    Some(arg) if arg.stored => {
+                        if let (true, node::InputDefault::Expr(_)) =
+                            (arg.delayed, node.input_default(input))
+                        {
+                            return Err(ProgramError::InputDefaultExpr {
+                                node: node_ix,
+                                input,
+                            });
+                        }
+                        let index = cold_inlet_indices[&(eval_step.node, input)];
+                        Arg::Stored { index }
+                    }
+                    Some(arg) => Arg::Output {
+                        step: step_indices[&arg.node],
+                        output: arg.output,
+                    },
+                    None => match node.input_default(input) {
+                        node::InputDefault::Expr(_) => {
+                            return Err(ProgramError::InputDefaultExpr {
+                                node: node_ix,
+                                input,
+                            });
+                        }
+                        node::InputDefault::Unit | node::InputDefault::Required => Arg::Unit,
+                    },
+                };
+                args.push(arg);
+            }
+
+            let state = node_state_indices.get(&eval_step.node).cloned();
+
+            // Outputs connected to a cold inlet are stored after evaluation.
+            let n_outputs = evaluator.n_outputs();
+            let cold_stores = g
+                .edges_directed(eval_step.node, petgraph::Outgoing)
+                .filter(|e_ref| !e_ref.weight().is_hot() && e_ref.weight().output.0 < n_outputs)
+                .map(|e_ref| {
+                    let w = e_ref.weight();
+                    (w.output, cold_inlet_indices[&(e_ref.target(), w.input)])
+                })
+                .collect();
+
+            step_indices.insert(eval_step.node, steps.len());
+            steps.push(Step {
+                node: node_ix,
+                native,
+                args,
+                state,
+                n_outputs,
+                optional_outputs: evaluator.optional_outputs().to_vec(),
+                cold_stores,
+            });
+        }
+
+        Ok(Program {
+            steps,
+            n_node_states,
+            n_cold_inlets: cold_inlet_indices.len(),
+        })
+    }
+
+    /// Prepare push evaluation from the given node for interpretation.
+    ///
+    /// See `codegen::push_eval_order`.
+    pub fn push_eval<G>(g: G, n: G::NodeId) -> Result<Self, ProgramError>
+    where
+        G: IntoEdgesDirected + IntoNodeReferences + NodeIndexable + Visitable,
+        G: Data<EdgeWeight = Edge>,
+        G::NodeId: Eq + Hash,
+        G::NodeWeight: Node,
+    {
+        Program::new(g, codegen::push_eval_order(g, n))
+    }
+
+    /// Prepare pull evaluation from the given node for interpretation.
+    ///
+    /// See `codegen::pull_eval_order`.
+    pub fn pull_eval<G>(g: G, n: G::NodeId) -> Result<Self, ProgramError>
+    where
+        G: IntoEdgesDirected + IntoNodeReferences + NodeIndexable + Visitable,
+        G: Data<EdgeWeight = Edge>,
+        G::NodeId: Eq + Hash,
+        G::NodeWeight: Node,
+    {
+        Program::new(g, codegen::pull_eval_order(g, n))
+    }
+
+    /// The number of node states expected by `eval`.
+    ///
+    /// This includes the state of each stateful node along with the stored value of each cold
+    /// inlet.
+    pub fn n_node_states(&self) -> usize {
+        self.n_node_states + self.n_cold_inlets
+    }
+
+    /// Evaluate the program with the given node states.
+    ///
+    /// The node states are laid out as expected by the equivalent generated evaluation function.
+    /// The stored value of each cold inlet must be an `Option<Box<dyn Any>>`.
+    pub fn eval(&self, node_states: &mut [&mut dyn Any]) -> Result<(), EvalError> {
+        if node_states.len() != self.n_node_states() {
+            return Err(EvalError::NodeStateCount {
+                expected: self.n_node_states(),
+                found: node_states.len(),
+            });
+        }
+
+        // Take the stored values of the cold inlets that are read during evaluation, so that they
+        // may be borrowed alongside the node states. This is equivalent to reading the values
+        // ahead of evaluation, as a cold inlet is only ever written to after it is read.
+        let mut stored = HashMap::new();
+        for step in &self.steps {
+            for arg in &step.args {
+                if let Arg::Stored { index } = *arg {
+                    if let Some(value) = cold_inlet(node_states, index)?.take() {
+                        stored.insert(index, value);
+                    }
+                }
+            }
+        }
+
+        let result = self.eval_steps(node_states, &stored);
+
+        // Restore the values of the cold inlets that were not replaced during evaluation.
+        for (index, value) in stored {
+            let slot = cold_inlet(node_states, index)?;
+            if slot.is_none() {
+                *slot = Some(value);
+            }
+        }
+
+        result
+    }
+
+    // Evaluate each of the steps in order.
+    fn eval_steps(
+        &self,
+        node_states: &mut [&mut dyn Any],
+        stored: &HashMap<usize, Box<dyn Any>>,
+    ) -> Result<(), EvalError> {
+        // The values produced by the outputs of each step.
+        let mut values: Vec<node::native::Outputs> = Vec::with_capacity(self.steps.len());
+        for step in &self.steps {
+            // Retrieve the value for each argument. The node is only evaluated if every argument
+            // has a value, otherwise none of its outputs produce a value.
+            let args: Option<Vec<&dyn Any>> = step
+                .args
+                .iter()
+                .map(|arg| match *arg {
+                    Arg::Unit => Some(&() as &dyn Any),
+                    Arg::Output { step, output } => values[step][output.0 as usize]
+                        .as_ref()
+                        .map(|value| node::Value::as_any(&**value)),
+                    Arg::Stored { index } => stored.get(&index).map(|value| &**value),
+                })
+                .collect();
+
+            let outputs = match args {
+                None => (0..step.n_outputs).map(|_| None).collect(),
+                Some(args) => {
+                    let state = step.state.map(|i| &mut *node_states[i]);
+                    let outputs = (step.native)(&args, state).map_err(|err| EvalError::Native {
+                        node: step.node,
+                        err,
+                    })?;
+                    step.check_outputs(&outputs)?;
+                    outputs
+                }
+            };
+
+            // Store the value of each output connected to a cold inlet.
+            for &(output, index) in &step.cold_stores {
+                if let Some(ref value) = outputs[output.0 as usize] {
+                    let value = node::Value::clone_value(&**value).into_any();
+                    *cold_inlet(node_states, index)? = Some(value);
+                }
+            }
+
+            values.push(outputs);
+        }
+        Ok(())
+    }
+}
+
+impl Step {
+    // Ensure the node produced a value for each non-optional output.
+    fn check_outputs(&self, outputs: &node::native::Outputs) -> Result<(), EvalError> {
+        if outputs.len() != self.n_outputs as usize {
+            return Err(EvalError::OutputCount {
+                node: self.node,
+                expected: self.n_outputs,
+                found: outputs.len(),
+            });
+        }
+        for (i, value) in outputs.iter().enumerate() {
+            let output = node::Output(i as u32);
+            if value.is_none() && !self.optional_outputs.contains(&output) {
+                return Err(EvalError::MissingOutput {
+                    node: self.node,
+                    output,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+// The stored value of the cold inlet at the given index into the node states.
+fn cold_inlet<'a>(
+    node_states: &'a mut [&mut dyn Any],
+    index: usize,
+) -> Result<&'a mut Option<Box<dyn Any>>, EvalError> {
+    node_states[index]
+        .downcast_mut()
+        .ok_or(EvalError::ColdInletType { index })
+}
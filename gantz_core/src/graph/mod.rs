@@ -1,12 +1,20 @@
 use crate::node::{self, Node, SerdeNode};
-use petgraph::visit::{Data, GraphBase};
+use petgraph::visit::{
+    Data, EdgeFiltered, EdgeRef, GraphBase, IntoEdgeReferences, IntoEdges, IntoNodeIdentifiers,
+    IntoNodeReferences, NodeIndexable, NodeRef,
+};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
 use std::ops::{Deref, DerefMut};
 use syn::punctuated::Punctuated;
 use syn::token::Comma;
 use syn::FnArg;
 
 pub mod codegen;
+pub mod interp;
+pub mod types;
+pub mod validate;
 
 /// Required by graphs that support nesting graphs of the same type as nodes.
 pub trait EvaluatorFnBlock: GraphBase {
@@ -49,6 +57,15 @@ pub trait AddNode: Data {
 /// The name of the function generated for performing full evaluation of the graph.
 pub const FULL_EVAL_FN_NAME: &str = "full_eval";
 
+/// The name of the function generated for constructing the default state of a graph's nodes.
+pub const NODE_STATE_DEFAULT_FN_NAME: &str = "node_state_default";
+
+/// The name of the function generated for migrating node state between builds of a graph.
+pub const NODE_STATE_MIGRATE_FN_NAME: &str = "node_state_migrate";
+
+/// The name of the function generated for describing the state type of a graph's nodes.
+pub const NODE_STATE_TYPE_ID_FN_NAME: &str = "node_state_type_id";
+
 /// Describes a connection between two nodes.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub struct Edge {
@@ -56,6 +73,55 @@ pub struct Edge {
     pub output: node::Output,
     /// The input of the node at the destination of this edge.
     pub input: node::Input,
+    /// Whether or not this edge marks an evaluation boundary.
+    ///
+    /// A **cold** edge carries a value but does not propagate evaluation. Pushing from the source
+    /// node will not evaluate the destination node, and pulling from the destination node will not
+    /// evaluate the source node. Instead, the last value produced by the source is stored and read
+    /// by the destination node the next time it is evaluated. Until a value has been stored, the
+    /// destination node is not evaluated.
+    ///
+    /// Values carried by cold edges must be `Clone + 'static`.
+    #[serde(default)]
+    pub cold: bool,
+    /// Whether or not this edge delays its value by one evaluation.
+    ///
+    /// A **delay** edge is like a **cold** edge in that it does not propagate evaluation, however
+    /// the destination node always reads the value stored during a *previous* evaluation, even if
+    /// the source node is evaluated within the same evaluation. Delay edges are ignored when
+    /// determining evaluation order, allowing for feedback loops. See `graph::cycles`.
+    ///
+    /// Until a value has been stored, the destination input's **InputDefault::Expr** is used. If
+    /// the input has no default expression, the destination node is not evaluated.
+    ///
+    /// Values carried by delay edges must be `Clone + 'static`.
+    #[serde(default)]
+    pub delay: bool,
+}
+
+/// An edge that refers to an output or input that does not exist on the nodes it connects.
+///
+/// This may occur after reconfiguring a **DynamicIo** node, or after changing the inlets or
+/// outlets of a nested graph.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct InvalidEdge<NI, EI> {
+    /// The ID of the invalid edge.
+    pub id: EI,
+    /// The node at the source of the edge.
+    pub source: NI,
+    /// The node at the destination of the edge.
+    pub target: NI,
+    /// The edge's weight describing the connected output and input.
+    pub edge: Edge,
+}
+
+/// A required node input that is not connected to any output.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MissingInput<NI> {
+    /// The node with the missing input.
+    pub node: NI,
+    /// The required input.
+    pub input: node::Input,
 }
 
 /// A node that itself is implemented in terms of a graph of nodes.
@@ -73,6 +139,9 @@ where
 }
 
 /// An inlet to a nested graph.
+///
+/// The inlet's type must implement `Default`, as the inlet's state is initialised via
+/// `Default::default()` until a value is received.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Inlet {
     /// The expected type for this inlet.
@@ -81,6 +150,9 @@ pub struct Inlet {
 }
 
 /// An outlet from a nested graph.
+///
+/// The outlet's type must implement `Default`, as the outlet's state is initialised via
+/// `Default::default()` until a value is produced.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Outlet {
     /// The expected type for this outlet.
@@ -92,7 +164,44 @@ impl Edge {
     /// Create an edge representing a connection from the given node `Output` to the given node
     /// `Input`.
     pub fn new(output: node::Output, input: node::Input) -> Self {
-        Edge { output, input }
+        Edge {
+            output,
+            input,
+            cold: false,
+            delay: false,
+        }
+    }
+
+    /// Create a **cold** edge from the given node `Output` to the given node `Input`.
+    ///
+    /// See the `Edge::cold` field for details.
+    pub fn cold(output: node::Output, input: node::Input) -> Self {
+        Edge {
+            output,
+            input,
+            cold: true,
+            delay: false,
+        }
+    }
+
+    /// Create a **delay** edge from the given node `Output` to the given node `Input`.
+    ///
+    /// See the `Edge::delay` field for details.
+    pub fn delay(output: node::Output, input: node::Input) -> Self {
+        Edge {
+            output,
+            input,
+            cold: false,
+            delay: true,
+        }
+    }
+
+    /// Whether or not evaluation propagates across this edge.
+    ///
+    /// This is `false` for both **cold** and **delay** edges, the values of which are stored
+    /// between evaluations.
+    pub fn is_hot(&self) -> bool {
+        !self.cold && !self.delay
     }
 }
 
@@ -197,7 +306,10 @@ where
             sig: *sig,
             block,
         };
-        node::Evaluator::Fn { fn_item }
+        node::Evaluator::Fn {
+            fn_item,
+            optional_outputs: vec![],
+        }
     }
 
     fn state_type(&self) -> Option<syn::Type> {
@@ -358,12 +470,23 @@ impl Node for Inlet {
             n_inputs,
             n_outputs,
             gen_expr,
+            optional_outputs: vec![],
         }
     }
 
     fn state_type(&self) -> Option<syn::Type> {
         Some(self.ty.clone())
     }
+
+    fn default_state(&self) -> Option<syn::Expr> {
+        Some(syn::parse_quote! { Default::default() })
+    }
+
+    fn io_types(&self) -> node::IoTypes {
+        let inputs = vec![];
+        let outputs = vec![Some(self.ty.clone())];
+        node::IoTypes { inputs, outputs }
+    }
 }
 
 impl Node for Outlet {
@@ -383,12 +506,23 @@ impl Node for Outlet {
             n_inputs,
             n_outputs,
             gen_expr,
+            optional_outputs: vec![],
         }
     }
 
     fn state_type(&self) -> Option<syn::Type> {
         Some(self.ty.clone())
     }
+
+    fn default_state(&self) -> Option<syn::Expr> {
+        Some(syn::parse_quote! { Default::default() })
+    }
+
+    fn io_types(&self) -> node::IoTypes {
+        let inputs = vec![Some(self.ty.clone())];
+        let outputs = vec![];
+        node::IoTypes { inputs, outputs }
+    }
 }
 
 impl<N, E, Ty, Ix> AddNode for petgraph::Graph<N, E, Ty, Ix>
@@ -452,8 +586,99 @@ where
     fn from((a, b): (A, B)) -> Self {
         let output = a.into();
         let input = b.into();
-        Edge { output, input }
+        Edge::new(output, input)
+    }
+}
+
+/// Find all edges within the graph that refer to an output or input that does not exist on the
+/// nodes they connect.
+///
+/// Code generation ignores invalid edges, however they should normally be removed from the graph.
+pub fn invalid_edges<G>(g: G) -> Vec<InvalidEdge<G::NodeId, G::EdgeId>>
+where
+    G: IntoEdgeReferences + IntoNodeReferences,
+    G: Data<EdgeWeight = Edge>,
+    G::NodeWeight: Node,
+    G::NodeId: Eq + Hash,
+{
+    let io: HashMap<_, _> = g
+        .node_references()
+        .map(|n| (n.id(), n.weight().evaluator().io()))
+        .collect();
+    g.edge_references()
+        .filter(|e| {
+            let edge = e.weight();
+            let valid_output = io
+                .get(&e.source())
+                .map(|io| edge.output.0 < io.n_outputs)
+                .unwrap_or(false);
+            let valid_input = io
+                .get(&e.target())
+                .map(|io| edge.input.0 < io.n_inputs)
+                .unwrap_or(false);
+            !valid_output || !valid_input
+        })
+        .map(|e| InvalidEdge {
+            id: e.id(),
+            source: e.source(),
+            target: e.target(),
+            edge: *e.weight(),
+        })
+        .collect()
+}
+
+/// Find all inputs whose **InputDefault** is **Required** but that are not connected via a
+/// valid edge.
+///
+/// Missing inputs are ordered by node and then by input.
+pub fn missing_required_inputs<G>(g: G) -> Vec<MissingInput<G::NodeId>>
+where
+    G: IntoEdgeReferences + IntoNodeReferences,
+    G: Data<EdgeWeight = Edge>,
+    G::NodeWeight: Node,
+    G::NodeId: Eq + Hash,
+    G::EdgeId: Eq + Hash,
+{
+    let invalid: HashSet<_> = invalid_edges(g).into_iter().map(|e| e.id).collect();
+    let connected: HashSet<_> = g
+        .edge_references()
+        .filter(|e| !invalid.contains(&e.id()))
+        .map(|e| (e.target(), e.weight().input))
+        .collect();
+    let mut missing = vec![];
+    for n in g.node_references() {
+        let node = n.weight();
+        for i in 0..node.evaluator().n_inputs() {
+            let input = node::Input(i);
+            if let node::InputDefault::Required = node.input_default(input) {
+                if !connected.contains(&(n.id(), input)) {
+                    let node = n.id();
+                    missing.push(MissingInput { node, input });
+                }
+            }
+        }
     }
+    missing
+}
+
+/// Find all cycles formed by edges that are not **delay** edges.
+///
+/// Evaluation order cannot be determined for nodes within such a cycle. Each cycle is yielded as
+/// the set of nodes forming a strongly connected component, including nodes connected to
+/// themselves.
+pub fn cycles<G>(g: G) -> Vec<Vec<G::NodeId>>
+where
+    G: IntoEdges + IntoNodeIdentifiers + NodeIndexable,
+    G: Data<EdgeWeight = Edge>,
+{
+    let no_delay = EdgeFiltered::from_fn(g, |e: G::EdgeRef| !e.weight().delay);
+    petgraph::algo::tarjan_scc(&no_delay)
+        .into_iter()
+        .filter(|scc| match &scc[..] {
+            &[n] => g.edges(n).any(|e| !e.weight().delay && e.target() == n),
+            _ => true,
+        })
+        .collect()
 }
 
 /// The identifier used for graph full eval functions.
@@ -461,6 +686,21 @@ pub fn full_eval_fn_ident() -> syn::Ident {
     syn::Ident::new(FULL_EVAL_FN_NAME, proc_macro2::Span::call_site())
 }
 
+/// The identifier used for generated node state default functions.
+pub fn node_state_default_fn_ident() -> syn::Ident {
+    syn::Ident::new(NODE_STATE_DEFAULT_FN_NAME, proc_macro2::Span::call_site())
+}
+
+/// The identifier used for generated node state migration functions.
+pub fn node_state_migrate_fn_ident() -> syn::Ident {
+    syn::Ident::new(NODE_STATE_MIGRATE_FN_NAME, proc_macro2::Span::call_site())
+}
+
+/// The identifier used for generated node state type ID functions.
+pub fn node_state_type_id_fn_ident() -> syn::Ident {
+    syn::Ident::new(NODE_STATE_TYPE_ID_FN_NAME, proc_macro2::Span::call_site())
+}
+
 /// The function signature for performing full evaluation of a graph.
 ///
 /// A `full_eval_fn` is generated once for every nested graph that contains one or more inlets or
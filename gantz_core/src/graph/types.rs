@@ -0,0 +1,268 @@
+//! Static checking of the types of values passed between nodes.
+//!
+//! Types are known for the inputs and outputs of nodes with **Fn** evaluators, as well as for
+//! graph **Inlet**s and **Outlet**s. See `Node::io_types`. Known output types are propagated along
+//! edges in order to infer the types flowing into nodes whose input types are unknown, e.g.
+//! **Expr** nodes.
+//!
+//! Types are compared structurally. Paths are compared by their final segment, so that
+//! `std::string::String` and `String` are considered to match. Type aliases are not resolved, so
+//! a mismatch is only certain where both types are fully resolved. See `is_resolved`.
+
+use super::Edge;
+use crate::node::{self, Node};
+use petgraph::visit::{Data, EdgeRef, IntoEdgeReferences, IntoNodeReferences, NodeRef};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+/// The known types of the inputs and outputs of the nodes within a graph.
+#[derive(Clone, Debug)]
+pub struct GraphTypes<NI>
+where
+    NI: Eq + Hash,
+{
+    /// The type of each input, either declared by the node or inferred from the connected output.
+    pub inputs: HashMap<(NI, node::Input), syn::Type>,
+    /// The type of the values produced by each output.
+    ///
+    /// For optional outputs, this is the type `T` of the `Option<T>` value produced by the node.
+    pub outputs: HashMap<(NI, node::Output), syn::Type>,
+}
+
+/// An edge whose source output type does not match its destination input type.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TypeMismatch<NI, EI> {
+    /// The ID of the edge.
+    pub edge: EI,
+    /// The node at the source of the edge.
+    pub source: NI,
+    /// The output of the source node.
+    pub output: node::Output,
+    /// The type of the values produced by the output.
+    pub output_ty: syn::Type,
+    /// The node at the destination of the edge.
+    pub target: NI,
+    /// The input of the destination node.
+    pub input: node::Input,
+    /// The type expected by the input.
+    pub input_ty: syn::Type,
+    /// Whether or not both types are fully resolved, in which case the types certainly differ.
+    ///
+    /// Otherwise, one of the types may refer to the other via a type alias or import. See
+    /// `is_resolved`.
+    pub resolved: bool,
+}
+
+/// Determine the known types of all node inputs and outputs within the graph.
+///
+/// The types of inputs that are not declared by their node are inferred from the connected
+/// output, if it is known. Edges referring to an output or input that does not exist are ignored.
+pub fn infer<G>(g: G) -> GraphTypes<G::NodeId>
+where
+    G: IntoEdgeReferences + IntoNodeReferences,
+    G: Data<EdgeWeight = Edge>,
+    G::NodeWeight: Node,
+    G::NodeId: Eq + Hash,
+    G::EdgeId: Eq + Hash,
+{
+    let mut inputs = HashMap::new();
+    let mut outputs = HashMap::new();
+
+    // Collect the declared types.
+    for n in g.node_references() {
+        let node = n.weight();
+        let eval = node.evaluator();
+        let io_types = node.io_types();
+        for (i, ty) in io_types.inputs.into_iter().enumerate() {
+            if let Some(ty) = ty {
+                inputs.insert((n.id(), node::Input(i as u32)), ty);
+            }
+        }
+        for (i, ty) in io_types.outputs.into_iter().enumerate() {
+            let output = node::Output(i as u32);
+            let ty = match (ty, eval.is_optional_output(output)) {
+                (Some(ty), false) => ty,
+                (Some(ty), true) => match option_inner_type(&ty) {
+                    Some(inner) => inner.clone(),
+                    None => continue,
+                },
+                (None, _) => continue,
+            };
+            outputs.insert((n.id(), output), ty);
+        }
+    }
+
+    // Infer the types of undeclared inputs from their connected outputs.
+    let declared: HashSet<_> = inputs.keys().cloned().collect();
+    let invalid: HashSet<_> = super::invalid_edges(g).into_iter().map(|e| e.id).collect();
+    for e_ref in g.edge_references() {
+        if invalid.contains(&e_ref.id()) {
+            continue;
+        }
+        let w = e_ref.weight();
+        let inlet = (e_ref.target(), w.input);
+        if declared.contains(&inlet) {
+            continue;
+        }
+        if let Some(ty) = outputs.get(&(e_ref.source(), w.output)) {
+            inputs.insert(inlet, ty.clone());
+        }
+    }
+
+    GraphTypes { inputs, outputs }
+}
+
+/// Find all edges whose source output type does not match the declared type of the destination
+/// input.
+///
+/// Edges are only checked where the types at both ends are known.
+pub fn mismatches<G>(g: G) -> Vec<TypeMismatch<G::NodeId, G::EdgeId>>
+where
+    G: IntoEdgeReferences + IntoNodeReferences,
+    G: Data<EdgeWeight = Edge>,
+    G::NodeWeight: Node,
+    G::NodeId: Eq + Hash,
+    G::EdgeId: Eq + Hash,
+{
+    let declared_inputs: HashMap<_, _> = g
+        .node_references()
+        .map(|n| (n.id(), n.weight().io_types().inputs))
+        .collect();
+    let types = infer(g);
+    let mut mismatches = vec![];
+    for e_ref in g.edge_references() {
+        let w = e_ref.weight();
+        let output_ty = match types.outputs.get(&(e_ref.source(), w.output)) {
+            None => continue,
+            Some(ty) => ty,
+        };
+        let input_ty = match declared_inputs
+            .get(&e_ref.target())
+            .and_then(|inputs| inputs.get(w.input.0 as usize))
+        {
+            Some(Some(ty)) => ty,
+            _ => continue,
+        };
+        if !types_match(output_ty, input_ty) {
+            mismatches.push(TypeMismatch {
+                edge: e_ref.id(),
+                source: e_ref.source(),
+                output: w.output,
+                output_ty: output_ty.clone(),
+                target: e_ref.target(),
+                input: w.input,
+                input_ty: input_ty.clone(),
+                resolved: is_resolved(output_ty) && is_resolved(input_ty),
+            });
+        }
+    }
+    mismatches
+}
+
+/// Whether or not the two given types may refer to the same type.
+///
+/// References are compared regardless of their lifetimes and paths are compared by their final
+/// segment and its generic arguments.
+pub fn types_match(a: &syn::Type, b: &syn::Type) -> bool {
+    use syn::Type;
+    match (a, b) {
+        (Type::Paren(a), _) => types_match(&a.elem, b),
+        (_, Type::Paren(b)) => types_match(a, &b.elem),
+        (Type::Group(a), _) => types_match(&a.elem, b),
+        (_, Type::Group(b)) => types_match(a, &b.elem),
+        (Type::Path(a), Type::Path(b)) => match (&a.qself, &b.qself) {
+            (None, None) => paths_match(&a.path, &b.path),
+            _ => a == b,
+        },
+        (Type::Reference(a), Type::Reference(b)) => {
+            a.mutability.is_some() == b.mutability.is_some() && types_match(&a.elem, &b.elem)
+        }
+        (Type::Ptr(a), Type::Ptr(b)) => {
+            a.mutability.is_some() == b.mutability.is_some() && types_match(&a.elem, &b.elem)
+        }
+        (Type::Tuple(a), Type::Tuple(b)) => {
+            a.elems.len() == b.elems.len()
+                && a.elems.iter().zip(&b.elems).all(|(a, b)| types_match(a, b))
+        }
+        (Type::Slice(a), Type::Slice(b)) => types_match(&a.elem, &b.elem),
+        (Type::Array(a), Type::Array(b)) => types_match(&a.elem, &b.elem) && a.len == b.len,
+        _ => a == b,
+    }
+}
+
+/// Whether or not the meaning of the given type is independent of any type aliases or imports.
+///
+/// This is the case for the primitive types, along with references, pointers, tuples, slices and
+/// arrays of such types. Paths to other types are never considered resolved, as even paths into
+/// the standard library may refer to aliases, e.g. `std::os::raw::c_float`.
+pub fn is_resolved(ty: &syn::Type) -> bool {
+    use syn::Type;
+    match ty {
+        Type::Paren(ty) => is_resolved(&ty.elem),
+        Type::Group(ty) => is_resolved(&ty.elem),
+        Type::Reference(ty) => is_resolved(&ty.elem),
+        Type::Ptr(ty) => is_resolved(&ty.elem),
+        Type::Slice(ty) => is_resolved(&ty.elem),
+        Type::Array(ty) => is_resolved(&ty.elem),
+        Type::Tuple(ty) => ty.elems.iter().all(is_resolved),
+        Type::Path(ty) if ty.qself.is_none() => is_primitive(&ty.path),
+        _ => false,
+    }
+}
+
+// Whether or not the given path refers to a primitive type.
+fn is_primitive(path: &syn::Path) -> bool {
+    const PRIMITIVES: &[&str] = &[
+        "bool", "char", "str", "f32", "f64", "i8", "i16", "i32", "i64", "i128", "isize", "u8",
+        "u16", "u32", "u64", "u128", "usize",
+    ];
+    match path.get_ident() {
+        None => false,
+        Some(ident) => PRIMITIVES.iter().any(|p| ident == p),
+    }
+}
+
+// Whether or not the two paths may refer to the same type.
+fn paths_match(a: &syn::Path, b: &syn::Path) -> bool {
+    let (a, b) = match (a.segments.last(), b.segments.last()) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return a == b,
+    };
+    if a.ident != b.ident {
+        return false;
+    }
+    match (&a.arguments, &b.arguments) {
+        (syn::PathArguments::AngleBracketed(a), syn::PathArguments::AngleBracketed(b)) => {
+            a.args.len() == b.args.len()
+                && a.args.iter().zip(&b.args).all(|(a, b)| match (a, b) {
+                    (syn::GenericArgument::Type(a), syn::GenericArgument::Type(b)) => {
+                        types_match(a, b)
+                    }
+                    (syn::GenericArgument::Lifetime(_), syn::GenericArgument::Lifetime(_)) => true,
+                    (a, b) => a == b,
+                })
+        }
+        (a, b) => a == b,
+    }
+}
+
+// The type `T` if the given type is an `Option<T>`.
+fn option_inner_type(ty: &syn::Type) -> Option<&syn::Type> {
+    let path = match ty {
+        syn::Type::Path(ty_path) if ty_path.qself.is_none() => &ty_path.path,
+        _ => return None,
+    };
+    let segment = path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match segment.arguments {
+        syn::PathArguments::AngleBracketed(ref args) if args.args.len() == 1 => {
+            match args.args[0] {
+                syn::GenericArgument::Type(ref ty) => Some(ty),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
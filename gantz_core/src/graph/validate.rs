@@ -0,0 +1,251 @@
+//! Items for checking the validity of a graph ahead of code generation.
+
+use super::Edge;
+use crate::node::{self, Node};
+use petgraph::visit::{Data, EdgeRef, IntoEdges, IntoNodeReferences, NodeIndexable, NodeRef};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::Hash;
+
+/// A problem found within a graph that would cause code generation to fail or to produce
+/// incorrect code.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Diagnostic<NI, EI> {
+    /// The kind of problem.
+    pub kind: DiagnosticKind,
+    /// Whether or not the problem prevents code generation.
+    pub severity: Severity,
+    /// The nodes involved in the problem.
+    pub nodes: Vec<NI>,
+    /// The edges involved in the problem.
+    pub edges: Vec<EI>,
+    /// A human readable description of the problem.
+    pub message: String,
+}
+
+/// The kinds of problems that may be found within a graph.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DiagnosticKind {
+    /// An edge refers to an output or input that does not exist on the nodes it connects.
+    InvalidEdge,
+    /// More than one edge connects to the same input.
+    DuplicateInput { input: node::Input },
+    /// The nodes form a cycle that is not broken by a **delay** edge.
+    Cycle,
+    /// A required input is not connected.
+    MissingRequiredInput { input: node::Input },
+    /// The type produced by the output at the source of an edge does not match the type expected
+    /// by the input at its destination.
+    TypeMismatch {
+        output: node::Output,
+        input: node::Input,
+    },
+    /// A graph inlet refers to a node that does not exist or that has no state type.
+    InvalidInlet,
+    /// A graph outlet refers to a node that does not exist or that has no state type.
+    InvalidOutlet,
+}
+
+/// The severity of a **Diagnostic**.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Severity {
+    /// The problem prevents code generation.
+    Error,
+    /// A possible problem that does not prevent code generation.
+    Warning,
+}
+
+// A node input along with the IDs of the edges connected to it.
+type InputEdges<NI, EI> = ((NI, node::Input), Vec<EI>);
+
+impl<NI, EI> Diagnostic<NI, EI> {
+    /// Whether or not the diagnostic is an error that prevents code generation.
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl<NI, EI> fmt::Display for Diagnostic<NI, EI> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Check the given graph and its inlets and outlets for problems that would cause code generation
+/// to fail or to produce incorrect code.
+///
+/// The arguments match those of `graph::codegen::file`. An empty list indicates a valid graph.
+/// Diagnostics with **Severity::Warning** describe possible problems that do not prevent code
+/// generation.
+///
+/// Diagnostics are ordered by kind in the order in which the **DiagnosticKind** variants are
+/// declared.
+pub fn diagnostics<G>(
+    g: G,
+    inlets: &[G::NodeId],
+    outlets: &[G::NodeId],
+) -> Vec<Diagnostic<G::NodeId, G::EdgeId>>
+where
+    G: IntoEdges + IntoNodeReferences + NodeIndexable,
+    G: Data<EdgeWeight = Edge>,
+    G::NodeWeight: Node,
+    G::NodeId: Eq + Hash,
+    G::EdgeId: Eq + Hash,
+{
+    let mut diagnostics = vec![];
+
+    // Edges referring to outputs or inputs that do not exist.
+    let invalid = super::invalid_edges(g);
+    let invalid_ids: HashSet<_> = invalid.iter().map(|e| e.id).collect();
+    for e in invalid {
+        let message = format!(
+            "edge from output {} of node {} to input {} of node {} refers to a non-existent \
+             output or input",
+            e.edge.output.0,
+            g.to_index(e.source),
+            e.edge.input.0,
+            g.to_index(e.target),
+        );
+        diagnostics.push(Diagnostic {
+            kind: DiagnosticKind::InvalidEdge,
+            severity: Severity::Error,
+            nodes: vec![e.source, e.target],
+            edges: vec![e.id],
+            message,
+        });
+    }
+
+    // Inputs with more than one incoming edge.
+    let mut inputs: Vec<InputEdges<G::NodeId, G::EdgeId>> = vec![];
+    let mut input_indices: HashMap<(G::NodeId, node::Input), usize> = HashMap::new();
+    for e_ref in g.edge_references() {
+        if invalid_ids.contains(&e_ref.id()) {
+            continue;
+        }
+        let inlet = (e_ref.target(), e_ref.weight().input);
+        let ix = *input_indices.entry(inlet).or_insert_with(|| {
+            inputs.push((inlet, vec![]));
+            inputs.len() - 1
+        });
+        inputs[ix].1.push(e_ref.id());
+    }
+    for ((n, input), edges) in inputs {
+        if edges.len() < 2 {
+            continue;
+        }
+        let message = format!(
+            "input {} of node {} has {} incoming edges, but only one is allowed",
+            input.0,
+            g.to_index(n),
+            edges.len(),
+        );
+        diagnostics.push(Diagnostic {
+            kind: DiagnosticKind::DuplicateInput { input },
+            severity: Severity::Error,
+            nodes: vec![n],
+            edges,
+            message,
+        });
+    }
+
+    // Cycles not broken by a delay edge.
+    for nodes in super::cycles(g) {
+        let ixs: Vec<_> = nodes.iter().map(|&n| g.to_index(n).to_string()).collect();
+        let message = format!(
+            "nodes [{}] form a cycle that is not broken by a delay edge",
+            ixs.join(", "),
+        );
+        diagnostics.push(Diagnostic {
+            kind: DiagnosticKind::Cycle,
+            severity: Severity::Error,
+            nodes,
+            edges: vec![],
+            message,
+        });
+    }
+
+    // Required inputs that are not connected.
+    for missing in super::missing_required_inputs(g) {
+        let message = format!(
+            "required input {} of node {} is not connected",
+            missing.input.0,
+            g.to_index(missing.node),
+        );
+        diagnostics.push(Diagnostic {
+            kind: DiagnosticKind::MissingRequiredInput {
+                input: missing.input,
+            },
+            severity: Severity::Error,
+            nodes: vec![missing.node],
+            edges: vec![],
+            message,
+        });
+    }
+
+    // Edges whose output and input types are known but do not match.
+    //
+    // Types that are not fully resolved may refer to the same type via an alias or import, so
+    // these mismatches are only reported as warnings.
+    for m in super::types::mismatches(g) {
+        let output_ty = &m.output_ty;
+        let input_ty = &m.input_ty;
+        let severity = match m.resolved {
+            true => Severity::Error,
+            false => Severity::Warning,
+        };
+        let message = format!(
+            "output {} of node {} produces `{}` but input {} of node {} expects `{}`",
+            m.output.0,
+            g.to_index(m.source),
+            quote::quote! { #output_ty },
+            m.input.0,
+            g.to_index(m.target),
+            quote::quote! { #input_ty },
+        );
+        diagnostics.push(Diagnostic {
+            kind: DiagnosticKind::TypeMismatch {
+                output: m.output,
+                input: m.input,
+            },
+            severity,
+            nodes: vec![m.source, m.target],
+            edges: vec![m.edge],
+            message,
+        });
+    }
+
+    // Inlets and outlets must refer to nodes with a state type.
+    let has_state_type = |id: G::NodeId| {
+        g.node_references()
+            .find(|n| n.id() == id)
+            .map(|n| n.weight().state_type().is_some())
+            .unwrap_or(false)
+    };
+    let io = inlets
+        .iter()
+        .map(|&n| (n, DiagnosticKind::InvalidInlet, "inlet"))
+        .chain(
+            outlets
+                .iter()
+                .map(|&n| (n, DiagnosticKind::InvalidOutlet, "outlet")),
+        );
+    for (n, kind, name) in io {
+        if has_state_type(n) {
+            continue;
+        }
+        let message = format!(
+            "{} node {} does not exist or has no state type",
+            name,
+            g.to_index(n),
+        );
+        diagnostics.push(Diagnostic {
+            kind,
+            severity: Severity::Error,
+            nodes: vec![n],
+            edges: vec![],
+            message,
+        });
+    }
+
+    diagnostics
+}
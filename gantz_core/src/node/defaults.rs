@@ -0,0 +1,83 @@
+use super::{Deserialize, Serialize};
+use crate::node::{self, Node};
+
+/// A trait implemented for all **Node** types allowing to specify the values passed to their
+/// inputs when unconnected.
+pub trait WithInputDefaults: Sized + Node {
+    /// Consume `self` and return a `Node` that uses the given defaults for its unconnected inputs.
+    ///
+    /// The `defaults` are indexed by input. Inputs beyond the end of `defaults` fall back to the
+    /// inner node's defaults.
+    fn with_input_defaults(self, defaults: Vec<node::InputDefault>) -> InputDefaults<Self>;
+}
+
+/// A wrapper around a **Node** that specifies the values passed to its unconnected inputs.
+///
+/// The implementation of `Node` will match the inner node type `N`, but with an `input_default`
+/// implementation that first checks the specified `defaults`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct InputDefaults<N> {
+    pub node: N,
+    /// The default for each input, indexed by input.
+    pub defaults: Vec<node::InputDefault>,
+}
+
+impl<N> InputDefaults<N> {
+    /// Given some node, return an **InputDefaults** node with the given input defaults.
+    pub fn new(node: N, defaults: Vec<node::InputDefault>) -> Self {
+        InputDefaults { node, defaults }
+    }
+}
+
+impl<N> WithInputDefaults for N
+where
+    N: Node,
+{
+    fn with_input_defaults(self, defaults: Vec<node::InputDefault>) -> InputDefaults<Self> {
+        InputDefaults::new(self, defaults)
+    }
+}
+
+impl<N> Node for InputDefaults<N>
+where
+    N: Node,
+{
+    fn evaluator(&self) -> node::Evaluator {
+        self.node.evaluator()
+    }
+
+    fn push_eval(&self) -> Option<node::EvalFn> {
+        self.node.push_eval()
+    }
+
+    fn pull_eval(&self) -> Option<node::EvalFn> {
+        self.node.pull_eval()
+    }
+
+    fn state_type(&self) -> Option<syn::Type> {
+        self.node.state_type()
+    }
+
+    fn default_state(&self) -> Option<syn::Expr> {
+        self.node.default_state()
+    }
+
+    fn crate_deps(&self) -> Vec<node::CrateDep> {
+        self.node.crate_deps()
+    }
+
+    fn io_types(&self) -> node::IoTypes {
+        self.node.io_types()
+    }
+
+    fn input_default(&self, input: node::Input) -> node::InputDefault {
+        match self.defaults.get(input.0 as usize) {
+            Some(default) => default.clone(),
+            None => self.node.input_default(input),
+        }
+    }
+
+    fn native(&self) -> Option<node::NativeFn> {
+        self.node.native()
+    }
+}
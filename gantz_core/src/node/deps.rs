@@ -76,7 +76,23 @@ where
         self.node.state_type()
     }
 
+    fn default_state(&self) -> Option<syn::Expr> {
+        self.node.default_state()
+    }
+
     fn crate_deps(&self) -> Vec<node::CrateDep> {
         self.crate_deps.clone()
     }
+
+    fn io_types(&self) -> node::IoTypes {
+        self.node.io_types()
+    }
+
+    fn input_default(&self, input: node::Input) -> node::InputDefault {
+        self.node.input_default(input)
+    }
+
+    fn native(&self) -> Option<node::NativeFn> {
+        self.node.native()
+    }
 }
@@ -85,6 +85,7 @@ impl Node for Expr {
             n_inputs,
             n_outputs,
             gen_expr,
+            optional_outputs: vec![],
         }
     }
 }
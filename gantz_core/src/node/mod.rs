@@ -2,15 +2,22 @@ use super::{Deserialize, Serialize};
 use std::str::FromStr;
 use thiserror::Error;
 
+pub mod defaults;
 pub mod deps;
 pub mod expr;
+pub mod native;
+pub mod optional;
 pub mod pull;
 pub mod push;
 pub mod serde;
 pub mod state;
 
+pub use self::defaults::{InputDefaults, WithInputDefaults};
 pub use self::deps::{Deps, WithCrateDeps};
 pub use self::expr::{Expr, NewExprError};
+pub use self::native::{Native, NativeError, NativeFn, SerdeNative};
+pub use self::native::{SerdeNativeFn, Value, WithNative};
+pub use self::optional::{Optional, WithOptionalOutputs};
 pub use self::pull::{Pull, WithPullEval};
 pub use self::push::{Push, WithPushEval};
 pub use self::serde::SerdeNode;
@@ -84,6 +91,17 @@ pub trait Node {
         None
     }
 
+    /// An expression producing the initial value of the node's state.
+    ///
+    /// Hosts use this to allocate the node's state without knowing its concrete type. See
+    /// `graph::codegen::node_state_default_fn`. The expression is evaluated with the node's
+    /// `state_type` as its expected type, e.g. `Default::default()`.
+    ///
+    /// By default, this is **None**, in which case the host must provide the node's state.
+    fn default_state(&self) -> Option<syn::Expr> {
+        None
+    }
+
     /// Specify a list of crate dependencies that should be in scope and available to all other
     /// code generated by all instances of this node.
     ///
@@ -94,6 +112,52 @@ pub trait Node {
     fn crate_deps(&self) -> Vec<CrateDep> {
         vec![]
     }
+
+    /// The types of the node's inputs and outputs, where known.
+    ///
+    /// These are used to check the types of the values passed between nodes ahead of code
+    /// generation. See `graph::types`.
+    ///
+    /// By default, the types are determined via `Evaluator::io_types`.
+    fn io_types(&self) -> IoTypes {
+        self.evaluator().io_types()
+    }
+
+    /// The value passed to the given input when it is not connected.
+    ///
+    /// This is also used when the input is connected, but the connected node is not evaluated
+    /// as a part of the same evaluation.
+    ///
+    /// By default, this is **InputDefault::Unit**.
+    fn input_default(&self, _input: Input) -> InputDefault {
+        InputDefault::Unit
+    }
+
+    /// A native rust implementation of the node's evaluator.
+    ///
+    /// This allows for evaluating graphs containing the node via `graph::interp` without first
+    /// compiling the generated code, e.g. for previewing a graph while its dynamic library builds.
+    /// The implementation must produce the same outputs as the node's **Evaluator**.
+    ///
+    /// By default, this is **None**, in which case the node may only be evaluated via the
+    /// generated code.
+    fn native(&self) -> Option<NativeFn> {
+        None
+    }
+}
+
+/// Implemented for nodes whose number of inputs and outputs depends on some configuration.
+///
+/// E.g. a node that packs `N` values into a tuple, or a variadic sum.
+///
+/// Graphs containing the node may hold edges to inputs or outputs that no longer exist after the
+/// node is reconfigured. See `graph::invalid_edges`.
+pub trait DynamicIo: Node {
+    /// Reconfigure the node to have the given number of inputs and outputs.
+    ///
+    /// Returns an error if the node does not support the given configuration, in which case the
+    /// node must remain unchanged.
+    fn set_io(&mut self, io: Io) -> Result<(), UnsupportedIoError>;
 }
 
 /// The method of evaluation used for a node.
@@ -113,6 +177,11 @@ pub enum Evaluator {
         /// A free-standing function, including its name, signature, the block and other
         /// attributes.
         fn_item: syn::ItemFn,
+        /// Outputs that may or may not produce a value upon evaluation.
+        ///
+        /// The function's return type for each of these outputs must be an `Option<T>`. See
+        /// `Evaluator::optional_outputs` for details.
+        optional_outputs: Vec<Output>,
     },
     /// Expressions have the benefit of not needing to know the exact types of a node's inputs and
     /// outputs. This simplifies the implementation of the `Node` trait for users.
@@ -123,6 +192,11 @@ pub enum Evaluator {
         n_inputs: u32,
         /// The number of outputs to the expression.
         n_outputs: u32,
+        /// Outputs that may or may not produce a value upon evaluation.
+        ///
+        /// The expression must evaluate to an `Option<T>` for each of these outputs. See
+        /// `Evaluator::optional_outputs` for details.
+        optional_outputs: Vec<Output>,
     },
 }
 
@@ -170,6 +244,44 @@ pub struct CrateDep {
     pub source: String,
 }
 
+/// The number of inputs and outputs of a node.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Deserialize, Serialize)]
+pub struct Io {
+    /// The number of inputs to the node.
+    pub n_inputs: u32,
+    /// The number of outputs from the node.
+    pub n_outputs: u32,
+}
+
+/// The types of the inputs and outputs of a node.
+///
+/// Each type is **None** if unknown, e.g. for the inputs and outputs of **Expr** evaluators or for
+/// types that depend on generic parameters. Optional outputs are described by their `Option<T>`
+/// type.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct IoTypes {
+    /// The type of each input to the node.
+    pub inputs: Vec<Option<syn::Type>>,
+    /// The type of each output from the node.
+    pub outputs: Vec<Option<syn::Type>>,
+}
+
+/// Describes the value passed to a node's input when it is not connected.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Deserialize, Serialize)]
+pub enum InputDefault {
+    /// The unit value `()` is passed.
+    #[default]
+    Unit,
+    /// The given expression is evaluated and passed.
+    Expr(#[serde(with = "crate::node::serde::expr")] syn::Expr),
+    /// The input must be connected.
+    ///
+    /// Graphs in which the input is not connected are reported via
+    /// `graph::missing_required_inputs`. If the input is connected but the connected node is not
+    /// evaluated as a part of the same evaluation, `()` is passed.
+    Required,
+}
+
 /// Represents an input of a node via an index.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Deserialize, Serialize)]
 pub struct Input(pub u32);
@@ -178,6 +290,14 @@ pub struct Input(pub u32);
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Deserialize, Serialize)]
 pub struct Output(pub u32);
 
+/// The requested I/O configuration is not supported by a **DynamicIo** node.
+#[derive(Clone, Debug, Error)]
+#[error("the node does not support {} inputs and {} outputs", .io.n_inputs, .io.n_outputs)]
+pub struct UnsupportedIoError {
+    /// The unsupported configuration.
+    pub io: Io,
+}
+
 /// Failure to parse a `str` as a `CrateDep`.
 #[derive(Clone, Debug, Error)]
 #[error("failed to parse the `str` as a valid `CrateDep`")]
@@ -187,7 +307,7 @@ impl Evaluator {
     /// The number of inputs to the node.
     pub fn n_inputs(&self) -> u32 {
         match *self {
-            Evaluator::Fn { ref fn_item } => count_fn_inputs(&fn_item.sig) as _,
+            Evaluator::Fn { ref fn_item, .. } => count_fn_inputs(&fn_item.sig) as _,
             Evaluator::Expr { n_inputs, .. } => n_inputs as _,
         }
     }
@@ -195,17 +315,69 @@ impl Evaluator {
     /// The number of outputs to the node.
     pub fn n_outputs(&self) -> u32 {
         match *self {
-            Evaluator::Fn { ref fn_item } => count_fn_outputs(&fn_item.sig) as _,
+            Evaluator::Fn { ref fn_item, .. } => count_fn_outputs(&fn_item.sig) as _,
             Evaluator::Expr { n_outputs, .. } => n_outputs as _,
         }
     }
 
+    /// The number of inputs and outputs of the node.
+    pub fn io(&self) -> Io {
+        Io {
+            n_inputs: self.n_inputs(),
+            n_outputs: self.n_outputs(),
+        }
+    }
+
+    /// The types of the node's inputs and outputs, where known.
+    ///
+    /// The types of **Fn** evaluators are determined by the function signature. Types that refer
+    /// to one of the function's generic type parameters, contain `impl Trait` or contain the `_`
+    /// placeholder are unknown. The types of **Expr** evaluators are always unknown.
+    pub fn io_types(&self) -> IoTypes {
+        match *self {
+            Evaluator::Fn { ref fn_item, .. } => fn_io_types(&fn_item.sig),
+            Evaluator::Expr {
+                n_inputs,
+                n_outputs,
+                ..
+            } => IoTypes {
+                inputs: vec![None; n_inputs as usize],
+                outputs: vec![None; n_outputs as usize],
+            },
+        }
+    }
+
+    /// The outputs of the node that may or may not produce a value upon evaluation.
+    ///
+    /// Each optional output is represented by an `Option<T>` in the generated code. Nodes that
+    /// are connected downstream of an optional output are only evaluated when the output yields
+    /// **Some** value, in which case they receive the inner `T`. If the output yields **None**,
+    /// the downstream nodes are skipped along with everything that depends on them. This allows
+    /// for nodes that act as gates, routers or that only fire when some value changes.
+    pub fn optional_outputs(&self) -> &[Output] {
+        match *self {
+            Evaluator::Fn {
+                ref optional_outputs,
+                ..
+            }
+            | Evaluator::Expr {
+                ref optional_outputs,
+                ..
+            } => optional_outputs,
+        }
+    }
+
+    /// Whether or not the given output may or may not produce a value upon evaluation.
+    pub fn is_optional_output(&self, output: Output) -> bool {
+        self.optional_outputs().contains(&output)
+    }
+
     /// Tokens representing the rust code that will evaluate to a tuple containing all outputs.
     ///
     /// TODO: Handle case where only a subset of inputs are connected. See issue #17.
     pub fn expr(&self, args: Vec<syn::Expr>, stateful: bool) -> syn::Expr {
         match *self {
-            Evaluator::Fn { ref fn_item } => fn_call_expr(fn_item, args, stateful),
+            Evaluator::Fn { ref fn_item, .. } => fn_call_expr(fn_item, args, stateful),
             Evaluator::Expr { ref gen_expr, .. } => (*gen_expr)(args),
         }
     }
@@ -231,9 +403,25 @@ where
         (**self).state_type()
     }
 
+    fn default_state(&self) -> Option<syn::Expr> {
+        (**self).default_state()
+    }
+
     fn crate_deps(&self) -> Vec<CrateDep> {
         (**self).crate_deps()
     }
+
+    fn io_types(&self) -> IoTypes {
+        (**self).io_types()
+    }
+
+    fn input_default(&self, input: Input) -> InputDefault {
+        (**self).input_default(input)
+    }
+
+    fn native(&self) -> Option<NativeFn> {
+        (**self).native()
+    }
 }
 
 macro_rules! impl_node_for_ptr {
@@ -255,9 +443,25 @@ macro_rules! impl_node_for_ptr {
                 (**self).state_type()
             }
 
+            fn default_state(&self) -> Option<syn::Expr> {
+                (**self).default_state()
+            }
+
             fn crate_deps(&self) -> Vec<CrateDep> {
                 (**self).crate_deps()
             }
+
+            fn io_types(&self) -> IoTypes {
+                (**self).io_types()
+            }
+
+            fn input_default(&self, input: Input) -> InputDefault {
+                (**self).input_default(input)
+            }
+
+            fn native(&self) -> Option<NativeFn> {
+                (**self).native()
+            }
         }
     };
 }
@@ -280,6 +484,24 @@ impl From<syn::ItemFn> for EvalFn {
     }
 }
 
+impl Io {
+    /// Create an I/O configuration with the given number of inputs and outputs.
+    pub fn new(n_inputs: u32, n_outputs: u32) -> Self {
+        Io {
+            n_inputs,
+            n_outputs,
+        }
+    }
+}
+
+impl InputDefault {
+    /// A short-hand for constructing an **InputDefault::Expr** from a `str`.
+    pub fn expr(expr: &str) -> syn::Result<Self> {
+        let expr: syn::Expr = syn::parse_str(expr)?;
+        Ok(InputDefault::Expr(expr))
+    }
+}
+
 impl From<u32> for Input {
     fn from(u: u32) -> Self {
         Input(u)
@@ -310,6 +532,24 @@ pub fn expr(expr: &str) -> Result<Expr, NewExprError> {
     Expr::new(expr)
 }
 
+/// The name of the **Node** type generated for the function with the given `snake_case` name.
+///
+/// The name is converted to `UpperCamelCase`, e.g. `add_f32` produces `AddF32`. Used by both the
+/// `#[node]` attribute and `gantz::wrap` so that generated nodes are named consistently.
+pub fn fn_node_type_name(fn_name: &str) -> String {
+    fn_name
+        .split('_')
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .into_iter()
+                .flat_map(|c| c.to_uppercase())
+                .chain(chars)
+        })
+        .collect()
+}
+
 // Count the number of arguments to the given function.
 //
 // This is used to determine the number of inputs to the function.
@@ -330,6 +570,48 @@ fn count_fn_outputs(signature: &syn::Signature) -> usize {
     }
 }
 
+// The known types of the inputs and outputs of a function node with the given signature.
+fn fn_io_types(signature: &syn::Signature) -> IoTypes {
+    let generics: Vec<_> = signature
+        .generics
+        .type_params()
+        .map(|param| param.ident.to_string())
+        .collect();
+    let known = |ty: &syn::Type| match is_concrete(quote::ToTokens::to_token_stream(ty), &generics)
+    {
+        true => Some(ty.clone()),
+        false => None,
+    };
+    let inputs = signature
+        .inputs
+        .iter()
+        .map(|arg| match arg {
+            syn::FnArg::Typed(ref pat_ty) => known(&pat_ty.ty),
+            syn::FnArg::Receiver(_) => None,
+        })
+        .collect();
+    let outputs = match signature.output {
+        syn::ReturnType::Default => vec![],
+        syn::ReturnType::Type(ref _r_arrow, ref ty) => match **ty {
+            syn::Type::Tuple(ref tuple) => tuple.elems.iter().map(known).collect(),
+            ref ty => vec![known(ty)],
+        },
+    };
+    IoTypes { inputs, outputs }
+}
+
+// Whether or not the given type tokens are free of generic parameters, `impl Trait` and `_`.
+fn is_concrete(tokens: proc_macro2::TokenStream, generics: &[String]) -> bool {
+    tokens.into_iter().all(|tt| match tt {
+        proc_macro2::TokenTree::Group(group) => is_concrete(group.stream(), generics),
+        proc_macro2::TokenTree::Ident(ident) => {
+            let ident = ident.to_string();
+            ident != "impl" && ident != "_" && !generics.contains(&ident)
+        }
+        _ => true,
+    })
+}
+
 // Create a rust expression that calls the given `signature` function with the given `args`
 // expressions as its inputs.
 fn fn_call_expr(fn_item: &syn::ItemFn, args: Vec<syn::Expr>, stateful: bool) -> syn::Expr {
@@ -0,0 +1,276 @@
+use super::{Deserialize, Serialize};
+use crate::node::{self, Node};
+use std::any::{self, Any};
+use std::fmt;
+use std::rc::Rc;
+use thiserror::Error;
+
+/// A native rust implementation of a node's evaluator.
+///
+/// The function receives a reference to the value passed to each of the node's inputs along with
+/// the node's state (if the node is stateful) and returns a value for each of the node's outputs.
+/// Outputs marked as optional by the node's **Evaluator** may return **None**, while all other
+/// outputs must return **Some** value.
+///
+/// Native implementations allow for evaluating a graph at runtime via `graph::interp` without
+/// first compiling the graph's generated code. They are expected to produce the same outputs
+/// as the node's **Evaluator**.
+pub type NativeFn = Rc<dyn Fn(&[&dyn Any], Option<&mut dyn Any>) -> Result<Outputs, NativeError>>;
+
+/// The values produced for each of a node's outputs by a **NativeFn**.
+pub type Outputs = Vec<Option<Box<dyn Value>>>;
+
+/// A value passed between nodes by the interpreter.
+///
+/// Implemented for all `Clone` types, allowing values to be stored for **cold** inlets in the
+/// same form as the generated code.
+pub trait Value: Any {
+    /// Clone the value into a new box.
+    fn clone_value(&self) -> Box<dyn Value>;
+    /// Upcast the value to `&dyn Any`.
+    fn as_any(&self) -> &dyn Any;
+    /// Upcast the boxed value to `Box<dyn Any>`.
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+/// A native implementation that may be serialized along with its node.
+///
+/// Unlike a **NativeFn**, implementations are named types registered via `typetag`, allowing
+/// nodes with a native implementation to be stored within a project. See **SerdeNative**.
+#[typetag::serde(tag = "type")]
+pub trait SerdeNativeFn {
+    /// Produce the native implementation of the node's evaluator.
+    fn native_fn(&self) -> NativeFn;
+}
+
+/// A trait implemented for all **Node** types allowing to provide a native implementation.
+pub trait WithNative: Sized + Node {
+    /// Consume `self` and return a **Node** with the given native implementation.
+    fn with_native(self, native: NativeFn) -> Native<Self>;
+
+    /// A short-hand for `with_native` that boxes the given function.
+    fn with_native_fn<F>(self, native: F) -> Native<Self>
+    where
+        F: 'static + Fn(&[&dyn Any], Option<&mut dyn Any>) -> Result<Outputs, NativeError>,
+    {
+        self.with_native(Rc::new(native))
+    }
+
+    /// Consume `self` and return a serializable **Node** with the given native implementation.
+    fn with_serde_native<F>(self, native: F) -> SerdeNative<Self>
+    where
+        F: 'static + SerdeNativeFn,
+    {
+        SerdeNative::new(self, Box::new(native))
+    }
+}
+
+/// A wrapper around a **Node** that provides a native implementation of its evaluator.
+///
+/// The implementation of `Node` will match the inner node type `N`, but with a unique
+/// implementation of `Node::native`.
+#[derive(Clone)]
+pub struct Native<N> {
+    pub node: N,
+    /// The native implementation of the node's evaluator.
+    pub native: NativeFn,
+}
+
+/// The same as **Native**, but with a serializable native implementation.
+///
+/// See **SerdeNativeFn**.
+#[derive(Deserialize, Serialize)]
+pub struct SerdeNative<N> {
+    pub node: N,
+    /// The native implementation of the node's evaluator.
+    pub native: Box<dyn SerdeNativeFn>,
+}
+
+/// Errors that may occur within a **NativeFn**.
+#[derive(Debug, Error)]
+pub enum NativeError {
+    #[error("no value was passed to input {input}")]
+    NoInput { input: usize },
+    #[error("the value passed to input {input} is not of the expected type `{expected}`")]
+    InputType {
+        input: usize,
+        expected: &'static str,
+    },
+    #[error("no state was provided for the node")]
+    NoState,
+    #[error("the node state is not of the expected type `{expected}`")]
+    StateType { expected: &'static str },
+}
+
+impl<N> Native<N> {
+    /// Given some node, return a **Native** node with the given native implementation.
+    pub fn new(node: N, native: NativeFn) -> Self {
+        Native { node, native }
+    }
+}
+
+impl<N> SerdeNative<N> {
+    /// Given some node, return a **SerdeNative** node with the given native implementation.
+    pub fn new(node: N, native: Box<dyn SerdeNativeFn>) -> Self {
+        SerdeNative { node, native }
+    }
+}
+
+impl<T> Value for T
+where
+    T: Any + Clone,
+{
+    fn clone_value(&self) -> Box<dyn Value> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+impl<N> WithNative for N
+where
+    N: Node,
+{
+    fn with_native(self, native: NativeFn) -> Native<Self> {
+        Native::new(self, native)
+    }
+}
+
+impl<N> fmt::Debug for Native<N>
+where
+    N: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Native").field("node", &self.node).finish()
+    }
+}
+
+impl<N> fmt::Debug for SerdeNative<N>
+where
+    N: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SerdeNative")
+            .field("node", &self.node)
+            .finish()
+    }
+}
+
+impl<N> Node for Native<N>
+where
+    N: Node,
+{
+    fn evaluator(&self) -> node::Evaluator {
+        self.node.evaluator()
+    }
+
+    fn push_eval(&self) -> Option<node::EvalFn> {
+        self.node.push_eval()
+    }
+
+    fn pull_eval(&self) -> Option<node::EvalFn> {
+        self.node.pull_eval()
+    }
+
+    fn state_type(&self) -> Option<syn::Type> {
+        self.node.state_type()
+    }
+
+    fn default_state(&self) -> Option<syn::Expr> {
+        self.node.default_state()
+    }
+
+    fn crate_deps(&self) -> Vec<node::CrateDep> {
+        self.node.crate_deps()
+    }
+
+    fn io_types(&self) -> node::IoTypes {
+        self.node.io_types()
+    }
+
+    fn input_default(&self, input: node::Input) -> node::InputDefault {
+        self.node.input_default(input)
+    }
+
+    fn native(&self) -> Option<NativeFn> {
+        Some(self.native.clone())
+    }
+}
+
+impl<N> Node for SerdeNative<N>
+where
+    N: Node,
+{
+    fn evaluator(&self) -> node::Evaluator {
+        self.node.evaluator()
+    }
+
+    fn push_eval(&self) -> Option<node::EvalFn> {
+        self.node.push_eval()
+    }
+
+    fn pull_eval(&self) -> Option<node::EvalFn> {
+        self.node.pull_eval()
+    }
+
+    fn state_type(&self) -> Option<syn::Type> {
+        self.node.state_type()
+    }
+
+    fn default_state(&self) -> Option<syn::Expr> {
+        self.node.default_state()
+    }
+
+    fn crate_deps(&self) -> Vec<node::CrateDep> {
+        self.node.crate_deps()
+    }
+
+    fn io_types(&self) -> node::IoTypes {
+        self.node.io_types()
+    }
+
+    fn input_default(&self, input: node::Input) -> node::InputDefault {
+        self.node.input_default(input)
+    }
+
+    fn native(&self) -> Option<NativeFn> {
+        Some(self.native.native_fn())
+    }
+}
+
+/// Downcast the value passed to the given input to `T`.
+pub fn input<'a, T>(args: &[&'a dyn Any], input: usize) -> Result<&'a T, NativeError>
+where
+    T: Any,
+{
+    let arg: &'a dyn Any = *args.get(input).ok_or(NativeError::NoInput { input })?;
+    arg.downcast_ref().ok_or(NativeError::InputType {
+        input,
+        expected: any::type_name::<T>(),
+    })
+}
+
+/// Downcast the given node state to `T`.
+pub fn state<T>(state: Option<&mut dyn Any>) -> Result<&mut T, NativeError>
+where
+    T: Any,
+{
+    let state = state.ok_or(NativeError::NoState)?;
+    state.downcast_mut().ok_or(NativeError::StateType {
+        expected: any::type_name::<T>(),
+    })
+}
+
+/// Produce the value for an output.
+pub fn output<T>(value: T) -> Option<Box<dyn Value>>
+where
+    T: Value,
+{
+    Some(Box::new(value))
+}
@@ -0,0 +1,101 @@
+use super::{Deserialize, Serialize};
+use crate::node::{self, Node};
+
+/// A trait implemented for all **Node** types allowing to mark some of their outputs as optional.
+/// This is particularly useful for **Expr** nodes that act as gates or routers.
+pub trait WithOptionalOutputs: Sized + Node {
+    /// Consume `self` and return a `Node` whose given outputs are optional.
+    ///
+    /// The node's expression must evaluate to an `Option<T>` for each of the given outputs.
+    fn with_optional_outputs(self, outputs: Vec<node::Output>) -> Optional<Self>;
+
+    /// A short-hand for `with_optional_outputs` for nodes with a single, optional output.
+    fn with_optional_output(self) -> Optional<Self> {
+        self.with_optional_outputs(vec![node::Output(0)])
+    }
+}
+
+/// A wrapper around a **Node** that marks some of its outputs as optional.
+///
+/// The implementation of `Node` will match the inner node type `N`, but with an `Evaluator` whose
+/// `optional_outputs` also contains the specified outputs.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Optional<N> {
+    pub node: N,
+    /// The outputs that may or may not produce a value upon evaluation.
+    pub outputs: Vec<node::Output>,
+}
+
+impl<N> Optional<N> {
+    /// Given some node, return an **Optional** node whose given outputs are optional.
+    pub fn new(node: N, outputs: Vec<node::Output>) -> Self {
+        Optional { node, outputs }
+    }
+}
+
+impl<N> WithOptionalOutputs for N
+where
+    N: Node,
+{
+    fn with_optional_outputs(self, outputs: Vec<node::Output>) -> Optional<Self> {
+        Optional::new(self, outputs)
+    }
+}
+
+impl<N> Node for Optional<N>
+where
+    N: Node,
+{
+    fn evaluator(&self) -> node::Evaluator {
+        let mut eval = self.node.evaluator();
+        match eval {
+            node::Evaluator::Fn {
+                ref mut optional_outputs,
+                ..
+            }
+            | node::Evaluator::Expr {
+                ref mut optional_outputs,
+                ..
+            } => {
+                for &output in &self.outputs {
+                    if !optional_outputs.contains(&output) {
+                        optional_outputs.push(output);
+                    }
+                }
+            }
+        }
+        eval
+    }
+
+    fn push_eval(&self) -> Option<node::EvalFn> {
+        self.node.push_eval()
+    }
+
+    fn pull_eval(&self) -> Option<node::EvalFn> {
+        self.node.pull_eval()
+    }
+
+    fn state_type(&self) -> Option<syn::Type> {
+        self.node.state_type()
+    }
+
+    fn default_state(&self) -> Option<syn::Expr> {
+        self.node.default_state()
+    }
+
+    fn crate_deps(&self) -> Vec<node::CrateDep> {
+        self.node.crate_deps()
+    }
+
+    fn io_types(&self) -> node::IoTypes {
+        self.node.io_types()
+    }
+
+    fn input_default(&self, input: node::Input) -> node::InputDefault {
+        self.node.input_default(input)
+    }
+
+    fn native(&self) -> Option<node::NativeFn> {
+        self.node.native()
+    }
+}
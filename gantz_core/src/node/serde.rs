@@ -5,6 +5,13 @@ use crate::node::{self, Node};
 #[typetag::serde(tag = "type")]
 pub trait SerdeNode {
     fn node(&self) -> &dyn Node;
+
+    /// Access to the node's **DynamicIo** implementation, allowing it to be reconfigured.
+    ///
+    /// By default, this is **None**, indicating a node with a fixed number of inputs and outputs.
+    fn dynamic_io_mut(&mut self) -> Option<&mut dyn node::DynamicIo> {
+        None
+    }
 }
 
 #[typetag::serde]
@@ -35,6 +42,62 @@ impl SerdeNode for node::State<node::Expr> {
     }
 }
 
+#[typetag::serde]
+impl SerdeNode for node::InputDefaults<node::Expr> {
+    fn node(&self) -> &dyn Node {
+        self
+    }
+}
+
+#[typetag::serde]
+impl SerdeNode for node::Optional<node::Expr> {
+    fn node(&self) -> &dyn Node {
+        self
+    }
+}
+
+#[typetag::serde]
+impl SerdeNode for node::State<node::Optional<node::Expr>> {
+    fn node(&self) -> &dyn Node {
+        self
+    }
+}
+
+#[typetag::serde(name = "SerdeNative<Expr>")]
+impl SerdeNode for node::SerdeNative<node::Expr> {
+    fn node(&self) -> &dyn Node {
+        self
+    }
+}
+
+#[typetag::serde(name = "Push<SerdeNative<Expr>>")]
+impl SerdeNode for node::Push<node::SerdeNative<node::Expr>> {
+    fn node(&self) -> &dyn Node {
+        self
+    }
+}
+
+#[typetag::serde(name = "Pull<SerdeNative<Expr>>")]
+impl SerdeNode for node::Pull<node::SerdeNative<node::Expr>> {
+    fn node(&self) -> &dyn Node {
+        self
+    }
+}
+
+#[typetag::serde(name = "State<SerdeNative<Expr>>")]
+impl SerdeNode for node::State<node::SerdeNative<node::Expr>> {
+    fn node(&self) -> &dyn Node {
+        self
+    }
+}
+
+#[typetag::serde(name = "Optional<SerdeNative<Expr>>")]
+impl SerdeNode for node::Optional<node::SerdeNative<node::Expr>> {
+    fn node(&self) -> &dyn Node {
+        self
+    }
+}
+
 pub mod signature {
     use serde::{Deserializer, Serializer};
 
@@ -141,3 +204,50 @@ pub mod ty {
         Ok(ty)
     }
 }
+
+pub mod expr {
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S>(expr: &syn::Expr, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        super::tts::serialize(expr, s)
+    }
+
+    pub fn deserialize<'de, D>(d: D) -> Result<syn::Expr, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let tts = super::tts::deserialize(d)?;
+        let expr: syn::Expr = syn::parse_quote! { #tts };
+        Ok(expr)
+    }
+}
+
+pub mod opt_expr {
+    use quote::ToTokens;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(expr: &Option<syn::Expr>, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *expr {
+            None => s.serialize_none(),
+            Some(ref expr) => s.serialize_some(&format!("{}", expr.into_token_stream())),
+        }
+    }
+
+    pub fn deserialize<'de, D>(d: D) -> Result<Option<syn::Expr>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<String>::deserialize(d)? {
+            None => Ok(None),
+            Some(string) => syn::parse_str(&string)
+                .map(Some)
+                .map_err(serde::de::Error::custom),
+        }
+    }
+}
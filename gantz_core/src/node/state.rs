@@ -12,6 +12,20 @@ pub trait WithStateType: Sized + Node {
         let ty: syn::Type = syn::parse_str(state_type)?;
         Ok(self.with_state_type(ty))
     }
+
+    /// The same as `with_state_type`, but the state is initialised via `Default::default()`.
+    ///
+    /// This allows hosts to allocate the node's state. See `Node::default_state`.
+    fn with_default_state_type(self, state_type: syn::Type) -> State<Self> {
+        self.with_state_type(state_type)
+            .with_default_state(syn::parse_quote! { Default::default() })
+    }
+
+    /// A short-hand for `with_default_state_type` - allows for describing the type via a `str`.
+    fn with_default_state_ty(self, state_type: &str) -> syn::Result<State<Self>> {
+        let ty: syn::Type = syn::parse_str(state_type)?;
+        Ok(self.with_default_state_type(ty))
+    }
 }
 
 /// A wrapper around a **Node** that adds some persistent state.
@@ -21,12 +35,25 @@ pub struct State<N> {
     /// Attributes for the generated `ItemFn`.
     #[serde(with = "crate::node::serde::ty")]
     pub state_type: syn::Type,
+    /// An expression producing the initial state, if any. See `Node::default_state`.
+    #[serde(default, with = "crate::node::serde::opt_expr")]
+    pub default_state: Option<syn::Expr>,
 }
 
 impl<N> State<N> {
     /// Given some node, return a **State** node enabling access to state of the given type.
     pub fn new(node: N, state_type: syn::Type) -> Self {
-        State { node, state_type }
+        State {
+            node,
+            state_type,
+            default_state: None,
+        }
+    }
+
+    /// Initialise the state via the given expression. See `Node::default_state`.
+    pub fn with_default_state(mut self, default_state: syn::Expr) -> Self {
+        self.default_state = Some(default_state);
+        self
     }
 }
 
@@ -59,7 +86,23 @@ where
         Some(self.state_type.clone())
     }
 
+    fn default_state(&self) -> Option<syn::Expr> {
+        self.default_state.clone()
+    }
+
     fn crate_deps(&self) -> Vec<node::CrateDep> {
         self.node.crate_deps()
     }
+
+    fn io_types(&self) -> node::IoTypes {
+        self.node.io_types()
+    }
+
+    fn input_default(&self, input: node::Input) -> node::InputDefault {
+        self.node.input_default(input)
+    }
+
+    fn native(&self) -> Option<node::NativeFn> {
+        self.node.native()
+    }
 }
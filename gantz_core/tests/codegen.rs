@@ -0,0 +1,51 @@
+// Testing the attribution of generated code to the nodes of the graph.
+
+use gantz_core::graph::codegen::{self, SrcLocation};
+use gantz_core::node::{self, Node, WithPushEval};
+use gantz_core::Edge;
+use quote::ToTokens;
+
+type Graph = petgraph::stable_graph::StableGraph<Box<dyn Node>, Edge>;
+
+// Code within a node's expression may resemble the generated code, e.g. variables named after
+// other nodes, functions and unbalanced delimiters within literals.
+//
+//    --------
+//    | push | // push_eval
+//    -+------
+//     |
+//    -+-----
+//    | one |
+//    -+-----
+//     |
+//    -+-------
+//    | float |
+//    ---------
+#[test]
+fn test_src_location() {
+    let mut g = Graph::default();
+    let push = node::expr("()").unwrap().with_push_eval_name("push");
+    let one = r#"{ #push; let _node2 = "} { ("; fn f() -> u32 { 1 } let c = '}'; f() }"#;
+    let float = r##"{ let x: u32 = #x; let s = r#"{"#; x }"##;
+    let p = g.add_node(Box::new(push));
+    let o = g.add_node(Box::new(node::expr(one).unwrap()));
+    let f = g.add_node(Box::new(node::expr(float).unwrap()));
+    g.add_edge(p, o, Edge::from((0, 0)));
+    g.add_edge(o, f, Edge::from((0, 0)));
+    let src = codegen::file(&g, &[], &[]).into_token_stream().to_string();
+
+    let location = |pat: &str| {
+        let offset = src.find(pat).expect("pattern not found");
+        codegen::src_location(&src, offset)
+    };
+    let node = |instance_id, input: Option<u32>| {
+        let input = input.map(node::Input);
+        Some(SrcLocation { instance_id, input })
+    };
+    assert_eq!(location("f ()"), node(1, None));
+    assert_eq!(location("let c"), node(1, None));
+    assert_eq!(location("let x"), node(2, None));
+    assert_eq!(location("_node2_input0 }"), node(2, Some(0)));
+    assert_eq!(location("let s"), node(2, None));
+    assert_eq!(codegen::src_location(&src, src.len()), None);
+}
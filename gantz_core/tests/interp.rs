@@ -0,0 +1,168 @@
+// Testing evaluation of graphs via the interpreter.
+
+use gantz_core::graph::interp::{EvalError, Program, ProgramError};
+use gantz_core::node::{self, native, Node, WithInputDefaults, WithNative, WithOptionalOutputs};
+use gantz_core::node::{WithPushEval, WithStateType};
+use gantz_core::Edge;
+use std::any::Any;
+
+type Graph = petgraph::stable_graph::StableGraph<Box<dyn Node>, Edge>;
+
+fn node_push(push_eval_name: &str) -> impl Node {
+    node::expr("()")
+        .unwrap()
+        .with_native_fn(|_args, _state| Ok(vec![native::output(())]))
+        .with_push_eval_name(push_eval_name)
+}
+
+// A simple counter node.
+//
+// Increases its `u32` state by `1` each time it receives an input of any type.
+fn node_counter() -> impl Node {
+    node::expr(r#"{ #push; let count = *state; *state += 1; count }"#)
+        .unwrap()
+        .with_native_fn(|_args, state| {
+            let state = native::state::<u32>(state)?;
+            let count = *state;
+            *state += 1;
+            Ok(vec![native::output(count)])
+        })
+        .with_state_ty("u32")
+        .unwrap()
+}
+
+// A gate that only produces a value for even inputs.
+fn node_even() -> impl Node {
+    node::expr(r#"{ let n: u32 = #n; if n % 2 == 0 { Some(n) } else { None } }"#)
+        .unwrap()
+        .with_native_fn(|args, _state| {
+            let n = *native::input::<u32>(args, 0)?;
+            Ok(vec![if n % 2 == 0 { native::output(n) } else { None }])
+        })
+        .with_optional_output()
+}
+
+// Stores the value received on its second input each time its first input is triggered.
+fn node_store() -> impl Node {
+    node::expr(r#"{ #push; let value: u32 = #value; *state = value; }"#)
+        .unwrap()
+        .with_native_fn(|args, state| {
+            let value = *native::input::<u32>(args, 1)?;
+            *native::state::<u32>(state)? = value;
+            Ok(vec![native::output(())])
+        })
+        .with_state_ty("u32")
+        .unwrap()
+}
+
+// Mirrors `test_graph_with_gate`, where half of the nodes are only evaluated on every second push.
+#[test]
+fn test_interp_gate() {
+    let mut g = Graph::default();
+    let p = g.add_node(Box::new(node_push("push")));
+    let c_a = g.add_node(Box::new(node_counter()));
+    let e = g.add_node(Box::new(node_even()));
+    let c_b = g.add_node(Box::new(node_counter()));
+    let c_c = g.add_node(Box::new(node_counter()));
+    g.add_edge(p, c_a, Edge::from((0, 0)));
+    g.add_edge(c_a, e, Edge::from((0, 0)));
+    g.add_edge(e, c_b, Edge::from((0, 0)));
+    g.add_edge(c_b, c_c, Edge::from((0, 0)));
+
+    let program = Program::push_eval(&g, p).unwrap();
+    assert_eq!(program.n_node_states(), 3);
+    let mut counts = [0u32; 3];
+    for _ in 0..5 {
+        let [ref mut a, ref mut b, ref mut c] = counts;
+        program.eval(&mut [a, b, c]).unwrap();
+    }
+    assert_eq!(counts, [5, 3, 3]);
+}
+
+// Mirrors `test_graph_with_cold_edge`, where the value produced by the counter is stored for the
+// cold inlet of the store.
+#[test]
+fn test_interp_cold_edge() {
+    let mut g = Graph::default();
+    let p_a = g.add_node(Box::new(node_push("push_a")));
+    let p_b = g.add_node(Box::new(node_push("push_b")));
+    let c = g.add_node(Box::new(node_counter()));
+    let s = g.add_node(Box::new(node_store()));
+    g.add_edge(p_a, c, Edge::from((0, 0)));
+    g.add_edge(p_b, s, Edge::from((0, 0)));
+    g.add_edge(c, s, Edge::cold(0.into(), 1.into()));
+
+    let push_a = Program::push_eval(&g, p_a).unwrap();
+    let push_b = Program::push_eval(&g, p_b).unwrap();
+    let mut count = 0u32;
+    let mut stored = 0u32;
+    let mut cold_inlet: Option<Box<dyn Any>> = None;
+
+    // The store is not evaluated until the counter has produced a value.
+    push_b.eval(&mut [&mut stored, &mut cold_inlet]).unwrap();
+    assert!(cold_inlet.is_none());
+    assert_eq!(stored, 0);
+
+    // Pushing from the counter stores the value without evaluating the store.
+    for _ in 0..3 {
+        push_a.eval(&mut [&mut count, &mut cold_inlet]).unwrap();
+    }
+    assert_eq!(count, 3);
+    assert_eq!(stored, 0);
+
+    // The value is stored in the same form as the generated code.
+    let value = cold_inlet
+        .as_ref()
+        .and_then(|value| value.downcast_ref::<u32>());
+    assert_eq!(value, Some(&2));
+
+    // Pushing from `push_b` reads the last value produced by the counter and leaves it in place.
+    push_b.eval(&mut [&mut stored, &mut cold_inlet]).unwrap();
+    assert_eq!(stored, 2);
+    assert!(cold_inlet.is_some());
+}
+
+// Graphs that cannot be interpreted are reported, as are node states of the wrong layout.
+#[test]
+fn test_interp_errors() {
+    // Nodes must provide a native implementation.
+    let mut g = Graph::default();
+    let p = g.add_node(Box::new(node_push("push")));
+    let e = g.add_node(Box::new(node::expr("#a + 1").unwrap()));
+    g.add_edge(p, e, Edge::from((0, 0)));
+    match Program::push_eval(&g, p) {
+        Err(ProgramError::NoNative { node }) => assert_eq!(node, e.index()),
+        _ => panic!("expected `NoNative` error"),
+    }
+
+    // Default expressions cannot be interpreted.
+    let mut g = Graph::default();
+    let s = node_store().with_input_defaults(vec![
+        node::InputDefault::Unit,
+        node::InputDefault::expr("1").unwrap(),
+    ]);
+    let p = g.add_node(Box::new(node_push("push")));
+    let s = g.add_node(Box::new(s));
+    g.add_edge(p, s, Edge::from((0, 0)));
+    match Program::push_eval(&g, p) {
+        Err(ProgramError::InputDefaultExpr { node, input }) => {
+            assert_eq!((node, input), (s.index(), node::Input(1)));
+        }
+        _ => panic!("expected `InputDefaultExpr` error"),
+    }
+
+    // Node states must match the expected layout.
+    let mut g = Graph::default();
+    let p = g.add_node(Box::new(node_push("push")));
+    let c = g.add_node(Box::new(node_counter()));
+    g.add_edge(p, c, Edge::from((0, 0)));
+    let program = Program::push_eval(&g, p).unwrap();
+    match program.eval(&mut []) {
+        Err(EvalError::NodeStateCount { expected, found }) => assert_eq!((expected, found), (1, 0)),
+        _ => panic!("expected `NodeStateCount` error"),
+    }
+    match program.eval(&mut [&mut 0i64]) {
+        Err(EvalError::Native { node, .. }) => assert_eq!(node, c.index()),
+        _ => panic!("expected `Native` error"),
+    }
+}
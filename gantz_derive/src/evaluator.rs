@@ -0,0 +1,128 @@
+//! Items shared by the macros for generating the evaluator function of a node.
+//!
+//! The generated evaluator function is compiled within the crate generated for a graph, rather
+//! than the crate in which the node is defined. As a result, paths to items within the defining
+//! crate are only known once `module_path!()` is expanded within the defining crate. The source of
+//! the function is produced with placeholders for these paths, which are replaced when the node's
+//! evaluator is requested.
+
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
+use quote::quote;
+
+// Replaced with the result of `module_path!()` within the defining crate.
+const MODULE_PATH_PLACEHOLDER: &str = "__gantz_module_path";
+
+// Replaced with the result of `module_path!()` with each `::` replaced by `_`, for use in idents.
+const MODULE_IDENT_PLACEHOLDER: &str = "__gantz_module_ident";
+
+// Replaced with the name of the defining crate.
+const CRATE_PLACEHOLDER: &str = "__gantz_crate";
+
+/// An ident that is replaced with the defining module's path in the evaluator source.
+pub fn module_path_placeholder() -> syn::Ident {
+    syn::Ident::new(MODULE_PATH_PLACEHOLDER, Span::call_site())
+}
+
+/// An ident that is replaced with the defining module's path in `snake_case`, followed by the
+/// given suffix.
+pub fn module_ident(suffix: &str) -> syn::Ident {
+    let s = format!("{}_{}", MODULE_IDENT_PLACEHOLDER, suffix);
+    syn::Ident::new(&s, Span::call_site())
+}
+
+/// Replace every `crate` ident within the given tokens with the crate placeholder, so that paths
+/// relative to the defining crate may be named from within the generated graph crate.
+pub fn replace_crate_idents(tokens: TokenStream) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|tt| match tt {
+            TokenTree::Ident(ref ident) if ident == "crate" => {
+                let ident = syn::Ident::new(CRATE_PLACEHOLDER, ident.span());
+                TokenTree::Ident(ident)
+            }
+            TokenTree::Group(ref group) => {
+                let stream = replace_crate_idents(group.stream());
+                let mut new_group = Group::new(group.delimiter(), stream);
+                new_group.set_span(group.span());
+                TokenTree::Group(new_group)
+            }
+            tt => tt,
+        })
+        .collect()
+}
+
+// An expression producing a `String` from the given source with all placeholders replaced.
+//
+// Paths are rooted at the defining crate, unless the name of another crate is given, e.g. that of
+// a `dep` through which the node's items are named from within the graph crate.
+fn src_expr(src: &str, krate: Option<&str>) -> TokenStream {
+    let krate = match krate {
+        None => quote! { &module_path[..module_path.len() - rest.len()] },
+        Some(name) => {
+            let name = name.replace('-', "_");
+            quote! { #name }
+        }
+    };
+    quote! {
+        {
+            let module_path = module_path!();
+            let rest = &module_path[module_path.find("::").unwrap_or(module_path.len())..];
+            let krate: &str = #krate;
+            let module_path = format!("{}{}", krate, rest);
+            #src.replace(#MODULE_PATH_PLACEHOLDER, &module_path)
+                .replace(#MODULE_IDENT_PLACEHOLDER, &module_path.replace("::", "_"))
+                .replace(#CRATE_PLACEHOLDER, krate)
+        }
+    }
+}
+
+/// An expression producing the `syn::ItemFn` described by the given source, with all placeholders
+/// replaced.
+///
+/// `root` is the path to the `gantz` crate within the defining crate. Paths are rooted at `krate`
+/// if given, or the defining crate otherwise.
+pub fn fn_item_expr(root: &TokenStream, fn_src: &str, krate: Option<&str>) -> TokenStream {
+    let src = src_expr(fn_src, krate);
+    quote! {
+        #root::__private::syn::parse_str::<#root::__private::syn::ItemFn>(&#src)
+            .expect("failed to parse generated evaluator fn")
+    }
+}
+
+/// An expression producing the `syn::Type` described by the given source, with all placeholders
+/// replaced.
+///
+/// `root` is the path to the `gantz` crate within the defining crate.
+pub fn type_expr(root: &TokenStream, ty_src: &str) -> TokenStream {
+    let src = src_expr(ty_src, None);
+    quote! {
+        #root::__private::syn::parse_str::<#root::__private::syn::Type>(&#src)
+            .expect("failed to parse generated type")
+    }
+}
+
+/// An expression producing a `CrateDep` on the defining crate via its manifest directory.
+pub fn path_crate_dep_expr(root: &TokenStream) -> TokenStream {
+    quote! {
+        #root::node::CrateDep {
+            name: env!("CARGO_PKG_NAME").to_string(),
+            source: format!("{{ path = {:?} }}", env!("CARGO_MANIFEST_DIR")),
+        }
+    }
+}
+
+/// Convert the given `UpperCamelCase` name to a `snake_case` name.
+pub fn snake_case(s: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in s.chars().enumerate() {
+        if c.is_uppercase() {
+            if i != 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
@@ -1,32 +1,75 @@
 #![recursion_limit = "128"]
 
 extern crate proc_macro;
-extern crate proc_macro2;
-extern crate syn;
-#[macro_use]
-extern crate quote;
 
+mod evaluator;
 mod node;
+mod node_fn;
 
 use proc_macro::TokenStream;
 
-#[proc_macro_derive(GantzNode, attributes(inlets, outlets, process_inlet, process_outlet))]
+/// Implement **Node** and **SerdeNode** for a struct that acts as the node's state.
+///
+/// Inlets and outlets are declared via the `#[inlets(name: Type, ..)]` and
+/// `#[outlets(name: Type, ..)]` attributes. The node's evaluator is an `Evaluator::Fn` that
+/// accepts a value for each inlet and returns a value for each outlet (a tuple in the case of
+/// multiple outlets) by calling the struct's `process` method. The method receives `&mut self`
+/// followed by the value for each inlet. An alternative method may be specified via
+/// `#[process = "method_name"]`. As the evaluator is compiled within the graph's crate rather than
+/// the defining crate, both the struct and the method must be `pub`. A struct that is not `pub` is
+/// rejected by the derive, while a method that is not `pub` fails to compile once the node is
+/// added to a graph.
+///
+/// The node's `state_type` is the struct itself, so the struct must implement `Default` along with
+/// `serde`'s `Deserialize` and `Serialize`. The struct must be reachable via the module path in
+/// which it is defined and may not be generic. Inlet and outlet types must either be nameable from
+/// any crate or be specified via a `crate::` path. The defining crate must depend on `gantz`,
+/// `serde` and `typetag`.
+#[proc_macro_derive(GantzNode, attributes(inlets, outlets, process))]
 pub fn gantz_node(input: TokenStream) -> TokenStream {
     impl_derive(input, node::impl_gantz_node)
 }
 
-#[proc_macro_derive(GantzNode_, attributes(inlets, outlets, process_inlet, process_outlet))]
+/// The same as **GantzNode** but for use internally within the gantz crate itself.
+#[proc_macro_derive(GantzNode_, attributes(inlets, outlets, process))]
 pub fn gantz_node_(input: TokenStream) -> TokenStream {
     impl_derive(input, node::impl_gantz_node_)
 }
 
+/// Generate a serializable **Node** type for the annotated free function.
+///
+/// The node type is named after the function in `UpperCamelCase`, e.g. `fn add_f32` produces
+/// `AddF32`. Its evaluator is an `Evaluator::Fn` with the same signature as the function that calls
+/// into it via its path in the defining crate, and its `crate_deps` refer to the defining crate.
+/// The node is registered as a `SerdeNode` via `typetag`.
+///
+/// The following optional arguments are accepted:
+///
+/// - `name = "Foo"`: the name of the generated node type.
+/// - `dep = "foo = \"0.1\""`: the crate dependency for the defining crate. By default, this is a
+///   `path` dependency on the directory of the defining crate's manifest. When given, the evaluator
+///   names the function via the dependency's crate, e.g. `foo::`, rather than the defining crate.
+///
+/// The function must be reachable via the module path in which it is defined, e.g. a `pub fn`
+/// within a `pub mod`. Types within the signature must either be nameable from any crate (e.g.
+/// `f32`, `Vec<f32>`, `std::time::Duration`) or be specified via a `crate::` path. The defining
+/// crate must depend on `gantz`, `serde` and `typetag`.
+#[proc_macro_attribute]
+pub fn node(args: TokenStream, input: TokenStream) -> TokenStream {
+    // Parse the function to which the attribute is applied.
+    let item_fn = syn::parse_macro_input!(input as syn::ItemFn);
+    node_fn::impl_node_fn(args.into(), &item_fn)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
 // Use the given function to generate a TokenStream for the derive implementation.
 fn impl_derive(
     input: TokenStream,
     generate_derive: fn(&syn::DeriveInput) -> proc_macro2::TokenStream,
 ) -> TokenStream {
     // Parse the input TokenStream representation.
-    let ast = syn::parse(input).unwrap();
+    let ast = syn::parse_macro_input!(input as syn::DeriveInput);
     // Build the implementation.
     let gen = generate_derive(&ast);
     // Return the generated impl.
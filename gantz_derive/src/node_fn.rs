@@ -0,0 +1,178 @@
+//! Implementation of the `#[node]` attribute macro for generating nodes from free functions.
+
+use crate::evaluator;
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+
+// Arguments that may be passed to the attribute, e.g. `#[node(name = "Foo")]`.
+#[derive(Debug, Default)]
+struct Args {
+    // The name of the generated node type.
+    name: Option<String>,
+    // The crate dependency, e.g. `foo = "0.1"`.
+    dep: Option<(String, String)>,
+}
+
+// Parse the comma-separated `ident = "literal"` pairs passed to the attribute.
+fn parse_args(args: TokenStream) -> syn::Result<Args> {
+    let mut parsed = Args::default();
+    let parser = Punctuated::<syn::MetaNameValue, syn::Token![,]>::parse_terminated;
+    for arg in parser.parse2(args)? {
+        let value = match arg.lit {
+            syn::Lit::Str(ref lit) => lit.value(),
+            ref lit => return Err(syn::Error::new(lit.span(), "expected a string literal")),
+        };
+        if arg.path.is_ident("name") {
+            parsed.name = Some(value);
+        } else if arg.path.is_ident("dep") {
+            let mut sides = value.splitn(2, '=');
+            let name = sides.next().unwrap().trim().to_string();
+            let source = match sides.next() {
+                Some(source) => source.trim().to_string(),
+                None => {
+                    let msg = "`dep` must be of the form `name = source`";
+                    return Err(syn::Error::new(arg.lit.span(), msg));
+                }
+            };
+            parsed.dep = Some((name, source));
+        } else {
+            let msg = "unknown `node` attr argument, expected `name` or `dep`";
+            return Err(syn::Error::new(arg.path.span(), msg));
+        }
+    }
+    Ok(parsed)
+}
+
+// Produce the source of the function used as the node's evaluator.
+//
+// The function has the same signature as the given function and simply calls into it via its path
+// within the defining crate.
+fn evaluator_fn_src(item: &syn::ItemFn) -> syn::Result<String> {
+    let sig = &item.sig;
+    let ident = &sig.ident;
+    let generics = &sig.generics;
+    let where_clause = &generics.where_clause;
+    let output = &sig.output;
+
+    if let Some(ref variadic) = sig.variadic {
+        let msg = "`node` attr does not support variadic functions";
+        return Err(syn::Error::new(variadic.span(), msg));
+    }
+
+    // Collect the name and type of each argument, naming arguments with complex patterns.
+    let mut arg_idents = vec![];
+    let mut arg_tys = vec![];
+    for (i, arg) in sig.inputs.iter().enumerate() {
+        match *arg {
+            syn::FnArg::Typed(ref arg) => {
+                let ident = match *arg.pat {
+                    syn::Pat::Ident(ref pat) => pat.ident.clone(),
+                    _ => syn::Ident::new(&format!("arg{}", i), Span::call_site()),
+                };
+                arg_idents.push(ident);
+                arg_tys.push(arg.ty.clone());
+            }
+            syn::FnArg::Receiver(ref receiver) => {
+                let msg = "`node` attr expects a free function, found a `self` argument";
+                return Err(syn::Error::new(receiver.span(), msg));
+            }
+        }
+    }
+
+    // Pass along any type and const parameters explicitly in case they cannot be inferred.
+    let param_idents: Vec<_> = generics
+        .params
+        .iter()
+        .filter_map(|param| match *param {
+            syn::GenericParam::Type(ref ty) => Some(ty.ident.clone()),
+            syn::GenericParam::Const(ref c) => Some(c.ident.clone()),
+            syn::GenericParam::Lifetime(_) => None,
+        })
+        .collect();
+    let turbofish = match param_idents.is_empty() {
+        true => quote! {},
+        false => quote! { ::<#(#param_idents),*> },
+    };
+
+    let module_path = evaluator::module_path_placeholder();
+    let tokens = quote! {
+        fn #ident #generics (#(#arg_idents: #arg_tys),*) #output #where_clause {
+            #module_path::#ident #turbofish (#(#arg_idents),*)
+        }
+    };
+    Ok(evaluator::replace_crate_idents(tokens).to_string())
+}
+
+/// Generates a serializable **Node** type for the free function to which the attribute is applied.
+///
+/// The function itself is left untouched. Alongside it, a unit struct is generated (named after the
+/// function in `UpperCamelCase` unless a `name` is given) with:
+///
+/// - A `Node` implementation whose evaluator is an `Evaluator::Fn` with the function's signature
+///   that calls into the function via its path within the defining crate. If a `dep` is given, the
+///   path is rooted at the dependency's crate instead, with any `-` in its name replaced by `_`.
+/// - A `Node::crate_deps` implementation returning the defining crate, as a `path` dependency
+///   unless a `dep` is given.
+/// - A `SerdeNode` implementation registered via `typetag`.
+pub fn impl_node_fn(args: TokenStream, item_fn: &syn::ItemFn) -> syn::Result<TokenStream> {
+    let args = parse_args(args)?;
+
+    let root = quote! { ::gantz };
+    let vis = &item_fn.vis;
+    let fn_name = item_fn.sig.ident.to_string();
+    let name = args
+        .name
+        .unwrap_or_else(|| gantz_core::node::fn_node_type_name(&fn_name));
+    let ident = syn::Ident::new(&name, Span::call_site());
+    let doc = format!("The **Node** generated for the `{}` function.", fn_name);
+    let fn_src = evaluator_fn_src(item_fn)?;
+
+    // Items are named via the dependency's crate, if given.
+    let dep_crate = args.dep.as_ref().map(|(dep_name, _)| &dep_name[..]);
+    let fn_item = evaluator::fn_item_expr(&root, &fn_src, dep_crate);
+
+    // The crate dependency, defaulting to the defining crate's manifest directory.
+    let crate_dep = match args.dep {
+        None => evaluator::path_crate_dep_expr(&root),
+        Some((dep_name, source)) => quote! {
+            ::gantz::node::CrateDep {
+                name: #dep_name.to_string(),
+                source: #source.to_string(),
+            }
+        },
+    };
+
+    Ok(quote! {
+        #item_fn
+
+        #[doc = #doc]
+        #[derive(
+            Clone, Copy, Debug, Default, Eq, Hash, PartialEq, ::serde::Deserialize, ::serde::Serialize,
+        )]
+        #vis struct #ident;
+
+        impl ::gantz::Node for #ident {
+            fn evaluator(&self) -> ::gantz::node::Evaluator {
+                let fn_item = #fn_item;
+                ::gantz::node::Evaluator::Fn {
+                    fn_item,
+                    optional_outputs: vec![],
+                }
+            }
+
+            fn crate_deps(&self) -> Vec<::gantz::node::CrateDep> {
+                vec![#crate_dep]
+            }
+        }
+
+        #[typetag::serde]
+        impl ::gantz::node::SerdeNode for #ident {
+            fn node(&self) -> &dyn ::gantz::Node {
+                self
+            }
+        }
+    })
+}
@@ -0,0 +1,232 @@
+//! Stateful nodes for controlling the flow of values.
+
+use crate::native::native_fn;
+use gantz_core::node::native::{self, Outputs};
+use gantz_core::node::{Evaluator, NativeError, NativeFn, Node};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+
+/// Produces the number of values of any type previously received as a `u64`.
+///
+/// The first value received produces `0`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Counter;
+
+/// Flips its `bool` state each time a value of any type is received, producing the new state.
+///
+/// The first value received produces `true`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Toggle;
+
+/// Stores the value received on the first input in the case that the `bool` received on the second
+/// input is `true`, producing the stored value.
+///
+/// The stored value is initialised to the default value of the type. The native implementation
+/// supports the primitive types and `String`.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct SampleAndHold {
+    /// The type of the stored value. Must implement `Clone` and `Default`.
+    #[serde(with = "gantz_core::node::serde::ty")]
+    pub ty: syn::Type,
+}
+
+/// Produces the received value only in the case that it differs from the previously received
+/// value.
+///
+/// Nodes connected to the output are only evaluated in the case that the value has changed. The
+/// first value received is always considered a change. The native implementation supports the
+/// primitive types and `String`.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Change {
+    /// The type of the received value. Must implement `Clone` and `PartialEq`.
+    #[serde(with = "gantz_core::node::serde::ty")]
+    pub ty: syn::Type,
+}
+
+impl SampleAndHold {
+    /// A **SampleAndHold** node for values of the given type.
+    pub fn new(ty: syn::Type) -> Self {
+        SampleAndHold { ty }
+    }
+}
+
+impl Change {
+    /// A **Change** node for values of the given type.
+    pub fn new(ty: syn::Type) -> Self {
+        Change { ty }
+    }
+}
+
+impl Node for Counter {
+    fn evaluator(&self) -> Evaluator {
+        crate::expr_evaluator(1, 1, |args| {
+            let trigger = &args[0];
+            syn::parse_quote! {{
+                #trigger;
+                let count = *state;
+                *state += 1;
+                count
+            }}
+        })
+    }
+
+    fn native(&self) -> Option<NativeFn> {
+        native_fn(|_args, state| {
+            let state = native::state::<u64>(state)?;
+            let count = *state;
+            *state += 1;
+            Ok(vec![native::output(count)])
+        })
+    }
+
+    fn state_type(&self) -> Option<syn::Type> {
+        Some(syn::parse_quote! { u64 })
+    }
+
+    fn default_state(&self) -> Option<syn::Expr> {
+        Some(syn::parse_quote! { 0 })
+    }
+}
+
+impl Node for Toggle {
+    fn evaluator(&self) -> Evaluator {
+        crate::expr_evaluator(1, 1, |args| {
+            let trigger = &args[0];
+            syn::parse_quote! {{
+                #trigger;
+                *state = !*state;
+                *state
+            }}
+        })
+    }
+
+    fn native(&self) -> Option<NativeFn> {
+        native_fn(|_args, state| {
+            let state = native::state::<bool>(state)?;
+            *state = !*state;
+            Ok(vec![native::output(*state)])
+        })
+    }
+
+    fn state_type(&self) -> Option<syn::Type> {
+        Some(syn::parse_quote! { bool })
+    }
+
+    fn default_state(&self) -> Option<syn::Expr> {
+        Some(syn::parse_quote! { false })
+    }
+}
+
+impl Node for SampleAndHold {
+    fn evaluator(&self) -> Evaluator {
+        let ty = self.ty.clone();
+        crate::expr_evaluator(2, 1, move |args| {
+            let (value, sample) = (&args[0], &args[1]);
+            syn::parse_quote! {{
+                let value: #ty = #value;
+                let sample: bool = #sample;
+                if sample {
+                    *state = value;
+                }
+                state.clone()
+            }}
+        })
+    }
+
+    fn native(&self) -> Option<NativeFn> {
+        native_fn(|args, state| {
+            let state = state.ok_or(NativeError::NoState)?;
+            value_types!(native_dispatch!(
+                &*state,
+                |T| T,
+                sample_and_hold(args, state),
+                |expected| NativeError::StateType { expected }
+            ))
+        })
+    }
+
+    fn state_type(&self) -> Option<syn::Type> {
+        Some(self.ty.clone())
+    }
+
+    fn default_state(&self) -> Option<syn::Expr> {
+        Some(syn::parse_quote! { Default::default() })
+    }
+}
+
+impl Node for Change {
+    fn evaluator(&self) -> Evaluator {
+        let ty = self.ty.clone();
+        crate::optional_expr_evaluator(1, 1, move |args| {
+            let value = &args[0];
+            syn::parse_quote! {{
+                let value: #ty = #value;
+                match *state {
+                    Some(ref prev) if *prev == value => None,
+                    _ => {
+                        *state = Some(value.clone());
+                        Some(value)
+                    }
+                }
+            }}
+        })
+    }
+
+    fn native(&self) -> Option<NativeFn> {
+        native_fn(|args, state| {
+            let state = state.ok_or(NativeError::NoState)?;
+            value_types!(native_dispatch!(
+                &*state,
+                |T| Option<T>,
+                change(args, state),
+                |expected| NativeError::StateType { expected }
+            ))
+        })
+    }
+
+    fn state_type(&self) -> Option<syn::Type> {
+        let ty = &self.ty;
+        Some(syn::parse_quote! { Option<#ty> })
+    }
+
+    fn default_state(&self) -> Option<syn::Expr> {
+        Some(syn::parse_quote! { None })
+    }
+}
+
+// The native implementation of **SampleAndHold** for values of type `T`.
+fn sample_and_hold<T>(args: &[&dyn Any], state: &mut dyn Any) -> Result<Outputs, NativeError>
+where
+    T: Any + Clone,
+{
+    let value = native::input::<T>(args, 0)?;
+    let sample = native::input::<bool>(args, 1)?;
+    let state = native::state::<T>(Some(state))?;
+    if *sample {
+        *state = value.clone();
+    }
+    Ok(vec![native::output(state.clone())])
+}
+
+// The native implementation of **Change** for values of type `T`.
+fn change<T>(args: &[&dyn Any], state: &mut dyn Any) -> Result<Outputs, NativeError>
+where
+    T: Any + Clone + PartialEq,
+{
+    let value = native::input::<T>(args, 0)?;
+    let state = native::state::<Option<T>>(Some(state))?;
+    match *state {
+        Some(ref prev) if prev == value => Ok(vec![None]),
+        _ => {
+            *state = Some(value.clone());
+            Ok(vec![native::output(value.clone())])
+        }
+    }
+}
+
+impl_serde_node! {
+    Counter => "gantz_std::Counter",
+    Toggle => "gantz_std::Toggle",
+    SampleAndHold => "gantz_std::SampleAndHold",
+    Change => "gantz_std::Change",
+}
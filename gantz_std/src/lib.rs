@@ -0,0 +1,120 @@
+//! A standard library of **Node**s for gantz.
+//!
+//! All nodes are serializable via the **SerdeNode** trait and generate their code via
+//! `Evaluator::Expr`, so the types of their inputs and outputs are inferred from the nodes to which
+//! they are connected. Nodes that require state and must know their type up-front (e.g.
+//! **SampleAndHold**) are configured with the type.
+//!
+//! Most nodes also provide a native implementation via `Node::native`, allowing graphs to be
+//! evaluated via `graph::interp` without compilation. As values are passed to native
+//! implementations as `&dyn Any`, these support a fixed set of types, namely the primitive types
+//! and `String` (along with `Option`s and `Vec`s of these where relevant). See each node's
+//! documentation for exceptions.
+//!
+//! Nodes are grouped into the following modules:
+//!
+//! - **ops**: arithmetic and comparison.
+//! - **logic**: boolean logic.
+//! - **option**: `Option` and `Result` unwrapping.
+//! - **tuple**: tuple packing and unpacking.
+//! - **vec**: `Vec` push, length and indexing.
+//! - **string**: string formatting.
+//! - **value**: constants and print/debug sinks.
+//! - **control**: counters, toggles, sample-and-hold and change detection.
+
+use gantz_core::node::{self, Evaluator};
+
+// Implement `SerdeNode` for each of the given node types, registering each under the given name.
+macro_rules! impl_serde_node {
+    ($($ty:ident => $name:literal,)*) => {
+        $(
+            #[typetag::serde(name = $name)]
+            impl gantz_core::node::SerdeNode for $ty {
+                fn node(&self) -> &dyn gantz_core::Node {
+                    self
+                }
+            }
+        )*
+    };
+}
+
+// Declare a unit struct **Node** for each of the given binary operators.
+//
+// The native implementations support the types listed by the given `native` macro.
+macro_rules! binary_op_nodes {
+    (native = $types:ident; $($(#[$attr:meta])* $ty:ident($op:tt) => $name:literal,)*) => {
+        $(
+            $(#[$attr])*
+            #[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+            #[derive(serde::Deserialize, serde::Serialize)]
+            pub struct $ty;
+
+            impl gantz_core::Node for $ty {
+                fn evaluator(&self) -> gantz_core::node::Evaluator {
+                    crate::expr_evaluator(2, 1, |args| {
+                        let (l, r) = (&args[0], &args[1]);
+                        syn::parse_quote! { #l $op #r }
+                    })
+                }
+
+                fn native(&self) -> Option<gantz_core::node::NativeFn> {
+                    crate::native::native_fn(|args, _state| {
+                        $types!(native_binary_op!(args, $op))
+                    })
+                }
+            }
+        )*
+
+        impl_serde_node! {
+            $($ty => $name,)*
+        }
+    };
+}
+
+#[macro_use]
+mod native;
+
+pub mod control;
+pub mod logic;
+pub mod ops;
+pub mod option;
+pub mod string;
+pub mod tuple;
+pub mod value;
+pub mod vec;
+
+pub use control::{Change, Counter, SampleAndHold, Toggle};
+pub use logic::{And, Not, Or, Xor};
+pub use ops::{Add, Div, Equal, Greater, GreaterEqual, Less, LessEqual, Mul, Neg, NotEqual};
+pub use ops::{Rem, Sub};
+pub use option::{UnwrapOption, UnwrapOr, UnwrapResult};
+pub use string::Format;
+pub use tuple::{Pack, Unpack};
+pub use value::{Const, Print, PrintDebug};
+pub use vec::{VecIndex, VecLen, VecPush};
+
+// Produce an `Evaluator::Expr` from the given expression generator.
+fn expr_evaluator<F>(n_inputs: u32, n_outputs: u32, gen_expr: F) -> Evaluator
+where
+    F: 'static + Fn(Vec<syn::Expr>) -> syn::Expr,
+{
+    Evaluator::Expr {
+        n_inputs,
+        n_outputs,
+        gen_expr: Box::new(gen_expr),
+        optional_outputs: vec![],
+    }
+}
+
+// Produce an `Evaluator::Expr` whose outputs are all optional.
+fn optional_expr_evaluator<F>(n_inputs: u32, n_outputs: u32, gen_expr: F) -> Evaluator
+where
+    F: 'static + Fn(Vec<syn::Expr>) -> syn::Expr,
+{
+    Evaluator::Expr {
+        n_inputs,
+        n_outputs,
+        gen_expr: Box::new(gen_expr),
+        optional_outputs: (0..n_outputs).map(node::Output).collect(),
+    }
+}
@@ -0,0 +1,44 @@
+//! Nodes for boolean logic.
+//!
+//! Each node applies its operator to its inputs, so types implementing the associated `std::ops`
+//! trait (e.g. integers for bitwise logic) are also supported, with the exception of the
+//! short-circuiting **And** and **Or**. The native implementations support `bool` along with the
+//! primitive integer types for **Xor** and **Not**.
+
+use gantz_core::node::{Evaluator, NativeFn, Node};
+use serde::{Deserialize, Serialize};
+
+binary_op_nodes! {
+    native = bool_types;
+    /// Produces whether or not both inputs are `true`.
+    And(&&) => "gantz_std::And",
+    /// Produces whether or not either input is `true`.
+    Or(||) => "gantz_std::Or",
+}
+
+binary_op_nodes! {
+    native = logic_types;
+    /// Produces whether or not exactly one of the inputs is `true`.
+    Xor(^) => "gantz_std::Xor",
+}
+
+/// Produces the logical negation of its input.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Not;
+
+impl Node for Not {
+    fn evaluator(&self) -> Evaluator {
+        crate::expr_evaluator(1, 1, |args| {
+            let value = &args[0];
+            syn::parse_quote! { !#value }
+        })
+    }
+
+    fn native(&self) -> Option<NativeFn> {
+        crate::native::native_fn(|args, _state| logic_types!(native_unary_op!(args, !)))
+    }
+}
+
+impl_serde_node! {
+    Not => "gantz_std::Not",
+}
@@ -0,0 +1,167 @@
+//! Items shared by the native implementations of the nodes.
+//!
+//! The generated code of most nodes is generic over the types of the values they receive, with the
+//! types inferred from the connected nodes. The interpreter instead passes values as `&dyn Any`, so
+//! native implementations support a fixed set of types, attempting to downcast to each in turn. A
+//! value of any other type results in a **NativeError::InputType**.
+
+use gantz_core::node::native::Outputs;
+use gantz_core::node::{NativeError, NativeFn};
+use std::any::Any;
+use std::rc::Rc;
+
+// Invoke the given macro with the given arguments followed by the supported numeric types.
+macro_rules! numeric_types {
+    ($m:ident!($($args:tt)*)) => {
+        $m!($($args)*, [i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64])
+    };
+}
+
+// Invoke the given macro with the given arguments followed by the supported signed types.
+macro_rules! signed_types {
+    ($m:ident!($($args:tt)*)) => {
+        $m!($($args)*, [i8, i16, i32, i64, i128, isize, f32, f64])
+    };
+}
+
+// Invoke the given macro with the given arguments followed by the types supporting bitwise logic.
+macro_rules! logic_types {
+    ($m:ident!($($args:tt)*)) => {
+        $m!($($args)*, [bool, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize])
+    };
+}
+
+// Invoke the given macro with the given arguments followed by the `bool` type.
+macro_rules! bool_types {
+    ($m:ident!($($args:tt)*)) => {
+        $m!($($args)*, [bool])
+    };
+}
+
+// Invoke the given macro with the given arguments followed by all supported value types.
+macro_rules! value_types {
+    ($m:ident!($($args:tt)*)) => {
+        $m!(
+            $($args)*,
+            [
+                i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64, bool,
+                char, String
+            ]
+        )
+    };
+}
+
+// Apply the binary operator to the first two arguments, both of which must be of the same type.
+macro_rules! native_binary_op {
+    ($args:ident, $op:tt, [$($t:ty),*]) => {{
+        let (l, r) = (crate::native::arg($args, 0)?, crate::native::arg($args, 1)?);
+        $(
+            if let (Some(l), Some(r)) = (l.downcast_ref::<$t>(), r.downcast_ref::<$t>()) {
+                return Ok(vec![gantz_core::node::native::output(l.clone() $op r.clone())]);
+            }
+        )*
+        Err(crate::native::unsupported(0, stringify!($($t),*)))
+    }};
+}
+
+// Apply the unary operator to the first argument.
+macro_rules! native_unary_op {
+    ($args:ident, $op:tt, [$($t:ty),*]) => {{
+        let value = crate::native::arg($args, 0)?;
+        $(
+            if let Some(value) = value.downcast_ref::<$t>() {
+                return Ok(vec![gantz_core::node::native::output($op value.clone())]);
+            }
+        )*
+        Err(crate::native::unsupported(0, stringify!($($t),*)))
+    }};
+}
+
+// Call the generic function with the first of the types for which the given value is a `$wrap`.
+//
+// The function must return a `Result<Outputs, NativeError>`. `$wrap` describes the type of the
+// value in terms of `T`, e.g. `Option<T>`. The error is produced from the list of types in the case
+// that the value is of none of the types.
+macro_rules! native_dispatch {
+    (
+        $value:expr,
+        |$T:ident| $wrap:ty,
+        $f:ident $fargs:tt,
+        |$expected:ident| $err:expr,
+        [$($t:ty),*]
+    ) => {{
+        let value: &dyn std::any::Any = $value;
+        $(
+            {
+                type $T = $t;
+                if value.is::<$wrap>() {
+                    return $f::<$t> $fargs;
+                }
+            }
+        )*
+        let $expected = stringify!($($t),*);
+        Err($err)
+    }};
+}
+
+// Produce a **NativeFn** from the given function.
+pub(crate) fn native_fn<F>(f: F) -> Option<NativeFn>
+where
+    F: 'static + Fn(&[&dyn Any], Option<&mut dyn Any>) -> Result<Outputs, NativeError>,
+{
+    Some(Rc::new(f))
+}
+
+// The value passed to the given input.
+pub(crate) fn arg<'a>(args: &[&'a dyn Any], input: usize) -> Result<&'a dyn Any, NativeError> {
+    args.get(input)
+        .cloned()
+        .ok_or(NativeError::NoInput { input })
+}
+
+// The error produced when the value passed to the given input is not of a supported type.
+pub(crate) fn unsupported(input: usize, expected: &'static str) -> NativeError {
+    NativeError::InputType { input, expected }
+}
+
+// Format the value passed to the given input via its `Display` implementation.
+pub(crate) fn display(args: &[&dyn Any], input: usize) -> Result<String, NativeError> {
+    macro_rules! display {
+        ($value:ident, [$($t:ty),*], [$($u:ty),*]) => {{
+            $(
+                if let Some(value) = $value.downcast_ref::<$t>() {
+                    return Ok(format!("{}", value));
+                }
+            )*
+            $(
+                if let Some(value) = $value.downcast_ref::<$u>() {
+                    return Ok(format!("{}", value));
+                }
+            )*
+            Err(unsupported(input, stringify!($($u,)* $($t),*)))
+        }};
+    }
+    let value = arg(args, input)?;
+    value_types!(display!(value, [&'static str]))
+}
+
+// Format the value passed to the given input via its `Debug` implementation.
+pub(crate) fn debug(args: &[&dyn Any], input: usize) -> Result<String, NativeError> {
+    macro_rules! debug {
+        ($value:ident, [$($t:ty),*], [$($u:ty),*]) => {{
+            $(
+                if let Some(value) = $value.downcast_ref::<$t>() {
+                    return Ok(format!("{:?}", value));
+                }
+            )*
+            $(
+                if let Some(value) = $value.downcast_ref::<$u>() {
+                    return Ok(format!("{:?}", value));
+                }
+            )*
+            Err(unsupported(input, stringify!($($u,)* $($t),*)))
+        }};
+    }
+    let value = arg(args, input)?;
+    value_types!(debug!(value, [&'static str, ()]))
+}
@@ -0,0 +1,59 @@
+//! Nodes for arithmetic and comparison.
+//!
+//! Each node applies its operator to its inputs, so any types implementing the associated
+//! `std::ops` or `std::cmp` trait are supported. The native implementations support the primitive
+//! numeric types, along with `bool`, `char` and `String` for comparison.
+
+use gantz_core::node::{Evaluator, NativeFn, Node};
+use serde::{Deserialize, Serialize};
+
+binary_op_nodes! {
+    native = numeric_types;
+    /// Produces the sum of the left and right inputs.
+    Add(+) => "gantz_std::Add",
+    /// Produces the difference of the left and right inputs.
+    Sub(-) => "gantz_std::Sub",
+    /// Produces the product of the left and right inputs.
+    Mul(*) => "gantz_std::Mul",
+    /// Produces the quotient of the left and right inputs.
+    Div(/) => "gantz_std::Div",
+    /// Produces the remainder of dividing the left input by the right input.
+    Rem(%) => "gantz_std::Rem",
+}
+
+binary_op_nodes! {
+    native = value_types;
+    /// Produces whether or not the left and right inputs are equal.
+    Equal(==) => "gantz_std::Equal",
+    /// Produces whether or not the left and right inputs are not equal.
+    NotEqual(!=) => "gantz_std::NotEqual",
+    /// Produces whether or not the left input is less than the right input.
+    Less(<) => "gantz_std::Less",
+    /// Produces whether or not the left input is less than or equal to the right input.
+    LessEqual(<=) => "gantz_std::LessEqual",
+    /// Produces whether or not the left input is greater than the right input.
+    Greater(>) => "gantz_std::Greater",
+    /// Produces whether or not the left input is greater than or equal to the right input.
+    GreaterEqual(>=) => "gantz_std::GreaterEqual",
+}
+
+/// Produces the negation of its input.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Neg;
+
+impl Node for Neg {
+    fn evaluator(&self) -> Evaluator {
+        crate::expr_evaluator(1, 1, |args| {
+            let value = &args[0];
+            syn::parse_quote! { -#value }
+        })
+    }
+
+    fn native(&self) -> Option<NativeFn> {
+        crate::native::native_fn(|args, _state| signed_types!(native_unary_op!(args, -)))
+    }
+}
+
+impl_serde_node! {
+    Neg => "gantz_std::Neg",
+}
@@ -0,0 +1,154 @@
+//! Nodes for unwrapping `Option` and `Result` values.
+//!
+//! The native implementations support values of the primitive types and `String`. The native
+//! implementation of **UnwrapOr** only supports `Option`s.
+
+use crate::native::{native_fn, unsupported};
+use gantz_core::node::native::{self, Outputs};
+use gantz_core::node::{Evaluator, NativeError, NativeFn, Node};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+
+/// Unwraps an `Option`, producing the contained value only if there is one.
+///
+/// Nodes connected to the output are only evaluated in the case that the input is `Some`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct UnwrapOption;
+
+/// Unwraps a `Result`, producing the `Ok` value on the first output or the `Err` value on the
+/// second output.
+///
+/// Nodes connected to each output are only evaluated in the case that the associated value is
+/// produced.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct UnwrapResult;
+
+/// Unwraps an `Option` or `Result` given by the first input, producing the second input in the
+/// case that there is no value.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct UnwrapOr;
+
+impl Node for UnwrapOption {
+    fn evaluator(&self) -> Evaluator {
+        crate::optional_expr_evaluator(1, 1, |args| {
+            let option = &args[0];
+            syn::parse_quote! {{
+                let option: Option<_> = #option;
+                option
+            }}
+        })
+    }
+
+    fn native(&self) -> Option<NativeFn> {
+        native_fn(|args, _state| {
+            value_types!(native_dispatch!(
+                crate::native::arg(args, 0)?,
+                |T| Option<T>,
+                unwrap_option(args),
+                |expected| unsupported(0, expected)
+            ))
+        })
+    }
+}
+
+impl Node for UnwrapResult {
+    fn evaluator(&self) -> Evaluator {
+        crate::optional_expr_evaluator(1, 2, |args| {
+            let result = &args[0];
+            syn::parse_quote! {{
+                let result: Result<_, _> = #result;
+                match result {
+                    Ok(ok) => (Some(ok), None),
+                    Err(err) => (None, Some(err)),
+                }
+            }}
+        })
+    }
+
+    fn native(&self) -> Option<NativeFn> {
+        native_fn(|args, _state| {
+            // Try each of the `Ok` types, each of which tries each of the `Err` types.
+            macro_rules! unwrap_result {
+                ($result:ident, [$($t:ty),*]) => {{
+                    $(
+                        if let Some(outputs) = unwrap_result::<$t>($result) {
+                            return Ok(outputs);
+                        }
+                    )*
+                    Err(unsupported(0, stringify!($($t),*)))
+                }};
+            }
+            let result = crate::native::arg(args, 0)?;
+            value_types!(unwrap_result!(result))
+        })
+    }
+}
+
+impl Node for UnwrapOr {
+    fn evaluator(&self) -> Evaluator {
+        crate::expr_evaluator(2, 1, |args| {
+            let (value, default) = (&args[0], &args[1]);
+            syn::parse_quote! { #value.unwrap_or(#default) }
+        })
+    }
+
+    fn native(&self) -> Option<NativeFn> {
+        native_fn(|args, _state| {
+            value_types!(native_dispatch!(
+                crate::native::arg(args, 0)?,
+                |T| Option<T>,
+                unwrap_or(args),
+                |expected| unsupported(0, expected)
+            ))
+        })
+    }
+}
+
+// The native implementation of **UnwrapOption** for values of type `T`.
+fn unwrap_option<T>(args: &[&dyn Any]) -> Result<Outputs, NativeError>
+where
+    T: Any + Clone,
+{
+    let option = native::input::<Option<T>>(args, 0)?;
+    Ok(vec![option.clone().and_then(native::output)])
+}
+
+// The outputs of **UnwrapResult** in the case that the given value is a `Result` with an `Ok` type
+// of `T` and an `Err` type of any of the supported types.
+fn unwrap_result<T>(result: &dyn Any) -> Option<Outputs>
+where
+    T: Any + Clone,
+{
+    macro_rules! unwrap_result {
+        ($result:ident, [$($e:ty),*]) => {{
+            $(
+                if let Some(result) = $result.downcast_ref::<Result<T, $e>>() {
+                    let outputs = match result.clone() {
+                        Ok(ok) => vec![native::output(ok), None],
+                        Err(err) => vec![None, native::output(err)],
+                    };
+                    return Some(outputs);
+                }
+            )*
+            None
+        }};
+    }
+    value_types!(unwrap_result!(result))
+}
+
+// The native implementation of **UnwrapOr** for values of type `T`.
+fn unwrap_or<T>(args: &[&dyn Any]) -> Result<Outputs, NativeError>
+where
+    T: Any + Clone,
+{
+    let option = native::input::<Option<T>>(args, 0)?;
+    let default = native::input::<T>(args, 1)?;
+    let value = option.clone().unwrap_or_else(|| default.clone());
+    Ok(vec![native::output(value)])
+}
+
+impl_serde_node! {
+    UnwrapOption => "gantz_std::UnwrapOption",
+    UnwrapResult => "gantz_std::UnwrapResult",
+    UnwrapOr => "gantz_std::UnwrapOr",
+}
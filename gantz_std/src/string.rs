@@ -0,0 +1,128 @@
+//! Nodes for producing strings.
+
+use crate::native::native_fn;
+use gantz_core::node::{native, Evaluator, NativeFn, Node};
+use serde::{Deserialize, Serialize};
+
+/// Formats the received values into a `String` via `format!`.
+///
+/// The node has an input for each `{}` placeholder within the format string, e.g. `"{} + {:?}"`
+/// results in two inputs. Placeholders referring to arguments by position or by name are not
+/// supported.
+///
+/// The native implementation is only provided in the case that all placeholders are `{}` or
+/// `{:?}`, and supports the primitive types, `String` and `&'static str`.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Format {
+    /// The format string passed to `format!`.
+    pub fmt: String,
+}
+
+// A piece of a format string.
+enum Piece {
+    Str(String),
+    Display,
+    Debug,
+}
+
+impl Format {
+    /// A **Format** node for the given format string.
+    pub fn new(fmt: &str) -> Self {
+        let fmt = fmt.to_string();
+        Format { fmt }
+    }
+
+    /// The number of placeholders within the format string.
+    pub fn n_placeholders(&self) -> u32 {
+        let mut count = 0;
+        let mut chars = self.fmt.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                }
+                '{' => {
+                    count += 1;
+                    chars.by_ref().take_while(|&c| c != '}').for_each(drop);
+                }
+                _ => (),
+            }
+        }
+        count
+    }
+}
+
+impl Node for Format {
+    fn evaluator(&self) -> Evaluator {
+        let fmt = self.fmt.clone();
+        crate::expr_evaluator(self.n_placeholders(), 1, move |args| {
+            syn::parse_quote! { format!(#fmt, #(#args),*) }
+        })
+    }
+
+    fn native(&self) -> Option<NativeFn> {
+        let pieces = pieces(&self.fmt)?;
+        native_fn(move |args, _state| {
+            let mut string = String::new();
+            let mut input = 0;
+            for piece in &pieces {
+                match *piece {
+                    Piece::Str(ref s) => string.push_str(s),
+                    Piece::Display => {
+                        string.push_str(&crate::native::display(args, input)?);
+                        input += 1;
+                    }
+                    Piece::Debug => {
+                        string.push_str(&crate::native::debug(args, input)?);
+                        input += 1;
+                    }
+                }
+            }
+            Ok(vec![native::output(string)])
+        })
+    }
+}
+
+// Split the format string into pieces.
+//
+// Returns `None` in the case that the format string contains a placeholder other than `{}` or
+// `{:?}` or is otherwise invalid.
+fn pieces(fmt: &str) -> Option<Vec<Piece>> {
+    let mut pieces = vec![];
+    let mut s = String::new();
+    let mut chars = fmt.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                s.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                s.push('}');
+            }
+            '{' => {
+                let spec: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                let piece = match &spec[..] {
+                    "" => Piece::Display,
+                    ":?" => Piece::Debug,
+                    _ => return None,
+                };
+                if !s.is_empty() {
+                    pieces.push(Piece::Str(std::mem::take(&mut s)));
+                }
+                pieces.push(piece);
+            }
+            '}' => return None,
+            c => s.push(c),
+        }
+    }
+    if !s.is_empty() {
+        pieces.push(Piece::Str(s));
+    }
+    Some(pieces)
+}
+
+impl_serde_node! {
+    Format => "gantz_std::Format",
+}
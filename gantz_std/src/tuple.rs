@@ -0,0 +1,106 @@
+//! Nodes for packing values into tuples and unpacking them again.
+//!
+//! Neither node provides a native implementation, as the length and element types of the tuple
+//! are only known to the generated code.
+
+use gantz_core::node::{self, DynamicIo, Evaluator, Node, SerdeNode};
+use serde::{Deserialize, Serialize};
+
+/// Packs the value received on each input into a tuple.
+///
+/// The number of inputs is the length of the tuple and may be changed via **DynamicIo**.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Pack {
+    /// The length of the tuple.
+    pub len: u32,
+}
+
+/// Unpacks a tuple, producing each of its elements on the output of the same index.
+///
+/// The number of outputs is the length of the tuple and may be changed via **DynamicIo**.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Unpack {
+    /// The length of the tuple.
+    pub len: u32,
+}
+
+impl Pack {
+    /// A **Pack** node for tuples of the given length.
+    pub fn new(len: u32) -> Self {
+        Pack { len }
+    }
+}
+
+impl Unpack {
+    /// An **Unpack** node for tuples of the given length.
+    pub fn new(len: u32) -> Self {
+        Unpack { len }
+    }
+}
+
+impl Node for Pack {
+    fn evaluator(&self) -> Evaluator {
+        crate::expr_evaluator(self.len, 1, |args| {
+            syn::parse_quote! { (#(#args,)*) }
+        })
+    }
+}
+
+impl Node for Unpack {
+    fn evaluator(&self) -> Evaluator {
+        let len = self.len;
+        crate::expr_evaluator(1, len, move |args| {
+            let tuple = &args[0];
+            match len {
+                // A single output is bound to the element rather than the tuple.
+                1 => syn::parse_quote! {{
+                    let (value,) = #tuple;
+                    value
+                }},
+                _ => syn::parse_quote! { #tuple },
+            }
+        })
+    }
+}
+
+impl DynamicIo for Pack {
+    fn set_io(&mut self, io: node::Io) -> Result<(), node::UnsupportedIoError> {
+        if io.n_outputs != 1 {
+            return Err(node::UnsupportedIoError { io });
+        }
+        self.len = io.n_inputs;
+        Ok(())
+    }
+}
+
+impl DynamicIo for Unpack {
+    fn set_io(&mut self, io: node::Io) -> Result<(), node::UnsupportedIoError> {
+        if io.n_inputs != 1 {
+            return Err(node::UnsupportedIoError { io });
+        }
+        self.len = io.n_outputs;
+        Ok(())
+    }
+}
+
+#[typetag::serde(name = "gantz_std::Pack")]
+impl SerdeNode for Pack {
+    fn node(&self) -> &dyn Node {
+        self
+    }
+
+    fn dynamic_io_mut(&mut self) -> Option<&mut dyn DynamicIo> {
+        Some(self)
+    }
+}
+
+#[typetag::serde(name = "gantz_std::Unpack")]
+impl SerdeNode for Unpack {
+    fn node(&self) -> &dyn Node {
+        self
+    }
+
+    fn dynamic_io_mut(&mut self) -> Option<&mut dyn DynamicIo> {
+        Some(self)
+    }
+}
@@ -0,0 +1,139 @@
+//! Nodes for producing constant values and consuming values via print sinks.
+
+use crate::native::native_fn;
+use gantz_core::node::{Evaluator, NativeFn, Node, Value};
+use serde::{Deserialize, Serialize};
+
+/// Produces the given expression each time a value of any type is received.
+///
+/// The native implementation is only provided for `bool` and `char` literals and for numeric
+/// literals with a type suffix, e.g. `42u32`, as the type of any other expression is inferred.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Const {
+    /// The expression producing the constant value.
+    #[serde(with = "gantz_core::node::serde::expr")]
+    pub value: syn::Expr,
+}
+
+/// Prints the received value to stdout via its `Display` implementation.
+///
+/// The native implementation supports the primitive types, `String` and `&'static str`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Print;
+
+/// Prints the received value to stdout via its `Debug` implementation.
+///
+/// The native implementation supports the primitive types, `String`, `&'static str` and `()`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct PrintDebug;
+
+impl Const {
+    /// A **Const** node producing the given expression, e.g. `"42u32"` or `"\"foo\""`.
+    ///
+    /// Returns an **Err** if the given string is not a valid expression.
+    pub fn new(value: &str) -> syn::Result<Self> {
+        let value = syn::parse_str(value)?;
+        Ok(Const { value })
+    }
+}
+
+impl Node for Const {
+    fn evaluator(&self) -> Evaluator {
+        let value = self.value.clone();
+        crate::expr_evaluator(1, 1, move |args| {
+            let trigger = &args[0];
+            syn::parse_quote! {{
+                #trigger;
+                #value
+            }}
+        })
+    }
+
+    fn native(&self) -> Option<NativeFn> {
+        let value = lit_value(&self.value)?;
+        native_fn(move |_args, _state| Ok(vec![Some(value.clone_value())]))
+    }
+}
+
+impl Node for Print {
+    fn evaluator(&self) -> Evaluator {
+        crate::expr_evaluator(1, 0, |args| {
+            let value = &args[0];
+            syn::parse_quote! { println!("{}", #value) }
+        })
+    }
+
+    fn native(&self) -> Option<NativeFn> {
+        native_fn(|args, _state| {
+            let string = crate::native::display(args, 0)?;
+            println!("{}", string);
+            Ok(vec![])
+        })
+    }
+}
+
+impl Node for PrintDebug {
+    fn evaluator(&self) -> Evaluator {
+        crate::expr_evaluator(1, 0, |args| {
+            let value = &args[0];
+            syn::parse_quote! { println!("{:?}", #value) }
+        })
+    }
+
+    fn native(&self) -> Option<NativeFn> {
+        native_fn(|args, _state| {
+            let string = crate::native::debug(args, 0)?;
+            println!("{}", string);
+            Ok(vec![])
+        })
+    }
+}
+
+// The value of the given literal expression in the case that its type is known.
+fn lit_value(expr: &syn::Expr) -> Option<Box<dyn Value>> {
+    // Parse the digits as the type named by the suffix.
+    macro_rules! parse_suffixed {
+        ($suffix:expr, $digits:expr, [$($t:ty),*]) => {{
+            $(
+                if $suffix == stringify!($t) {
+                    let value = $digits.parse::<$t>().ok()?;
+                    return Some(Box::new(value));
+                }
+            )*
+            None
+        }};
+    }
+
+    let (neg, lit) = match *expr {
+        syn::Expr::Lit(ref expr) => (false, &expr.lit),
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            ref expr,
+            ..
+        }) => match **expr {
+            syn::Expr::Lit(ref expr) => (true, &expr.lit),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let sign = if neg { "-" } else { "" };
+    match *lit {
+        syn::Lit::Bool(ref lit) if !neg => Some(Box::new(lit.value)),
+        syn::Lit::Char(ref lit) if !neg => Some(Box::new(lit.value())),
+        syn::Lit::Int(ref lit) => {
+            let digits = format!("{}{}", sign, lit.base10_digits());
+            numeric_types!(parse_suffixed!(lit.suffix(), digits))
+        }
+        syn::Lit::Float(ref lit) => {
+            let digits = format!("{}{}", sign, lit.base10_digits());
+            numeric_types!(parse_suffixed!(lit.suffix(), digits))
+        }
+        _ => None,
+    }
+}
+
+impl_serde_node! {
+    Const => "gantz_std::Const",
+    Print => "gantz_std::Print",
+    PrintDebug => "gantz_std::PrintDebug",
+}
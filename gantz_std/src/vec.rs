@@ -0,0 +1,132 @@
+//! Nodes for working with `Vec`s.
+//!
+//! The native implementations support `Vec`s of the primitive types and `String`.
+
+use crate::native::{native_fn, unsupported};
+use gantz_core::node::native::{self, Outputs};
+use gantz_core::node::{Evaluator, NativeError, NativeFn, Node};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+
+/// Pushes the value received on the second input onto the `Vec` received on the first input,
+/// producing the resulting `Vec`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct VecPush;
+
+/// Produces the length of the received `Vec`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct VecLen;
+
+/// Produces the element of the `Vec` received on the first input at the `usize` index received on
+/// the second input.
+///
+/// Nodes connected to the output are only evaluated in the case that the index is in bounds.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct VecIndex;
+
+impl Node for VecPush {
+    fn evaluator(&self) -> Evaluator {
+        crate::expr_evaluator(2, 1, |args| {
+            let (vec, value) = (&args[0], &args[1]);
+            syn::parse_quote! {{
+                let mut vec: Vec<_> = #vec;
+                vec.push(#value);
+                vec
+            }}
+        })
+    }
+
+    fn native(&self) -> Option<NativeFn> {
+        native_fn(|args, _state| {
+            value_types!(native_dispatch!(
+                crate::native::arg(args, 0)?,
+                |T| Vec<T>,
+                vec_push(args),
+                |expected| unsupported(0, expected)
+            ))
+        })
+    }
+}
+
+impl Node for VecLen {
+    fn evaluator(&self) -> Evaluator {
+        crate::expr_evaluator(1, 1, |args| {
+            let vec = &args[0];
+            syn::parse_quote! {{
+                let vec: Vec<_> = #vec;
+                vec.len()
+            }}
+        })
+    }
+
+    fn native(&self) -> Option<NativeFn> {
+        native_fn(|args, _state| {
+            value_types!(native_dispatch!(
+                crate::native::arg(args, 0)?,
+                |T| Vec<T>,
+                vec_len(args),
+                |expected| unsupported(0, expected)
+            ))
+        })
+    }
+}
+
+impl Node for VecIndex {
+    fn evaluator(&self) -> Evaluator {
+        crate::optional_expr_evaluator(2, 1, |args| {
+            let (vec, index) = (&args[0], &args[1]);
+            syn::parse_quote! {{
+                let vec: Vec<_> = #vec;
+                let index: usize = #index;
+                vec.into_iter().nth(index)
+            }}
+        })
+    }
+
+    fn native(&self) -> Option<NativeFn> {
+        native_fn(|args, _state| {
+            value_types!(native_dispatch!(
+                crate::native::arg(args, 0)?,
+                |T| Vec<T>,
+                vec_index(args),
+                |expected| unsupported(0, expected)
+            ))
+        })
+    }
+}
+
+// The native implementation of **VecPush** for elements of type `T`.
+fn vec_push<T>(args: &[&dyn Any]) -> Result<Outputs, NativeError>
+where
+    T: Any + Clone,
+{
+    let mut vec = native::input::<Vec<T>>(args, 0)?.clone();
+    let value = native::input::<T>(args, 1)?;
+    vec.push(value.clone());
+    Ok(vec![native::output(vec)])
+}
+
+// The native implementation of **VecLen** for elements of type `T`.
+fn vec_len<T>(args: &[&dyn Any]) -> Result<Outputs, NativeError>
+where
+    T: Any + Clone,
+{
+    let vec = native::input::<Vec<T>>(args, 0)?;
+    Ok(vec![native::output(vec.len())])
+}
+
+// The native implementation of **VecIndex** for elements of type `T`.
+fn vec_index<T>(args: &[&dyn Any]) -> Result<Outputs, NativeError>
+where
+    T: Any + Clone,
+{
+    let vec = native::input::<Vec<T>>(args, 0)?;
+    let index = native::input::<usize>(args, 1)?;
+    Ok(vec![vec.get(*index).cloned().and_then(native::output)])
+}
+
+impl_serde_node! {
+    VecPush => "gantz_std::VecPush",
+    VecLen => "gantz_std::VecLen",
+    VecIndex => "gantz_std::VecIndex",
+}
@@ -0,0 +1,107 @@
+// Testing the stateful counter, toggle, sample-and-hold and change detection nodes.
+
+use gantz::node::{self, SerdeNode, WithPushEval, WithStateType};
+use gantz::Edge;
+use gantz_std::{Change, Counter, SampleAndHold, Toggle};
+
+fn node_push() -> node::Push<node::Expr> {
+    node::expr("()").unwrap().with_push_eval_name("push")
+}
+
+// Collects every value received.
+fn node_collect(ty: &str) -> node::State<node::Expr> {
+    node::expr("{ state.push(#value); }")
+        .unwrap()
+        .with_default_state_ty(&format!("Vec<{}>", ty))
+        .unwrap()
+}
+
+// The counter is sampled on every second push and the held value's changes are counted.
+//
+//    --------
+//    | push | // push_eval
+//    -+------
+//     |
+//     |------------
+//     |           |
+//    -+---------  -+--------
+//    | counter |  | toggle |
+//    -+---------  -+--------
+//     |           |
+//    -+-----------+--
+//    | sample&hold  |
+//    -+--------------
+//     |
+//    -+--------
+//    | change |
+//    -+--------
+//     |
+//    -+---------
+//    | counter |
+//    -+---------
+//     |
+//    -+---------
+//    | collect |
+//    -----------
+#[test]
+fn test_control() {
+    let mut project = gantz::TempProject::open_with_name("test_std_control").unwrap();
+
+    // Add the nodes to the project.
+    let push = project
+        .add_core_node(Box::new(node_push()) as Box<dyn SerdeNode>)
+        .unwrap();
+    let counter = project.add_core_node(Box::new(Counter) as Box<_>).unwrap();
+    let toggle = project.add_core_node(Box::new(Toggle) as Box<_>).unwrap();
+    let sample_and_hold = SampleAndHold::new(syn::parse_quote! { u64 });
+    let sample_and_hold = project
+        .add_core_node(Box::new(sample_and_hold) as Box<_>)
+        .unwrap();
+    let change = Change::new(syn::parse_quote! { u64 });
+    let change = project.add_core_node(Box::new(change) as Box<_>).unwrap();
+    let collect = project
+        .add_core_node(Box::new(node_collect("u64")) as Box<_>)
+        .unwrap();
+
+    // Compose the graph.
+    let root = project.root_node_id();
+    let mut ids = None;
+    project
+        .update_graph(&root, |g| {
+            let push = g.add_node(push);
+            let counter_a = g.add_node(counter);
+            let toggle = g.add_node(toggle);
+            let sample_and_hold = g.add_node(sample_and_hold);
+            let change = g.add_node(change);
+            let counter_b = g.add_node(counter);
+            let collect = g.add_node(collect);
+            g.add_edge(push, counter_a, Edge::from((0, 0)));
+            g.add_edge(push, toggle, Edge::from((0, 0)));
+            g.add_edge(counter_a, sample_and_hold, Edge::from((0, 0)));
+            g.add_edge(toggle, sample_and_hold, Edge::from((0, 1)));
+            g.add_edge(sample_and_hold, change, Edge::from((0, 0)));
+            g.add_edge(change, counter_b, Edge::from((0, 0)));
+            g.add_edge(counter_b, collect, Edge::from((0, 0)));
+            ids = Some((counter_a, toggle, sample_and_hold, collect));
+        })
+        .unwrap();
+    let (counter, toggle, sample_and_hold, collect) = ids.unwrap();
+
+    // Push five times.
+    let graph = project.load_graph(&root).expect("failed to load graph");
+    let push_fn = graph.push_fn("push").unwrap();
+    let mut state = gantz::GraphState::new(&project, &graph).unwrap();
+    for _ in 0..5 {
+        state.call(&push_fn).unwrap();
+    }
+
+    // The counter produced 0..5 and the toggle produced `true` on each odd push.
+    assert_eq!(state.state::<u64>(counter), Some(&5));
+    assert_eq!(state.state::<bool>(toggle), Some(&true));
+
+    // The counter was sampled on pushes 1, 3 and 5, producing 0, 0, 2, 2 and 4.
+    assert_eq!(state.state::<u64>(sample_and_hold), Some(&4));
+
+    // The held value changed on pushes 1, 3 and 5.
+    assert_eq!(state.state::<Vec<u64>>(collect), Some(&vec![0, 1, 2]));
+}
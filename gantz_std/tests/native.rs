@@ -0,0 +1,202 @@
+// Testing the native implementations of the nodes.
+
+use gantz::graph::interp::Program;
+use gantz::node::native::Outputs;
+use gantz::node::{self, native, NativeError, WithNative, WithPushEval, WithStateType};
+use gantz::{Edge, Node};
+use gantz_std::{Add, Change, Const, Counter, Equal, Format, Mul, Neg, Not, Pack, SampleAndHold};
+use gantz_std::{Toggle, UnwrapOption, UnwrapOr, UnwrapResult, VecIndex, VecLen, VecPush, Xor};
+use std::any::Any;
+
+type Graph = petgraph::stable_graph::StableGraph<Box<dyn Node>, Edge>;
+
+fn node_push() -> impl Node {
+    node::expr("()")
+        .unwrap()
+        .with_native_fn(|_args, _state| Ok(vec![native::output(())]))
+        .with_push_eval_name("push")
+}
+
+// Stores the last `u64` received.
+fn node_store() -> impl Node {
+    node::expr("{ *state = #value; }")
+        .unwrap()
+        .with_native_fn(|args, state| {
+            *native::state::<u64>(state)? = *native::input::<u64>(args, 0)?;
+            Ok(vec![native::output(())])
+        })
+        .with_state_ty("u64")
+        .unwrap()
+}
+
+// Evaluate the node's native implementation.
+fn eval(node: &dyn Node, args: &[&dyn Any], state: Option<&mut dyn Any>) -> Outputs {
+    let native = node.native().expect("no native implementation");
+    native(args, state).unwrap()
+}
+
+// The value produced for the given output, if any.
+fn output<T: Any + Clone>(outputs: &Outputs, index: usize) -> Option<T> {
+    outputs[index]
+        .as_ref()
+        .map(|value| value.as_any().downcast_ref::<T>().unwrap().clone())
+}
+
+// The counter is doubled and stored on each push.
+//
+//    --------
+//    | push | // push_eval
+//    -+------
+//     |
+//     |--------------
+//     |             |
+//    -+---------   -+------
+//    | Counter |   | 2u64 |
+//    -+---------   -+------
+//     |             |
+//    -+-------------+-
+//    |      Mul      |
+//    -+---------------
+//     |
+//    -+-------
+//    | store |
+//    ---------
+#[test]
+fn test_native_graph() {
+    let mut g = Graph::default();
+    let push = g.add_node(Box::new(node_push()));
+    let counter = g.add_node(Box::new(Counter));
+    let two = g.add_node(Box::new(Const::new("2u64").unwrap()));
+    let mul = g.add_node(Box::new(Mul));
+    let store = g.add_node(Box::new(node_store()));
+    g.add_edge(push, counter, Edge::from((0, 0)));
+    g.add_edge(push, two, Edge::from((0, 0)));
+    g.add_edge(counter, mul, Edge::from((0, 0)));
+    g.add_edge(two, mul, Edge::from((0, 1)));
+    g.add_edge(mul, store, Edge::from((0, 0)));
+
+    let program = Program::push_eval(&g, push).unwrap();
+    let (mut count, mut stored) = (0u64, 0u64);
+    for _ in 0..3 {
+        program.eval(&mut [&mut count, &mut stored]).unwrap();
+    }
+    assert_eq!((count, stored), (3, 4));
+}
+
+#[test]
+fn test_native_ops() {
+    assert_eq!(
+        output::<i32>(&eval(&Add, &[&1i32, &2i32], None), 0),
+        Some(3)
+    );
+    assert_eq!(output::<f32>(&eval(&Neg, &[&1.5f32], None), 0), Some(-1.5));
+    assert_eq!(output::<u8>(&eval(&Not, &[&0u8], None), 0), Some(255));
+    assert_eq!(
+        output::<bool>(&eval(&Xor, &[&true, &true], None), 0),
+        Some(false)
+    );
+    let (a, b) = ("foo".to_string(), "foo".to_string());
+    assert_eq!(
+        output::<bool>(&eval(&Equal, &[&a, &b], None), 0),
+        Some(true)
+    );
+
+    // Values of differing or unsupported types are an error.
+    let native = Add.native().unwrap();
+    match native(&[&1i32, &2u32], None) {
+        Err(NativeError::InputType { input: 0, .. }) => (),
+        _ => panic!("expected an input type error"),
+    }
+    match native(&[&(), &()], None) {
+        Err(NativeError::InputType { input: 0, .. }) => (),
+        _ => panic!("expected an input type error"),
+    }
+}
+
+#[test]
+fn test_native_control() {
+    let mut count = 0u64;
+    eval(&Counter, &[&()], Some(&mut count));
+    assert_eq!(
+        output::<u64>(&eval(&Counter, &[&()], Some(&mut count)), 0),
+        Some(1)
+    );
+
+    let mut toggled = false;
+    assert_eq!(
+        output::<bool>(&eval(&Toggle, &[&()], Some(&mut toggled)), 0),
+        Some(true)
+    );
+
+    let sah = SampleAndHold::new(syn::parse_quote! { String });
+    let mut held = String::new();
+    let value = "foo".to_string();
+    let outputs = eval(&sah, &[&value, &false], Some(&mut held));
+    assert_eq!(output::<String>(&outputs, 0), Some(String::new()));
+    let outputs = eval(&sah, &[&value, &true], Some(&mut held));
+    assert_eq!(output::<String>(&outputs, 0), Some(value));
+
+    let change = Change::new(syn::parse_quote! { f64 });
+    let mut prev: Option<f64> = None;
+    assert_eq!(
+        output::<f64>(&eval(&change, &[&1.0f64], Some(&mut prev)), 0),
+        Some(1.0)
+    );
+    assert_eq!(
+        output::<f64>(&eval(&change, &[&1.0f64], Some(&mut prev)), 0),
+        None
+    );
+    assert_eq!(
+        output::<f64>(&eval(&change, &[&2.0f64], Some(&mut prev)), 0),
+        Some(2.0)
+    );
+}
+
+#[test]
+fn test_native_values() {
+    let const_value = |expr: &str| Const::new(expr).unwrap().native();
+    let outputs = eval(&Const::new("-42i64").unwrap(), &[&()], None);
+    assert_eq!(output::<i64>(&outputs, 0), Some(-42));
+    let outputs = eval(&Const::new("'x'").unwrap(), &[&()], None);
+    assert_eq!(output::<char>(&outputs, 0), Some('x'));
+    // The types of unsuffixed literals and other expressions are inferred.
+    assert!(const_value("42").is_none());
+    assert!(const_value("\"foo\"").is_none());
+
+    let format = Format::new("{} and {:?} {{}}");
+    let value = "b".to_string();
+    let outputs = eval(&format, &[&1u8, &value], None);
+    assert_eq!(output::<String>(&outputs, 0).unwrap(), "1 and \"b\" {}");
+    assert!(Format::new("{:>4}").native().is_none());
+
+    assert!(Pack::new(2).native().is_none());
+}
+
+#[test]
+fn test_native_option_result_vec() {
+    let outputs = eval(&UnwrapOption, &[&Some(1u32)], None);
+    assert_eq!(output::<u32>(&outputs, 0), Some(1));
+    let outputs = eval(&UnwrapOption, &[&None::<u32>], None);
+    assert_eq!(output::<u32>(&outputs, 0), None);
+
+    let result: Result<u8, String> = Err("oops".to_string());
+    let outputs = eval(&UnwrapResult, &[&result], None);
+    assert_eq!(output::<u8>(&outputs, 0), None);
+    assert_eq!(output::<String>(&outputs, 1).unwrap(), "oops");
+
+    let outputs = eval(&UnwrapOr, &[&None::<char>, &'x'], None);
+    assert_eq!(output::<char>(&outputs, 0), Some('x'));
+
+    let vec = vec![1i16, 2];
+    let outputs = eval(&VecPush, &[&vec, &3i16], None);
+    assert_eq!(output::<Vec<i16>>(&outputs, 0), Some(vec![1, 2, 3]));
+    assert_eq!(output::<usize>(&eval(&VecLen, &[&vec], None), 0), Some(2));
+    assert_eq!(
+        output::<i16>(&eval(&VecIndex, &[&vec, &1usize], None), 0),
+        Some(2)
+    );
+    assert_eq!(
+        output::<i16>(&eval(&VecIndex, &[&vec, &2usize], None), 0),
+        None
+    );
+}
@@ -0,0 +1,200 @@
+// Testing the arithmetic, comparison and logic nodes.
+
+use gantz::node::{self, SerdeNode, WithPushEval, WithStateType};
+use gantz::Edge;
+use gantz_std::{Add, Const, Greater, Mul, Neg, Not, Or, Sub, Xor};
+
+fn node_push() -> node::Push<node::Expr> {
+    node::expr("()").unwrap().with_push_eval_name("push")
+}
+
+// Stores the last value received.
+fn node_store(ty: &str) -> node::State<node::Expr> {
+    node::expr("{ *state = #value; }")
+        .unwrap()
+        .with_default_state_ty(ty)
+        .unwrap()
+}
+
+// Arithmetic followed by a comparison of the result.
+//
+//    --------
+//    | push | // push_eval
+//    -+------
+//     |
+//     |-------------------------
+//     |         |              |
+//    -+----    -+----         -+----
+//    | 3  |    | 4  |         | 2  |
+//    -+----    -+----         -+----
+//     |         |              |
+//    -+---------+--            |
+//    |    add     |            |
+//    -+------------            |
+//     |         ----------------
+//     |         |              |
+//    -+---------+--           -+--------
+//    |    mul     |           |  neg   |
+//    -+------------           -+--------
+//     |                        |
+//     |         ----------------
+//     |         |              |
+//    -+---------+--           -+----------
+//    |    sub     |           | greater  | <- 3
+//    -+------------           -+----------
+//     |                        |
+//    -+-------               -+-------
+//    | store |               | store |
+//    ---------               ---------
+#[test]
+fn test_arithmetic_and_comparison() {
+    let mut project = gantz::TempProject::open_with_name("test_std_arithmetic").unwrap();
+
+    // Add the nodes to the project.
+    let push = project
+        .add_core_node(Box::new(node_push()) as Box<dyn SerdeNode>)
+        .unwrap();
+    let three = project
+        .add_core_node(Box::new(Const::new("3i32").unwrap()) as Box<_>)
+        .unwrap();
+    let four = project
+        .add_core_node(Box::new(Const::new("4i32").unwrap()) as Box<_>)
+        .unwrap();
+    let two = project
+        .add_core_node(Box::new(Const::new("2i32").unwrap()) as Box<_>)
+        .unwrap();
+    let add = project.add_core_node(Box::new(Add) as Box<_>).unwrap();
+    let mul = project.add_core_node(Box::new(Mul) as Box<_>).unwrap();
+    let neg = project.add_core_node(Box::new(Neg) as Box<_>).unwrap();
+    let sub = project.add_core_node(Box::new(Sub) as Box<_>).unwrap();
+    let greater = project.add_core_node(Box::new(Greater) as Box<_>).unwrap();
+    let store_i32 = project
+        .add_core_node(Box::new(node_store("i32")) as Box<_>)
+        .unwrap();
+    let store_bool = project
+        .add_core_node(Box::new(node_store("bool")) as Box<_>)
+        .unwrap();
+
+    // Compose the graph.
+    let root = project.root_node_id();
+    let mut stores = None;
+    project
+        .update_graph(&root, |g| {
+            let push = g.add_node(push);
+            let three = g.add_node(three);
+            let four = g.add_node(four);
+            let two = g.add_node(two);
+            let add = g.add_node(add);
+            let mul = g.add_node(mul);
+            let neg = g.add_node(neg);
+            let sub = g.add_node(sub);
+            let greater = g.add_node(greater);
+            let store_i32 = g.add_node(store_i32);
+            let store_bool = g.add_node(store_bool);
+            g.add_edge(push, three, Edge::from((0, 0)));
+            g.add_edge(push, four, Edge::from((0, 0)));
+            g.add_edge(push, two, Edge::from((0, 0)));
+            g.add_edge(three, add, Edge::from((0, 0)));
+            g.add_edge(four, add, Edge::from((0, 1)));
+            g.add_edge(add, mul, Edge::from((0, 0)));
+            g.add_edge(two, mul, Edge::from((0, 1)));
+            g.add_edge(two, neg, Edge::from((0, 0)));
+            g.add_edge(mul, sub, Edge::from((0, 0)));
+            g.add_edge(neg, sub, Edge::from((0, 1)));
+            g.add_edge(neg, greater, Edge::from((0, 0)));
+            g.add_edge(three, greater, Edge::from((0, 1)));
+            g.add_edge(sub, store_i32, Edge::from((0, 0)));
+            g.add_edge(greater, store_bool, Edge::from((0, 0)));
+            stores = Some((store_i32, store_bool));
+        })
+        .unwrap();
+    let (store_i32, store_bool) = stores.unwrap();
+
+    // Evaluate the graph and check the results.
+    let graph = project.load_graph(&root).expect("failed to load graph");
+    let mut state = gantz::GraphState::new(&project, &graph).unwrap();
+    state.call(&graph.push_fn("push").unwrap()).unwrap();
+    assert_eq!(state.state::<i32>(store_i32), Some(&16));
+    assert_eq!(state.state::<bool>(store_bool), Some(&false));
+}
+
+// Boolean logic.
+//
+//    --------
+//    | push | // push_eval
+//    -+------
+//     |
+//     |--------------
+//     |             |
+//    -+-------     -+--------
+//    | true  |     | false  |
+//    -+-------     -+--------
+//     |             |
+//     |            -+----
+//     |            | not |
+//     |            -+----
+//     |             |
+//     |-------------|---------
+//     |             |        |
+//    -+-------------+--     -+-------------
+//    |       xor      |     |     or      | <- false
+//    -+----------------     -+-------------
+//     |                      |
+//    -+-------              -+-------
+//    | store |              | store |
+//    ---------              ---------
+#[test]
+fn test_logic() {
+    let mut project = gantz::TempProject::open_with_name("test_std_logic").unwrap();
+
+    // Add the nodes to the project.
+    let push = project
+        .add_core_node(Box::new(node_push()) as Box<dyn SerdeNode>)
+        .unwrap();
+    let t = project
+        .add_core_node(Box::new(Const::new("true").unwrap()) as Box<_>)
+        .unwrap();
+    let f = project
+        .add_core_node(Box::new(Const::new("false").unwrap()) as Box<_>)
+        .unwrap();
+    let not = project.add_core_node(Box::new(Not) as Box<_>).unwrap();
+    let xor = project.add_core_node(Box::new(Xor) as Box<_>).unwrap();
+    let or = project.add_core_node(Box::new(Or) as Box<_>).unwrap();
+    let store = project
+        .add_core_node(Box::new(node_store("bool")) as Box<_>)
+        .unwrap();
+
+    // Compose the graph.
+    let root = project.root_node_id();
+    let mut stores = None;
+    project
+        .update_graph(&root, |g| {
+            let push = g.add_node(push);
+            let t = g.add_node(t);
+            let f = g.add_node(f);
+            let not = g.add_node(not);
+            let xor = g.add_node(xor);
+            let or = g.add_node(or);
+            let store_xor = g.add_node(store);
+            let store_or = g.add_node(store);
+            g.add_edge(push, t, Edge::from((0, 0)));
+            g.add_edge(push, f, Edge::from((0, 0)));
+            g.add_edge(f, not, Edge::from((0, 0)));
+            g.add_edge(t, xor, Edge::from((0, 0)));
+            g.add_edge(not, xor, Edge::from((0, 1)));
+            g.add_edge(t, or, Edge::from((0, 0)));
+            g.add_edge(f, or, Edge::from((0, 1)));
+            g.add_edge(xor, store_xor, Edge::from((0, 0)));
+            g.add_edge(or, store_or, Edge::from((0, 0)));
+            stores = Some((store_xor, store_or));
+        })
+        .unwrap();
+    let (store_xor, store_or) = stores.unwrap();
+
+    // Evaluate the graph and check the results.
+    let graph = project.load_graph(&root).expect("failed to load graph");
+    let mut state = gantz::GraphState::new(&project, &graph).unwrap();
+    state.call(&graph.push_fn("push").unwrap()).unwrap();
+    assert_eq!(state.state::<bool>(store_xor), Some(&false));
+    assert_eq!(state.state::<bool>(store_or), Some(&true));
+}
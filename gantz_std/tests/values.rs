@@ -0,0 +1,255 @@
+// Testing the nodes for `Option`s, `Result`s, tuples, `Vec`s, strings and print sinks.
+
+use gantz::node::{self, SerdeNode, WithPushEval, WithStateType};
+use gantz::Edge;
+use gantz_std::{Const, Format, Pack, PrintDebug, Unpack, UnwrapOption, UnwrapOr, UnwrapResult};
+use gantz_std::{VecIndex, VecLen, VecPush};
+
+fn node_push() -> node::Push<node::Expr> {
+    node::expr("()").unwrap().with_push_eval_name("push")
+}
+
+// Collects every value received.
+fn node_collect(ty: &str) -> node::State<node::Expr> {
+    node::expr("{ state.push(#value); }")
+        .unwrap()
+        .with_default_state_ty(&format!("Vec<{}>", ty))
+        .unwrap()
+}
+
+// Add a node to the project, returning its ID.
+fn add(project: &mut gantz::Project, node: impl SerdeNode + 'static) -> gantz::project::NodeId {
+    project
+        .add_core_node(Box::new(node) as Box<dyn SerdeNode>)
+        .unwrap()
+}
+
+// Only the values within `Some`, `Ok` and `Err` are passed on to the associated output, while
+// `UnwrapOr` always produces a value.
+//
+//    --------
+//    | push | // push_eval
+//    -+------
+//     |
+//     |-------------------------------------------------
+//     |             |              |              |    |
+//    -+-------     -+-------     -+-------     -+-------
+//    | Some  |     | None  |     |  Ok   |     |  Err  |
+//    -+-------     -+-------     -+-------     -+-------
+//     |             |              |              |
+//    -+--------    -+--------    -+--------    -+--------
+//    | unwrap |    | unwrap |    | unwrap |    | unwrap |
+//    -+--------    -+--------    -+-+------    -+-+------
+//     |             |              | |            | |
+//    collect       collect         collect        collect
+//
+// The `None` is also unwrapped via `UnwrapOr` with a default of `5`.
+#[test]
+fn test_unwrap() {
+    let mut project = gantz::TempProject::open_with_name("test_std_unwrap").unwrap();
+
+    // Add the nodes to the project.
+    let push = add(&mut project, node_push());
+    let some = add(&mut project, Const::new("Some(1u8)").unwrap());
+    let none = add(&mut project, Const::new("None::<u8>").unwrap());
+    let ok = add(&mut project, Const::new("Ok::<u8, u8>(3)").unwrap());
+    let err = add(&mut project, Const::new("Err::<u8, u8>(4)").unwrap());
+    let five = add(&mut project, Const::new("5u8").unwrap());
+    let unwrap_option = add(&mut project, UnwrapOption);
+    let unwrap_result = add(&mut project, UnwrapResult);
+    let unwrap_or = add(&mut project, UnwrapOr);
+    let collect = add(&mut project, node_collect("u8"));
+
+    // Compose the graph, collecting the values produced by each output.
+    let root = project.root_node_id();
+    let mut collectors = vec![];
+    project
+        .update_graph(&root, |g| {
+            let push = g.add_node(push);
+            let some = g.add_node(some);
+            let none = g.add_node(none);
+            let ok = g.add_node(ok);
+            let err = g.add_node(err);
+            let five = g.add_node(five);
+            let unwrap_some = g.add_node(unwrap_option);
+            let unwrap_none = g.add_node(unwrap_option);
+            let unwrap_ok = g.add_node(unwrap_result);
+            let unwrap_err = g.add_node(unwrap_result);
+            let unwrap_or = g.add_node(unwrap_or);
+            for &n in &[some, none, ok, err, five] {
+                g.add_edge(push, n, Edge::from((0, 0)));
+            }
+            g.add_edge(some, unwrap_some, Edge::from((0, 0)));
+            g.add_edge(none, unwrap_none, Edge::from((0, 0)));
+            g.add_edge(ok, unwrap_ok, Edge::from((0, 0)));
+            g.add_edge(err, unwrap_err, Edge::from((0, 0)));
+            g.add_edge(none, unwrap_or, Edge::from((0, 0)));
+            g.add_edge(five, unwrap_or, Edge::from((0, 1)));
+            let outputs = [
+                (unwrap_some, 0),
+                (unwrap_none, 0),
+                (unwrap_ok, 0),
+                (unwrap_ok, 1),
+                (unwrap_err, 0),
+                (unwrap_err, 1),
+                (unwrap_or, 0),
+            ];
+            for &(n, output) in &outputs {
+                let c = g.add_node(collect);
+                g.add_edge(n, c, Edge::from((output, 0)));
+                collectors.push(c);
+            }
+        })
+        .unwrap();
+
+    // Evaluate the graph and check the values that were produced.
+    let graph = project.load_graph(&root).expect("failed to load graph");
+    let mut state = gantz::GraphState::new(&project, &graph).unwrap();
+    state.call(&graph.push_fn("push").unwrap()).unwrap();
+    let collected: Vec<_> = collectors
+        .iter()
+        .map(|&c| state.state::<Vec<u8>>(c).unwrap().clone())
+        .collect();
+    let expected = vec![vec![1], vec![], vec![3], vec![], vec![], vec![4], vec![5]];
+    assert_eq!(collected, expected);
+}
+
+// Values are packed into a tuple, unpacked again and formatted, while a `Vec` is pushed to,
+// indexed and measured.
+//
+//    --------
+//    | push | // push_eval
+//    -+------
+//     |
+//     |----------------------------------------
+//     |         |         |         |         |
+//    -+----    -+----    -+------  -+------  -+---------
+//    | 1  |    | 2  |    | vec |  | vec |  | 1usize |
+//    -+----    -+----    -+------  -+------  -+---------
+//     |         |         |         |         |
+//    -+---------+--      -+------  -+------   |
+//    |    pack    |      | push |  | len  |   |
+//    -+------------      -+------  -+------   |
+//     |                   |         |         |
+//    -+-----------       -+---------|---------+-
+//    |  unpack   |       |        index        |
+//    -+-----+-----       -+---------------------
+//     |     |             |         |
+//    -+-----+----        -+-------  collect
+//    |  format  |        | print |
+//    -+----------        ---------
+//     |
+//    collect
+//
+// The `Vec` push also receives a `2` and the index is also collected.
+#[test]
+fn test_tuple_vec_format() {
+    let mut project = gantz::TempProject::open_with_name("test_std_tuple_vec_format").unwrap();
+
+    // Add the nodes to the project.
+    let push = add(&mut project, node_push());
+    let one = add(&mut project, Const::new("1i32").unwrap());
+    let two = add(&mut project, Const::new("2i32").unwrap());
+    let vec = add(&mut project, Const::new("vec![1i32]").unwrap());
+    let index = add(&mut project, Const::new("1usize").unwrap());
+    let pack = add(&mut project, Pack::new(2));
+    let unpack = add(&mut project, Unpack::new(2));
+    let format = add(&mut project, Format::new("{} + {:?} = {{three}}"));
+    let vec_push = add(&mut project, VecPush);
+    let vec_index = add(&mut project, VecIndex);
+    let vec_len = add(&mut project, VecLen);
+    let print = add(&mut project, PrintDebug);
+    let collect_string = add(&mut project, node_collect("String"));
+    let collect_i32 = add(&mut project, node_collect("i32"));
+    let collect_usize = add(&mut project, node_collect("usize"));
+
+    // Compose the graph.
+    let root = project.root_node_id();
+    let mut collectors = None;
+    project
+        .update_graph(&root, |g| {
+            let push = g.add_node(push);
+            let one = g.add_node(one);
+            let two = g.add_node(two);
+            let two_b = g.add_node(two);
+            let vec_a = g.add_node(vec);
+            let vec_b = g.add_node(vec);
+            let index = g.add_node(index);
+            let pack = g.add_node(pack);
+            let unpack = g.add_node(unpack);
+            let format = g.add_node(format);
+            let vec_push = g.add_node(vec_push);
+            let vec_index = g.add_node(vec_index);
+            let vec_len = g.add_node(vec_len);
+            let print = g.add_node(print);
+            let collect_string = g.add_node(collect_string);
+            let collect_i32 = g.add_node(collect_i32);
+            let collect_usize = g.add_node(collect_usize);
+            for &n in &[one, two, two_b, vec_a, vec_b, index] {
+                g.add_edge(push, n, Edge::from((0, 0)));
+            }
+            g.add_edge(one, pack, Edge::from((0, 0)));
+            g.add_edge(two, pack, Edge::from((0, 1)));
+            g.add_edge(pack, unpack, Edge::from((0, 0)));
+            g.add_edge(unpack, format, Edge::from((0, 0)));
+            g.add_edge(unpack, format, Edge::from((1, 1)));
+            g.add_edge(format, collect_string, Edge::from((0, 0)));
+            g.add_edge(vec_a, vec_push, Edge::from((0, 0)));
+            g.add_edge(two_b, vec_push, Edge::from((0, 1)));
+            g.add_edge(vec_push, vec_index, Edge::from((0, 0)));
+            g.add_edge(index, vec_index, Edge::from((0, 1)));
+            g.add_edge(vec_index, collect_i32, Edge::from((0, 0)));
+            g.add_edge(vec_index, print, Edge::from((0, 0)));
+            g.add_edge(vec_b, vec_len, Edge::from((0, 0)));
+            g.add_edge(vec_len, collect_usize, Edge::from((0, 0)));
+            collectors = Some((collect_string, collect_i32, collect_usize));
+        })
+        .unwrap();
+    let (collect_string, collect_i32, collect_usize) = collectors.unwrap();
+
+    // Evaluate the graph and check the results.
+    let graph = project.load_graph(&root).expect("failed to load graph");
+    let mut state = gantz::GraphState::new(&project, &graph).unwrap();
+    state.call(&graph.push_fn("push").unwrap()).unwrap();
+    let expected = vec!["1 + 2 = {three}".to_string()];
+    assert_eq!(state.state::<Vec<String>>(collect_string), Some(&expected));
+    assert_eq!(state.state::<Vec<i32>>(collect_i32), Some(&vec![2]));
+    assert_eq!(state.state::<Vec<usize>>(collect_usize), Some(&vec![1]));
+}
+
+// Nodes may be serialized and deserialized as `SerdeNode` trait objects and reconfigured via
+// `DynamicIo` where supported.
+#[test]
+fn test_serde() {
+    let nodes: Vec<Box<dyn SerdeNode>> = vec![
+        Box::new(gantz_std::Add),
+        Box::new(gantz_std::Not),
+        Box::new(UnwrapResult),
+        Box::new(Pack::new(3)),
+        Box::new(Format::new("{}, {}")),
+        Box::new(Const::new("42").unwrap()),
+        Box::new(gantz_std::Change::new(syn::parse_quote! { f32 })),
+    ];
+    let json = serde_json::to_string(&nodes).unwrap();
+    let mut nodes: Vec<Box<dyn SerdeNode>> = serde_json::from_str(&json).unwrap();
+    let io: Vec<_> = nodes.iter().map(|n| n.node().evaluator().io()).collect();
+    let expected = vec![
+        node::Io::new(2, 1),
+        node::Io::new(1, 1),
+        node::Io::new(1, 2),
+        node::Io::new(3, 1),
+        node::Io::new(2, 1),
+        node::Io::new(1, 1),
+        node::Io::new(1, 1),
+    ];
+    assert_eq!(io, expected);
+    let f32_ty: syn::Type = syn::parse_quote! { Option<f32> };
+    assert_eq!(nodes[6].node().state_type(), Some(f32_ty));
+
+    // Only the tuple nodes support reconfiguring their I/O.
+    assert!(nodes[0].dynamic_io_mut().is_none());
+    let pack = nodes[3].dynamic_io_mut().unwrap();
+    pack.set_io(node::Io::new(5, 1)).unwrap();
+    assert!(pack.set_io(node::Io::new(5, 2)).is_err());
+    assert_eq!(nodes[3].node().evaluator().n_inputs(), 5);
+}